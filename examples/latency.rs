@@ -0,0 +1,88 @@
+//! Compares request latency percentiles of the tokio work-stealing mode with the
+//! thread-per-core mode.
+//!
+//! ```shell
+//! cargo run --release --example latency -- [clients] [seconds]
+//! ```
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use tokio::net::TcpListener;
+
+use my_redis::server::{Server, DEFAULT_DATABASES};
+
+const KEYS_PER_CLIENT: usize = 1000;
+
+fn main() {
+    let clients = std::env::args().nth(1).map_or(64, |clients| clients.parse().expect("number of clients"));
+    let seconds = std::env::args().nth(2).map_or(5, |seconds| seconds.parse().expect("duration in seconds"));
+    let workers = thread::available_parallelism().map_or(1, |count| count.get());
+
+    println!("{:<16} {:>10} {:>10} {:>10} {:>10}", "mode", "p50 (us)", "p99 (us)", "p99.9 (us)", "max (us)");
+    for (mode, server) in [
+        ("work-stealing", Server::new(DEFAULT_DATABASES)),
+        ("thread-per-core", Server::thread_per_core(DEFAULT_DATABASES, workers)),
+    ] {
+        let address = start_server(server);
+        let mut latencies = measure(&address, clients, Duration::from_secs(seconds));
+        latencies.sort_unstable();
+
+        let percentile = |p: f64| latencies[((latencies.len() - 1) as f64 * p) as usize].as_micros();
+        println!("{:<16} {:>10} {:>10} {:>10} {:>10}", mode, percentile(0.5), percentile(0.99), percentile(0.999), percentile(1.0));
+    }
+}
+
+fn start_server(server: Server) -> String {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let listener = runtime.block_on(TcpListener::bind("127.0.0.1:0")).unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+
+    thread::spawn(move || {
+        runtime.block_on(async move {
+            loop {
+                let (socket, _) = listener.accept().await.unwrap();
+                let server = server.clone();
+                tokio::spawn(async move { server.process(socket).await });
+            }
+        });
+    });
+
+    address
+}
+
+fn measure(address: &str, clients: usize, duration: Duration) -> Vec<Duration> {
+    let running = Arc::new(AtomicBool::new(true));
+
+    let handles: Vec<_> = (0..clients)
+        .map(|client| {
+            let mut connection = redis::Client::open(format!("redis://{}/", address)).unwrap().get_connection().unwrap();
+            let running = running.clone();
+
+            thread::spawn(move || {
+                let mut latencies = vec![];
+                let mut i = 0;
+                while running.load(Ordering::Relaxed) {
+                    let key = format!("client:{}:key:{}", client, i % KEYS_PER_CLIENT);
+
+                    let start = Instant::now();
+                    let _: () = redis::cmd("SET").arg(&key).arg(i).query(&mut connection).unwrap();
+                    latencies.push(start.elapsed());
+
+                    let start = Instant::now();
+                    let _: Option<String> = redis::cmd("GET").arg(&key).query(&mut connection).unwrap();
+                    latencies.push(start.elapsed());
+                    i += 1;
+                }
+                latencies
+            })
+        })
+        .collect();
+
+    thread::sleep(duration);
+    running.store(false, Ordering::Relaxed);
+
+    handles.into_iter().flat_map(|handle| handle.join().unwrap()).collect()
+}
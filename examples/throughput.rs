@@ -0,0 +1,81 @@
+//! Measures SET/GET throughput of the server with a growing number of concurrent clients.
+//!
+//! ```shell
+//! cargo run --release --example throughput -- [seconds]
+//! ```
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use tokio::net::TcpListener;
+
+use my_redis::server::Server;
+
+const CLIENTS: [usize; 3] = [1, 8, 64];
+const KEYS_PER_CLIENT: usize = 1000;
+
+fn main() {
+    let seconds = std::env::args().nth(1).map_or(3, |seconds| seconds.parse().expect("duration in seconds"));
+    let address = start_server();
+
+    println!("{:>8} {:>14}", "clients", "ops/sec");
+    for clients in CLIENTS {
+        let throughput = measure(&address, clients, Duration::from_secs(seconds));
+        println!("{:>8} {:>14.0}", clients, throughput);
+    }
+}
+
+fn start_server() -> String {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let listener = runtime.block_on(TcpListener::bind("127.0.0.1:0")).unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+
+    thread::spawn(move || {
+        runtime.block_on(async move {
+            let server = Server::default();
+            loop {
+                let (socket, _) = listener.accept().await.unwrap();
+                let server = server.clone();
+                tokio::spawn(async move { server.process(socket).await });
+            }
+        });
+    });
+
+    address
+}
+
+fn measure(address: &str, clients: usize, duration: Duration) -> f64 {
+    let operations = Arc::new(AtomicU64::new(0));
+    let running = Arc::new(AtomicBool::new(true));
+
+    let handles: Vec<_> = (0..clients)
+        .map(|client| {
+            let mut connection = redis::Client::open(format!("redis://{}/", address)).unwrap().get_connection().unwrap();
+            let operations = operations.clone();
+            let running = running.clone();
+
+            thread::spawn(move || {
+                let mut i = 0;
+                while running.load(Ordering::Relaxed) {
+                    let key = format!("client:{}:key:{}", client, i % KEYS_PER_CLIENT);
+                    let _: () = redis::cmd("SET").arg(&key).arg(i).query(&mut connection).unwrap();
+                    let _: Option<String> = redis::cmd("GET").arg(&key).query(&mut connection).unwrap();
+                    operations.fetch_add(2, Ordering::Relaxed);
+                    i += 1;
+                }
+            })
+        })
+        .collect();
+
+    let start = Instant::now();
+    thread::sleep(duration);
+    running.store(false, Ordering::Relaxed);
+    let elapsed = start.elapsed();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    operations.load(Ordering::Relaxed) as f64 / elapsed.as_secs_f64()
+}
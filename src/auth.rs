@@ -0,0 +1,174 @@
+use std::vec::IntoIter;
+
+use crate::command::{error_frame, next_string, syntax_error, wrong_arity};
+use crate::frame::Frame;
+use crate::Result;
+
+/// The only user there is without ACLs, the one `requirepass` sets the password of.
+const DEFAULT_USER: &str = "default";
+
+/// Reply to every command but AUTH and HELLO until the connection authenticated.
+pub(crate) fn required() -> Frame {
+    Frame::SimpleError("NOAUTH Authentication required.".to_string())
+}
+
+pub(crate) fn wrong_password() -> Frame {
+    Frame::SimpleError("WRONGPASS invalid username-password pair or user is disabled.".to_string())
+}
+
+/// Checks the credentials of AUTH [username] password against the configured password.
+pub(crate) fn auth(password: Option<&str>, frames: Vec<Frame>) -> Result<bool> {
+    let mut frames = frames.into_iter();
+    frames.next();
+    let legacy = frames.len() == 1;
+    let (username, given) = match frames.len() {
+        0 => return Err(wrong_arity("auth")),
+        1 => (DEFAULT_USER.to_string(), next_string(&mut frames)?),
+        2 => (next_string(&mut frames)?, next_string(&mut frames)?),
+        _ => return Err(syntax_error()),
+    };
+    // Like Redis, only the form without a username is refused when no password is set.
+    if password.is_none() && legacy {
+        return Err("AUTH <password> called without any password configured for the default user. Are you sure your configuration is correct?".into());
+    }
+
+    Ok(check(password, &username, &given))
+}
+
+/// Whether the credentials are the ones of the default user, who accepts any password
+/// when none is configured.
+pub(crate) fn check(password: Option<&str>, username: &str, given: &str) -> bool {
+    username == DEFAULT_USER && password.is_none_or(|password| same(password.as_bytes(), given.as_bytes()))
+}
+
+/// Compares every byte of the longer value whatever the first difference, so timing tells
+/// nothing about the password, not even its length once the given one is at least as long.
+fn same(expected: &[u8], given: &[u8]) -> bool {
+    let byte = |value: &[u8], index: usize| value.get(index).copied().unwrap_or_default() as usize;
+    let difference = (0..expected.len().max(given.len()))
+        .fold(expected.len() ^ given.len(), |difference, index| difference | (byte(expected, index) ^ byte(given, index)));
+    difference == 0
+}
+
+/// HELLO [protover [AUTH username password]], only RESP2 being spoken.
+pub(crate) struct Hello {
+    pub(crate) credentials: Option<(String, String)>,
+}
+
+impl Hello {
+    pub(crate) fn parse(frames: Vec<Frame>) -> std::result::Result<Hello, Frame> {
+        let mut frames: IntoIter<Frame> = frames.into_iter();
+        frames.next();
+        let mut hello = Hello { credentials: None };
+        if frames.len() == 0 {
+            return Ok(hello);
+        }
+
+        match next_string(&mut frames).ok().and_then(|version| version.parse::<i64>().ok()) {
+            Some(2) => {}
+            Some(3) => return Err(Frame::SimpleError("NOPROTO unsupported protocol version".to_string())),
+            _ => return Err(error_frame("Protocol version is not an integer or out of range")),
+        }
+        while let Ok(option) = next_string(&mut frames) {
+            match option.to_uppercase().as_str() {
+                "AUTH" if frames.len() > 1 => {
+                    let username = next_string(&mut frames).map_err(|err| error_frame(&err.to_string()))?;
+                    let password = next_string(&mut frames).map_err(|err| error_frame(&err.to_string()))?;
+                    hello.credentials = Some((username, password));
+                }
+                _ => return Err(error_frame(&format!("Syntax error in HELLO option '{}'", option))),
+            }
+        }
+
+        Ok(hello)
+    }
+}
+
+/// Properties of the server HELLO replies with, as a flat list of names and values.
+pub(crate) fn hello_reply(id: u64, cluster: bool, replica: bool) -> Frame {
+    let bulk = |value: &str| Frame::Bulk(value.to_string().into());
+    Frame::Array(vec![
+        bulk("server"), bulk("redis"),
+        bulk("version"), bulk("7.2.0"),
+        bulk("proto"), Frame::Integer(2),
+        bulk("id"), Frame::Integer(id as i64),
+        bulk("mode"), bulk(if cluster { "cluster" } else { "standalone" }),
+        bulk("role"), bulk(if replica { "replica" } else { "master" }),
+        bulk("modules"), Frame::Array(vec![]),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use tokio::net::{TcpListener, TcpStream};
+
+    use crate::connection::Connection;
+    use crate::server::Server;
+
+    use super::*;
+
+    async fn client(server: Server) -> Connection {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(server.serve(listener));
+        Connection::new(TcpStream::connect(("127.0.0.1", port)).await.unwrap())
+    }
+
+    async fn call(connection: &mut Connection, arguments: &[&str]) -> Frame {
+        let request = Frame::Array(arguments.iter().map(|argument| Frame::Bulk(Bytes::from(argument.to_string()))).collect());
+        connection.write_frame(request).await.unwrap();
+        connection.read_frame().await.unwrap().unwrap()
+    }
+
+    #[test]
+    fn it_compares_passwords_of_any_length() {
+        assert!(same(b"secret", b"secret"));
+        assert!(!same(b"secret", b"secret\0"));
+        assert!(!same(b"secret\0", b"secret"));
+        assert!(!same(b"secret", b"secreT"));
+        assert!(!same(b"", b"secret"));
+        assert!(same(b"", b""));
+    }
+
+    #[tokio::test]
+    async fn it_refuses_commands_until_authenticated() {
+        let mut connection = client(Server::default().requirepass("secret")).await;
+        let ok = Frame::Simple("OK".to_string());
+
+        assert_eq!(required(), call(&mut connection, &["SET", "a", "1"]).await);
+        assert!(matches!(call(&mut connection, &["HELLO", "2"]).await, Frame::SimpleError(err) if err.starts_with("NOAUTH")));
+        assert_eq!(wrong_password(), call(&mut connection, &["AUTH", "guess"]).await);
+        assert_eq!(wrong_password(), call(&mut connection, &["AUTH", "admin", "secret"]).await);
+        assert_eq!(required(), call(&mut connection, &["GET", "a"]).await);
+
+        assert_eq!(ok, call(&mut connection, &["AUTH", "secret"]).await);
+        assert_eq!(ok, call(&mut connection, &["SET", "a", "1"]).await);
+        assert_eq!(ok, call(&mut connection, &["AUTH", "default", "secret"]).await);
+    }
+
+    #[tokio::test]
+    async fn it_authenticates_with_hello() {
+        let mut connection = client(Server::default().requirepass("secret")).await;
+
+        assert_eq!(wrong_password(), call(&mut connection, &["HELLO", "2", "AUTH", "default", "guess"]).await);
+        let Frame::Array(properties) = call(&mut connection, &["HELLO", "2", "AUTH", "default", "secret"]).await else {
+            panic!("HELLO did not reply with the server properties");
+        };
+        assert_eq!(Frame::Integer(2), properties[5]);
+        assert_eq!(Frame::Bulk(Bytes::from("master")), properties[11]);
+        assert_eq!(Frame::Bulk(Bytes::from("PONG")), call(&mut connection, &["PING", "PONG"]).await);
+
+        assert_eq!(Frame::SimpleError("NOPROTO unsupported protocol version".to_string()), call(&mut connection, &["HELLO", "3"]).await);
+    }
+
+    #[tokio::test]
+    async fn it_needs_no_password_unless_required() {
+        let mut connection = client(Server::default()).await;
+
+        assert_eq!(Frame::Simple("OK".to_string()), call(&mut connection, &["SET", "a", "1"]).await);
+        assert!(matches!(call(&mut connection, &["AUTH", "guess"]).await, Frame::SimpleError(err) if err.contains("without any password configured")));
+        assert_eq!(Frame::Simple("OK".to_string()), call(&mut connection, &["AUTH", "default", "anything"]).await);
+        assert!(matches!(call(&mut connection, &["HELLO"]).await, Frame::Array(_)));
+    }
+}
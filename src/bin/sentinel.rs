@@ -0,0 +1,38 @@
+use tokio::net::TcpListener;
+
+use my_redis::sentinel::Sentinel;
+
+#[tokio::main]
+async fn main() {
+    let monitor = values("--monitor", 4).next().expect("--monitor expects a name, a host, a port and a quorum");
+    let port = monitor[2].parse().expect("--monitor expects a port number");
+    let quorum = monitor[3].parse().expect("--monitor expects a positive quorum");
+    let mut sentinel = Sentinel::monitor(&monitor[0], &monitor[1], port, quorum);
+
+    for name in ["down-after-milliseconds", "failover-timeout", "auth-pass"] {
+        if let Some(value) = values(&format!("--{}", name), 1).next() {
+            sentinel.configure(name, &value[0]).unwrap_or_else(|err| panic!("--{}: {}", name, err));
+        }
+    }
+    for peer in values("--sentinel", 1) {
+        let (host, port) = peer[0].rsplit_once(':').expect("--sentinel expects host:port");
+        sentinel.add_peer(host, port.parse().expect("--sentinel expects a port number"));
+    }
+
+    let port: u16 = values("--port", 1).next().map_or(26379, |port| port[0].parse().expect("--port expects a port number"));
+    let listener = TcpListener::bind(("127.0.0.1", port)).await.unwrap();
+
+    println!("Listening");
+
+    sentinel.serve(listener).await;
+}
+
+/// Values following every occurrence of the flag on the command line.
+fn values(name: &str, count: usize) -> impl Iterator<Item = Vec<String>> + '_ {
+    let args: Vec<String> = std::env::args().collect();
+    let positions: Vec<usize> = args.iter().enumerate().filter(|(_, arg)| *arg == name).map(|(position, _)| position).collect();
+    positions.into_iter().filter_map(move |position| {
+        let values = args.get(position + 1..position + 1 + count)?;
+        Some(values.to_vec())
+    })
+}
@@ -1,22 +1,39 @@
 use tokio::net::TcpListener;
 
-use my_redis::server::Server;
+use my_redis::server::{Server, DEFAULT_DATABASES};
 
 #[tokio::main]
 async fn main() {
-    let listener = TcpListener::bind("127.0.0.1:6379").await.unwrap();
-
-    println!("Listening");
+    let databases = option("--databases").map_or(DEFAULT_DATABASES, |count| count.parse().expect("--databases expects a positive number"));
+    let mut server = match option("--workers") {
+        Some(count) => Server::thread_per_core(databases, count.parse().expect("--workers expects a positive number")),
+        None => Server::new(databases),
+    };
+    if option("--cluster-enabled").is_some_and(|enabled| enabled == "yes") {
+        server = server.cluster_mode();
+    }
+    // An empty password requires none, as in Redis.
+    if let Some(password) = option("--requirepass").filter(|password| !password.is_empty()) {
+        server = server.requirepass(&password);
+    }
+    if let Some(password) = option("--masterauth").filter(|password| !password.is_empty()) {
+        server = server.masterauth(&password);
+    }
+    for name in ["maxmemory", "maxmemory-policy", "maxmemory-samples"] {
+        if let Some(value) = option(&format!("--{}", name)) {
+            server.configure(name, &value).unwrap_or_else(|err| panic!("--{}: {}", name, err));
+        }
+    }
 
-    let server: Server = Default::default();
+    let port: u16 = option("--port").map_or(6379, |port| port.parse().expect("--port expects a port number"));
+    let listener = TcpListener::bind(("127.0.0.1", port)).await.unwrap();
 
-    loop {
-        let (socket, _) = listener.accept().await.unwrap();
-        let server = server.clone();
+    println!("Listening");
 
-        tokio::spawn(async move {
-            server.process(socket).await;
-        });
-    }
+    server.serve(listener).await;
 }
 
+/// Value following the flag on the command line.
+fn option(name: &str) -> Option<String> {
+    std::env::args().skip_while(|arg| arg != name).nth(1)
+}
@@ -0,0 +1,179 @@
+use crate::hyperloglog::murmur_hash_64a;
+use crate::rdb::{Corrupt, ModuleWriter, Reader};
+
+pub(crate) const DEFAULT_ERROR_RATE: f64 = 0.01;
+pub(crate) const DEFAULT_CAPACITY: u64 = 100;
+pub(crate) const DEFAULT_EXPANSION: u32 = 2;
+/// Each new layer of a scalable filter gets a tighter error rate so the compound rate stays bounded.
+const TIGHTENING_RATIO: f64 = 0.5;
+const SEED: u64 = 0xc6a4_a793_5bd1_e995;
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct FilterFull;
+
+/// Scalable Bloom filter, a stack of fixed size filters where a new, bigger one
+/// is added once the last one holds as many items as it was sized for.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BloomFilter {
+    layers: Vec<Layer>,
+    expansion: Option<u32>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Layer {
+    bits: Vec<u64>,
+    hashes: u32,
+    capacity: u64,
+    error_rate: f64,
+    items: u64,
+}
+
+impl Default for BloomFilter {
+    fn default() -> Self {
+        BloomFilter::new(DEFAULT_ERROR_RATE, DEFAULT_CAPACITY, Some(DEFAULT_EXPANSION))
+    }
+}
+
+impl BloomFilter {
+    /// Creates a filter, one without expansion refuses new items once it reaches the capacity.
+    pub(crate) fn new(error_rate: f64, capacity: u64, expansion: Option<u32>) -> Self {
+        BloomFilter { layers: vec![Layer::new(error_rate, capacity)], expansion }
+    }
+
+    /// Adds the item, returning false when it was possibly present already.
+    pub(crate) fn add(&mut self, item: &[u8]) -> Result<bool, FilterFull> {
+        let (h1, h2) = hashes(item);
+        if self.layers.iter().any(|layer| layer.contains(h1, h2)) {
+            return Ok(false);
+        }
+
+        let last = self.layers.last().unwrap();
+        if last.items >= last.capacity {
+            let expansion = self.expansion.ok_or(FilterFull)?;
+            let layer = Layer::new(last.error_rate * TIGHTENING_RATIO, last.capacity.saturating_mul(expansion as u64));
+            self.layers.push(layer);
+        }
+
+        self.layers.last_mut().unwrap().insert(h1, h2);
+        Ok(true)
+    }
+
+    /// Approximate number of bytes taken by the filter.
+    pub(crate) fn memory_usage(&self) -> usize {
+        self.layers.iter().map(|layer| layer.bits.len() * 8 + std::mem::size_of::<Layer>()).sum()
+    }
+
+    pub(crate) fn contains(&self, item: &[u8]) -> bool {
+        let (h1, h2) = hashes(item);
+        self.layers.iter().any(|layer| layer.contains(h1, h2))
+    }
+
+    pub(crate) fn save(&self, writer: &mut ModuleWriter) {
+        writer.uint(self.expansion.unwrap_or(0) as u64);
+        writer.uint(self.layers.len() as u64);
+        for layer in &self.layers {
+            writer.uint(layer.hashes as u64);
+            writer.uint(layer.capacity);
+            writer.double(layer.error_rate);
+            writer.uint(layer.items);
+            writer.string(&layer.bits.iter().flat_map(|word| word.to_le_bytes()).collect::<Vec<u8>>());
+        }
+    }
+
+    pub(crate) fn load(reader: &mut Reader) -> Result<Self, Corrupt> {
+        let expansion = Some(reader.module_uint()? as u32).filter(|expansion| *expansion > 0);
+        let layers = (0..reader.module_count()?)
+            .map(|_| {
+                let (hashes, capacity, error_rate, items) = (reader.module_uint()? as u32, reader.module_uint()?, reader.module_double()?, reader.module_uint()?);
+                let bits = reader.module_string()?;
+                if bits.is_empty() || !bits.len().is_multiple_of(8) {
+                    return Err(Corrupt);
+                }
+                let bits = bits.chunks(8).map(|word| u64::from_le_bytes(word.try_into().unwrap())).collect();
+                Ok(Layer { bits, hashes, capacity, error_rate, items })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        match layers.is_empty() {
+            true => Err(Corrupt),
+            false => Ok(BloomFilter { layers, expansion }),
+        }
+    }
+}
+
+impl Layer {
+    fn new(error_rate: f64, capacity: u64) -> Self {
+        let bits_per_item = -error_rate.ln() / std::f64::consts::LN_2.powi(2);
+        let bits = ((capacity as f64 * bits_per_item).ceil() as u64).max(64);
+
+        Layer {
+            bits: vec![0; bits.div_ceil(64) as usize],
+            hashes: (bits_per_item * std::f64::consts::LN_2).ceil() as u32,
+            capacity,
+            error_rate,
+            items: 0,
+        }
+    }
+
+    fn positions(&self, h1: u64, h2: u64) -> impl Iterator<Item = usize> {
+        let size = self.bits.len() as u64 * 64;
+        (0..self.hashes as u64).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % size) as usize)
+    }
+
+    fn contains(&self, h1: u64, h2: u64) -> bool {
+        self.positions(h1, h2).all(|bit| self.bits[bit / 64] & (1 << (bit % 64)) != 0)
+    }
+
+    fn insert(&mut self, h1: u64, h2: u64) {
+        let positions: Vec<usize> = self.positions(h1, h2).collect();
+        for bit in positions {
+            self.bits[bit / 64] |= 1 << (bit % 64);
+        }
+        self.items += 1;
+    }
+}
+
+fn hashes(item: &[u8]) -> (u64, u64) {
+    let h1 = murmur_hash_64a(item, SEED);
+    (h1, murmur_hash_64a(item, h1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_reports_added_items() {
+        let mut filter = BloomFilter::default();
+
+        assert_eq!(Ok(true), filter.add(b"a"));
+        assert_eq!(Ok(false), filter.add(b"a"));
+        assert!(filter.contains(b"a"));
+        assert!(!filter.contains(b"b"));
+    }
+
+    #[test]
+    fn it_scales_beyond_initial_capacity() {
+        let mut filter = BloomFilter::new(0.01, 100, Some(2));
+
+        for i in 0..1000 {
+            filter.add(format!("item:{}", i).as_bytes()).unwrap();
+        }
+
+        assert!(filter.layers.len() > 1);
+        assert!((0..1000).all(|i| filter.contains(format!("item:{}", i).as_bytes())));
+        let false_positives = (1000..11000).filter(|i| filter.contains(format!("item:{}", i).as_bytes())).count();
+        assert!(false_positives < 300, "{} false positives", false_positives);
+    }
+
+    #[test]
+    fn it_refuses_items_when_non_scaling_filter_is_full() {
+        let mut filter = BloomFilter::new(0.01, 2, None);
+
+        filter.add(b"a").unwrap();
+        filter.add(b"b").unwrap();
+
+        assert_eq!(Err(FilterFull), filter.add(b"c"));
+        assert_eq!(2, filter.layers[0].items);
+    }
+}
@@ -0,0 +1,879 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinSet;
+
+use crate::command::{command_keys, command_name, command_slot, error_frame, next_integer, parse_integer, next_string, syntax_error, wrong_arity};
+use crate::connection::Connection;
+use crate::database::Database;
+use crate::eviction::random;
+use crate::frame::Frame;
+use crate::server::Server;
+use crate::Result;
+
+/// Number of hash slots the keyspace of a cluster is split into.
+pub(crate) const SLOTS: usize = 16384;
+/// The cluster bus listens on the client port plus this offset unless told otherwise, as in Redis.
+const BUS_PORT_OFFSET: u16 = 10000;
+/// How often every known node is pinged over the cluster bus.
+const PING_INTERVAL: Duration = Duration::from_millis(100);
+/// Nodes leaving a ping unanswered for this long are flagged as failing.
+const NODE_TIMEOUT: Duration = Duration::from_secs(15);
+/// Exchanges over the bus taking longer are given up.
+const BUS_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Hash slot of the key: the CRC16 of the key, or of its hash tag, the part between the
+/// first `{` and the next `}` when not empty, so related keys can share a slot.
+pub(crate) fn key_slot(key: &[u8]) -> u16 {
+    let tag = key.iter().position(|byte| *byte == b'{').and_then(|open| {
+        let length = key[open + 1..].iter().position(|byte| *byte == b'}')?;
+        (length > 0).then(|| &key[open + 1..open + 1 + length])
+    });
+
+    crc16(tag.unwrap_or(key)) % SLOTS as u16
+}
+
+/// CRC16 with the XMODEM polynomial, as used for hash slots.
+fn crc16(bytes: &[u8]) -> u16 {
+    bytes.iter().fold(0, |crc, byte| {
+        (0..8).fold(crc ^ ((*byte as u16) << 8), |crc, _| match crc & 0x8000 {
+            0 => crc << 1,
+            _ => (crc << 1) ^ 0x1021,
+        })
+    })
+}
+
+/// Nodes of the cluster as this one knows them and the slots they serve. Nodes learn
+/// about each other over the cluster bus, a second listener where they ping every node
+/// they know with their own slots and the nodes they know, and meet the nodes they hear of.
+pub(crate) struct Cluster {
+    pub(crate) myself: String,
+    state: Mutex<State>,
+}
+
+pub(crate) struct State {
+    nodes: Vec<Node>,
+    /// Node serving each slot.
+    slots: Vec<Option<String>>,
+    /// Highest epoch seen in the cluster.
+    current_epoch: u64,
+    /// Slots moving to another node, clients are asked to look there for keys missing here.
+    pub(crate) migrating: HashMap<u16, String>,
+    /// Slots moving from another node, served to clients which sent ASKING first.
+    pub(crate) importing: HashMap<u16, String>,
+}
+
+struct Node {
+    id: String,
+    host: String,
+    port: u16,
+    bus_port: u16,
+    /// Epoch of the last change to the slots of the node, claims of a higher epoch win.
+    config_epoch: u64,
+    /// When the oldest unanswered ping was sent to the node.
+    ping_sent: Option<SystemTime>,
+    pong_received: Option<SystemTime>,
+}
+
+/// Message exchanged over the bus, describing its sender and the nodes it knows.
+struct Header {
+    kind: String,
+    id: String,
+    host: String,
+    port: u16,
+    bus_port: u16,
+    config_epoch: u64,
+    current_epoch: u64,
+    slots: Vec<(u16, u16)>,
+    /// Id, host, port and bus port of the nodes the sender knows.
+    gossip: Vec<(String, String, u16, u16)>,
+}
+
+impl Default for Cluster {
+    fn default() -> Self {
+        let myself = format!("{:016x}{:016x}{:08x}", random(), random(), random() as u32);
+        let node = Node { id: myself.clone(), host: String::new(), port: 0, bus_port: 0, config_epoch: 0, ping_sent: None, pong_received: None };
+
+        Cluster {
+            myself,
+            state: Mutex::new(State {
+                nodes: vec![node],
+                slots: vec![None; SLOTS],
+                current_epoch: 0,
+                migrating: HashMap::new(),
+                importing: HashMap::new(),
+            }),
+        }
+    }
+}
+
+impl State {
+    fn node(&self, id: &str) -> Option<&Node> {
+        self.nodes.iter().find(|node| node.id == id)
+    }
+
+    /// Contiguous ranges of the slots the node serves.
+    fn ranges(&self, id: &str) -> Vec<(u16, u16)> {
+        let mut ranges: Vec<(u16, u16)> = vec![];
+        for slot in (0..SLOTS).filter(|slot| self.slots[*slot].as_deref() == Some(id)) {
+            match ranges.last_mut() {
+                Some((_, end)) if *end as usize + 1 == slot => *end = slot as u16,
+                _ => ranges.push((slot as u16, slot as u16)),
+            }
+        }
+        ranges
+    }
+
+    fn failing(&self, node: &Node) -> bool {
+        node.ping_sent.is_some_and(|sent| sent.elapsed().unwrap_or_default() > NODE_TIMEOUT)
+    }
+
+    /// Whether every slot is served by a node which is not failing.
+    fn healthy(&self) -> bool {
+        self.slots.iter().all(|owner| owner.as_deref().and_then(|owner| self.node(owner)).is_some_and(|node| !self.failing(node)))
+    }
+}
+
+impl Cluster {
+    pub(crate) fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap()
+    }
+
+    /// Listens on the cluster bus next to the client address, then keeps pinging the nodes.
+    pub(crate) async fn start(self: &Arc<Self>, address: SocketAddr) -> Result<()> {
+        let bus = match address.port().checked_add(BUS_PORT_OFFSET) {
+            Some(port) => TcpListener::bind((address.ip(), port)).await,
+            None => TcpListener::bind((address.ip(), 0)).await,
+        };
+        // Ports picked by the system may leave no room for the offset, the bus port is announced anyway.
+        let bus = match bus {
+            Ok(bus) => bus,
+            Err(_) => TcpListener::bind((address.ip(), 0)).await?,
+        };
+        {
+            let mut state = self.state();
+            let myself = &mut state.nodes[0];
+            myself.host = address.ip().to_string();
+            myself.port = address.port();
+            myself.bus_port = bus.local_addr()?.port();
+        }
+
+        let cluster = self.clone();
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = bus.accept().await {
+                let cluster = cluster.clone();
+                tokio::spawn(async move { cluster.serve_bus(socket).await });
+            }
+        });
+        tokio::spawn(self.clone().ping());
+
+        Ok(())
+    }
+
+    async fn serve_bus(self: Arc<Self>, socket: TcpStream) {
+        let mut connection = Connection::new(socket);
+        while let Ok(Some(Frame::Array(frames))) = connection.read_frame().await {
+            let Some(header) = Header::parse(frames) else { return };
+            self.receive(header);
+            let pong = self.header("PONG");
+            if connection.write_frame(pong).await.is_err() {
+                return;
+            }
+        }
+    }
+
+    async fn ping(self: Arc<Self>) {
+        let mut interval = tokio::time::interval(PING_INTERVAL);
+        loop {
+            interval.tick().await;
+            let targets: Vec<(String, u16)> = {
+                let mut state = self.state();
+                let now = SystemTime::now();
+                state.nodes.iter_mut().skip(1)
+                    .map(|node| {
+                        node.ping_sent.get_or_insert(now);
+                        (node.host.clone(), node.bus_port)
+                    })
+                    .collect()
+            };
+            // Nodes are pinged all at once, so unreachable ones do not delay the others.
+            let mut exchanges = JoinSet::new();
+            for (host, bus_port) in targets {
+                exchanges.spawn(self.clone().exchange(host, bus_port, "PING"));
+            }
+            while exchanges.join_next().await.is_some() {}
+        }
+    }
+
+    /// Sends a message to the bus of a node and takes in the reply.
+    async fn exchange(self: Arc<Self>, host: String, bus_port: u16, kind: &str) {
+        let message = self.header(kind);
+        let exchange = async {
+            let mut connection = Connection::new(TcpStream::connect((host.as_str(), bus_port)).await.ok()?);
+            connection.write_frame(message).await.ok()?;
+            match connection.read_frame().await.ok()? {
+                Some(Frame::Array(frames)) => Header::parse(frames),
+                _ => None,
+            }
+        };
+        if let Ok(Some(header)) = tokio::time::timeout(BUS_TIMEOUT, exchange).await {
+            self.receive(header);
+        }
+    }
+
+    /// Takes in what the sender tells about itself, its slots and the nodes it knows.
+    fn receive(self: &Arc<Self>, header: Header) {
+        let mut state = self.state();
+        let known = state.nodes.iter().any(|node| node.id == header.id);
+        // Nodes join by being met, pings from unknown nodes are answered but not trusted.
+        if header.id == self.myself || (!known && header.kind == "PING") {
+            return;
+        }
+        if !known {
+            state.nodes.push(Node {
+                id: header.id.clone(),
+                host: String::new(),
+                port: 0,
+                bus_port: 0,
+                config_epoch: 0,
+                ping_sent: None,
+                pong_received: None,
+            });
+        }
+
+        let node = state.nodes.iter_mut().find(|node| node.id == header.id).unwrap();
+        node.host = header.host;
+        node.port = header.port;
+        node.bus_port = header.bus_port;
+        node.config_epoch = header.config_epoch;
+        node.ping_sent = None;
+        node.pong_received = Some(SystemTime::now());
+        state.current_epoch = state.current_epoch.max(header.current_epoch);
+
+        for slot in header.slots.iter().flat_map(|(start, end)| *start..=*end) {
+            let owner = state.slots[slot as usize].as_deref();
+            let claimed = match owner.map(|owner| (owner, state.node(owner))) {
+                Some((owner, _)) if owner == header.id => false,
+                Some((_, Some(owner))) => owner.config_epoch < header.config_epoch,
+                _ => true,
+            };
+            if claimed {
+                state.slots[slot as usize] = Some(header.id.clone());
+            }
+        }
+
+        let unknown: Vec<(String, u16)> = header.gossip.into_iter()
+            .filter(|(id, ..)| state.node(id).is_none())
+            .map(|(_, host, _, bus_port)| (host, bus_port))
+            .collect();
+        drop(state);
+        for (host, bus_port) in unknown {
+            tokio::spawn(self.clone().exchange(host, bus_port, "MEET"));
+        }
+    }
+
+    fn header(&self, kind: &str) -> Frame {
+        let state = self.state();
+        let myself = &state.nodes[0];
+        let slots = state.ranges(&self.myself).iter()
+            .map(|(start, end)| format!("{}-{}", start, end))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let gossip = state.nodes.iter().skip(1)
+            .map(|node| Frame::Array(bulks(&[&node.id, &node.host, &node.port.to_string(), &node.bus_port.to_string()])))
+            .collect();
+
+        let mut frames = bulks(&[
+            kind,
+            &myself.id,
+            &myself.host,
+            &myself.port.to_string(),
+            &myself.bus_port.to_string(),
+            &myself.config_epoch.to_string(),
+            &state.current_epoch.to_string(),
+            &slots,
+        ]);
+        frames.push(Frame::Array(gossip));
+        Frame::Array(frames)
+    }
+
+    /// Where a command with keys has to go when this node does not serve them, as a MOVED
+    /// or ASK redirection, or the error refusing it, `None` when it runs here.
+    pub(crate) async fn redirect(&self, frames: &[Frame], server: &Server, db: &Database, asking: bool) -> Option<Frame> {
+        let slot = match command_slot(frames) {
+            Ok(slot) => slot?,
+            Err(crossslot) => return Some(crossslot),
+        };
+
+        // MIGRATE restores keys with RESTORE-ASKING on the node importing their slot.
+        let asking = asking || command_name(frames).as_deref() == Some("RESTORE-ASKING");
+        let target = {
+            let state = self.state();
+            let address = |id: &str| state.node(id).map_or_else(String::new, |node| format!("{}:{}", node.host, node.port));
+            let Some(owner) = state.slots[slot as usize].as_deref() else {
+                return Some(Frame::SimpleError("CLUSTERDOWN Hash slot not served".to_string()));
+            };
+
+            if owner != self.myself {
+                if asking && state.importing.contains_key(&slot) {
+                    return None;
+                }
+                return Some(Frame::SimpleError(format!("MOVED {} {}", slot, address(owner))));
+            }
+            address(state.migrating.get(&slot)?)
+        };
+
+        // The keys may be held by workers, they are counted the way EXISTS counts them.
+        let keys = command_keys(frames)?;
+        let exists = std::iter::once(Bytes::from("EXISTS")).chain(keys.iter().cloned()).map(Frame::Bulk).collect();
+        let existing = match server.apply(exists, &mut db.clone()).await {
+            Ok(Frame::Integer(existing)) => existing as usize,
+            _ => 0,
+        };
+        match keys.len() - existing {
+            0 => None,
+            missing if missing == keys.len() => Some(Frame::SimpleError(format!("ASK {} {}", slot, target))),
+            _ => Some(Frame::SimpleError("TRYAGAIN Multiple keys request during rehashing of slot".to_string())),
+        }
+    }
+
+    /// Runs a CLUSTER subcommand.
+    pub(crate) async fn command(self: &Arc<Self>, frames: Vec<Frame>, server: &Server, db: &Database) -> Result<Frame> {
+        let mut frames = frames.into_iter();
+        frames.next();
+        let subcommand = next_string(&mut frames).map_err(|_| wrong_arity("cluster"))?.to_uppercase();
+        let arity_error = || wrong_arity(&format!("cluster|{}", subcommand.to_lowercase()));
+
+        match subcommand.as_str() {
+            "ADDSLOTS" if frames.len() > 0 => {
+                let mut slots = vec![];
+                while frames.len() > 0 {
+                    slots.push(next_slot(&mut frames)?);
+                }
+                self.add_slots(slots)
+            }
+            "ADDSLOTSRANGE" if frames.len() > 0 && frames.len().is_multiple_of(2) => {
+                let mut slots = vec![];
+                while frames.len() > 0 {
+                    let (start, end) = (next_slot(&mut frames)?, next_slot(&mut frames)?);
+                    if start > end {
+                        return Err(format!("start slot number {} is greater than end slot number {}", start, end).into());
+                    }
+                    slots.extend(start..=end);
+                }
+                self.add_slots(slots)
+            }
+            "COUNTKEYSINSLOT" if frames.len() == 1 => {
+                let slot = next_slot(&mut frames)?;
+                Ok(Frame::Integer(server.keys_in_slot(db, slot, usize::MAX).await.len() as i64))
+            }
+            "GETKEYSINSLOT" if frames.len() == 2 => {
+                let slot = next_slot(&mut frames)?;
+                let count = usize::try_from(next_integer(&mut frames)?).map_err(|_| "Invalid number of keys")?;
+                Ok(Frame::Array(server.keys_in_slot(db, slot, count).await.into_iter().map(Frame::Bulk).collect()))
+            }
+            "INFO" if frames.len() == 0 => Ok(Frame::Bulk(Bytes::from(self.info()))),
+            "KEYSLOT" if frames.len() == 1 => {
+                let key = frames.next().and_then(|frame| match frame {
+                    Frame::Bulk(key) => Some(key),
+                    Frame::Simple(key) => Some(Bytes::from(key)),
+                    _ => None,
+                });
+                Ok(Frame::Integer(key_slot(&key.ok_or_else(arity_error)?) as i64))
+            }
+            "MEET" if frames.len() == 2 || frames.len() == 3 => {
+                let host = next_string(&mut frames)?;
+                let port = next_integer(&mut frames)?;
+                let bus_port = match frames.len() {
+                    0 => port + BUS_PORT_OFFSET as i64,
+                    _ => next_integer(&mut frames)?,
+                };
+                let (Ok(_), Ok(bus_port)) = (u16::try_from(port), u16::try_from(bus_port)) else {
+                    return Err(format!("Invalid node address specified: {}:{}", host, port).into());
+                };
+                tokio::spawn(self.clone().exchange(host, bus_port, "MEET"));
+                Ok(Frame::Simple("OK".to_string()))
+            }
+            "MYID" if frames.len() == 0 => Ok(Frame::Bulk(Bytes::from(self.myself.clone()))),
+            "NODES" if frames.len() == 0 => Ok(Frame::Bulk(Bytes::from(self.nodes()))),
+            "SETSLOT" if frames.len() == 2 || frames.len() == 3 => {
+                let slot = next_slot(&mut frames)?;
+                let action = next_string(&mut frames)?.to_uppercase();
+                let node = match (action.as_str(), frames.len()) {
+                    ("STABLE", 0) => None,
+                    ("IMPORTING" | "MIGRATING" | "NODE", 1) => Some(next_string(&mut frames)?),
+                    _ => return Err(syntax_error()),
+                };
+                let holds_keys = action == "NODE" && !server.keys_in_slot(db, slot, 1).await.is_empty();
+                self.set_slot(slot as u16, &action, node, holds_keys)
+            }
+            "SHARDS" if frames.len() == 0 => Ok(self.shards()),
+            "SLOTS" if frames.len() == 0 => Ok(self.slots()),
+            "ADDSLOTS" | "ADDSLOTSRANGE" | "COUNTKEYSINSLOT" | "GETKEYSINSLOT" | "INFO" | "KEYSLOT" | "MEET" | "MYID" | "NODES"
+            | "SETSLOT" | "SHARDS" | "SLOTS" => Err(arity_error()),
+            _ => Err(format!("unknown subcommand '{}'. Try CLUSTER HELP.", subcommand).into()),
+        }
+    }
+
+    /// Assigns the slots to this node, all of them or none when one is invalid or served already.
+    fn add_slots(&self, slots: Vec<usize>) -> Result<Frame> {
+        let mut seen = vec![false; SLOTS];
+        for slot in &slots {
+            if std::mem::replace(&mut seen[*slot], true) {
+                return Err(format!("Slot {} specified multiple times", slot).into());
+            }
+        }
+
+        let mut state = self.state();
+        if let Some(slot) = slots.iter().find(|slot| state.slots[**slot].is_some()) {
+            return Err(format!("Slot {} is already busy", slot).into());
+        }
+        for slot in slots {
+            state.slots[slot] = Some(self.myself.clone());
+        }
+
+        Ok(Frame::Simple("OK".to_string()))
+    }
+
+    /// Moves a slot between nodes: the node giving it away marks it as migrating and the
+    /// one receiving it as importing while the keys are moved with MIGRATE, then both
+    /// assign it to the receiving node, which claims it in a new epoch so the other nodes
+    /// take the change over what they knew.
+    fn set_slot(&self, slot: u16, action: &str, node: Option<String>, holds_keys: bool) -> Result<Frame> {
+        let mut state = self.state();
+        if let Some(node) = node.as_ref().filter(|node| state.node(node).is_none()) {
+            return Err(format!("I don't know about node {}", node).into());
+        }
+        let owned = state.slots[slot as usize].as_deref() == Some(self.myself.as_str());
+
+        match (action, node) {
+            ("MIGRATING", _) if !owned => return Err(format!("I'm not the owner of hash slot {}", slot).into()),
+            ("MIGRATING", Some(node)) => {
+                state.migrating.insert(slot, node);
+            }
+            ("IMPORTING", _) if owned => return Err(format!("I'm already the owner of hash slot {}", slot).into()),
+            ("IMPORTING", Some(node)) => {
+                state.importing.insert(slot, node);
+            }
+            ("NODE", Some(node)) => {
+                if owned && node != self.myself && holds_keys {
+                    return Err(format!("Can't assign hashslot {} to a different node while I still hold keys for this hash slot.", slot).into());
+                }
+                state.migrating.remove(&slot);
+                if state.importing.remove(&slot).is_some() && node == self.myself {
+                    state.current_epoch += 1;
+                    state.nodes[0].config_epoch = state.current_epoch;
+                }
+                state.slots[slot as usize] = Some(node);
+            }
+            _ => {
+                state.migrating.remove(&slot);
+                state.importing.remove(&slot);
+            }
+        }
+
+        Ok(Frame::Simple("OK".to_string()))
+    }
+
+    fn info(&self) -> String {
+        let state = self.state();
+        let assigned = state.slots.iter().filter(|owner| owner.is_some()).count();
+        let failing = state.slots.iter()
+            .filter(|owner| owner.as_deref().and_then(|owner| state.node(owner)).is_some_and(|node| state.failing(node)))
+            .count();
+        let size = state.nodes.iter().filter(|node| state.slots.contains(&Some(node.id.clone()))).count();
+
+        [
+            ("cluster_enabled", "1".to_string()),
+            ("cluster_state", if state.healthy() { "ok" } else { "fail" }.to_string()),
+            ("cluster_slots_assigned", assigned.to_string()),
+            ("cluster_slots_ok", (assigned - failing).to_string()),
+            ("cluster_slots_pfail", failing.to_string()),
+            ("cluster_slots_fail", "0".to_string()),
+            ("cluster_known_nodes", state.nodes.len().to_string()),
+            ("cluster_size", size.to_string()),
+            ("cluster_current_epoch", state.current_epoch.to_string()),
+            ("cluster_my_epoch", state.nodes[0].config_epoch.to_string()),
+        ]
+            .iter()
+            .map(|(name, value)| format!("{}:{}\r\n", name, value))
+            .collect()
+    }
+
+    /// One line per node, formatted like Redis' CLUSTER NODES.
+    fn nodes(&self) -> String {
+        let state = self.state();
+        state.nodes.iter().enumerate()
+            .map(|(index, node)| {
+                let mut flags = if index == 0 { "myself,master" } else { "master" }.to_string();
+                if state.failing(node) {
+                    flags.push_str(",fail?");
+                }
+                let mut line = format!(
+                    "{} {}:{}@{} {} - {} {} {} {}",
+                    node.id,
+                    node.host,
+                    node.port,
+                    node.bus_port,
+                    flags,
+                    node.ping_sent.map_or(0, milliseconds),
+                    node.pong_received.map_or(0, milliseconds),
+                    node.config_epoch,
+                    if index == 0 || node.pong_received.is_some() { "connected" } else { "disconnected" },
+                );
+                for (start, end) in state.ranges(&node.id) {
+                    match start == end {
+                        true => line.push_str(&format!(" {}", start)),
+                        false => line.push_str(&format!(" {}-{}", start, end)),
+                    }
+                }
+                line + "\n"
+            })
+            .collect()
+    }
+
+    fn slots(&self) -> Frame {
+        let state = self.state();
+        let mut ranges: Vec<(u16, u16, &Node)> = state.nodes.iter()
+            .flat_map(|node| state.ranges(&node.id).into_iter().map(move |(start, end)| (start, end, node)))
+            .collect();
+        ranges.sort_by_key(|(start, ..)| *start);
+
+        Frame::Array(ranges.into_iter()
+            .map(|(start, end, node)| Frame::Array(vec![
+                Frame::Integer(start as i64),
+                Frame::Integer(end as i64),
+                Frame::Array(vec![
+                    Frame::Bulk(Bytes::from(node.host.clone())),
+                    Frame::Integer(node.port as i64),
+                    Frame::Bulk(Bytes::from(node.id.clone())),
+                ]),
+            ]))
+            .collect())
+    }
+
+    /// Every node is a shard of its own, there are no replicas in a cluster.
+    fn shards(&self) -> Frame {
+        let state = self.state();
+        Frame::Array(state.nodes.iter()
+            .map(|node| Frame::Array(vec![
+                Frame::Bulk(Bytes::from("slots")),
+                Frame::Array(state.ranges(&node.id).into_iter()
+                    .flat_map(|(start, end)| [Frame::Integer(start as i64), Frame::Integer(end as i64)])
+                    .collect()),
+                Frame::Bulk(Bytes::from("nodes")),
+                Frame::Array(vec![Frame::Array(vec![
+                    Frame::Bulk(Bytes::from("id")),
+                    Frame::Bulk(Bytes::from(node.id.clone())),
+                    Frame::Bulk(Bytes::from("port")),
+                    Frame::Integer(node.port as i64),
+                    Frame::Bulk(Bytes::from("ip")),
+                    Frame::Bulk(Bytes::from(node.host.clone())),
+                    Frame::Bulk(Bytes::from("endpoint")),
+                    Frame::Bulk(Bytes::from(node.host.clone())),
+                    Frame::Bulk(Bytes::from("role")),
+                    Frame::Bulk(Bytes::from("master")),
+                    Frame::Bulk(Bytes::from("replication-offset")),
+                    Frame::Integer(0),
+                    Frame::Bulk(Bytes::from("health")),
+                    Frame::Bulk(Bytes::from(if state.failing(node) { "fail" } else { "online" })),
+                ])]),
+            ]))
+            .collect())
+    }
+}
+
+impl Header {
+    fn parse(frames: Vec<Frame>) -> Option<Header> {
+        let mut frames = frames.into_iter();
+        let mut text = || next_string(&mut frames).ok();
+        let (kind, id, host, port, bus_port, config_epoch, current_epoch, slots) =
+            (text()?, text()?, text()?, text()?, text()?, text()?, text()?, text()?);
+        let Some(Frame::Array(gossip)) = frames.next() else { return None };
+
+        let slots = slots.split_whitespace()
+            .map(|range| {
+                let (start, end) = range.split_once('-')?;
+                let (start, end): (u16, u16) = (start.parse().ok()?, end.parse().ok()?);
+                (start <= end && (end as usize) < SLOTS).then_some((start, end))
+            })
+            .collect::<Option<Vec<(u16, u16)>>>()?;
+        let gossip = gossip.into_iter()
+            .map(|node| {
+                let Frame::Array(fields) = node else { return None };
+                let mut fields = fields.into_iter();
+                let mut text = || next_string(&mut fields).ok();
+                Some((text()?, text()?, text()?.parse().ok()?, text()?.parse().ok()?))
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(Header {
+            kind,
+            id,
+            host,
+            port: port.parse().ok()?,
+            bus_port: bus_port.parse().ok()?,
+            config_epoch: config_epoch.parse().ok()?,
+            current_epoch: current_epoch.parse().ok()?,
+            slots,
+            gossip,
+        })
+    }
+}
+
+/// Up to `count` keys of the database hashing to the slot.
+pub(crate) fn keys_in_slot(db: &Database, slot: usize, count: usize) -> Vec<Bytes> {
+    let keyspace = db.lock().unwrap();
+    keyspace.keys().filter(|key| key_slot(key) as usize == slot).take(count).cloned().collect()
+}
+
+fn next_slot(frames: &mut IntoIter<Frame>) -> Result<usize> {
+    next_integer(frames).ok()
+        .and_then(|slot| usize::try_from(slot).ok())
+        .filter(|slot| *slot < SLOTS)
+        .ok_or_else(|| "Invalid or out of range slot".into())
+}
+
+fn bulks(values: &[&str]) -> Vec<Frame> {
+    values.iter().map(|value| Frame::Bulk(Bytes::from(value.to_string()))).collect()
+}
+
+fn milliseconds(time: SystemTime) -> u128 {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis()
+}
+
+/// Refuses the commands reaching a database other than zero, the only one in a cluster
+/// since keys are spread by slot over the nodes.
+pub(crate) fn check_database(frames: &[Frame]) -> Result<()> {
+    let index = |frame: Option<&Frame>| match frame {
+        Some(Frame::Bulk(data)) => parse_integer(data),
+        Some(Frame::Simple(data)) => parse_integer(data.as_bytes()),
+        Some(Frame::Integer(index)) => Some(*index),
+        _ => None,
+    };
+
+    match command_name(frames).as_deref() {
+        // Indexes which are not integers are refused by SELECT itself.
+        Some("SELECT") if index(frames.get(1)).is_some_and(|index| index != 0) => Err("SELECT is not allowed in cluster mode".into()),
+        Some("MOVE") => Err("MOVE is not allowed in cluster mode".into()),
+        Some("SWAPDB") => Err("SWAPDB is not allowed in cluster mode".into()),
+        Some("COPY") => {
+            let mut options = frames.iter().skip(3);
+            while let Some(option) = options.next() {
+                let is_db = matches!(option, Frame::Bulk(data) if data.eq_ignore_ascii_case(b"DB"));
+                if is_db && index(options.next()).is_some_and(|index| index != 0) {
+                    return Err("Copying to another database is not allowed in cluster mode".into());
+                }
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Error of the cluster commands on a server running without cluster mode.
+pub(crate) fn disabled() -> Frame {
+    error_frame("This instance has cluster support disabled")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(arguments: &[&str]) -> Frame {
+        Frame::Array(bulks(arguments))
+    }
+
+    async fn start() -> (Server, u16) {
+        let server = Server::new(1).cluster_mode();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(server.clone().serve(listener));
+        (server, port)
+    }
+
+    async fn call(port: u16, arguments: &[&str]) -> Frame {
+        let mut connection = Connection::new(TcpStream::connect(("127.0.0.1", port)).await.unwrap());
+        connection.write_frame(request(arguments)).await.unwrap();
+        connection.read_frame().await.unwrap().unwrap()
+    }
+
+    #[test]
+    fn it_hashes_keys_and_tags_to_slots() {
+        assert_eq!(0x31c3, crc16(b"123456789"));
+        assert_eq!(12182, key_slot(b"foo"));
+        assert_eq!(key_slot(b"user1000"), key_slot(b"{user1000}.following"));
+        assert_eq!(key_slot(b"{user1000}.followers"), key_slot(b"{user1000}.following"));
+        // Empty tags do not count, only the first one does.
+        assert_eq!(crc16(b"foo{}{bar}") % SLOTS as u16, key_slot(b"foo{}{bar}"));
+        assert_eq!(key_slot(b"{bar"), key_slot(b"foo{{bar}}zap"));
+    }
+
+    #[test]
+    fn it_refuses_messages_claiming_slots_out_of_range() {
+        let message = |slots: &str| {
+            let mut frames = bulks(&["MEET", "abc", "127.0.0.1", "7000", "17000", "1", "1", slots]);
+            frames.push(Frame::Array(vec![]));
+            Header::parse(frames)
+        };
+
+        assert_eq!(Some(vec![(0, 5), (16383, 16383)]), message("0-5 16383-16383").map(|header| header.slots));
+        assert!(message("0-20000").is_none());
+        assert!(message("16384-16384").is_none());
+        assert!(message("5-3").is_none());
+    }
+
+    #[tokio::test]
+    async fn it_refuses_commands_reaching_other_databases() {
+        let (_, port) = start().await;
+        let refused = |message: &str| Frame::SimpleError(format!("ERR {} is not allowed in cluster mode", message));
+
+        assert_eq!(Frame::Simple("OK".to_string()), call(port, &["SELECT", "0"]).await);
+        assert_eq!(refused("SELECT"), call(port, &["SELECT", "1"]).await);
+        assert_eq!(refused("SELECT"), call(port, &["SELECT", "-1"]).await);
+        assert!(matches!(call(port, &["SELECT", "00"]).await, Frame::SimpleError(err) if !err.contains("cluster")));
+        assert_eq!(refused("MOVE"), call(port, &["MOVE", "foo", "0"]).await);
+        assert_eq!(refused("SWAPDB"), call(port, &["SWAPDB", "0", "1"]).await);
+        assert_eq!(refused("Copying to another database"), call(port, &["COPY", "{a}1", "{a}2", "replace", "db", "1"]).await);
+    }
+
+    #[tokio::test]
+    async fn it_finds_the_keys_of_a_slot_held_by_workers() {
+        let server = Server::thread_per_core(1, 4).cluster_mode();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(server.serve(listener));
+
+        let slot = key_slot(b"{user}").to_string();
+        call(port, &["CLUSTER", "ADDSLOTS", &slot]).await;
+        let keys: Vec<String> = (0..20).map(|i| format!("{{user}}{}", i)).collect();
+        for key in &keys {
+            call(port, &["SET", key, "1"]).await;
+        }
+
+        assert_eq!(Frame::Integer(20), call(port, &["CLUSTER", "COUNTKEYSINSLOT", &slot]).await);
+        assert!(matches!(call(port, &["CLUSTER", "GETKEYSINSLOT", &slot, "5"]).await, Frame::Array(keys) if keys.len() == 5));
+        assert!(matches!(call(port, &["MGET", &keys[0], &keys[1], &keys[2]]).await, Frame::Array(values) if values.iter().all(|value| *value == Frame::Bulk(Bytes::from("1")))));
+    }
+
+    #[tokio::test]
+    async fn it_redirects_keys_to_the_node_serving_their_slot() {
+        let (_, first) = start().await;
+        let (second_server, second) = start().await;
+        assert!(matches!(call(first, &["GET", "foo"]).await, Frame::SimpleError(err) if err.starts_with("CLUSTERDOWN")));
+
+        let slot = key_slot(b"foo").to_string();
+        assert_eq!(Frame::Simple("OK".to_string()), call(second, &["CLUSTER", "ADDSLOTS", &slot]).await);
+        assert!(matches!(call(second, &["CLUSTER", "ADDSLOTS", &slot]).await, Frame::SimpleError(err) if err.contains("busy")));
+        let bus_port = second_server.cluster.as_ref().unwrap().state().nodes[0].bus_port.to_string();
+        call(first, &["CLUSTER", "MEET", "127.0.0.1", &second.to_string(), &bus_port]).await;
+
+        let moved = Frame::SimpleError(format!("MOVED {} 127.0.0.1:{}", slot, second));
+        for _ in 0..100 {
+            if call(first, &["GET", "foo"]).await == moved {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(moved, call(first, &["GET", "foo"]).await);
+        assert_eq!(moved, call(first, &["TS.ADD", "foo", "1", "1"]).await);
+        assert_eq!(Frame::Simple("OK".to_string()), call(second, &["SET", "foo", "1"]).await);
+        assert!(matches!(call(second, &["MGET", "foo", "bar"]).await, Frame::SimpleError(err) if err.starts_with("CROSSSLOT")));
+        assert_eq!(Frame::Integer(key_slot(b"foo") as i64), call(first, &["CLUSTER", "KEYSLOT", "{foo}bar"]).await);
+
+        // The node which was met learns about the first one from it.
+        let Frame::Bulk(nodes) = call(second, &["CLUSTER", "NODES"]).await else { panic!("expected nodes") };
+        assert_eq!(2, nodes.split(|byte| *byte == b'\n').filter(|line| !line.is_empty()).count());
+        let Frame::Array(slots) = call(first, &["CLUSTER", "SLOTS"]).await else { panic!("expected slots") };
+        assert_eq!(Frame::Array(vec![
+            Frame::Integer(key_slot(b"foo") as i64),
+            Frame::Integer(key_slot(b"foo") as i64),
+            Frame::Array(vec![
+                Frame::Bulk(Bytes::from("127.0.0.1")),
+                Frame::Integer(second as i64),
+                Frame::Bulk(Bytes::from(second_server.cluster.as_ref().unwrap().myself.clone())),
+            ]),
+        ]), slots[0]);
+    }
+
+    #[tokio::test]
+    async fn it_migrates_a_slot_between_nodes() {
+        let (source_server, source) = start().await;
+        let (target_server, target) = start().await;
+        let (source_id, target_id) = (source_server.cluster.as_ref().unwrap().myself.clone(), target_server.cluster.as_ref().unwrap().myself.clone());
+        let slot = key_slot(b"foo").to_string();
+        call(source, &["CLUSTER", "ADDSLOTS", &slot]).await;
+        call(source, &["SET", "foo", "1"]).await;
+        call(source, &["SET", "{foo}2", "2"]).await;
+        let bus_port = target_server.cluster.as_ref().unwrap().state().nodes[0].bus_port.to_string();
+        call(source, &["CLUSTER", "MEET", "127.0.0.1", &target.to_string(), &bus_port]).await;
+        for _ in 0..100 {
+            if target_server.cluster.as_ref().unwrap().state().slots[key_slot(b"foo") as usize].is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        assert_eq!(Frame::Simple("OK".to_string()), call(target, &["CLUSTER", "SETSLOT", &slot, "IMPORTING", &source_id]).await);
+        assert_eq!(Frame::Simple("OK".to_string()), call(source, &["CLUSTER", "SETSLOT", &slot, "MIGRATING", &target_id]).await);
+        assert_eq!(Frame::Integer(2), call(source, &["CLUSTER", "COUNTKEYSINSLOT", &slot]).await);
+
+        // Keys move one at a time, the source answers for those it still holds.
+        let reply = call(source, &["MIGRATE", "127.0.0.1", &target.to_string(), "", "0", "1000", "KEYS", "foo"]).await;
+        assert_eq!(Frame::Simple("OK".to_string()), reply);
+        assert_eq!(Frame::Bulk(Bytes::from("2")), call(source, &["GET", "{foo}2"]).await);
+        assert_eq!(Frame::SimpleError(format!("ASK {} 127.0.0.1:{}", slot, target)), call(source, &["GET", "foo"]).await);
+        let mut connection = Connection::new(TcpStream::connect(("127.0.0.1", target)).await.unwrap());
+        connection.write_frame(request(&["ASKING"])).await.unwrap();
+        connection.read_frame().await.unwrap();
+        connection.write_frame(request(&["GET", "foo"])).await.unwrap();
+        assert_eq!(Some(Frame::Bulk(Bytes::from("1"))), connection.read_frame().await.unwrap());
+
+        let node = call(source, &["CLUSTER", "SETSLOT", &slot, "NODE", &target_id]).await;
+        assert!(matches!(node, Frame::SimpleError(err) if err.contains("still hold keys")));
+        call(source, &["MIGRATE", "127.0.0.1", &target.to_string(), "{foo}2", "0", "1000"]).await;
+        assert_eq!(Frame::Array(vec![]), call(source, &["CLUSTER", "GETKEYSINSLOT", &slot, "10"]).await);
+        call(target, &["CLUSTER", "SETSLOT", &slot, "NODE", &target_id]).await;
+        call(source, &["CLUSTER", "SETSLOT", &slot, "NODE", &target_id]).await;
+
+        assert_eq!(Frame::Bulk(Bytes::from("2")), call(target, &["GET", "{foo}2"]).await);
+        assert_eq!(Frame::SimpleError(format!("MOVED {} 127.0.0.1:{}", slot, target)), call(source, &["GET", "foo"]).await);
+        // The new epoch of the target wins over what the source claimed before.
+        tokio::time::sleep(PING_INTERVAL * 3).await;
+        assert_eq!(Some(target_id), source_server.cluster.as_ref().unwrap().state().slots[key_slot(b"foo") as usize]);
+    }
+
+    #[tokio::test]
+    async fn it_asks_for_keys_of_migrating_slots_elsewhere() {
+        let (server, port) = start().await;
+        let slot = key_slot(b"foo");
+        call(port, &["CLUSTER", "ADDSLOTS", &slot.to_string()]).await;
+        call(port, &["SET", "foo", "1"]).await;
+
+        let cluster = server.cluster.as_ref().unwrap();
+        cluster.state().migrating.insert(slot, cluster.myself.clone());
+        assert_eq!(Frame::Bulk(Bytes::from("1")), call(port, &["GET", "foo"]).await);
+        let ask = call(port, &["GET", "{foo}missing"]).await;
+        assert_eq!(Frame::SimpleError(format!("ASK {} 127.0.0.1:{}", slot, port)), ask);
+        assert!(matches!(call(port, &["MGET", "foo", "{foo}missing"]).await, Frame::SimpleError(err) if err.starts_with("TRYAGAIN")));
+
+        // Only clients sending ASKING first are served slots being imported.
+        {
+            let mut state = cluster.state();
+            state.migrating.clear();
+            state.slots[slot as usize] = Some("other".to_string());
+            state.importing.insert(slot, "other".to_string());
+        }
+        let mut connection = Connection::new(TcpStream::connect(("127.0.0.1", port)).await.unwrap());
+        for (arguments, redirected) in [(&["GET", "foo"][..], true), (&["ASKING"][..], false), (&["GET", "foo"][..], false)] {
+            connection.write_frame(request(arguments)).await.unwrap();
+            let reply = connection.read_frame().await.unwrap().unwrap();
+            assert_eq!(redirected, matches!(reply, Frame::SimpleError(err) if err.starts_with("MOVED")), "{:?}", arguments);
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use std::vec::IntoIter;
+
+use bytes::{Bytes, BytesMut};
+
+use crate::database::{Database, MAX_STRING_LENGTH};
+use crate::frame::Frame;
+use crate::Error;
+
+use super::{Command, error_frame, next_bytes, wrong_arity};
+
+pub(crate) struct Append {
+    key: Bytes,
+    value: Bytes,
+}
+
+impl Command for Append {
+    fn execute(&self, db: Database) -> Frame {
+        let mut db = db.lock_keys([&self.key]).unwrap();
+
+        match db.get_mut(&self.key) {
+            Err(err) => err.into(),
+            Ok(Some(stored)) => {
+                if stored.len() + self.value.len() > MAX_STRING_LENGTH {
+                    return error_frame("string exceeds maximum allowed size (proto-max-bulk-len)");
+                }
+
+                let mut value = BytesMut::with_capacity(stored.len() + self.value.len());
+                value.extend_from_slice(stored);
+                value.extend_from_slice(&self.value);
+                *stored = value.freeze();
+
+                Frame::Integer(stored.len() as i64)
+            }
+            Ok(None) => {
+                db.insert(self.key.clone(), self.value.clone());
+
+                Frame::Integer(self.value.len() as i64)
+            }
+        }
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for Append {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() != 2 {
+            return Err(wrong_arity("append"));
+        }
+
+        Ok(Append {
+            key: next_bytes(frames)?,
+            value: next_bytes(frames)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::new_db;
+
+    use super::*;
+
+    #[test]
+    fn it_appends_to_existing_value() {
+        let db = new_db();
+        db.lock().unwrap().insert("greeting".to_string(), Bytes::from("Hello"));
+        let command = Append { key: Bytes::from("greeting"), value: Bytes::from(" World") };
+
+        let result = command.execute(db.clone());
+
+        assert_eq!(Frame::Integer(11), result);
+        assert_eq!(Bytes::from("Hello World"), db.lock().unwrap().get("greeting").unwrap().unwrap());
+    }
+
+    #[test]
+    fn it_creates_missing_key() {
+        let db = new_db();
+        let command = Append { key: Bytes::from("greeting"), value: Bytes::from("Hi") };
+
+        let result = command.execute(db);
+
+        assert_eq!(Frame::Integer(2), result);
+    }
+}
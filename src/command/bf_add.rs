@@ -0,0 +1,118 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::bloom::BloomFilter;
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::Error;
+
+use super::{Command, error_frame, next_bytes, wrong_arity};
+
+/// BF.ADD and BF.MADD, the latter replying with an array even for a single item.
+pub(crate) struct BfAdd {
+    key: Bytes,
+    items: Vec<Bytes>,
+    multiple: bool,
+}
+
+impl Command for BfAdd {
+    fn execute(&self, db: Database) -> Frame {
+        let mut db = db.lock_keys([&self.key]).unwrap();
+        let filter = match db.get_bloom_mut(&self.key) {
+            Ok(Some(filter)) => filter,
+            Ok(None) => {
+                db.insert(self.key.clone(), BloomFilter::default());
+                db.get_bloom_mut(&self.key).unwrap().unwrap()
+            }
+            Err(err) => return err.into(),
+        };
+
+        let mut results = self.items.iter().map(|item| match filter.add(item) {
+            Ok(added) => Frame::Integer(added as i64),
+            Err(_) => error_frame("non scaling filter is full"),
+        });
+
+        match self.multiple {
+            true => Frame::Array(results.collect()),
+            false => results.next().unwrap(),
+        }
+    }
+}
+
+impl BfAdd {
+    pub(crate) fn madd(frames: &mut IntoIter<Frame>) -> Result<Self, Error> {
+        if frames.len() < 2 {
+            return Err(wrong_arity("bf.madd"));
+        }
+
+        let key = next_bytes(frames)?;
+        let mut items = vec![];
+        while frames.len() > 0 {
+            items.push(next_bytes(frames)?);
+        }
+
+        Ok(BfAdd { key, items, multiple: true })
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for BfAdd {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() != 2 {
+            return Err(wrong_arity("bf.add"));
+        }
+
+        Ok(BfAdd { key: next_bytes(frames)?, items: vec![next_bytes(frames)?], multiple: false })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::new_db;
+
+    use super::*;
+
+    #[test]
+    fn it_adds_item_creating_default_filter() {
+        let db = new_db();
+        let command = BfAdd { key: Bytes::from("bf"), items: vec![Bytes::from("a")], multiple: false };
+
+        assert_eq!(Frame::Integer(1), command.execute(db.clone()));
+        assert_eq!(Frame::Integer(0), command.execute(db.clone()));
+    }
+
+    #[test]
+    fn it_adds_multiple_items() {
+        let db = new_db();
+        let command = BfAdd { key: Bytes::from("bf"), items: vec![Bytes::from("a"), Bytes::from("b"), Bytes::from("a")], multiple: true };
+
+        let result = command.execute(db.clone());
+
+        assert_eq!(Frame::Array(vec![Frame::Integer(1), Frame::Integer(1), Frame::Integer(0)]), result);
+    }
+
+    #[test]
+    fn it_reports_full_non_scaling_filter() {
+        let db = new_db();
+        db.lock().unwrap().insert("bf".to_string(), BloomFilter::new(0.01, 1, None));
+        let command = BfAdd { key: Bytes::from("bf"), items: vec![Bytes::from("a"), Bytes::from("b")], multiple: true };
+
+        let result = command.execute(db.clone());
+
+        assert_eq!(Frame::Array(vec![
+            Frame::Integer(1),
+            Frame::SimpleError("ERR non scaling filter is full".to_string()),
+        ]), result);
+    }
+
+    #[test]
+    fn it_fails_for_wrong_type() {
+        let db = new_db();
+        db.lock().unwrap().insert("bf".to_string(), Bytes::from("value"));
+        let command = BfAdd { key: Bytes::from("bf"), items: vec![Bytes::from("a")], multiple: false };
+
+        assert_eq!(Frame::SimpleError("WRONGTYPE Operation against a key holding the wrong kind of value".to_string()), command.execute(db.clone()));
+    }
+}
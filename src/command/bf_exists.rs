@@ -0,0 +1,92 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::Error;
+
+use super::{Command, next_bytes, wrong_arity};
+
+/// BF.EXISTS and BF.MEXISTS, the latter replying with an array even for a single item.
+pub(crate) struct BfExists {
+    key: Bytes,
+    items: Vec<Bytes>,
+    multiple: bool,
+}
+
+impl Command for BfExists {
+    fn execute(&self, db: Database) -> Frame {
+        let db = db.lock_keys([&self.key]).unwrap();
+        let filter = match db.get_bloom(&self.key) {
+            Ok(filter) => filter,
+            Err(err) => return err.into(),
+        };
+
+        let mut results = self.items.iter()
+            .map(|item| Frame::Integer(filter.is_some_and(|filter| filter.contains(item)) as i64));
+
+        match self.multiple {
+            true => Frame::Array(results.collect()),
+            false => results.next().unwrap(),
+        }
+    }
+}
+
+impl BfExists {
+    pub(crate) fn mexists(frames: &mut IntoIter<Frame>) -> Result<Self, Error> {
+        if frames.len() < 2 {
+            return Err(wrong_arity("bf.mexists"));
+        }
+
+        let key = next_bytes(frames)?;
+        let mut items = vec![];
+        while frames.len() > 0 {
+            items.push(next_bytes(frames)?);
+        }
+
+        Ok(BfExists { key, items, multiple: true })
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for BfExists {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() != 2 {
+            return Err(wrong_arity("bf.exists"));
+        }
+
+        Ok(BfExists { key: next_bytes(frames)?, items: vec![next_bytes(frames)?], multiple: false })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bloom::BloomFilter;
+    use crate::database::new_db;
+
+    use super::*;
+
+    #[test]
+    fn it_checks_items() {
+        let db = new_db();
+        let mut filter = BloomFilter::default();
+        filter.add(b"a").unwrap();
+        db.lock().unwrap().insert("bf".to_string(), filter);
+
+        let single = BfExists { key: Bytes::from("bf"), items: vec![Bytes::from("a")], multiple: false };
+        let multiple = BfExists { key: Bytes::from("bf"), items: vec![Bytes::from("a"), Bytes::from("b")], multiple: true };
+
+        assert_eq!(Frame::Integer(1), single.execute(db.clone()));
+        assert_eq!(Frame::Array(vec![Frame::Integer(1), Frame::Integer(0)]), multiple.execute(db.clone()));
+    }
+
+    #[test]
+    fn it_returns_zero_for_missing_key() {
+        let db = new_db();
+        let command = BfExists { key: Bytes::from("bf"), items: vec![Bytes::from("a")], multiple: false };
+
+        assert_eq!(Frame::Integer(0), command.execute(db.clone()));
+    }
+}
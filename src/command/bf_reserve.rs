@@ -0,0 +1,101 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::bloom::BloomFilter;
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::Error;
+
+use super::{Command, error_frame, next_bytes, next_float, next_integer, next_string, syntax_error, wrong_arity};
+
+pub(crate) struct BfReserve {
+    key: Bytes,
+    error_rate: f64,
+    capacity: u64,
+    expansion: Option<u32>,
+}
+
+impl Command for BfReserve {
+    fn execute(&self, db: Database) -> Frame {
+        let mut db = db.lock_keys([&self.key]).unwrap();
+        if db.contains_key(&self.key) {
+            return error_frame("item exists");
+        }
+
+        db.insert(self.key.clone(), BloomFilter::new(self.error_rate, self.capacity, self.expansion));
+        Frame::Simple("OK".to_string())
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for BfReserve {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() < 3 {
+            return Err(wrong_arity("bf.reserve"));
+        }
+
+        let key = next_bytes(frames)?;
+        let error_rate = next_float(frames)?;
+        if error_rate <= 0.0 || error_rate >= 1.0 {
+            return Err("(0 < error rate range < 1)".into());
+        }
+        let capacity = next_integer(frames)?;
+        if capacity <= 0 {
+            return Err("(capacity should be larger than 0)".into());
+        }
+
+        let mut expansion = Some(crate::bloom::DEFAULT_EXPANSION);
+        let mut nonscaling = false;
+        while frames.len() > 0 {
+            match next_string(frames)?.to_uppercase().as_str() {
+                "NONSCALING" => nonscaling = true,
+                "EXPANSION" => {
+                    let value = next_integer(frames)?;
+                    if !(1..=u32::MAX as i64).contains(&value) {
+                        return Err("expansion should be greater or equal to 1".into());
+                    }
+                    expansion = Some(value as u32);
+                }
+                _ => return Err(syntax_error()),
+            }
+        }
+        if nonscaling {
+            expansion = None;
+        }
+
+        Ok(BfReserve { key, error_rate, capacity: capacity as u64, expansion })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::database::new_db;
+
+    use super::*;
+
+    fn reserve(arguments: &[&str]) -> Result<BfReserve, Error> {
+        let frames: Vec<Frame> = arguments.iter().map(|argument| Frame::Bulk(Bytes::from(argument.to_string()))).collect();
+        BfReserve::try_from(&mut frames.into_iter())
+    }
+
+    #[test]
+    fn it_creates_filter_once() {
+        let db = new_db();
+        let command = reserve(&["bf", "0.001", "1000", "NONSCALING"]).unwrap();
+
+        assert_eq!(Frame::Simple("OK".to_string()), command.execute(db.clone()));
+        assert_eq!(Frame::SimpleError("ERR item exists".to_string()), command.execute(db.clone()));
+        assert!(db.lock().unwrap().get_bloom("bf").unwrap().is_some());
+    }
+
+    #[test]
+    fn it_validates_arguments() {
+        assert_eq!("(0 < error rate range < 1)", reserve(&["bf", "1", "100"]).err().unwrap().to_string());
+        assert_eq!("(capacity should be larger than 0)", reserve(&["bf", "0.1", "0"]).err().unwrap().to_string());
+        assert_eq!("expansion should be greater or equal to 1", reserve(&["bf", "0.1", "10", "EXPANSION", "0"]).err().unwrap().to_string());
+    }
+}
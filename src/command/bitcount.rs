@@ -0,0 +1,145 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::Error;
+
+use super::{Command, next_bytes, next_integer, next_string, syntax_error, wrong_arity};
+use super::getrange::range;
+
+pub(crate) struct BitCount {
+    key: Bytes,
+    range: Option<BitRange>,
+}
+
+/// Range of a bitmap given either in bytes (the default) or in bits.
+#[derive(Debug, PartialEq)]
+pub(crate) struct BitRange {
+    pub start: i64,
+    pub end: i64,
+    pub bit: bool,
+}
+
+impl BitRange {
+    pub(crate) fn whole() -> Self {
+        BitRange { start: 0, end: -1, bit: false }
+    }
+
+    /// Resolves the range to the first and the last bit (both inclusive)
+    /// within a string of the given length in bytes.
+    pub(crate) fn resolve(&self, len: usize) -> Option<(usize, usize)> {
+        if self.bit {
+            range(self.start, self.end, len * 8)
+        } else {
+            range(self.start, self.end, len).map(|(start, end)| (start * 8, end * 8 + 7))
+        }
+    }
+
+    /// Parses the optional `BYTE | BIT` unit following the range boundaries.
+    pub(crate) fn parse_unit(frames: &mut IntoIter<Frame>) -> Result<bool, Error> {
+        match next_string(frames) {
+            Err(_) => Ok(false),
+            Ok(unit) => match unit.to_uppercase().as_str() {
+                "BYTE" if frames.len() == 0 => Ok(false),
+                "BIT" if frames.len() == 0 => Ok(true),
+                _ => Err(syntax_error()),
+            },
+        }
+    }
+}
+
+/// Counts set bits between the first and the last bit, both inclusive.
+pub(crate) fn count_bits(value: &[u8], first: usize, last: usize) -> usize {
+    let (first_byte, last_byte) = (first >> 3, last >> 3);
+    let mut count: usize = value[first_byte..=last_byte].iter()
+        .map(|byte| byte.count_ones() as usize)
+        .sum();
+
+    count -= (value[first_byte] & !(0xFFu8 >> (first & 7))).count_ones() as usize;
+    count -= (value[last_byte] & 0xFFu8.checked_shr((last & 7) as u32 + 1).unwrap_or(0)).count_ones() as usize;
+
+    count
+}
+
+impl Command for BitCount {
+    fn execute(&self, db: Database) -> Frame {
+        let db = db.lock_keys([&self.key]).unwrap();
+        let value = match db.get(&self.key) {
+            Ok(Some(value)) => value,
+            Ok(None) => return Frame::Integer(0),
+            Err(err) => return err.into(),
+        };
+
+        let range = self.range.as_ref().unwrap_or(&BitRange::whole()).resolve(value.len());
+        match range {
+            Some((first, last)) => Frame::Integer(count_bits(value, first, last) as i64),
+            None => Frame::Integer(0),
+        }
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for BitCount {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() == 0 {
+            return Err(wrong_arity("bitcount"));
+        }
+
+        let key = next_bytes(frames)?;
+        let range = match frames.len() {
+            0 => None,
+            1 => return Err(syntax_error()),
+            _ => Some(BitRange {
+                start: next_integer(frames)?,
+                end: next_integer(frames)?,
+                bit: BitRange::parse_unit(frames)?,
+            }),
+        };
+
+        Ok(BitCount { key, range })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::database::new_db;
+
+    use super::*;
+
+    fn bit_count(range: Option<BitRange>) -> Frame {
+        let db = new_db();
+        db.lock().unwrap().insert("key".to_string(), Bytes::from("foobar"));
+
+        BitCount { key: Bytes::from("key"), range }.execute(db)
+    }
+
+    #[test]
+    fn it_counts_bits_in_byte_ranges() {
+        assert_eq!(Frame::Integer(26), bit_count(None));
+        assert_eq!(Frame::Integer(4), bit_count(Some(BitRange { start: 0, end: 0, bit: false })));
+        assert_eq!(Frame::Integer(6), bit_count(Some(BitRange { start: 1, end: 1, bit: false })));
+        assert_eq!(Frame::Integer(12), bit_count(Some(BitRange { start: 1, end: -4, bit: false })));
+        assert_eq!(Frame::Integer(0), bit_count(Some(BitRange { start: -1, end: -3, bit: false })));
+    }
+
+    #[test]
+    fn it_counts_bits_in_bit_ranges() {
+        assert_eq!(Frame::Integer(17), bit_count(Some(BitRange { start: 5, end: 30, bit: true })));
+        assert_eq!(Frame::Integer(1), bit_count(Some(BitRange { start: 1, end: 1, bit: true })));
+    }
+
+    #[test]
+    fn it_requires_both_range_boundaries() {
+        let mut iter: IntoIter<Frame> = vec![
+            Frame::Bulk(Bytes::from("key")),
+            Frame::Bulk(Bytes::from("1")),
+        ].into_iter();
+
+        assert!(BitCount::try_from(&mut iter).is_err());
+    }
+}
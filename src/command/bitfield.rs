@@ -0,0 +1,364 @@
+use std::vec::IntoIter;
+
+use bytes::BytesMut;
+
+use bytes::Bytes;
+
+use crate::database::{Database, MAX_STRING_LENGTH};
+use crate::frame::Frame;
+use crate::Error;
+
+use super::{Command, next_bytes, next_integer, next_string, syntax_error, wrong_arity};
+
+/// Covers BITFIELD and its read only variant BITFIELD_RO.
+pub(crate) struct BitField {
+    key: Bytes,
+    operations: Vec<Operation>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Operation {
+    Get(Encoding, usize),
+    Set(Encoding, usize, i64, Overflow),
+    IncrBy(Encoding, usize, i64, Overflow),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Encoding {
+    signed: bool,
+    bits: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Overflow {
+    Wrap,
+    Sat,
+    Fail,
+}
+
+impl Command for BitField {
+    fn execute(&self, db: Database) -> Frame {
+        let mut db = db.lock_keys([&self.key]).unwrap();
+        let mut value = match db.get(&self.key) {
+            Ok(value) => BytesMut::from(&value.cloned().unwrap_or_default()[..]),
+            Err(err) => return err.into(),
+        };
+        let mut modified = false;
+        let mut results = vec![];
+
+        for operation in &self.operations {
+            let result = match *operation {
+                Operation::Get(encoding, offset) => Frame::Integer(encoding.read(&value, offset)),
+                Operation::Set(encoding, offset, new, overflow) => {
+                    let old = encoding.read(&value, offset);
+                    match encoding.fit(new, 0, overflow) {
+                        Some(new) => {
+                            encoding.write(&mut value, offset, new);
+                            modified = true;
+                            Frame::Integer(old)
+                        }
+                        None => Frame::Null,
+                    }
+                }
+                Operation::IncrBy(encoding, offset, increment, overflow) => {
+                    let old = encoding.read(&value, offset);
+                    match encoding.fit(old, increment, overflow) {
+                        Some(new) => {
+                            encoding.write(&mut value, offset, new);
+                            modified = true;
+                            Frame::Integer(new)
+                        }
+                        None => Frame::Null,
+                    }
+                }
+            };
+            results.push(result);
+        }
+
+        if modified {
+            db.update(&self.key, value.freeze());
+        }
+
+        Frame::Array(results)
+    }
+}
+
+impl Encoding {
+    fn read(&self, value: &[u8], offset: usize) -> i64 {
+        let mut result: u64 = 0;
+        for i in 0..self.bits as usize {
+            let position = offset + i;
+            let bit = value.get(position >> 3).map_or(0, |byte| (byte >> (7 - (position & 7))) & 1);
+            result = (result << 1) | bit as u64;
+        }
+
+        if self.signed && self.bits < 64 && result & (1 << (self.bits - 1)) != 0 {
+            result |= u64::MAX << self.bits;
+        }
+
+        result as i64
+    }
+
+    fn write(&self, value: &mut BytesMut, offset: usize, new: i64) {
+        let end = (offset + self.bits as usize).div_ceil(8);
+        if value.len() < end {
+            value.resize(end, 0);
+        }
+
+        let new = new as u64;
+        for i in 0..self.bits as usize {
+            let position = offset + i;
+            let mask = 1u8 << (7 - (position & 7));
+            if (new >> (self.bits as usize - 1 - i)) & 1 == 1 {
+                value[position >> 3] |= mask;
+            } else {
+                value[position >> 3] &= !mask;
+            }
+        }
+    }
+
+    /// Adds the increment to the value handling overflow the same way as Redis does,
+    /// returns None when the result does not fit and the policy is FAIL.
+    fn fit(&self, value: i64, increment: i64, overflow: Overflow) -> Option<i64> {
+        let wrapped = self.wrap(value.wrapping_add(increment));
+
+        if self.signed {
+            let max = if self.bits == 64 { i64::MAX } else { (1i64 << (self.bits - 1)) - 1 };
+            let min = -max - 1;
+            let max_increment = max.wrapping_sub(value);
+            let min_increment = min.wrapping_sub(value);
+
+            if value > max
+                || (self.bits != 64 && increment > max_increment)
+                || (value >= 0 && increment > 0 && increment > max_increment) {
+                return self.on_overflow(overflow, wrapped, max);
+            }
+            if value < min
+                || (self.bits != 64 && increment < min_increment)
+                || (value < 0 && increment < 0 && increment < min_increment) {
+                return self.on_overflow(overflow, wrapped, min);
+            }
+        } else {
+            let max = (1u64 << self.bits) - 1;
+            let value = value as u64;
+            let max_increment = max.wrapping_sub(value) as i64;
+            let min_increment = (value as i64).wrapping_neg();
+
+            if value > max || (increment > 0 && increment > max_increment) {
+                return self.on_overflow(overflow, wrapped, max as i64);
+            }
+            if increment < 0 && increment < min_increment {
+                return self.on_overflow(overflow, wrapped, 0);
+            }
+        }
+
+        Some(wrapped)
+    }
+
+    fn on_overflow(&self, overflow: Overflow, wrapped: i64, limit: i64) -> Option<i64> {
+        match overflow {
+            Overflow::Wrap => Some(wrapped),
+            Overflow::Sat => Some(limit),
+            Overflow::Fail => None,
+        }
+    }
+
+    /// Truncates the value to the width of the encoding.
+    fn wrap(&self, value: i64) -> i64 {
+        if self.bits == 64 {
+            return value;
+        }
+
+        let value = value as u64;
+        let mask = u64::MAX << self.bits;
+        if self.signed && value & (1 << (self.bits - 1)) != 0 {
+            (value | mask) as i64
+        } else {
+            (value & !mask) as i64
+        }
+    }
+}
+
+impl BitField {
+    pub(crate) fn read_only(frames: &mut IntoIter<Frame>) -> Result<Self, Error> {
+        let command = BitField::parse(frames, "bitfield_ro")?;
+
+        if command.operations.iter().any(|operation| !matches!(operation, Operation::Get(..))) {
+            return Err("BITFIELD_RO only supports the GET subcommand".into());
+        }
+
+        Ok(command)
+    }
+
+    fn parse(frames: &mut IntoIter<Frame>, name: &str) -> Result<Self, Error> {
+        if frames.len() == 0 {
+            return Err(wrong_arity(name));
+        }
+
+        let key = next_bytes(frames)?;
+        let mut operations = vec![];
+        let mut overflow = Overflow::Wrap;
+
+        while let Ok(subcommand) = next_string(frames) {
+            let subcommand = subcommand.to_uppercase();
+            match subcommand.as_str() {
+                "GET" => {
+                    let encoding = next_encoding(frames)?;
+                    let offset = next_offset(frames, encoding)?;
+                    operations.push(Operation::Get(encoding, offset));
+                }
+                "SET" | "INCRBY" => {
+                    let encoding = next_encoding(frames)?;
+                    let offset = next_offset(frames, encoding)?;
+                    let value = next_integer(frames)?;
+                    operations.push(match subcommand.as_str() {
+                        "SET" => Operation::Set(encoding, offset, value, overflow),
+                        _ => Operation::IncrBy(encoding, offset, value, overflow),
+                    });
+                }
+                "OVERFLOW" => {
+                    overflow = match next_string(frames).map(|policy| policy.to_uppercase()).as_deref() {
+                        Ok("WRAP") => Overflow::Wrap,
+                        Ok("SAT") => Overflow::Sat,
+                        Ok("FAIL") => Overflow::Fail,
+                        _ => return Err("Invalid OVERFLOW type specified".into()),
+                    };
+                }
+                _ => return Err(syntax_error()),
+            }
+        }
+
+        Ok(BitField { key, operations })
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for BitField {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        BitField::parse(frames, "bitfield")
+    }
+}
+
+fn next_encoding(frames: &mut IntoIter<Frame>) -> Result<Encoding, Error> {
+    let invalid = || -> Error {
+        "Invalid bitfield type. Use something like i16 u8. Note that u64 is not supported but i64 is.".into()
+    };
+    let encoding = next_string(frames).map_err(|_| syntax_error())?;
+    let (signed, bits) = match encoding.split_at_checked(1) {
+        Some(("i" | "I", bits)) => (true, bits),
+        Some(("u" | "U", bits)) => (false, bits),
+        _ => return Err(invalid()),
+    };
+    let bits: u32 = bits.parse().map_err(|_| invalid())?;
+
+    if bits == 0 || (signed && bits > 64) || (!signed && bits > 63) {
+        return Err(invalid());
+    }
+
+    Ok(Encoding { signed, bits })
+}
+
+/// Reads an offset given either in bits or, when prefixed with `#`, in multiples of the encoding width.
+fn next_offset(frames: &mut IntoIter<Frame>, encoding: Encoding) -> Result<usize, Error> {
+    let invalid = || -> Error { "bit offset is not an integer or out of range".into() };
+    let offset = next_string(frames).map_err(|_| syntax_error())?;
+
+    let offset = match offset.strip_prefix('#') {
+        Some(multiple) => multiple.parse::<i64>().ok().and_then(|m| m.checked_mul(encoding.bits as i64)),
+        None => offset.parse::<i64>().ok(),
+    };
+
+    match offset {
+        Some(offset) if offset >= 0 && (offset as usize + encoding.bits as usize) >> 3 < MAX_STRING_LENGTH => Ok(offset as usize),
+        _ => Err(invalid()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::database::new_db;
+
+    use super::*;
+
+    fn bitfield(arguments: &[&str]) -> (Frame, Option<Bytes>) {
+        let db = new_db();
+        let mut frames = vec![Frame::Bulk(Bytes::from("key"))];
+        frames.extend(arguments.iter().map(|argument| Frame::Bulk(Bytes::from(argument.to_string()))));
+        let command = BitField::try_from(&mut frames.into_iter()).unwrap();
+
+        let result = command.execute(db.clone());
+        let stored = db.lock().unwrap().get("key").unwrap().cloned();
+        (result, stored)
+    }
+
+    #[test]
+    fn it_sets_and_gets_signed_and_unsigned_integers() {
+        let (result, stored) = bitfield(&["SET", "u8", "#1", "255", "GET", "u8", "8", "GET", "i8", "8", "GET", "u4", "#3"]);
+
+        assert_eq!(Frame::Array(vec![Frame::Integer(0), Frame::Integer(255), Frame::Integer(-1), Frame::Integer(15)]), result);
+        assert_eq!(Some(Bytes::from(&[0x00, 0xFF][..])), stored);
+    }
+
+    #[test]
+    fn it_wraps_by_default() {
+        let (result, _) = bitfield(&["INCRBY", "u2", "100", "1", "INCRBY", "u2", "100", "5", "INCRBY", "i5", "0", "20"]);
+
+        assert_eq!(Frame::Array(vec![Frame::Integer(1), Frame::Integer(2), Frame::Integer(-12)]), result);
+    }
+
+    #[test]
+    fn it_saturates_and_fails_on_overflow() {
+        let (result, _) = bitfield(&[
+            "OVERFLOW", "SAT", "INCRBY", "u2", "0", "10", "INCRBY", "i8", "8", "-200",
+            "OVERFLOW", "FAIL", "INCRBY", "u2", "0", "1", "SET", "i8", "16", "128",
+        ]);
+
+        assert_eq!(Frame::Array(vec![Frame::Integer(3), Frame::Integer(-128), Frame::Null, Frame::Null]), result);
+    }
+
+    #[test]
+    fn it_handles_64_bit_signed_integers() {
+        let (result, _) = bitfield(&["SET", "i64", "0", "-1", "GET", "i64", "0", "OVERFLOW", "SAT", "INCRBY", "i64", "0", "-9223372036854775807"]);
+
+        assert_eq!(Frame::Array(vec![Frame::Integer(0), Frame::Integer(-1), Frame::Integer(i64::MIN)]), result);
+    }
+
+    #[test]
+    fn it_does_not_create_key_with_get_only() {
+        let (result, stored) = bitfield(&["GET", "u8", "0"]);
+
+        assert_eq!(Frame::Array(vec![Frame::Integer(0)]), result);
+        assert_eq!(None, stored);
+    }
+
+    #[test]
+    fn it_rejects_unsupported_encodings() {
+        let mut iter: IntoIter<Frame> = vec![
+            Frame::Bulk(Bytes::from("key")),
+            Frame::Bulk(Bytes::from("GET")),
+            Frame::Bulk(Bytes::from("u64")),
+            Frame::Bulk(Bytes::from("0")),
+        ].into_iter();
+
+        assert!(BitField::try_from(&mut iter).is_err());
+    }
+
+    #[test]
+    fn it_allows_only_get_in_read_only_variant() {
+        let mut iter: IntoIter<Frame> = vec![
+            Frame::Bulk(Bytes::from("key")),
+            Frame::Bulk(Bytes::from("SET")),
+            Frame::Bulk(Bytes::from("u8")),
+            Frame::Bulk(Bytes::from("0")),
+            Frame::Bulk(Bytes::from("1")),
+        ].into_iter();
+
+        assert_eq!(
+            "BITFIELD_RO only supports the GET subcommand",
+            BitField::read_only(&mut iter).err().unwrap().to_string()
+        );
+    }
+}
@@ -0,0 +1,121 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::Error;
+
+use super::{Command, next_bytes, next_string, syntax_error, wrong_arity};
+
+pub(crate) struct BitOp {
+    operation: Operation,
+    destination: Bytes,
+    keys: Vec<Bytes>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Operation {
+    And,
+    Or,
+    Xor,
+    Not,
+}
+
+impl Command for BitOp {
+    fn execute(&self, db: Database) -> Frame {
+        let mut db = db.lock_keys(std::iter::once(&self.destination).chain(&self.keys)).unwrap();
+        let sources: Vec<Bytes> = match self.keys.iter()
+            .map(|key| db.get(key).map(|value| value.cloned().unwrap_or_default()))
+            .collect() {
+            Ok(sources) => sources,
+            Err(err) => return err.into(),
+        };
+        let len = sources.iter().map(Bytes::len).max().unwrap_or(0);
+
+        let result: Vec<u8> = (0..len)
+            .map(|i| {
+                let mut bytes = sources.iter().map(|source| source.get(i).copied().unwrap_or(0));
+                let first = bytes.next().unwrap();
+                match self.operation {
+                    Operation::And => bytes.fold(first, |acc, byte| acc & byte),
+                    Operation::Or => bytes.fold(first, |acc, byte| acc | byte),
+                    Operation::Xor => bytes.fold(first, |acc, byte| acc ^ byte),
+                    Operation::Not => !first,
+                }
+            })
+            .collect();
+
+        if result.is_empty() {
+            db.remove(&self.destination);
+        } else {
+            db.insert(self.destination.clone(), Bytes::from(result));
+        }
+
+        Frame::Integer(len as i64)
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for BitOp {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() < 3 {
+            return Err(wrong_arity("bitop"));
+        }
+
+        let operation = match next_string(frames)?.to_uppercase().as_str() {
+            "AND" => Operation::And,
+            "OR" => Operation::Or,
+            "XOR" => Operation::Xor,
+            "NOT" => Operation::Not,
+            _ => return Err(syntax_error()),
+        };
+        let destination = next_bytes(frames)?;
+        let mut keys = vec![];
+        while frames.len() > 0 {
+            keys.push(next_bytes(frames)?);
+        }
+
+        if operation == Operation::Not && keys.len() != 1 {
+            return Err("BITOP NOT must be called with a single source key.".into());
+        }
+
+        Ok(BitOp { operation, destination, keys })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::new_db;
+
+    use super::*;
+
+    fn bit_op(operation: Operation, keys: Vec<&str>) -> (Frame, Option<Bytes>) {
+        let db = new_db();
+        db.lock().unwrap().insert("a".to_string(), Bytes::from(&[0b1100, 0xFF][..]));
+        db.lock().unwrap().insert("b".to_string(), Bytes::from(&[0b1010][..]));
+        let command = BitOp {
+            operation,
+            destination: Bytes::from("dest"),
+            keys: keys.into_iter().map(|key| Bytes::copy_from_slice(key.as_bytes())).collect(),
+        };
+
+        let result = command.execute(db.clone());
+        let stored = db.lock().unwrap().get("dest").unwrap().cloned();
+        (result, stored)
+    }
+
+    #[test]
+    fn it_pads_shorter_strings_with_zeros() {
+        assert_eq!((Frame::Integer(2), Some(Bytes::from(&[0b1000, 0x00][..]))), bit_op(Operation::And, vec!["a", "b"]));
+        assert_eq!((Frame::Integer(2), Some(Bytes::from(&[0b1110, 0xFF][..]))), bit_op(Operation::Or, vec!["a", "b"]));
+        assert_eq!((Frame::Integer(2), Some(Bytes::from(&[0b0110, 0xFF][..]))), bit_op(Operation::Xor, vec!["a", "b"]));
+        assert_eq!((Frame::Integer(1), Some(Bytes::from(&[0b1111_0101][..]))), bit_op(Operation::Not, vec!["b"]));
+    }
+
+    #[test]
+    fn it_removes_destination_when_result_is_empty() {
+        assert_eq!((Frame::Integer(0), None), bit_op(Operation::Or, vec!["missing"]));
+    }
+}
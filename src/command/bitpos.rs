@@ -0,0 +1,132 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::Error;
+
+use super::{Command, next_bytes, next_integer, wrong_arity};
+use super::bitcount::BitRange;
+
+pub(crate) struct BitPos {
+    key: Bytes,
+    bit: bool,
+    range: BitRange,
+    end_given: bool,
+}
+
+impl Command for BitPos {
+    fn execute(&self, db: Database) -> Frame {
+        let db = db.lock_keys([&self.key]).unwrap();
+        let value = match db.get(&self.key) {
+            Ok(Some(value)) => value,
+            Ok(None) => return Frame::Integer(if self.bit { -1 } else { 0 }),
+            Err(err) => return err.into(),
+        };
+
+        let (first, last) = match self.range.resolve(value.len()) {
+            Some(range) => range,
+            None => return Frame::Integer(-1),
+        };
+
+        let skipped = if self.bit { 0x00 } else { 0xFF };
+        let mut position = first;
+        while position <= last {
+            let byte = value[position >> 3];
+
+            if position & 7 == 0 && position + 7 <= last && byte == skipped {
+                position += 8;
+                continue;
+            }
+
+            if ((byte >> (7 - (position & 7))) & 1 == 1) == self.bit {
+                return Frame::Integer(position as i64);
+            }
+            position += 1;
+        }
+
+        if !self.bit && !self.end_given {
+            return Frame::Integer(position as i64);
+        }
+
+        Frame::Integer(-1)
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for BitPos {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() < 2 {
+            return Err(wrong_arity("bitpos"));
+        }
+
+        let key = next_bytes(frames)?;
+        let bit = match next_integer(frames) {
+            Ok(0) => false,
+            Ok(1) => true,
+            _ => return Err("The bit argument must be 1 or 0.".into()),
+        };
+        let mut range = BitRange::whole();
+        let end_given = frames.len() > 1;
+
+        if frames.len() > 0 {
+            range.start = next_integer(frames)?;
+        }
+        if frames.len() > 0 {
+            range.end = next_integer(frames)?;
+            range.bit = BitRange::parse_unit(frames)?;
+        }
+
+        Ok(BitPos { key, bit, range, end_given })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::database::new_db;
+
+    use super::*;
+
+    fn bit_pos(value: &'static [u8], bit: bool, range: BitRange, end_given: bool) -> Frame {
+        let db = new_db();
+        db.lock().unwrap().insert("key".to_string(), Bytes::from(value));
+
+        BitPos { key: Bytes::from("key"), bit, range, end_given }.execute(db)
+    }
+
+    #[test]
+    fn it_finds_first_clear_bit() {
+        assert_eq!(Frame::Integer(12), bit_pos(&[0xFF, 0xF0, 0x00], false, BitRange::whole(), false));
+    }
+
+    #[test]
+    fn it_finds_first_set_bit_in_range() {
+        let value: &[u8] = &[0x00, 0xFF, 0xF0];
+
+        assert_eq!(Frame::Integer(8), bit_pos(value, true, BitRange::whole(), false));
+        assert_eq!(Frame::Integer(16), bit_pos(value, true, BitRange { start: 2, end: -1, bit: false }, true));
+        assert_eq!(Frame::Integer(8), bit_pos(value, true, BitRange { start: 7, end: 15, bit: true }, true));
+        assert_eq!(Frame::Integer(-1), bit_pos(&[0x00, 0x00], true, BitRange::whole(), false));
+    }
+
+    #[test]
+    fn it_treats_string_as_padded_with_zeros_without_explicit_end() {
+        assert_eq!(Frame::Integer(24), bit_pos(&[0xFF, 0xFF, 0xFF], false, BitRange::whole(), false));
+        assert_eq!(Frame::Integer(-1), bit_pos(&[0xFF, 0xFF, 0xFF], false, BitRange { start: 0, end: -1, bit: false }, true));
+    }
+
+    #[test]
+    fn it_handles_missing_keys() {
+        let db = new_db();
+
+        let clear = BitPos { key: Bytes::from("nope"), bit: false, range: BitRange::whole(), end_given: false };
+        let set = BitPos { key: Bytes::from("nope"), bit: true, range: BitRange::whole(), end_given: false };
+
+        assert_eq!(Frame::Integer(0), clear.execute(db.clone()));
+        assert_eq!(Frame::Integer(-1), set.execute(db));
+    }
+}
@@ -0,0 +1,60 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::cuckoo::CuckooFilter;
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::Error;
+
+use super::{Command, next_bytes, wrong_arity};
+
+pub(crate) struct CfAdd {
+    key: Bytes,
+    item: Bytes,
+}
+
+impl Command for CfAdd {
+    fn execute(&self, db: Database) -> Frame {
+        let mut db = db.lock_keys([&self.key]).unwrap();
+        match db.get_cuckoo_mut(&self.key) {
+            Ok(Some(filter)) => filter.add(&self.item),
+            Ok(None) => {
+                let mut filter = CuckooFilter::default();
+                filter.add(&self.item);
+                db.insert(self.key.clone(), filter);
+            }
+            Err(err) => return err.into(),
+        }
+
+        Frame::Integer(1)
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for CfAdd {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() != 2 {
+            return Err(wrong_arity("cf.add"));
+        }
+
+        Ok(CfAdd { key: next_bytes(frames)?, item: next_bytes(frames)? })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::new_db;
+
+    use super::*;
+
+    #[test]
+    fn it_adds_item_creating_default_filter() {
+        let db = new_db();
+        let command = CfAdd { key: Bytes::from("cf"), item: Bytes::from("a") };
+
+        assert_eq!(Frame::Integer(1), command.execute(db.clone()));
+        assert!(db.lock().unwrap().get_cuckoo("cf").unwrap().unwrap().contains(b"a"));
+    }
+}
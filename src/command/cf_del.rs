@@ -0,0 +1,65 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::Error;
+
+use super::{Command, error_frame, next_bytes, wrong_arity};
+
+pub(crate) struct CfDel {
+    key: Bytes,
+    item: Bytes,
+}
+
+impl Command for CfDel {
+    fn execute(&self, db: Database) -> Frame {
+        let mut db = db.lock_keys([&self.key]).unwrap();
+        match db.get_cuckoo_mut(&self.key) {
+            Ok(Some(filter)) => Frame::Integer(filter.remove(&self.item) as i64),
+            Ok(None) => error_frame("Not found"),
+            Err(err) => err.into(),
+        }
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for CfDel {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() != 2 {
+            return Err(wrong_arity("cf.del"));
+        }
+
+        Ok(CfDel { key: next_bytes(frames)?, item: next_bytes(frames)? })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cuckoo::CuckooFilter;
+    use crate::database::new_db;
+
+    use super::*;
+
+    #[test]
+    fn it_deletes_item() {
+        let db = new_db();
+        let mut filter = CuckooFilter::default();
+        filter.add(b"a");
+        db.lock().unwrap().insert("cf".to_string(), filter);
+        let command = CfDel { key: Bytes::from("cf"), item: Bytes::from("a") };
+
+        assert_eq!(Frame::Integer(1), command.execute(db.clone()));
+        assert_eq!(Frame::Integer(0), command.execute(db.clone()));
+    }
+
+    #[test]
+    fn it_fails_for_missing_key() {
+        let db = new_db();
+        let command = CfDel { key: Bytes::from("cf"), item: Bytes::from("a") };
+
+        assert_eq!(Frame::SimpleError("ERR Not found".to_string()), command.execute(db.clone()));
+    }
+}
@@ -0,0 +1,57 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::Error;
+
+use super::{Command, next_bytes, wrong_arity};
+
+pub(crate) struct CfExists {
+    key: Bytes,
+    item: Bytes,
+}
+
+impl Command for CfExists {
+    fn execute(&self, db: Database) -> Frame {
+        let db = db.lock_keys([&self.key]).unwrap();
+        match db.get_cuckoo(&self.key) {
+            Ok(Some(filter)) => Frame::Integer(filter.contains(&self.item) as i64),
+            Ok(None) => Frame::Integer(0),
+            Err(err) => err.into(),
+        }
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for CfExists {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() != 2 {
+            return Err(wrong_arity("cf.exists"));
+        }
+
+        Ok(CfExists { key: next_bytes(frames)?, item: next_bytes(frames)? })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cuckoo::CuckooFilter;
+    use crate::database::new_db;
+
+    use super::*;
+
+    #[test]
+    fn it_checks_item() {
+        let db = new_db();
+        let mut filter = CuckooFilter::default();
+        filter.add(b"a");
+        db.lock().unwrap().insert("cf".to_string(), filter);
+
+        assert_eq!(Frame::Integer(1), CfExists { key: Bytes::from("cf"), item: Bytes::from("a") }.execute(db.clone()));
+        assert_eq!(Frame::Integer(0), CfExists { key: Bytes::from("cf"), item: Bytes::from("b") }.execute(db.clone()));
+        assert_eq!(Frame::Integer(0), CfExists { key: Bytes::from("other"), item: Bytes::from("a") }.execute(db.clone()));
+    }
+}
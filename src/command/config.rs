@@ -0,0 +1,210 @@
+use std::vec::IntoIter;
+
+use crate::database::Database;
+use crate::eviction::{EvictionPolicy, Memory};
+use crate::frame::Frame;
+use crate::glob;
+use crate::Error;
+
+use super::{Command, error_frame, next_string, wrong_arity};
+
+/// Parameters CONFIG knows about, in the order CONFIG GET reports them.
+const PARAMETERS: [&str; 5] = ["maxmemory", "maxmemory-policy", "maxmemory-samples", "zset-max-listpack-entries", "zset-max-listpack-value"];
+
+pub(crate) enum Config {
+    Get(Vec<String>),
+    Set(Vec<(String, String)>),
+}
+
+impl Command for Config {
+    fn execute(&self, db: Database) -> Frame {
+        match self {
+            Config::Get(patterns) => {
+                let parameters = PARAMETERS.iter()
+                    .filter(|name| patterns.iter().any(|pattern| glob::matches(pattern.to_lowercase().as_bytes(), name.as_bytes())))
+                    .flat_map(|name| [name.to_string(), get(db.memory(), name)])
+                    .map(|value| Frame::Bulk(value.into()))
+                    .collect();
+
+                Frame::Array(parameters)
+            }
+            Config::Set(parameters) => {
+                // All values are checked before any is applied, so a bad one changes nothing.
+                for (name, value) in parameters {
+                    if let Err(err) = parse(name, value) {
+                        return error_frame(&format!("CONFIG SET failed (possibly related to argument '{}') - {}", name, err));
+                    }
+                }
+                for (name, value) in parameters {
+                    let _ = set(db.memory(), name, value);
+                }
+
+                Frame::Simple("OK".to_string())
+            }
+        }
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for Config {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        let subcommand = next_string(frames).map_err(|_| wrong_arity("config"))?.to_uppercase();
+
+        match subcommand.as_str() {
+            "GET" if frames.len() > 0 => {
+                let mut patterns = vec![];
+                while frames.len() > 0 {
+                    patterns.push(next_string(frames)?);
+                }
+                Ok(Config::Get(patterns))
+            }
+            "SET" if frames.len() > 0 && frames.len().is_multiple_of(2) => {
+                let mut parameters = vec![];
+                while frames.len() > 0 {
+                    parameters.push((next_string(frames)?.to_lowercase(), next_string(frames)?));
+                }
+                Ok(Config::Set(parameters))
+            }
+            "GET" | "SET" => Err(wrong_arity(&format!("config|{}", subcommand.to_lowercase()))),
+            _ => Err(format!("unknown subcommand '{}'. Try CONFIG HELP.", subcommand).into()),
+        }
+    }
+}
+
+enum Parameter {
+    MaxMemory(usize),
+    Policy(EvictionPolicy),
+    Samples(usize),
+    CompactEntries(usize),
+    CompactValue(usize),
+}
+
+fn parse(name: &str, value: &str) -> Result<Parameter, String> {
+    match name.to_lowercase().as_str() {
+        "maxmemory" => parse_memory(value).map(Parameter::MaxMemory).ok_or_else(|| "argument must be a memory value".to_string()),
+        "maxmemory-policy" => EvictionPolicy::parse(value).map(Parameter::Policy).ok_or_else(|| "argument(s) must be one of the following: volatile-lru, allkeys-lru, volatile-lfu, allkeys-lfu, volatile-random, allkeys-random, volatile-ttl, noeviction".to_string()),
+        "maxmemory-samples" => match value.parse() {
+            Ok(samples) if (1..=64).contains(&samples) => Ok(Parameter::Samples(samples)),
+            _ => Err("argument must be between 1 and 64 inclusive".to_string()),
+        },
+        "zset-max-listpack-entries" => value.parse().map(Parameter::CompactEntries).map_err(|_| "argument couldn't be parsed into an integer".to_string()),
+        "zset-max-listpack-value" => value.parse().map(Parameter::CompactValue).map_err(|_| "argument couldn't be parsed into an integer".to_string()),
+        _ => Err("Unknown option or number of arguments for CONFIG SET".to_string()),
+    }
+}
+
+/// Applies the parameter, the server uses it for the settings given on the command line.
+pub(crate) fn set(memory: &Memory, name: &str, value: &str) -> Result<(), String> {
+    match parse(name, value)? {
+        Parameter::MaxMemory(bytes) => memory.set_limit(bytes),
+        Parameter::Policy(policy) => memory.set_policy(policy),
+        Parameter::Samples(samples) => memory.set_samples(samples),
+        Parameter::CompactEntries(entries) => memory.set_compact_entries(entries),
+        Parameter::CompactValue(length) => memory.set_compact_value(length),
+    }
+
+    Ok(())
+}
+
+fn get(memory: &Memory, name: &str) -> String {
+    match name {
+        "maxmemory" => memory.limit().to_string(),
+        "maxmemory-policy" => memory.policy().name().to_string(),
+        "maxmemory-samples" => memory.samples().to_string(),
+        "zset-max-listpack-entries" => memory.compact_limits().entries.to_string(),
+        _ => memory.compact_limits().value.to_string(),
+    }
+}
+
+/// Parses a number of bytes, optionally with a unit like `100mb`.
+fn parse_memory(value: &str) -> Option<usize> {
+    let value = value.to_lowercase();
+    let split = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let multiplier = match unit {
+        "" | "b" => 1,
+        "k" => 1000,
+        "kb" => 1024,
+        "m" => 1000 * 1000,
+        "mb" => 1024 * 1024,
+        "g" => 1000 * 1000 * 1000,
+        "gb" => 1024 * 1024 * 1024,
+        _ => return None,
+    };
+
+    number.parse::<usize>().ok()?.checked_mul(multiplier)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::new_db;
+    use crate::sorted_set::CompactLimits;
+
+    use super::*;
+
+    fn config(args: &[&str]) -> Result<Config, Error> {
+        let frames: Vec<Frame> = args.iter().map(|arg| Frame::Bulk(arg.to_string().into())).collect();
+        Config::try_from(&mut frames.into_iter())
+    }
+
+    #[test]
+    fn it_sets_and_gets_parameters() {
+        let db = new_db();
+
+        let reply = config(&["SET", "maxmemory", "2mb", "maxmemory-policy", "allkeys-lru"]).unwrap().execute(db.clone());
+        assert_eq!(Frame::Simple("OK".to_string()), reply);
+
+        assert_eq!(
+            Frame::Array(vec![
+                Frame::Bulk("maxmemory".into()),
+                Frame::Bulk("2097152".into()),
+                Frame::Bulk("maxmemory-policy".into()),
+                Frame::Bulk("allkeys-lru".into()),
+            ]),
+            config(&["GET", "maxmemory", "*-POLICY"]).unwrap().execute(db.clone())
+        );
+        assert_eq!(
+            Frame::Array(vec![Frame::Bulk("maxmemory-samples".into()), Frame::Bulk("5".into())]),
+            config(&["GET", "*samples"]).unwrap().execute(db.clone())
+        );
+    }
+
+    #[test]
+    fn it_sets_compact_encoding_thresholds() {
+        let db = new_db();
+
+        let reply = config(&["SET", "zset-max-listpack-entries", "1", "zset-max-listpack-value", "16"]).unwrap().execute(db.clone());
+        assert_eq!(Frame::Simple("OK".to_string()), reply);
+
+        assert_eq!(
+            Frame::Array(vec![Frame::Bulk("zset-max-listpack-entries".into()), Frame::Bulk("1".into())]),
+            config(&["GET", "zset-*-entries"]).unwrap().execute(db.clone())
+        );
+        assert_eq!(CompactLimits { entries: 1, value: 16 }, db.memory().compact_limits());
+        assert_eq!(CompactLimits::default(), new_db().memory().compact_limits());
+        assert!(matches!(
+            config(&["SET", "zset-max-listpack-value", "-1"]).unwrap().execute(db.clone()),
+            Frame::SimpleError(_)
+        ));
+    }
+
+    #[test]
+    fn it_rejects_invalid_values_without_applying_any() {
+        let db = new_db();
+
+        let reply = config(&["SET", "maxmemory", "1gb", "maxmemory-policy", "sometimes"]).unwrap().execute(db.clone());
+
+        assert!(matches!(reply, Frame::SimpleError(message) if message.contains("maxmemory-policy")));
+        assert_eq!(0, db.memory().limit());
+    }
+
+    #[test]
+    fn it_parses_memory_units() {
+        assert_eq!(Some(100), parse_memory("100"));
+        assert_eq!(Some(1000), parse_memory("1k"));
+        assert_eq!(Some(3 * 1024 * 1024), parse_memory("3MB"));
+        assert_eq!(None, parse_memory("1tb"));
+        assert_eq!(None, parse_memory("mb"));
+    }
+}
@@ -0,0 +1,129 @@
+use std::time::SystemTime;
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::database::{Database, Keyspace, Value};
+use crate::frame::Frame;
+use crate::Error;
+
+use super::{Command, error_frame, next_bytes, next_db_index, next_string, syntax_error, wrong_arity};
+
+pub(crate) struct Copy {
+    source: Bytes,
+    destination: Bytes,
+    /// Database to copy into, the selected one when absent.
+    db: Option<usize>,
+    replace: bool,
+}
+
+impl Command for Copy {
+    fn execute(&self, db: Database) -> Frame {
+        let target = self.db.unwrap_or(db.index());
+        if db.select(target).is_none() {
+            return error_frame("DB index is out of range");
+        }
+
+        let entry = |keyspace: &Keyspace| {
+            keyspace.get_value(&self.source).cloned().map(|value| (value, keyspace.expiration(&self.source)))
+        };
+
+        if target == db.index() {
+            if self.source == self.destination {
+                return Frame::Integer(0);
+            }
+            let mut keyspace = db.lock_keys([&self.source, &self.destination]).unwrap();
+            let entry = entry(&keyspace);
+            self.store(&mut keyspace, entry)
+        } else {
+            let (source, mut destination) = db.lock_pair(db.index(), target);
+            self.store(&mut destination, entry(&source))
+        }
+    }
+}
+
+impl Copy {
+    fn store(&self, keyspace: &mut Keyspace, entry: Option<(Value, Option<SystemTime>)>) -> Frame {
+        match entry {
+            Some(_) if !self.replace && keyspace.contains_key(&self.destination) => Frame::Integer(0),
+            Some((value, expires_at)) => {
+                keyspace.insert_with_expiration(self.destination.clone(), value, expires_at);
+                Frame::Integer(1)
+            }
+            None => Frame::Integer(0),
+        }
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for Copy {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() < 2 {
+            return Err(wrong_arity("copy"));
+        }
+
+        let mut command = Copy { source: next_bytes(frames)?, destination: next_bytes(frames)?, db: None, replace: false };
+        while frames.len() > 0 {
+            match next_string(frames)?.to_uppercase().as_str() {
+                "DB" if frames.len() > 0 => command.db = Some(next_db_index(frames)?),
+                "REPLACE" => command.replace = true,
+                _ => return Err(syntax_error()),
+            }
+        }
+
+        Ok(command)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use bytes::Bytes;
+
+    use crate::database::new_db;
+
+    use super::*;
+
+    fn copy(source: &str, destination: &str, replace: bool) -> Copy {
+        Copy { source: Bytes::copy_from_slice(source.as_bytes()), destination: Bytes::copy_from_slice(destination.as_bytes()), db: None, replace }
+    }
+
+    #[test]
+    fn it_copies_value_with_expiration() {
+        let db = new_db();
+        let future = SystemTime::now() + Duration::from_secs(60);
+        db.lock().unwrap().insert_with_expiration("a".to_string(), Bytes::from("1"), Some(future));
+
+        assert_eq!(Frame::Integer(1), copy("a", "b", false).execute(db.clone()));
+
+        let binding = db.lock().unwrap();
+        assert_eq!(Ok(Some(&Bytes::from("1"))), binding.get("b"));
+        assert_eq!(Some(future), binding.expiration("b"));
+    }
+
+    #[test]
+    fn it_replaces_destination_only_when_asked() {
+        let db = new_db();
+        db.lock().unwrap().insert("a".to_string(), Bytes::from("1"));
+        db.lock().unwrap().insert("b".to_string(), Bytes::from("2"));
+
+        assert_eq!(Frame::Integer(0), copy("a", "b", false).execute(db.clone()));
+        assert_eq!(Frame::Integer(1), copy("a", "b", true).execute(db.clone()));
+        assert_eq!(Frame::Integer(0), copy("missing", "b", true).execute(db.clone()));
+        assert_eq!(Ok(Some(&Bytes::from("1"))), db.lock().unwrap().get("b"));
+    }
+
+    #[test]
+    fn it_copies_into_another_database() {
+        let db = new_db();
+        db.lock().unwrap().insert("a".to_string(), Bytes::from("1"));
+        let command = Copy { db: Some(2), ..copy("a", "a", false) };
+
+        assert_eq!(Frame::Integer(1), command.execute(db.clone()));
+        assert_eq!(Frame::Integer(0), command.execute(db.clone()));
+        assert_eq!(Ok(Some(&Bytes::from("1"))), db.select(2).unwrap().lock().unwrap().get("a"));
+        assert!(db.lock().unwrap().contains_key("a"));
+    }
+}
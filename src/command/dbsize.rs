@@ -0,0 +1,47 @@
+use std::vec::IntoIter;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::Error;
+
+use super::{Command, wrong_arity};
+
+pub(crate) struct DbSize;
+
+impl Command for DbSize {
+    fn execute(&self, db: Database) -> Frame {
+        Frame::Integer(db.lock().unwrap().len() as i64)
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for DbSize {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() > 0 {
+            return Err(wrong_arity("dbsize"));
+        }
+
+        Ok(DbSize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use bytes::Bytes;
+
+    use crate::database::new_db;
+
+    use super::*;
+
+    #[test]
+    fn it_counts_live_keys() {
+        let db = new_db();
+        db.lock().unwrap().insert("a".to_string(), Bytes::from("1"));
+        db.lock().unwrap().insert_with_expiration("b".to_string(), Bytes::from("2"), Some(SystemTime::now() - Duration::from_secs(1)));
+
+        assert_eq!(Frame::Integer(1), DbSize.execute(db.clone()));
+    }
+}
@@ -0,0 +1,88 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::database::{drop_in_background, Database};
+use crate::frame::Frame;
+use crate::Error;
+
+use super::{Command, next_bytes, wrong_arity};
+
+/// DEL and UNLINK, the latter freeing the removed values outside of the keyspace lock.
+pub(crate) struct Del {
+    keys: Vec<Bytes>,
+    unlink: bool,
+}
+
+impl Command for Del {
+    fn execute(&self, db: Database) -> Frame {
+        let mut db = db.lock_keys(&self.keys).unwrap();
+        let removed: Vec<_> = self.keys.iter().filter_map(|key| db.remove(key)).collect();
+        drop(db);
+
+        let count = removed.len() as i64;
+        if self.unlink {
+            drop_in_background(removed);
+        }
+
+        Frame::Integer(count)
+    }
+}
+
+impl Del {
+    pub(crate) fn unlink(frames: &mut IntoIter<Frame>) -> Result<Self, Error> {
+        Self::parse(frames, "unlink", true)
+    }
+
+    fn parse(frames: &mut IntoIter<Frame>, name: &str, unlink: bool) -> Result<Self, Error> {
+        if frames.len() == 0 {
+            return Err(wrong_arity(name));
+        }
+
+        let mut keys = vec![];
+        while frames.len() > 0 {
+            keys.push(next_bytes(frames)?);
+        }
+
+        Ok(Del { keys, unlink })
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for Del {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        Self::parse(frames, "del", false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::database::new_db;
+    use crate::sorted_set::SortedSet;
+
+    use super::*;
+
+    #[test]
+    fn it_removes_existing_keys() {
+        let db = new_db();
+        db.lock().unwrap().insert("a".to_string(), Bytes::from("1"));
+        db.lock().unwrap().insert("b".to_string(), SortedSet::default());
+        let command = Del { keys: vec![Bytes::from("a"), Bytes::from("b"), Bytes::from("c"), Bytes::from("a")], unlink: false };
+
+        assert_eq!(Frame::Integer(2), command.execute(db.clone()));
+        assert!(db.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn it_unlinks_keys_in_background() {
+        let db = new_db();
+        db.lock().unwrap().insert("a".to_string(), Bytes::from("1"));
+        let command = Del::unlink(&mut vec![Frame::Bulk(Bytes::from("a"))].into_iter()).unwrap();
+
+        assert_eq!(Frame::Integer(1), command.execute(db.clone()));
+        assert!(!db.lock().unwrap().contains_key("a"));
+    }
+}
@@ -0,0 +1,36 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::rdb;
+use crate::Error;
+
+use super::{Command, next_bytes, wrong_arity};
+
+/// Serializes the value of a key in the format RESTORE reads.
+pub(crate) struct Dump {
+    key: Bytes,
+}
+
+impl Command for Dump {
+    fn execute(&self, db: Database) -> Frame {
+        match db.lock_keys([&self.key]).unwrap().get_value(&self.key) {
+            Some(value) => Frame::Bulk(Bytes::from(rdb::dump(value))),
+            None => Frame::Null,
+        }
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for Dump {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() != 1 {
+            return Err(wrong_arity("dump"));
+        }
+
+        Ok(Dump { key: next_bytes(frames)? })
+    }
+}
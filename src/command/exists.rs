@@ -0,0 +1,68 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::Error;
+
+use super::{Command, next_bytes, wrong_arity};
+
+/// EXISTS and TOUCH, both count the given keys that exist, repeated keys being counted again.
+pub(crate) struct Exists {
+    keys: Vec<Bytes>,
+}
+
+impl Command for Exists {
+    fn execute(&self, db: Database) -> Frame {
+        let db = db.lock_keys(&self.keys).unwrap();
+        let count = self.keys.iter().filter(|key| db.contains_key(key)).count();
+
+        Frame::Integer(count as i64)
+    }
+}
+
+impl Exists {
+    pub(crate) fn touch(frames: &mut IntoIter<Frame>) -> Result<Self, Error> {
+        Self::parse(frames, "touch")
+    }
+
+    fn parse(frames: &mut IntoIter<Frame>, name: &str) -> Result<Self, Error> {
+        if frames.len() == 0 {
+            return Err(wrong_arity(name));
+        }
+
+        let mut keys = vec![];
+        while frames.len() > 0 {
+            keys.push(next_bytes(frames)?);
+        }
+
+        Ok(Exists { keys })
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for Exists {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        Self::parse(frames, "exists")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::database::new_db;
+
+    use super::*;
+
+    #[test]
+    fn it_counts_existing_keys() {
+        let db = new_db();
+        db.lock().unwrap().insert("a".to_string(), Bytes::from("1"));
+        let command = Exists { keys: vec![Bytes::from("a"), Bytes::from("b"), Bytes::from("a")] };
+
+        assert_eq!(Frame::Integer(2), command.execute(db.clone()));
+    }
+}
@@ -0,0 +1,97 @@
+use std::vec::IntoIter;
+
+use crate::database::{drop_in_background, Database};
+use crate::frame::Frame;
+use crate::Error;
+
+use super::{Command, next_string, syntax_error, wrong_arity};
+
+/// FLUSHDB and FLUSHALL, with ASYNC the removed values are freed in the background.
+pub(crate) struct FlushDb {
+    all: bool,
+    asynchronous: bool,
+}
+
+impl Command for FlushDb {
+    fn execute(&self, db: Database) -> Frame {
+        let values = match self.all {
+            true => db.lock_all().iter_mut().flat_map(|keyspace| keyspace.clear()).collect(),
+            false => db.lock().unwrap().clear(),
+        };
+        if self.asynchronous {
+            drop_in_background(values);
+        }
+
+        Frame::Simple("OK".to_string())
+    }
+}
+
+impl FlushDb {
+    pub(crate) fn flushall(frames: &mut IntoIter<Frame>) -> Result<Self, Error> {
+        Self::parse(frames, "flushall", true)
+    }
+
+    fn parse(frames: &mut IntoIter<Frame>, name: &str, all: bool) -> Result<Self, Error> {
+        let asynchronous = match frames.len() {
+            0 => false,
+            1 => match next_string(frames)?.to_uppercase().as_str() {
+                "ASYNC" => true,
+                "SYNC" => false,
+                _ => return Err(syntax_error()),
+            },
+            _ => return Err(wrong_arity(name)),
+        };
+
+        Ok(FlushDb { all, asynchronous })
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for FlushDb {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        Self::parse(frames, "flushdb", false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::database::new_db;
+
+    use super::*;
+
+    #[test]
+    fn it_removes_all_keys() {
+        let db = new_db();
+        db.lock().unwrap().insert("a".to_string(), Bytes::from("1"));
+
+        assert_eq!(Frame::Simple("OK".to_string()), FlushDb { all: false, asynchronous: false }.execute(db.clone()));
+        assert!(db.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn it_flushes_only_the_selected_database() {
+        let db = new_db();
+        let other = db.select(1).unwrap();
+        db.lock().unwrap().insert("a".to_string(), Bytes::from("1"));
+        other.lock().unwrap().insert("b".to_string(), Bytes::from("2"));
+
+        FlushDb { all: false, asynchronous: false }.execute(other.clone());
+        assert_eq!(1, db.lock().unwrap().len());
+        assert!(other.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn it_removes_all_keys_asynchronously() {
+        let db = new_db();
+        db.lock().unwrap().insert("a".to_string(), Bytes::from("1"));
+        db.select(5).unwrap().lock().unwrap().insert("b".to_string(), Bytes::from("2"));
+        let command = FlushDb::flushall(&mut vec![Frame::Bulk(Bytes::from("async"))].into_iter()).unwrap();
+
+        assert_eq!(Frame::Simple("OK".to_string()), command.execute(db.clone()));
+        assert!(db.lock().unwrap().is_empty());
+        assert!(db.select(5).unwrap().lock().unwrap().is_empty());
+    }
+}
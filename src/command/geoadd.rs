@@ -0,0 +1,175 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::geo;
+use crate::sorted_set::{CompactLimits, SortedSet};
+use crate::Error;
+
+use super::{Command, next_bytes, next_float, next_string, syntax_error, wrong_arity};
+
+pub(crate) struct GeoAdd {
+    key: Bytes,
+    only_new: bool,
+    only_existing: bool,
+    changed: bool,
+    points: Vec<(f64, f64, Bytes)>,
+}
+
+impl Command for GeoAdd {
+    fn execute(&self, db: Database) -> Frame {
+        let limits = db.memory().compact_limits();
+        let mut db = db.lock_keys([&self.key]).unwrap();
+
+        match db.get_sorted_set_mut(&self.key) {
+            Ok(Some(set)) => Frame::Integer(self.add_to(set, limits)),
+            Ok(None) => {
+                let mut set = SortedSet::default();
+                let count = self.add_to(&mut set, limits);
+                if !set.is_empty() {
+                    db.insert(self.key.clone(), set);
+                }
+                Frame::Integer(count)
+            }
+            Err(err) => err.into(),
+        }
+    }
+}
+
+impl GeoAdd {
+    fn add_to(&self, set: &mut SortedSet, limits: CompactLimits) -> i64 {
+        let mut count = 0;
+
+        for (longitude, latitude, member) in &self.points {
+            let score = geo::score(*longitude, *latitude);
+            let previous = set.score(member);
+
+            if (self.only_new && previous.is_some()) || (self.only_existing && previous.is_none()) {
+                continue;
+            }
+
+            set.insert(member.clone(), score, limits);
+            if previous.is_none() || (self.changed && previous != Some(score)) {
+                count += 1;
+            }
+        }
+
+        count
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for GeoAdd {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() < 4 {
+            return Err(wrong_arity("geoadd"));
+        }
+
+        let mut command = GeoAdd {
+            key: next_bytes(frames)?,
+            only_new: false,
+            only_existing: false,
+            changed: false,
+            points: vec![],
+        };
+
+        while !frames.len().is_multiple_of(3) {
+            match next_string(frames)?.to_uppercase().as_str() {
+                "NX" => command.only_new = true,
+                "XX" => command.only_existing = true,
+                "CH" => command.changed = true,
+                _ => return Err(syntax_error()),
+            }
+        }
+
+        if frames.len() == 0 || (command.only_new && command.only_existing) {
+            return Err(syntax_error());
+        }
+
+        while frames.len() > 0 {
+            let longitude = next_float(frames)?;
+            let latitude = next_float(frames)?;
+            let member = next_bytes(frames)?;
+
+            if !geo::is_valid(longitude, latitude) {
+                return Err(format!("invalid longitude,latitude pair {:.6},{:.6}", longitude, latitude).into());
+            }
+
+            command.points.push((longitude, latitude, member));
+        }
+
+        Ok(command)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::new_db;
+
+    use super::*;
+
+    fn geoadd(db: &Database, arguments: &[&str]) -> Result<Frame, Error> {
+        let mut frames = vec![Frame::Bulk(Bytes::from("Sicily"))];
+        frames.extend(arguments.iter().map(|argument| Frame::Bulk(Bytes::from(argument.to_string()))));
+        let command = GeoAdd::try_from(&mut frames.into_iter())?;
+
+        Ok(command.execute(db.clone()))
+    }
+
+    #[test]
+    fn it_stores_positions_as_geohash_scores() {
+        let db = new_db();
+
+        let result = geoadd(&db, &["13.361389", "38.115556", "Palermo", "15.087269", "37.502669", "Catania"]).unwrap();
+
+        assert_eq!(Frame::Integer(2), result);
+        let binding = db.lock().unwrap();
+        let set = binding.get_sorted_set("Sicily").unwrap().unwrap();
+        assert_eq!(Some(3479099956230698.0), set.score(b"Palermo"));
+    }
+
+    #[test]
+    fn it_counts_changed_members_with_ch() {
+        let db = new_db();
+        geoadd(&db, &["13.361389", "38.115556", "Palermo"]).unwrap();
+
+        let result = geoadd(&db, &["CH", "13.5", "38.115556", "Palermo", "15.087269", "37.502669", "Catania"]).unwrap();
+
+        assert_eq!(Frame::Integer(2), result);
+    }
+
+    #[test]
+    fn it_respects_nx_and_xx() {
+        let db = new_db();
+        geoadd(&db, &["13.361389", "38.115556", "Palermo"]).unwrap();
+
+        assert_eq!(Frame::Integer(0), geoadd(&db, &["XX", "15.087269", "37.502669", "Catania"]).unwrap());
+        assert_eq!(Frame::Integer(0), geoadd(&db, &["NX", "CH", "10", "10", "Palermo"]).unwrap());
+        assert!(geoadd(&db, &["NX", "XX", "10", "10", "Palermo"]).is_err());
+    }
+
+    #[test]
+    fn it_converts_sets_past_the_thresholds_of_its_server() {
+        let db = new_db();
+        db.memory().set_compact_entries(1);
+
+        geoadd(&db, &["13.361389", "38.115556", "Palermo", "15.087269", "37.502669", "Catania"]).unwrap();
+
+        assert!(!db.lock().unwrap().get_sorted_set("Sicily").unwrap().unwrap().is_compact());
+        let other = new_db();
+        geoadd(&other, &["13.361389", "38.115556", "Palermo", "15.087269", "37.502669", "Catania"]).unwrap();
+        assert!(other.lock().unwrap().get_sorted_set("Sicily").unwrap().unwrap().is_compact());
+    }
+
+    #[test]
+    fn it_rejects_positions_outside_of_the_index() {
+        let db = new_db();
+
+        let result = geoadd(&db, &["181", "10", "Nowhere"]);
+
+        assert_eq!("invalid longitude,latitude pair 181.000000,10.000000", result.err().unwrap().to_string());
+    }
+}
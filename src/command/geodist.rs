@@ -0,0 +1,113 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::geo;
+use crate::Error;
+
+use super::{Command, next_bytes, next_string, syntax_error, wrong_arity};
+
+pub(crate) struct GeoDist {
+    key: Bytes,
+    members: (Bytes, Bytes),
+    unit: f64,
+}
+
+impl Command for GeoDist {
+    fn execute(&self, db: Database) -> Frame {
+        let db = db.lock_keys([&self.key]).unwrap();
+        let set = match db.get_sorted_set(&self.key) {
+            Ok(Some(set)) => set,
+            Ok(None) => return Frame::Null,
+            Err(err) => return err.into(),
+        };
+
+        let (first, second) = match (set.score(&self.members.0), set.score(&self.members.1)) {
+            (Some(first), Some(second)) => (geo::position(first), geo::position(second)),
+            _ => return Frame::Null,
+        };
+        let distance = geo::distance(first.0, first.1, second.0, second.1);
+
+        Frame::Bulk(Bytes::from(format!("{:.4}", distance / self.unit)))
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for GeoDist {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() < 3 {
+            return Err(wrong_arity("geodist"));
+        }
+        if frames.len() > 4 {
+            return Err(syntax_error());
+        }
+
+        let key = next_bytes(frames)?;
+        let members = (next_bytes(frames)?, next_bytes(frames)?);
+        let unit = match frames.len() {
+            0 => 1.0,
+            _ => next_unit(frames)?,
+        };
+
+        Ok(GeoDist { key, members, unit })
+    }
+}
+
+/// Reads a distance unit and returns how many meters it stands for.
+pub(crate) fn next_unit(frames: &mut IntoIter<Frame>) -> Result<f64, Error> {
+    geo::unit_to_meters(&next_string(frames)?)
+        .ok_or_else(|| "unsupported unit provided. please use M, KM, FT, MI".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::new_db;
+    use crate::sorted_set::{CompactLimits, SortedSet};
+
+    use super::*;
+
+    fn sicily() -> Database {
+        let db = new_db();
+        let mut set = SortedSet::default();
+        set.insert(Bytes::from("Palermo"), geo::score(13.361389, 38.115556), CompactLimits::default());
+        set.insert(Bytes::from("Catania"), geo::score(15.087269, 37.502669), CompactLimits::default());
+        db.lock().unwrap().insert("Sicily".to_string(), set);
+        db
+    }
+
+    fn geodist(db: &Database, arguments: &[&str]) -> Result<Frame, Error> {
+        let frames: Vec<Frame> = arguments.iter().map(|argument| Frame::Bulk(Bytes::from(argument.to_string()))).collect();
+        let command = GeoDist::try_from(&mut frames.into_iter())?;
+
+        Ok(command.execute(db.clone()))
+    }
+
+    #[test]
+    fn it_returns_distance_in_requested_unit() {
+        let db = sicily();
+
+        assert_eq!(Frame::Bulk(Bytes::from("166274.1516")), geodist(&db, &["Sicily", "Palermo", "Catania"]).unwrap());
+        assert_eq!(Frame::Bulk(Bytes::from("166.2742")), geodist(&db, &["Sicily", "Palermo", "Catania", "km"]).unwrap());
+        assert_eq!(Frame::Bulk(Bytes::from("103.3182")), geodist(&db, &["Sicily", "Palermo", "Catania", "MI"]).unwrap());
+    }
+
+    #[test]
+    fn it_returns_null_for_missing_member() {
+        let db = sicily();
+
+        assert_eq!(Frame::Null, geodist(&db, &["Sicily", "Palermo", "Agrigento"]).unwrap());
+        assert_eq!(Frame::Null, geodist(&db, &["Unknown", "Palermo", "Catania"]).unwrap());
+    }
+
+    #[test]
+    fn it_rejects_unknown_unit() {
+        let db = sicily();
+
+        let result = geodist(&db, &["Sicily", "Palermo", "Catania", "yd"]);
+
+        assert_eq!("unsupported unit provided. please use M, KM, FT, MI", result.err().unwrap().to_string());
+    }
+}
@@ -0,0 +1,81 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::geo;
+use crate::Error;
+
+use super::{Command, next_bytes, wrong_arity};
+
+pub(crate) struct GeoHash {
+    key: Bytes,
+    members: Vec<Bytes>,
+}
+
+impl Command for GeoHash {
+    fn execute(&self, db: Database) -> Frame {
+        let db = db.lock_keys([&self.key]).unwrap();
+        let set = match db.get_sorted_set(&self.key) {
+            Ok(set) => set,
+            Err(err) => return err.into(),
+        };
+
+        let hashes = self.members.iter()
+            .map(|member| match set.and_then(|set| set.score(member)) {
+                Some(score) => Frame::Bulk(Bytes::from(geo::geohash_string(score))),
+                None => Frame::Null,
+            })
+            .collect();
+
+        Frame::Array(hashes)
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for GeoHash {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() == 0 {
+            return Err(wrong_arity("geohash"));
+        }
+
+        let key = next_bytes(frames)?;
+        let mut members = vec![];
+        while frames.len() > 0 {
+            members.push(next_bytes(frames)?);
+        }
+
+        Ok(GeoHash { key, members })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::new_db;
+    use crate::sorted_set::{CompactLimits, SortedSet};
+
+    use super::*;
+
+    #[test]
+    fn it_returns_geohash_strings() {
+        let db = new_db();
+        let mut set = SortedSet::default();
+        set.insert(Bytes::from("Palermo"), geo::score(13.361389, 38.115556), CompactLimits::default());
+        set.insert(Bytes::from("Catania"), geo::score(15.087269, 37.502669), CompactLimits::default());
+        db.lock().unwrap().insert("Sicily".to_string(), set);
+        let command = GeoHash {
+            key: Bytes::from("Sicily"),
+            members: vec![Bytes::from("Palermo"), Bytes::from("Catania"), Bytes::from("Agrigento")],
+        };
+
+        let result = command.execute(db.clone());
+
+        assert_eq!(Frame::Array(vec![
+            Frame::Bulk(Bytes::from("sqc8b49rny0")),
+            Frame::Bulk(Bytes::from("sqdtr74hyu0")),
+            Frame::Null,
+        ]), result);
+    }
+}
@@ -0,0 +1,98 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::geo;
+use crate::Error;
+
+use super::{Command, next_bytes, wrong_arity};
+
+pub(crate) struct GeoPos {
+    key: Bytes,
+    members: Vec<Bytes>,
+}
+
+impl Command for GeoPos {
+    fn execute(&self, db: Database) -> Frame {
+        let db = db.lock_keys([&self.key]).unwrap();
+        let set = match db.get_sorted_set(&self.key) {
+            Ok(set) => set,
+            Err(err) => return err.into(),
+        };
+
+        let positions = self.members.iter()
+            .map(|member| match set.and_then(|set| set.score(member)) {
+                Some(score) => {
+                    let (longitude, latitude) = geo::position(score);
+                    Frame::Array(vec![
+                        Frame::Bulk(Bytes::from(geo::format_coordinate(longitude))),
+                        Frame::Bulk(Bytes::from(geo::format_coordinate(latitude))),
+                    ])
+                }
+                None => Frame::Null,
+            })
+            .collect();
+
+        Frame::Array(positions)
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for GeoPos {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() == 0 {
+            return Err(wrong_arity("geopos"));
+        }
+
+        let key = next_bytes(frames)?;
+        let mut members = vec![];
+        while frames.len() > 0 {
+            members.push(next_bytes(frames)?);
+        }
+
+        Ok(GeoPos { key, members })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::new_db;
+    use crate::sorted_set::{CompactLimits, SortedSet};
+
+    use super::*;
+
+    #[test]
+    fn it_returns_positions_of_members() {
+        let db = new_db();
+        let mut set = SortedSet::default();
+        set.insert(Bytes::from("Palermo"), geo::score(13.361389, 38.115556), CompactLimits::default());
+        db.lock().unwrap().insert("Sicily".to_string(), set);
+        let command = GeoPos {
+            key: Bytes::from("Sicily"),
+            members: vec![Bytes::from("Palermo"), Bytes::from("Agrigento")],
+        };
+
+        let result = command.execute(db.clone());
+
+        assert_eq!(Frame::Array(vec![
+            Frame::Array(vec![
+                Frame::Bulk(Bytes::from("13.36138933897018433")),
+                Frame::Bulk(Bytes::from("38.11555639549629859")),
+            ]),
+            Frame::Null,
+        ]), result);
+    }
+
+    #[test]
+    fn it_returns_nulls_for_missing_key() {
+        let db = new_db();
+        let command = GeoPos { key: Bytes::from("Sicily"), members: vec![Bytes::from("Palermo")] };
+
+        let result = command.execute(db.clone());
+
+        assert_eq!(Frame::Array(vec![Frame::Null]), result);
+    }
+}
@@ -0,0 +1,372 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::geo::{self, Shape};
+use crate::sorted_set::SortedSet;
+use crate::Error;
+
+use super::geodist::next_unit;
+use super::{Command, error_frame, next_bytes, next_float, next_integer, next_string, syntax_error, wrong_arity};
+
+enum Origin {
+    Member(Bytes),
+    Position(f64, f64),
+}
+
+#[derive(PartialEq)]
+enum Order {
+    Unsorted,
+    Ascending,
+    Descending,
+}
+
+struct Found {
+    member: Bytes,
+    score: f64,
+    distance: f64,
+    position: (f64, f64),
+}
+
+pub(crate) struct GeoSearch {
+    destination: Option<Bytes>,
+    key: Bytes,
+    origin: Origin,
+    shape: Shape,
+    unit: f64,
+    order: Order,
+    count: Option<usize>,
+    any: bool,
+    with_coord: bool,
+    with_dist: bool,
+    with_hash: bool,
+    store_dist: bool,
+}
+
+impl Command for GeoSearch {
+    fn execute(&self, db: Database) -> Frame {
+        let limits = db.memory().compact_limits();
+        let mut db = db.lock_keys(std::iter::once(&self.key).chain(&self.destination)).unwrap();
+        let found = match db.get_sorted_set(&self.key) {
+            Ok(Some(set)) => match self.search(set) {
+                Some(found) => found,
+                None => return error_frame("could not decode requested zset member"),
+            },
+            Ok(None) => vec![],
+            Err(err) => return err.into(),
+        };
+
+        let destination = match &self.destination {
+            Some(destination) => destination,
+            None => return Frame::Array(found.iter().map(|found| self.reply(found)).collect()),
+        };
+
+        let count = found.len() as i64;
+        if found.is_empty() {
+            db.remove(destination);
+            return Frame::Integer(0);
+        }
+
+        let mut set = SortedSet::default();
+        for found in found {
+            let score = if self.store_dist { found.distance / self.unit } else { found.score };
+            set.insert(found.member, score, limits);
+        }
+        db.insert(destination.clone(), set);
+
+        Frame::Integer(count)
+    }
+}
+
+impl GeoSearch {
+    pub(crate) fn store(frames: &mut IntoIter<Frame>) -> Result<Self, Error> {
+        if frames.len() < 2 {
+            return Err(wrong_arity("geosearchstore"));
+        }
+
+        let destination = next_bytes(frames)?;
+        let mut command = Self::parse(frames, "geosearchstore", true)?;
+        command.destination = Some(destination);
+
+        Ok(command)
+    }
+
+    fn parse(frames: &mut IntoIter<Frame>, name: &str, store: bool) -> Result<Self, Error> {
+        if frames.len() < 5 {
+            return Err(wrong_arity(name));
+        }
+
+        let key = next_bytes(frames)?;
+        let mut origin = None;
+        let mut shape = None;
+        let mut command = GeoSearch {
+            destination: None,
+            key,
+            origin: Origin::Position(0.0, 0.0),
+            shape: Shape::Radius(0.0),
+            unit: 1.0,
+            order: Order::Unsorted,
+            count: None,
+            any: false,
+            with_coord: false,
+            with_dist: false,
+            with_hash: false,
+            store_dist: false,
+        };
+
+        while frames.len() > 0 {
+            match next_string(frames)?.to_uppercase().as_str() {
+                "FROMMEMBER" if origin.is_none() => origin = Some(Origin::Member(next_bytes(frames)?)),
+                "FROMLONLAT" if origin.is_none() => {
+                    let longitude = next_float(frames)?;
+                    let latitude = next_float(frames)?;
+                    if !geo::is_valid(longitude, latitude) {
+                        return Err(format!("invalid longitude,latitude pair {:.6},{:.6}", longitude, latitude).into());
+                    }
+                    origin = Some(Origin::Position(longitude, latitude));
+                }
+                "FROMMEMBER" | "FROMLONLAT" => {
+                    return Err("exactly one of FROMMEMBER or FROMLONLAT can be specified for GEOSEARCH".into());
+                }
+                "BYRADIUS" if shape.is_none() => {
+                    let radius = next_float(frames)?;
+                    if radius < 0.0 {
+                        return Err("radius cannot be negative".into());
+                    }
+                    command.unit = next_unit(frames)?;
+                    shape = Some(Shape::Radius(radius * command.unit));
+                }
+                "BYBOX" if shape.is_none() => {
+                    let width = next_float(frames)?;
+                    let height = next_float(frames)?;
+                    if width < 0.0 || height < 0.0 {
+                        return Err("height or width cannot be negative".into());
+                    }
+                    command.unit = next_unit(frames)?;
+                    shape = Some(Shape::Box { width: width * command.unit, height: height * command.unit });
+                }
+                "BYRADIUS" | "BYBOX" => {
+                    return Err("exactly one of BYRADIUS and BYBOX can be specified for GEOSEARCH".into());
+                }
+                "ASC" => command.order = Order::Ascending,
+                "DESC" => command.order = Order::Descending,
+                "COUNT" => {
+                    let count = next_integer(frames)?;
+                    if count <= 0 {
+                        return Err("COUNT must be > 0".into());
+                    }
+                    command.count = Some(count as usize);
+                }
+                "ANY" => command.any = true,
+                "WITHCOORD" if !store => command.with_coord = true,
+                "WITHDIST" if !store => command.with_dist = true,
+                "WITHHASH" if !store => command.with_hash = true,
+                "STOREDIST" if store => command.store_dist = true,
+                _ => return Err(syntax_error()),
+            }
+        }
+
+        command.origin = origin
+            .ok_or("exactly one of FROMMEMBER or FROMLONLAT can be specified for GEOSEARCH")?;
+        command.shape = shape
+            .ok_or("exactly one of BYRADIUS and BYBOX can be specified for GEOSEARCH")?;
+        if command.any && command.count.is_none() {
+            return Err("the ANY argument requires COUNT argument".into());
+        }
+
+        Ok(command)
+    }
+
+    /// Returns the members within the shape, `None` when the origin member does not exist.
+    fn search(&self, set: &SortedSet) -> Option<Vec<Found>> {
+        let center = match &self.origin {
+            Origin::Member(member) => geo::position(set.score(member)?),
+            Origin::Position(longitude, latitude) => (*longitude, *latitude),
+        };
+
+        let mut found = vec![];
+        'areas: for area in geo::search_areas(self.shape, center) {
+            let (min, max) = geo::score_range(area);
+            for (member, score) in set.range_by_score(min, max) {
+                let position = geo::position(score);
+                if let Some(distance) = geo::distance_within(self.shape, center, position) {
+                    found.push(Found { member: member.clone(), score, distance, position });
+
+                    if self.any && Some(found.len()) == self.count {
+                        break 'areas;
+                    }
+                }
+            }
+        }
+
+        let order = match self.order {
+            Order::Unsorted if self.count.is_some() && !self.any => &Order::Ascending,
+            ref order => order,
+        };
+        match order {
+            Order::Ascending => found.sort_by(|a, b| a.distance.total_cmp(&b.distance)),
+            Order::Descending => found.sort_by(|a, b| b.distance.total_cmp(&a.distance)),
+            Order::Unsorted => {}
+        }
+        if let Some(count) = self.count {
+            found.truncate(count);
+        }
+
+        Some(found)
+    }
+
+    fn reply(&self, found: &Found) -> Frame {
+        let member = Frame::Bulk(found.member.clone());
+        if !self.with_dist && !self.with_hash && !self.with_coord {
+            return member;
+        }
+
+        let mut item = vec![member];
+        if self.with_dist {
+            item.push(Frame::Bulk(Bytes::from(format!("{:.4}", found.distance / self.unit))));
+        }
+        if self.with_hash {
+            item.push(Frame::Integer(found.score as i64));
+        }
+        if self.with_coord {
+            item.push(Frame::Array(vec![
+                Frame::Bulk(Bytes::from(geo::format_coordinate(found.position.0))),
+                Frame::Bulk(Bytes::from(geo::format_coordinate(found.position.1))),
+            ]));
+        }
+
+        Frame::Array(item)
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for GeoSearch {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        Self::parse(frames, "geosearch", false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::new_db;
+    use crate::sorted_set::CompactLimits;
+
+    use super::*;
+
+    fn sicily() -> Database {
+        let db = new_db();
+        let mut set = SortedSet::default();
+        set.insert(Bytes::from("Palermo"), geo::score(13.361389, 38.115556), CompactLimits::default());
+        set.insert(Bytes::from("Catania"), geo::score(15.087269, 37.502669), CompactLimits::default());
+        set.insert(Bytes::from("edge1"), geo::score(12.758489, 38.788135), CompactLimits::default());
+        set.insert(Bytes::from("edge2"), geo::score(17.241510, 38.788135), CompactLimits::default());
+        db.lock().unwrap().insert("Sicily".to_string(), set);
+        db
+    }
+
+    fn frames(arguments: &[&str]) -> IntoIter<Frame> {
+        let frames: Vec<Frame> = arguments.iter().map(|argument| Frame::Bulk(Bytes::from(argument.to_string()))).collect();
+        frames.into_iter()
+    }
+
+    fn geosearch(db: &Database, arguments: &[&str]) -> Result<Frame, Error> {
+        Ok(GeoSearch::try_from(&mut frames(arguments))?.execute(db.clone()))
+    }
+
+    fn bulk(value: &str) -> Frame {
+        Frame::Bulk(Bytes::from(value.to_string()))
+    }
+
+    #[test]
+    fn it_searches_by_radius() {
+        let db = sicily();
+
+        let result = geosearch(&db, &["Sicily", "FROMLONLAT", "15", "37", "BYRADIUS", "200", "km", "ASC"]).unwrap();
+
+        assert_eq!(Frame::Array(vec![bulk("Catania"), bulk("Palermo")]), result);
+    }
+
+    #[test]
+    fn it_searches_by_box_with_distances_and_coordinates() {
+        let db = sicily();
+
+        let result = geosearch(&db, &["Sicily", "FROMLONLAT", "15", "37", "BYBOX", "400", "400", "km", "ASC", "WITHCOORD", "WITHDIST"]).unwrap();
+
+        let items = match result {
+            Frame::Array(items) => items,
+            frame => panic!("unexpected reply {:?}", frame),
+        };
+        let names: Vec<&Frame> = items.iter().map(|item| match item {
+            Frame::Array(fields) => &fields[0],
+            frame => panic!("unexpected item {:?}", frame),
+        }).collect();
+        assert_eq!(vec![&bulk("Catania"), &bulk("Palermo"), &bulk("edge2"), &bulk("edge1")], names);
+        assert_eq!(Frame::Array(vec![
+            bulk("Catania"),
+            bulk("56.4413"),
+            Frame::Array(vec![bulk("15.08726745843887329"), bulk("37.50266842333162032")]),
+        ]), items[0]);
+    }
+
+    #[test]
+    fn it_searches_from_member_with_count() {
+        let db = sicily();
+
+        let result = geosearch(&db, &["Sicily", "FROMMEMBER", "Palermo", "BYRADIUS", "500", "km", "COUNT", "2", "WITHHASH"]).unwrap();
+
+        assert_eq!(Frame::Array(vec![
+            Frame::Array(vec![bulk("Palermo"), Frame::Integer(3479099956230698)]),
+            Frame::Array(vec![bulk("edge1"), Frame::Integer(geo::score(12.758489, 38.788135) as i64)]),
+        ]), result);
+    }
+
+    #[test]
+    fn it_fails_for_missing_origin_member() {
+        let db = sicily();
+
+        let result = geosearch(&db, &["Sicily", "FROMMEMBER", "Agrigento", "BYRADIUS", "10", "km"]).unwrap();
+
+        assert_eq!(Frame::SimpleError("ERR could not decode requested zset member".to_string()), result);
+    }
+
+    #[test]
+    fn it_validates_options() {
+        let db = sicily();
+
+        let error = |arguments: &[&str]| geosearch(&db, arguments).err().unwrap().to_string();
+
+        assert_eq!("exactly one of FROMMEMBER or FROMLONLAT can be specified for GEOSEARCH", error(&["Sicily", "BYRADIUS", "10", "km", "ASC"]));
+        assert_eq!("exactly one of BYRADIUS and BYBOX can be specified for GEOSEARCH", error(&["Sicily", "FROMLONLAT", "15", "37", "ASC"]));
+        assert_eq!("COUNT must be > 0", error(&["Sicily", "FROMLONLAT", "15", "37", "BYRADIUS", "10", "km", "COUNT", "0"]));
+        assert_eq!("the ANY argument requires COUNT argument", error(&["Sicily", "FROMLONLAT", "15", "37", "BYRADIUS", "10", "km", "ANY"]));
+        assert_eq!("syntax error", error(&["Sicily", "FROMLONLAT", "15", "37", "BYRADIUS", "10", "km", "STOREDIST"]));
+    }
+
+    #[test]
+    fn it_stores_results_with_distances() {
+        let db = sicily();
+        let command = GeoSearch::store(&mut frames(&["Near", "Sicily", "FROMLONLAT", "15", "37", "BYRADIUS", "100", "km", "STOREDIST"])).unwrap();
+
+        let result = command.execute(db.clone());
+
+        assert_eq!(Frame::Integer(1), result);
+        let binding = db.lock().unwrap();
+        let stored = binding.get_sorted_set("Near").unwrap().unwrap();
+        assert_eq!(56, stored.score(b"Catania").unwrap() as i64);
+    }
+
+    #[test]
+    fn it_removes_destination_for_empty_result() {
+        let db = sicily();
+        db.lock().unwrap().insert("Near".to_string(), Bytes::from("value"));
+        let command = GeoSearch::store(&mut frames(&["Near", "Sicily", "FROMLONLAT", "0", "0", "BYRADIUS", "1", "km"])).unwrap();
+
+        let result = command.execute(db.clone());
+
+        assert_eq!(Frame::Integer(0), result);
+        assert!(!db.lock().unwrap().contains_key("Near"));
+    }
+}
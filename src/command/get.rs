@@ -1,30 +1,40 @@
 use std::vec::IntoIter;
 
+use bytes::Bytes;
+
 use crate::database::Database;
 use crate::frame::Frame;
 
-use super::{Command, next_string};
+use crate::Error;
+
+use super::{Command, next_bytes, wrong_arity};
 
 pub(crate) struct Get {
-    key: String,
+    key: Bytes,
 }
 
 impl Command for Get {
     fn execute(&self, db: Database) -> Frame {
-        let db = db.lock().unwrap();
-        if let Some(value) = db.get(&*self.key) {
-            Frame::Bulk(value.clone())
-        } else {
-            Frame::Null
+        let db = db.lock_keys([&self.key]).unwrap();
+        match db.get(&self.key) {
+            Ok(Some(value)) => Frame::Bulk(value.clone()),
+            Ok(None) => Frame::Null,
+            Err(err) => err.into(),
         }
     }
 }
 
-impl From<&mut IntoIter<Frame>> for Get {
-    fn from(frames: &mut IntoIter<Frame>) -> Self {
-        Get {
-            key: next_string(frames).unwrap()
+impl TryFrom<&mut IntoIter<Frame>> for Get {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() != 1 {
+            return Err(wrong_arity("get"));
         }
+
+        Ok(Get {
+            key: next_bytes(frames)?
+        })
     }
 }
 
@@ -39,11 +49,20 @@ mod tests {
         let key: String = "number".to_string();
         let mut iter: IntoIter<Frame> = vec![Frame::Simple(key.clone())].into_iter();
 
-        let command: Get = (&mut iter).into();
+        let command: Get = (&mut iter).try_into().unwrap();
 
         assert_eq!(key, command.key);
     }
 
+    #[test]
+    fn it_requires_exactly_one_key() {
+        let mut iter: IntoIter<Frame> = vec![].into_iter();
+
+        let result = Get::try_from(&mut iter);
+
+        assert!(result.is_err());
+    }
+
     #[test]
     fn it_returns_data_from_db() {
         let db = new_db();
@@ -51,7 +70,7 @@ mod tests {
             let db = db.clone();
             db.lock().unwrap().insert("key".to_string(), Bytes::from("value"));
         }
-        let command = Get { key: "key".to_string() };
+        let command = Get { key: Bytes::from("key") };
 
         let result = command.execute(db);
 
@@ -61,7 +80,7 @@ mod tests {
     #[test]
     fn it_returns_null_when_key_does_not_exist() {
         let db = new_db();
-        let command = Get { key: "key".to_string() };
+        let command = Get { key: Bytes::from("key") };
 
         let result = command.execute(db);
 
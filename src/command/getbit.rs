@@ -0,0 +1,65 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::Error;
+
+use super::{Command, next_bit_offset, next_bytes, wrong_arity};
+
+pub(crate) struct GetBit {
+    key: Bytes,
+    offset: usize,
+}
+
+impl Command for GetBit {
+    fn execute(&self, db: Database) -> Frame {
+        let db = db.lock_keys([&self.key]).unwrap();
+        let value = match db.get(&self.key) {
+            Ok(value) => value,
+            Err(err) => return err.into(),
+        };
+        let bit = value
+            .and_then(|value| value.get(self.offset >> 3))
+            .map_or(0, |byte| (byte >> (7 - (self.offset & 7))) & 1);
+
+        Frame::Integer(bit as i64)
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for GetBit {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() != 2 {
+            return Err(wrong_arity("getbit"));
+        }
+
+        Ok(GetBit {
+            key: next_bytes(frames)?,
+            offset: next_bit_offset(frames)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::database::new_db;
+
+    use super::*;
+
+    #[test]
+    fn it_reads_bits() {
+        let db = new_db();
+        db.lock().unwrap().insert("bits".to_string(), Bytes::from(&[0b0100_0000][..]));
+
+        let get = |offset| GetBit { key: Bytes::from("bits"), offset }.execute(db.clone());
+
+        assert_eq!(Frame::Integer(0), get(0));
+        assert_eq!(Frame::Integer(1), get(1));
+        assert_eq!(Frame::Integer(0), get(100));
+    }
+}
@@ -0,0 +1,62 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::Error;
+
+use super::{Command, next_bytes, wrong_arity};
+
+pub(crate) struct GetDel {
+    key: Bytes,
+}
+
+impl Command for GetDel {
+    fn execute(&self, db: Database) -> Frame {
+        let mut db = db.lock_keys([&self.key]).unwrap();
+
+        let value = match db.get(&self.key) {
+            Ok(Some(value)) => value.clone(),
+            Ok(None) => return Frame::Null,
+            Err(err) => return err.into(),
+        };
+
+        db.remove(&self.key);
+        Frame::Bulk(value)
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for GetDel {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() != 1 {
+            return Err(wrong_arity("getdel"));
+        }
+
+        Ok(GetDel {
+            key: next_bytes(frames)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::database::new_db;
+
+    use super::*;
+
+    #[test]
+    fn it_returns_and_removes_the_value() {
+        let db = new_db();
+        db.lock().unwrap().insert("key".to_string(), Bytes::from("value"));
+        let command = GetDel { key: Bytes::from("key") };
+
+        assert_eq!(Frame::Bulk(Bytes::from("value")), command.execute(db.clone()));
+        assert_eq!(Frame::Null, command.execute(db.clone()));
+        assert!(!db.lock().unwrap().contains_key("key"));
+    }
+}
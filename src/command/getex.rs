@@ -0,0 +1,123 @@
+use std::time::{Duration, SystemTime};
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::Error;
+
+use super::{Command, next_bytes, next_string, next_ttl, syntax_error, wrong_arity};
+
+pub(crate) struct GetEx {
+    key: Bytes,
+    expiration: Expiration,
+}
+
+#[derive(Debug, PartialEq)]
+enum Expiration {
+    Keep,
+    Persist,
+    Ttl(Duration),
+}
+
+impl Command for GetEx {
+    fn execute(&self, db: Database) -> Frame {
+        let mut db = db.lock_keys([&self.key]).unwrap();
+        let value = match db.get(&self.key) {
+            Ok(Some(value)) => value.clone(),
+            Ok(None) => return Frame::Null,
+            Err(err) => return err.into(),
+        };
+
+        match self.expiration {
+            Expiration::Keep => {}
+            Expiration::Persist => {
+                db.set_expiration(&self.key, None);
+            }
+            Expiration::Ttl(ttl) => {
+                db.set_expiration(&self.key, Some(SystemTime::now() + ttl));
+            }
+        }
+
+        Frame::Bulk(value)
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for GetEx {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() == 0 {
+            return Err(wrong_arity("getex"));
+        }
+
+        let key = next_bytes(frames)?;
+        let mut expiration = Expiration::Keep;
+
+        while let Ok(option) = next_string(frames) {
+            let option = option.to_uppercase();
+            match option.as_str() {
+                "EX" | "PX" | "EXAT" | "PXAT" if expiration == Expiration::Keep => {
+                    expiration = Expiration::Ttl(next_ttl(&option, frames, "getex")?);
+                }
+                "PERSIST" if expiration == Expiration::Keep => {
+                    expiration = Expiration::Persist;
+                }
+                _ => return Err(syntax_error()),
+            }
+        }
+
+        Ok(GetEx { key, expiration })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::database::new_db;
+
+    use super::*;
+
+    #[test]
+    fn it_sets_expiration_while_reading() {
+        let db = new_db();
+        db.lock().unwrap().insert("key".to_string(), Bytes::from("value"));
+        let mut iter: IntoIter<Frame> = vec![
+            Frame::Bulk(Bytes::from("key")),
+            Frame::Bulk(Bytes::from("px")),
+            Frame::Bulk(Bytes::from("5000")),
+        ].into_iter();
+        let command = GetEx::try_from(&mut iter).unwrap();
+
+        let result = command.execute(db.clone());
+
+        assert_eq!(Frame::Bulk(Bytes::from("value")), result);
+        assert!(db.lock().unwrap().expiration("key").is_some());
+    }
+
+    #[test]
+    fn it_removes_expiration_with_persist() {
+        let db = new_db();
+        let expires_at = SystemTime::now() + Duration::from_secs(10);
+        db.lock().unwrap().insert_with_expiration("key".to_string(), Bytes::from("value"), Some(expires_at));
+        let command = GetEx { key: Bytes::from("key"), expiration: Expiration::Persist };
+
+        command.execute(db.clone());
+
+        assert_eq!(None, db.lock().unwrap().expiration("key"));
+    }
+
+    #[test]
+    fn it_rejects_multiple_expiration_options() {
+        let mut iter: IntoIter<Frame> = vec![
+            Frame::Bulk(Bytes::from("key")),
+            Frame::Bulk(Bytes::from("EX")),
+            Frame::Bulk(Bytes::from("5")),
+            Frame::Bulk(Bytes::from("PERSIST")),
+        ].into_iter();
+
+        assert!(GetEx::try_from(&mut iter).is_err());
+    }
+}
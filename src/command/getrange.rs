@@ -0,0 +1,95 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::Error;
+
+use super::{Command, next_bytes, next_integer, wrong_arity};
+
+pub(crate) struct GetRange {
+    key: Bytes,
+    start: i64,
+    end: i64,
+}
+
+impl Command for GetRange {
+    fn execute(&self, db: Database) -> Frame {
+        let db = db.lock_keys([&self.key]).unwrap();
+        let value = match db.get(&self.key) {
+            Ok(Some(value)) => value,
+            Ok(None) => return Frame::Bulk(Bytes::new()),
+            Err(err) => return err.into(),
+        };
+
+        match range(self.start, self.end, value.len()) {
+            Some((start, end)) => Frame::Bulk(value.slice(start..=end)),
+            None => Frame::Bulk(Bytes::new()),
+        }
+    }
+}
+
+/// Resolves inclusive, possibly negative indexes against a string of the given length.
+pub(crate) fn range(start: i64, end: i64, len: usize) -> Option<(usize, usize)> {
+    let len = len as i64;
+
+    if len == 0 || (start < 0 && end < 0 && start > end) {
+        return None;
+    }
+
+    let start = if start < 0 { (len + start).max(0) } else { start };
+    let end = if end < 0 { (len + end).max(0) } else { end.min(len - 1) };
+
+    if start > end {
+        return None;
+    }
+
+    Some((start as usize, end as usize))
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for GetRange {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() != 3 {
+            return Err(wrong_arity("getrange"));
+        }
+
+        Ok(GetRange {
+            key: next_bytes(frames)?,
+            start: next_integer(frames)?,
+            end: next_integer(frames)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::new_db;
+
+    use super::*;
+
+    fn get_range(start: i64, end: i64) -> Frame {
+        let db = new_db();
+        db.lock().unwrap().insert("key".to_string(), Bytes::from("This is a string"));
+        let command = GetRange { key: Bytes::from("key"), start, end };
+
+        command.execute(db)
+    }
+
+    #[test]
+    fn it_returns_substrings() {
+        assert_eq!(Frame::Bulk(Bytes::from("This")), get_range(0, 3));
+        assert_eq!(Frame::Bulk(Bytes::from("ing")), get_range(-3, -1));
+        assert_eq!(Frame::Bulk(Bytes::from("This is a string")), get_range(0, -1));
+        assert_eq!(Frame::Bulk(Bytes::from("string")), get_range(10, 100));
+    }
+
+    #[test]
+    fn it_returns_empty_string_for_invalid_ranges() {
+        assert_eq!(Frame::Bulk(Bytes::new()), get_range(5, 3));
+        assert_eq!(Frame::Bulk(Bytes::new()), get_range(-1, -5));
+        assert_eq!(Frame::Bulk(Bytes::new()), get_range(100, 200));
+    }
+}
@@ -0,0 +1,154 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::Error;
+
+use super::{Command, error_frame, next_bytes, next_integer, parse_integer, wrong_arity};
+
+/// Covers INCR, DECR, INCRBY and DECRBY which only differ in the increment.
+pub(crate) struct IncrBy {
+    key: Bytes,
+    increment: i64,
+}
+
+impl Command for IncrBy {
+    fn execute(&self, db: Database) -> Frame {
+        let mut db = db.lock_keys([&self.key]).unwrap();
+
+        let current = match db.get(&self.key) {
+            Ok(Some(value)) => match parse_integer(value) {
+                Some(current) => current,
+                None => return error_frame("value is not an integer or out of range"),
+            },
+            Ok(None) => 0,
+            Err(err) => return err.into(),
+        };
+
+        let result = match current.checked_add(self.increment) {
+            Some(result) => result,
+            None => return error_frame("increment or decrement would overflow"),
+        };
+
+        db.update(&self.key, Bytes::from(result.to_string()));
+
+        Frame::Integer(result)
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for IncrBy {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() != 2 {
+            return Err(wrong_arity("incrby"));
+        }
+
+        let key = next_bytes(frames)?;
+        let increment = next_integer(frames)?;
+
+        Ok(IncrBy { key, increment })
+    }
+}
+
+impl IncrBy {
+    pub(crate) fn incr(frames: &mut IntoIter<Frame>) -> Result<Self, Error> {
+        IncrBy::with_increment(frames, 1, "incr")
+    }
+
+    pub(crate) fn decr(frames: &mut IntoIter<Frame>) -> Result<Self, Error> {
+        IncrBy::with_increment(frames, -1, "decr")
+    }
+
+    pub(crate) fn decr_by(frames: &mut IntoIter<Frame>) -> Result<Self, Error> {
+        if frames.len() != 2 {
+            return Err(wrong_arity("decrby"));
+        }
+
+        let key = next_bytes(frames)?;
+        let increment = next_integer(frames)?
+            .checked_neg()
+            .ok_or("decrement would overflow")?;
+
+        Ok(IncrBy { key, increment })
+    }
+
+    fn with_increment(frames: &mut IntoIter<Frame>, increment: i64, name: &str) -> Result<Self, Error> {
+        if frames.len() != 1 {
+            return Err(wrong_arity(name));
+        }
+
+        Ok(IncrBy {
+            key: next_bytes(frames)?,
+            increment,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use crate::database::new_db;
+
+    use super::*;
+
+    #[test]
+    fn it_increments_missing_key_from_zero() {
+        let db = new_db();
+        let command = IncrBy { key: Bytes::from("counter"), increment: 5 };
+
+        let result = command.execute(db.clone());
+
+        assert_eq!(Frame::Integer(5), result);
+        assert_eq!(Bytes::from("5"), db.lock().unwrap().get("counter").unwrap().unwrap());
+    }
+
+    #[test]
+    fn it_decrements_below_zero() {
+        let db = new_db();
+        db.lock().unwrap().insert("counter".to_string(), Bytes::from("1"));
+        let mut iter: IntoIter<Frame> = vec![Frame::Bulk(Bytes::from("counter")), Frame::Bulk(Bytes::from("3"))].into_iter();
+        let command = IncrBy::decr_by(&mut iter).unwrap();
+
+        let result = command.execute(db);
+
+        assert_eq!(Frame::Integer(-2), result);
+    }
+
+    #[test]
+    fn it_keeps_expiration() {
+        let db = new_db();
+        let expires_at = SystemTime::now() + Duration::from_secs(10);
+        db.lock().unwrap().insert_with_expiration("counter".to_string(), Bytes::from("1"), Some(expires_at));
+        let command = IncrBy { key: Bytes::from("counter"), increment: 1 };
+
+        command.execute(db.clone());
+
+        assert_eq!(Some(expires_at), db.lock().unwrap().expiration("counter"));
+    }
+
+    #[test]
+    fn it_rejects_values_that_are_not_integers() {
+        let db = new_db();
+        db.lock().unwrap().insert("name".to_string(), Bytes::from("Jasper"));
+        let command = IncrBy { key: Bytes::from("name"), increment: 1 };
+
+        let result = command.execute(db);
+
+        assert_eq!(Frame::SimpleError("ERR value is not an integer or out of range".to_string()), result);
+    }
+
+    #[test]
+    fn it_detects_overflow() {
+        let db = new_db();
+        db.lock().unwrap().insert("counter".to_string(), Bytes::from(i64::MAX.to_string()));
+        let command = IncrBy { key: Bytes::from("counter"), increment: 1 };
+
+        let result = command.execute(db);
+
+        assert_eq!(Frame::SimpleError("ERR increment or decrement would overflow".to_string()), result);
+    }
+}
@@ -0,0 +1,94 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::Error;
+
+use super::{Command, error_frame, next_bytes, next_float, parse_float, wrong_arity};
+
+pub(crate) struct IncrByFloat {
+    key: Bytes,
+    increment: f64,
+}
+
+impl Command for IncrByFloat {
+    fn execute(&self, db: Database) -> Frame {
+        let mut db = db.lock_keys([&self.key]).unwrap();
+
+        let current = match db.get(&self.key) {
+            Ok(Some(value)) => match parse_float(value) {
+                Some(current) => current,
+                None => return error_frame("value is not a valid float"),
+            },
+            Ok(None) => 0.0,
+            Err(err) => return err.into(),
+        };
+
+        let result = current + self.increment;
+        if !result.is_finite() {
+            return error_frame("increment would produce NaN or Infinity");
+        }
+
+        let value = Bytes::from(result.to_string());
+        db.update(&self.key, value.clone());
+
+        Frame::Bulk(value)
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for IncrByFloat {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() != 2 {
+            return Err(wrong_arity("incrbyfloat"));
+        }
+
+        Ok(IncrByFloat {
+            key: next_bytes(frames)?,
+            increment: next_float(frames)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::new_db;
+
+    use super::*;
+
+    #[test]
+    fn it_increments_by_float() {
+        let db = new_db();
+        db.lock().unwrap().insert("price".to_string(), Bytes::from("10.50"));
+        let command = IncrByFloat { key: Bytes::from("price"), increment: 0.1 };
+
+        let result = command.execute(db.clone());
+
+        assert_eq!(Frame::Bulk(Bytes::from("10.6")), result);
+        assert_eq!(Bytes::from("10.6"), db.lock().unwrap().get("price").unwrap().unwrap());
+    }
+
+    #[test]
+    fn it_formats_integral_results_without_fraction() {
+        let db = new_db();
+        db.lock().unwrap().insert("price".to_string(), Bytes::from("5.0e3"));
+        let command = IncrByFloat { key: Bytes::from("price"), increment: 200.0 };
+
+        let result = command.execute(db);
+
+        assert_eq!(Frame::Bulk(Bytes::from("5200")), result);
+    }
+
+    #[test]
+    fn it_rejects_infinite_results() {
+        let db = new_db();
+        let command = IncrByFloat { key: Bytes::from("price"), increment: f64::INFINITY };
+
+        let result = command.execute(db);
+
+        assert_eq!(Frame::SimpleError("ERR increment would produce NaN or Infinity".to_string()), result);
+    }
+}
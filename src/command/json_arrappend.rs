@@ -0,0 +1,110 @@
+use std::vec::IntoIter;
+
+use serde_json::Value;
+
+use bytes::Bytes;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::json::{self, Path};
+use crate::Error;
+
+use super::{Command, error_frame, next_bytes, next_json, next_json_path, path_not_found, wrong_arity};
+
+pub(crate) struct JsonArrAppend {
+    key: Bytes,
+    path: Path,
+    values: Vec<Value>,
+}
+
+impl Command for JsonArrAppend {
+    fn execute(&self, db: Database) -> Frame {
+        let mut db = db.lock_keys([&self.key]).unwrap();
+        let document = match db.get_json_mut(&self.key) {
+            Ok(Some(document)) => document,
+            Ok(None) => return error_frame("could not perform this operation on a key that doesn't exist"),
+            Err(err) => return err.into(),
+        };
+
+        let lengths: Vec<Option<usize>> = self.path.locate(document).iter()
+            .map(|location| match json::get_mut(document, location) {
+                Some(Value::Array(items)) => {
+                    items.extend(self.values.iter().cloned());
+                    Some(items.len())
+                }
+                _ => None,
+            })
+            .collect();
+
+        if !self.path.is_legacy() {
+            return Frame::Array(lengths.into_iter().map(length_frame).collect());
+        }
+
+        match lengths.last() {
+            Some(Some(length)) => Frame::Integer(*length as i64),
+            Some(None) => error_frame("wrong type of path value - expected an array"),
+            None => path_not_found(&self.path),
+        }
+    }
+}
+
+fn length_frame(length: Option<usize>) -> Frame {
+    match length {
+        Some(length) => Frame::Integer(length as i64),
+        None => Frame::Null,
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for JsonArrAppend {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() < 3 {
+            return Err(wrong_arity("json.arrappend"));
+        }
+
+        let key = next_bytes(frames)?;
+        let path = next_json_path(frames)?;
+        let mut values = vec![];
+        while frames.len() > 0 {
+            values.push(next_json(frames)?);
+        }
+
+        Ok(JsonArrAppend { key, path, values })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use serde_json::json;
+
+    use crate::database::new_db;
+
+    use super::*;
+
+    fn arrappend(db: &Database, arguments: &[&str]) -> Frame {
+        let frames: Vec<Frame> = arguments.iter().map(|argument| Frame::Bulk(Bytes::from(argument.to_string()))).collect();
+        JsonArrAppend::try_from(&mut frames.into_iter()).unwrap().execute(db.clone())
+    }
+
+    #[test]
+    fn it_appends_to_all_matching_arrays() {
+        let db = new_db();
+        db.lock().unwrap().insert("doc".to_string(), json!({"a": [1], "b": {"a": "x"}}));
+
+        let result = arrappend(&db, &["doc", "$..a", "2", r#""three""#]);
+
+        assert_eq!(Frame::Array(vec![Frame::Integer(3), Frame::Null]), result);
+        assert_eq!(&json!({"a": [1, 2, "three"], "b": {"a": "x"}}), db.lock().unwrap().get_json("doc").unwrap().unwrap());
+    }
+
+    #[test]
+    fn it_returns_length_for_legacy_path() {
+        let db = new_db();
+        db.lock().unwrap().insert("doc".to_string(), json!({"a": [], "b": 1}));
+
+        assert_eq!(Frame::Integer(1), arrappend(&db, &["doc", ".a", "null"]));
+        assert_eq!(Frame::SimpleError("ERR wrong type of path value - expected an array".to_string()), arrappend(&db, &["doc", ".b", "1"]));
+    }
+}
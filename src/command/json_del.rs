@@ -0,0 +1,97 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::json::{self, Path};
+use crate::Error;
+
+use super::{Command, next_bytes, next_json_path, syntax_error, wrong_arity};
+
+pub(crate) struct JsonDel {
+    key: Bytes,
+    path: Path,
+}
+
+impl Command for JsonDel {
+    fn execute(&self, db: Database) -> Frame {
+        let mut db = db.lock_keys([&self.key]).unwrap();
+        let document = match db.get_json_mut(&self.key) {
+            Ok(Some(document)) => document,
+            Ok(None) => return Frame::Integer(0),
+            Err(err) => return err.into(),
+        };
+
+        if self.path.is_root() {
+            db.remove(&self.key);
+            return Frame::Integer(1);
+        }
+
+        // Removing later siblings first keeps the array indexes of earlier matches valid.
+        let mut locations = self.path.locate(document);
+        locations.sort();
+        let removed = locations.iter().rev()
+            .filter(|location| json::remove(document, location))
+            .count();
+
+        Frame::Integer(removed as i64)
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for JsonDel {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() == 0 {
+            return Err(wrong_arity("json.del"));
+        }
+        if frames.len() > 2 {
+            return Err(syntax_error());
+        }
+
+        let key = next_bytes(frames)?;
+        let path = match frames.len() {
+            0 => Path::root(),
+            _ => next_json_path(frames)?,
+        };
+
+        Ok(JsonDel { key, path })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use serde_json::json;
+
+    use crate::database::new_db;
+
+    use super::*;
+
+    fn json_del(db: &Database, arguments: &[&str]) -> Frame {
+        let frames: Vec<Frame> = arguments.iter().map(|argument| Frame::Bulk(Bytes::from(argument.to_string()))).collect();
+        JsonDel::try_from(&mut frames.into_iter()).unwrap().execute(db.clone())
+    }
+
+    #[test]
+    fn it_deletes_matching_values() {
+        let db = new_db();
+        db.lock().unwrap().insert("doc".to_string(), json!({"a": [1, 2, 3, 4], "b": {"a": 1}}));
+
+        assert_eq!(Frame::Integer(2), json_del(&db, &["doc", "$.a[1,2]"]));
+        assert_eq!(Frame::Integer(2), json_del(&db, &["doc", "$..a"]));
+        assert_eq!(Frame::Integer(0), json_del(&db, &["doc", "$.x"]));
+        assert_eq!(&json!({"b": {}}), db.lock().unwrap().get_json("doc").unwrap().unwrap());
+    }
+
+    #[test]
+    fn it_deletes_key_for_root_path() {
+        let db = new_db();
+        db.lock().unwrap().insert("doc".to_string(), json!({"a": 1}));
+
+        assert_eq!(Frame::Integer(1), json_del(&db, &["doc"]));
+        assert!(!db.lock().unwrap().contains_key("doc"));
+        assert_eq!(Frame::Integer(0), json_del(&db, &["doc", "$"]));
+    }
+}
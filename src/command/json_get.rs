@@ -0,0 +1,123 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+use serde_json::{Map, Value};
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::json::{self, Path};
+use crate::Error;
+
+use super::{Command, next_bytes, next_json_path, path_not_found, wrong_arity};
+
+pub(crate) struct JsonGet {
+    key: Bytes,
+    paths: Vec<Path>,
+}
+
+impl Command for JsonGet {
+    fn execute(&self, db: Database) -> Frame {
+        let db = db.lock_keys([&self.key]).unwrap();
+        let document = match db.get_json(&self.key) {
+            Ok(Some(document)) => document,
+            Ok(None) => return Frame::Null,
+            Err(err) => return err.into(),
+        };
+
+        let reply = match &self.paths[..] {
+            [path] => match select(path, document) {
+                Some(value) => value,
+                None => return path_not_found(path),
+            },
+            paths => {
+                let mut reply = Map::new();
+                for path in paths {
+                    match select(path, document) {
+                        Some(value) => reply.insert(path.as_str().to_string(), value),
+                        None => return path_not_found(path),
+                    };
+                }
+                Value::Object(reply)
+            }
+        };
+
+        Frame::Bulk(Bytes::from(reply.to_string()))
+    }
+}
+
+/// Legacy paths reply with the first match, JSONPaths with an array of all matches.
+fn select(path: &Path, document: &Value) -> Option<Value> {
+    let mut matches = path.locate(document).into_iter()
+        .filter_map(|location| json::get(document, &location).cloned());
+
+    match path.is_legacy() {
+        true => matches.next(),
+        false => Some(Value::Array(matches.collect())),
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for JsonGet {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() == 0 {
+            return Err(wrong_arity("json.get"));
+        }
+
+        let key = next_bytes(frames)?;
+        let mut paths = vec![];
+        while frames.len() > 0 {
+            paths.push(next_json_path(frames)?);
+        }
+        if paths.is_empty() {
+            paths.push(Path::root());
+        }
+
+        Ok(JsonGet { key, paths })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::database::new_db;
+
+    use super::*;
+
+    fn json_get(db: &Database, arguments: &[&str]) -> Frame {
+        let frames: Vec<Frame> = arguments.iter().map(|argument| Frame::Bulk(Bytes::from(argument.to_string()))).collect();
+        JsonGet::try_from(&mut frames.into_iter()).unwrap().execute(db.clone())
+    }
+
+    fn with_document() -> Database {
+        let db = new_db();
+        db.lock().unwrap().insert("doc".to_string(), json!({"a": {"b": 1}, "c": [1, 2]}));
+        db
+    }
+
+    #[test]
+    fn it_returns_whole_document_by_default() {
+        let db = with_document();
+
+        assert_eq!(Frame::Bulk(Bytes::from(r#"{"a":{"b":1},"c":[1,2]}"#)), json_get(&db, &["doc"]));
+        assert_eq!(Frame::Null, json_get(&db, &["missing"]));
+    }
+
+    #[test]
+    fn it_returns_matches_of_json_path() {
+        let db = with_document();
+
+        assert_eq!(Frame::Bulk(Bytes::from("[1]")), json_get(&db, &["doc", "$.a.b"]));
+        assert_eq!(Frame::Bulk(Bytes::from("[]")), json_get(&db, &["doc", "$.x"]));
+        assert_eq!(Frame::Bulk(Bytes::from(r#"{"$.a.b":[1],"$.c[0]":[1]}"#)), json_get(&db, &["doc", "$.a.b", "$.c[0]"]));
+    }
+
+    #[test]
+    fn it_returns_single_value_of_legacy_path() {
+        let db = with_document();
+
+        assert_eq!(Frame::Bulk(Bytes::from("2")), json_get(&db, &["doc", ".c[1]"]));
+        assert_eq!(Frame::SimpleError("ERR Path '.x' does not exist".to_string()), json_get(&db, &["doc", ".x"]));
+    }
+}
@@ -0,0 +1,121 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+use serde_json::{Number, Value};
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::json::{self, Path};
+use crate::Error;
+
+use super::{Command, error_frame, next_bytes, next_json_path, next_string, path_not_found, wrong_arity};
+
+pub(crate) struct JsonNumIncrBy {
+    key: Bytes,
+    path: Path,
+    increment: Number,
+}
+
+impl Command for JsonNumIncrBy {
+    fn execute(&self, db: Database) -> Frame {
+        let mut db = db.lock_keys([&self.key]).unwrap();
+        let document = match db.get_json_mut(&self.key) {
+            Ok(Some(document)) => document,
+            Ok(None) => return error_frame("could not perform this operation on a key that doesn't exist"),
+            Err(err) => return err.into(),
+        };
+
+        let mut results = vec![];
+        for location in self.path.locate(document) {
+            let result = match json::get_mut(document, &location) {
+                Some(Value::Number(number)) => match add(number, &self.increment) {
+                    Some(sum) => {
+                        *number = sum.clone();
+                        Value::Number(sum)
+                    }
+                    None => return error_frame("result is not a number or infinity"),
+                },
+                _ => Value::Null,
+            };
+            results.push(result);
+        }
+
+        if !self.path.is_legacy() {
+            return Frame::Bulk(Bytes::from(Value::Array(results).to_string()));
+        }
+
+        match results.into_iter().last() {
+            Some(Value::Null) => error_frame("wrong type of path value - expected a number"),
+            Some(result) => Frame::Bulk(Bytes::from(result.to_string())),
+            None => path_not_found(&self.path),
+        }
+    }
+}
+
+/// Adds integers exactly and falls back to floating point otherwise.
+fn add(number: &Number, increment: &Number) -> Option<Number> {
+    if let (Some(a), Some(b)) = (number.as_i64(), increment.as_i64()) {
+        if let Some(sum) = a.checked_add(b) {
+            return Some(Number::from(sum));
+        }
+    }
+
+    Number::from_f64(number.as_f64()? + increment.as_f64()?)
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for JsonNumIncrBy {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() != 3 {
+            return Err(wrong_arity("json.numincrby"));
+        }
+
+        let key = next_bytes(frames)?;
+        let path = next_json_path(frames)?;
+        let increment = match serde_json::from_str(&next_string(frames)?) {
+            Ok(Value::Number(increment)) => increment,
+            _ => return Err("expected a number".into()),
+        };
+
+        Ok(JsonNumIncrBy { key, path, increment })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::database::new_db;
+
+    use super::*;
+
+    fn numincrby(db: &Database, arguments: &[&str]) -> Frame {
+        let frames: Vec<Frame> = arguments.iter().map(|argument| Frame::Bulk(Bytes::from(argument.to_string()))).collect();
+        JsonNumIncrBy::try_from(&mut frames.into_iter()).unwrap().execute(db.clone())
+    }
+
+    fn with_document() -> Database {
+        let db = new_db();
+        db.lock().unwrap().insert("doc".to_string(), json!({"a": "x", "b": 1, "c": {"b": 2.5}}));
+        db
+    }
+
+    #[test]
+    fn it_increments_all_matching_numbers() {
+        let db = with_document();
+
+        assert_eq!(Frame::Bulk(Bytes::from("[3,4.5]")), numincrby(&db, &["doc", "$..b", "2"]));
+        assert_eq!(Frame::Bulk(Bytes::from("[null]")), numincrby(&db, &["doc", "$.a", "2"]));
+        assert_eq!(&json!({"a": "x", "b": 3, "c": {"b": 4.5}}), db.lock().unwrap().get_json("doc").unwrap().unwrap());
+    }
+
+    #[test]
+    fn it_returns_single_value_for_legacy_path() {
+        let db = with_document();
+
+        assert_eq!(Frame::Bulk(Bytes::from("1.5")), numincrby(&db, &["doc", ".b", "0.5"]));
+        assert_eq!(Frame::SimpleError("ERR Path '.x' does not exist".to_string()), numincrby(&db, &["doc", ".x", "1"]));
+        assert_eq!(Frame::SimpleError("ERR wrong type of path value - expected a number".to_string()), numincrby(&db, &["doc", ".a", "1"]));
+    }
+}
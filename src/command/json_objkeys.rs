@@ -0,0 +1,101 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+use serde_json::Value;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::json::{self, Path};
+use crate::Error;
+
+use super::{Command, error_frame, next_bytes, next_json_path, path_not_found, syntax_error, wrong_arity};
+
+pub(crate) struct JsonObjKeys {
+    key: Bytes,
+    path: Path,
+}
+
+impl Command for JsonObjKeys {
+    fn execute(&self, db: Database) -> Frame {
+        let db = db.lock_keys([&self.key]).unwrap();
+        let document = match db.get_json(&self.key) {
+            Ok(Some(document)) => document,
+            Ok(None) => return Frame::Null,
+            Err(err) => return err.into(),
+        };
+
+        let keys: Vec<Frame> = self.path.locate(document).iter()
+            .map(|location| match json::get(document, location) {
+                Some(Value::Object(map)) => Frame::Array(map.keys().map(|key| Frame::Bulk(Bytes::from(key.clone()))).collect()),
+                _ => Frame::Null,
+            })
+            .collect();
+
+        if !self.path.is_legacy() {
+            return Frame::Array(keys);
+        }
+
+        match keys.into_iter().next() {
+            Some(Frame::Null) => error_frame("wrong type of path value - expected an object"),
+            Some(keys) => keys,
+            None => path_not_found(&self.path),
+        }
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for JsonObjKeys {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() == 0 {
+            return Err(wrong_arity("json.objkeys"));
+        }
+        if frames.len() > 2 {
+            return Err(syntax_error());
+        }
+
+        let key = next_bytes(frames)?;
+        let path = match frames.len() {
+            0 => Path::root(),
+            _ => next_json_path(frames)?,
+        };
+
+        Ok(JsonObjKeys { key, path })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::database::new_db;
+
+    use super::*;
+
+    fn objkeys(db: &Database, arguments: &[&str]) -> Frame {
+        let frames: Vec<Frame> = arguments.iter().map(|argument| Frame::Bulk(Bytes::from(argument.to_string()))).collect();
+        JsonObjKeys::try_from(&mut frames.into_iter()).unwrap().execute(db.clone())
+    }
+
+    fn keys(names: &[&str]) -> Frame {
+        Frame::Array(names.iter().map(|name| Frame::Bulk(Bytes::from(name.to_string()))).collect())
+    }
+
+    #[test]
+    fn it_lists_object_keys_in_insertion_order() {
+        let db = new_db();
+        db.lock().unwrap().insert("doc".to_string(), serde_json::from_str::<Value>(r#"{"z":1,"a":{"y":1,"b":2},"c":[]}"#).unwrap());
+
+        assert_eq!(keys(&["z", "a", "c"]), objkeys(&db, &["doc"]));
+        assert_eq!(Frame::Array(vec![keys(&["y", "b"]), Frame::Null]), objkeys(&db, &["doc", "$['a','c']"]));
+        assert_eq!(Frame::Null, objkeys(&db, &["missing"]));
+    }
+
+    #[test]
+    fn it_fails_for_legacy_path_to_non_object() {
+        let db = new_db();
+        db.lock().unwrap().insert("doc".to_string(), json!({"a": 1}));
+
+        assert_eq!(Frame::SimpleError("ERR wrong type of path value - expected an object".to_string()), objkeys(&db, &["doc", ".a"]));
+    }
+}
@@ -0,0 +1,160 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::json::{self, Path};
+use crate::Error;
+
+use super::{Command, error_frame, next_bytes, next_json, next_json_path, next_string, syntax_error, wrong_arity};
+
+pub(crate) struct JsonSet {
+    key: Bytes,
+    path: Path,
+    value: serde_json::Value,
+    only_new: bool,
+    only_existing: bool,
+}
+
+impl Command for JsonSet {
+    fn execute(&self, db: Database) -> Frame {
+        let mut db = db.lock_keys([&self.key]).unwrap();
+        let document = match db.get_json_mut(&self.key) {
+            Ok(Some(document)) => document,
+            Ok(None) if !self.path.is_root() => return error_frame("new objects must be created at the root"),
+            Ok(None) if self.only_existing => return Frame::Null,
+            Ok(None) => {
+                db.insert(self.key.clone(), self.value.clone());
+                return Frame::Simple("OK".to_string());
+            }
+            Err(err) => return err.into(),
+        };
+
+        let locations = self.path.locate(document);
+        if !locations.is_empty() {
+            if self.only_new {
+                return Frame::Null;
+            }
+            for location in locations {
+                if let Some(target) = json::get_mut(document, &location) {
+                    *target = self.value.clone();
+                }
+            }
+            return Frame::Simple("OK".to_string());
+        }
+
+        let Some((locations, name)) = self.path.parents(document) else {
+            return Frame::Null;
+        };
+        if self.only_existing || locations.is_empty() {
+            return Frame::Null;
+        }
+        for location in locations {
+            if let Some(serde_json::Value::Object(map)) = json::get_mut(document, &location) {
+                map.insert(name.clone(), self.value.clone());
+            }
+        }
+
+        Frame::Simple("OK".to_string())
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for JsonSet {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() < 3 {
+            return Err(wrong_arity("json.set"));
+        }
+
+        let mut command = JsonSet {
+            key: next_bytes(frames)?,
+            path: next_json_path(frames)?,
+            value: next_json(frames)?,
+            only_new: false,
+            only_existing: false,
+        };
+
+        while frames.len() > 0 {
+            match next_string(frames)?.to_uppercase().as_str() {
+                "NX" if !command.only_existing => command.only_new = true,
+                "XX" if !command.only_new => command.only_existing = true,
+                _ => return Err(syntax_error()),
+            }
+        }
+
+        Ok(command)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use serde_json::json;
+
+    use crate::database::new_db;
+
+    use super::*;
+
+    fn json_set(db: &Database, arguments: &[&str]) -> Frame {
+        let frames: Vec<Frame> = arguments.iter().map(|argument| Frame::Bulk(Bytes::from(argument.to_string()))).collect();
+        JsonSet::try_from(&mut frames.into_iter()).unwrap().execute(db.clone())
+    }
+
+    fn document(db: &Database) -> serde_json::Value {
+        db.lock().unwrap().get_json("doc").unwrap().unwrap().clone()
+    }
+
+    #[test]
+    fn it_creates_document_at_root() {
+        let db = new_db();
+
+        let result = json_set(&db, &["doc", "$", r#"{"a":{"b":1}}"#]);
+
+        assert_eq!(Frame::Simple("OK".to_string()), result);
+        assert_eq!(json!({"a": {"b": 1}}), document(&db));
+    }
+
+    #[test]
+    fn it_updates_nested_values_and_adds_members() {
+        let db = new_db();
+        json_set(&db, &["doc", "$", r#"{"a":{"b":1},"c":{"b":2}}"#]);
+
+        json_set(&db, &["doc", "$..b", "10"]);
+        json_set(&db, &["doc", "$.a.d", r#""new""#]);
+
+        assert_eq!(json!({"a": {"b": 10, "d": "new"}, "c": {"b": 10}}), document(&db));
+    }
+
+    #[test]
+    fn it_respects_nx_and_xx() {
+        let db = new_db();
+        json_set(&db, &["doc", "$", r#"{"a":1}"#]);
+
+        assert_eq!(Frame::Null, json_set(&db, &["doc", "$.a", "2", "NX"]));
+        assert_eq!(Frame::Null, json_set(&db, &["doc", "$.b", "2", "XX"]));
+        assert_eq!(Frame::Simple("OK".to_string()), json_set(&db, &["doc", "$.b", "2", "NX"]));
+        assert_eq!(json!({"a": 1, "b": 2}), document(&db));
+    }
+
+    #[test]
+    fn it_requires_new_documents_at_root() {
+        let db = new_db();
+
+        let result = json_set(&db, &["doc", "$.a", "1"]);
+
+        assert_eq!(Frame::SimpleError("ERR new objects must be created at the root".to_string()), result);
+    }
+
+    #[test]
+    fn it_rejects_invalid_json() {
+        let frames = vec![
+            Frame::Bulk(Bytes::from("doc")),
+            Frame::Bulk(Bytes::from("$")),
+            Frame::Bulk(Bytes::from("{invalid")),
+        ];
+
+        assert!(JsonSet::try_from(&mut frames.into_iter()).is_err());
+    }
+}
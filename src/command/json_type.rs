@@ -0,0 +1,89 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::json::{self, Path};
+use crate::Error;
+
+use super::{Command, next_bytes, next_json_path, syntax_error, wrong_arity};
+
+pub(crate) struct JsonType {
+    key: Bytes,
+    path: Path,
+}
+
+impl Command for JsonType {
+    fn execute(&self, db: Database) -> Frame {
+        let db = db.lock_keys([&self.key]).unwrap();
+        let document = match db.get_json(&self.key) {
+            Ok(Some(document)) => document,
+            Ok(None) => return Frame::Null,
+            Err(err) => return err.into(),
+        };
+
+        let mut types = self.path.locate(document).into_iter()
+            .filter_map(|location| json::get(document, &location).map(json::type_name));
+
+        if self.path.is_legacy() {
+            return match types.next() {
+                Some(name) => Frame::Simple(name.to_string()),
+                None => Frame::Null,
+            };
+        }
+
+        Frame::Array(types.map(|name| Frame::Bulk(Bytes::from(name))).collect())
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for JsonType {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() == 0 {
+            return Err(wrong_arity("json.type"));
+        }
+        if frames.len() > 2 {
+            return Err(syntax_error());
+        }
+
+        let key = next_bytes(frames)?;
+        let path = match frames.len() {
+            0 => Path::root(),
+            _ => next_json_path(frames)?,
+        };
+
+        Ok(JsonType { key, path })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::database::new_db;
+
+    use super::*;
+
+    fn json_type(db: &Database, arguments: &[&str]) -> Frame {
+        let frames: Vec<Frame> = arguments.iter().map(|argument| Frame::Bulk(Bytes::from(argument.to_string()))).collect();
+        JsonType::try_from(&mut frames.into_iter()).unwrap().execute(db.clone())
+    }
+
+    #[test]
+    fn it_reports_types_of_matching_values() {
+        let db = new_db();
+        db.lock().unwrap().insert("doc".to_string(), json!({"a": 1, "b": 1.5, "c": "x", "d": [true, null]}));
+
+        assert_eq!(Frame::Simple("object".to_string()), json_type(&db, &["doc"]));
+        assert_eq!(Frame::Array(vec![
+            Frame::Bulk(Bytes::from("integer")),
+            Frame::Bulk(Bytes::from("number")),
+            Frame::Bulk(Bytes::from("string")),
+            Frame::Bulk(Bytes::from("array")),
+        ]), json_type(&db, &["doc", "$.*"]));
+        assert_eq!(Frame::Array(vec![Frame::Bulk(Bytes::from("boolean")), Frame::Bulk(Bytes::from("null"))]), json_type(&db, &["doc", "$.d[*]"]));
+        assert_eq!(Frame::Null, json_type(&db, &["missing"]));
+    }
+}
@@ -0,0 +1,55 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::Error;
+
+use super::{Command, next_bytes, wrong_arity};
+
+pub(crate) struct KeyType {
+    key: Bytes,
+}
+
+impl Command for KeyType {
+    fn execute(&self, db: Database) -> Frame {
+        let db = db.lock_keys([&self.key]).unwrap();
+        let name = db.get_value(&self.key).map_or("none", |value| value.type_name());
+
+        Frame::Simple(name.to_string())
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for KeyType {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() != 1 {
+            return Err(wrong_arity("type"));
+        }
+
+        Ok(KeyType { key: next_bytes(frames)? })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::database::new_db;
+    use crate::sorted_set::SortedSet;
+
+    use super::*;
+
+    #[test]
+    fn it_reports_type_of_value() {
+        let db = new_db();
+        db.lock().unwrap().insert("string".to_string(), Bytes::from("1"));
+        db.lock().unwrap().insert("zset".to_string(), SortedSet::default());
+
+        assert_eq!(Frame::Simple("string".to_string()), KeyType { key: Bytes::from("string") }.execute(db.clone()));
+        assert_eq!(Frame::Simple("zset".to_string()), KeyType { key: Bytes::from("zset") }.execute(db.clone()));
+        assert_eq!(Frame::Simple("none".to_string()), KeyType { key: Bytes::from("missing") }.execute(db.clone()));
+    }
+}
@@ -0,0 +1,62 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::glob;
+use crate::Error;
+
+use super::{Command, next_bytes, wrong_arity};
+
+pub(crate) struct Keys {
+    pattern: Bytes,
+}
+
+impl Command for Keys {
+    fn execute(&self, db: Database) -> Frame {
+        let db = db.lock().unwrap();
+        let keys = db.keys()
+            .filter(|key| glob::matches(&self.pattern, key))
+            .map(|key| Frame::Bulk(key.clone()))
+            .collect();
+
+        Frame::Array(keys)
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for Keys {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() != 1 {
+            return Err(wrong_arity("keys"));
+        }
+
+        Ok(Keys { pattern: next_bytes(frames)? })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::new_db;
+
+    use super::*;
+
+    #[test]
+    fn it_returns_matching_keys() {
+        let db = new_db();
+        for key in ["user:1", "user:2", "session:1"] {
+            db.lock().unwrap().insert(key.to_string(), Bytes::from("value"));
+        }
+
+        let result = Keys { pattern: Bytes::from("user:*") }.execute(db.clone());
+
+        let mut keys = match result {
+            Frame::Array(keys) => keys,
+            frame => panic!("unexpected reply {:?}", frame),
+        };
+        keys.sort_by_key(|key| format!("{:?}", key));
+        assert_eq!(vec![Frame::Bulk(Bytes::from("user:1")), Frame::Bulk(Bytes::from("user:2"))], keys);
+    }
+}
@@ -0,0 +1,225 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::Error;
+
+use super::{Command, next_bytes, next_integer, next_string, syntax_error, wrong_arity};
+
+pub(crate) struct Lcs {
+    first: Bytes,
+    second: Bytes,
+    len: bool,
+    idx: bool,
+    min_match_len: usize,
+    with_match_len: bool,
+}
+
+impl Command for Lcs {
+    fn execute(&self, db: Database) -> Frame {
+        let db = db.lock_keys([&self.first, &self.second]).unwrap();
+        let (a, b) = match (db.get(&self.first), db.get(&self.second)) {
+            (Ok(a), Ok(b)) => (a.cloned().unwrap_or_default(), b.cloned().unwrap_or_default()),
+            (Err(err), _) | (_, Err(err)) => return err.into(),
+        };
+        drop(db);
+
+        let (subsequence, matches) = longest_common_subsequence(&a, &b);
+
+        if self.len {
+            return Frame::Integer(subsequence.len() as i64);
+        }
+        if !self.idx {
+            return Frame::Bulk(Bytes::from(subsequence));
+        }
+
+        let matches = matches.into_iter()
+            .filter(|m| self.min_match_len == 0 || m.len >= self.min_match_len)
+            .map(|m| {
+                let mut frames = vec![
+                    Frame::Array(vec![Frame::Integer(m.a.0 as i64), Frame::Integer(m.a.1 as i64)]),
+                    Frame::Array(vec![Frame::Integer(m.b.0 as i64), Frame::Integer(m.b.1 as i64)]),
+                ];
+                if self.with_match_len {
+                    frames.push(Frame::Integer(m.len as i64));
+                }
+                Frame::Array(frames)
+            })
+            .collect();
+
+        Frame::Array(vec![
+            Frame::Bulk(Bytes::from("matches")),
+            Frame::Array(matches),
+            Frame::Bulk(Bytes::from("len")),
+            Frame::Integer(subsequence.len() as i64),
+        ])
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Match {
+    a: (usize, usize),
+    b: (usize, usize),
+    len: usize,
+}
+
+/// Computes the subsequence with the dynamic programming approach used by Redis,
+/// reporting contiguous matching ranges from the end of the strings backwards.
+fn longest_common_subsequence(a: &[u8], b: &[u8]) -> (Vec<u8>, Vec<Match>) {
+    let width = b.len() + 1;
+    let mut table = vec![0u32; (a.len() + 1) * width];
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            table[i * width + j] = if a[i - 1] == b[j - 1] {
+                table[(i - 1) * width + j - 1] + 1
+            } else {
+                table[(i - 1) * width + j].max(table[i * width + j - 1])
+            };
+        }
+    }
+
+    let mut idx = table[a.len() * width + b.len()] as usize;
+    let mut result = vec![0u8; idx];
+    let mut matches = vec![];
+    let (mut i, mut j) = (a.len(), b.len());
+    let mut current: Option<Match> = None;
+
+    while i > 0 && j > 0 {
+        let mut emit = false;
+
+        if a[i - 1] == b[j - 1] {
+            result[idx - 1] = a[i - 1];
+
+            match current.as_mut() {
+                None => {
+                    current = Some(Match { a: (i - 1, i - 1), b: (j - 1, j - 1), len: 1 });
+                }
+                Some(range) if range.a.0 == i && range.b.0 == j => {
+                    range.a.0 -= 1;
+                    range.b.0 -= 1;
+                    range.len += 1;
+                }
+                Some(_) => emit = true,
+            }
+
+            if current.as_ref().is_some_and(|range| range.a.0 == 0 || range.b.0 == 0) {
+                emit = true;
+            }
+
+            idx -= 1;
+            i -= 1;
+            j -= 1;
+        } else {
+            if table[(i - 1) * width + j] > table[i * width + j - 1] {
+                i -= 1;
+            } else {
+                j -= 1;
+            }
+            emit = current.is_some();
+        }
+
+        if emit {
+            matches.extend(current.take());
+        }
+    }
+
+    (result, matches)
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for Lcs {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() < 2 {
+            return Err(wrong_arity("lcs"));
+        }
+
+        let mut command = Lcs {
+            first: next_bytes(frames)?,
+            second: next_bytes(frames)?,
+            len: false,
+            idx: false,
+            min_match_len: 0,
+            with_match_len: false,
+        };
+
+        while let Ok(option) = next_string(frames) {
+            match option.to_uppercase().as_str() {
+                "LEN" => command.len = true,
+                "IDX" => command.idx = true,
+                "WITHMATCHLEN" => command.with_match_len = true,
+                "MINMATCHLEN" => {
+                    command.min_match_len = next_integer(frames).map_err(|_| syntax_error())?.max(0) as usize;
+                }
+                _ => return Err(syntax_error()),
+            }
+        }
+
+        if command.len && command.idx {
+            return Err("If you want both the length and indexes, please just use IDX.".into());
+        }
+
+        Ok(command)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::new_db;
+
+    use super::*;
+
+    fn lcs(len: bool, idx: bool, min_match_len: usize, with_match_len: bool) -> Frame {
+        let db = new_db();
+        db.lock().unwrap().insert("key1".to_string(), Bytes::from("ohmytext"));
+        db.lock().unwrap().insert("key2".to_string(), Bytes::from("mynewtext"));
+        let command = Lcs {
+            first: Bytes::from("key1"),
+            second: Bytes::from("key2"),
+            len,
+            idx,
+            min_match_len,
+            with_match_len,
+        };
+
+        command.execute(db)
+    }
+
+    #[test]
+    fn it_returns_the_subsequence() {
+        assert_eq!(Frame::Bulk(Bytes::from("mytext")), lcs(false, false, 0, false));
+        assert_eq!(Frame::Integer(6), lcs(true, false, 0, false));
+    }
+
+    #[test]
+    fn it_returns_matching_ranges() {
+        let range = |a0, a1, b0, b1| Frame::Array(vec![
+            Frame::Array(vec![Frame::Integer(a0), Frame::Integer(a1)]),
+            Frame::Array(vec![Frame::Integer(b0), Frame::Integer(b1)]),
+        ]);
+
+        assert_eq!(Frame::Array(vec![
+            Frame::Bulk(Bytes::from("matches")),
+            Frame::Array(vec![range(4, 7, 5, 8), range(2, 3, 0, 1)]),
+            Frame::Bulk(Bytes::from("len")),
+            Frame::Integer(6),
+        ]), lcs(false, true, 0, false));
+    }
+
+    #[test]
+    fn it_filters_short_matches_and_reports_their_length() {
+        assert_eq!(Frame::Array(vec![
+            Frame::Bulk(Bytes::from("matches")),
+            Frame::Array(vec![Frame::Array(vec![
+                Frame::Array(vec![Frame::Integer(4), Frame::Integer(7)]),
+                Frame::Array(vec![Frame::Integer(5), Frame::Integer(8)]),
+                Frame::Integer(4),
+            ])]),
+            Frame::Bulk(Bytes::from("len")),
+            Frame::Integer(6),
+        ]), lcs(false, true, 4, true));
+    }
+}
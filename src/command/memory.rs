@@ -0,0 +1,164 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::database::Database;
+use crate::eviction;
+use crate::frame::Frame;
+use crate::Error;
+
+use super::{Command, next_bytes, next_integer, next_string, syntax_error, wrong_arity};
+
+/// Memory used below which MEMORY DOCTOR has nothing to say, as in Redis.
+const DOCTOR_MINIMUM: usize = 5 * 1024 * 1024;
+
+pub(crate) enum Memory {
+    /// Bytes taken by the key, values are measured exactly so SAMPLES is accepted but not needed.
+    Usage(Bytes),
+    Stats,
+    Doctor,
+}
+
+impl Command for Memory {
+    fn execute(&self, db: Database) -> Frame {
+        match self {
+            Memory::Usage(key) => match db.lock_keys([key]).unwrap().memory_usage(key) {
+                Some(bytes) => Frame::Integer(bytes as i64),
+                None => Frame::Null,
+            },
+            Memory::Stats => stats(db.memory(), key_counts(&db)),
+            Memory::Doctor => Frame::Bulk(Bytes::from(doctor(&db))),
+        }
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for Memory {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        let subcommand = next_string(frames).map_err(|_| wrong_arity("memory"))?.to_uppercase();
+
+        match subcommand.as_str() {
+            "USAGE" if frames.len() == 1 || frames.len() == 3 => {
+                let key = next_bytes(frames)?;
+                if frames.len() > 0 && (!next_string(frames)?.eq_ignore_ascii_case("SAMPLES") || next_integer(frames)? < 0) {
+                    return Err(syntax_error());
+                }
+                Ok(Memory::Usage(key))
+            }
+            "STATS" if frames.len() == 0 => Ok(Memory::Stats),
+            "DOCTOR" if frames.len() == 0 => Ok(Memory::Doctor),
+            "USAGE" | "STATS" | "DOCTOR" => Err(wrong_arity(&format!("memory|{}", subcommand.to_lowercase()))),
+            _ => Err(format!("unknown subcommand '{}'. Try MEMORY HELP.", subcommand).into()),
+        }
+    }
+}
+
+/// Number of keys, and of keys with an expiration, of every database.
+pub(crate) fn key_counts(db: &Database) -> Vec<(usize, usize)> {
+    db.lock_all().iter().map(|keyspace| (keyspace.len(), keyspace.expires_len())).collect()
+}
+
+/// Name and value pairs describing the memory used, with the keys of every non empty database.
+pub(crate) fn stats(memory: &eviction::Memory, counts: Vec<(usize, usize)>) -> Frame {
+    let mut keys = 0;
+    let mut reply = vec![
+        Frame::Bulk(Bytes::from("peak.allocated")),
+        Frame::Integer(memory.peak() as i64),
+        Frame::Bulk(Bytes::from("total.allocated")),
+        Frame::Integer(memory.used() as i64),
+    ];
+
+    for (index, (count, expires)) in counts.into_iter().enumerate().filter(|(_, (count, _))| *count > 0) {
+        keys += count;
+        reply.push(Frame::Bulk(Bytes::from(format!("db.{}", index))));
+        reply.push(Frame::Array(vec![
+            Frame::Bulk(Bytes::from("keys")),
+            Frame::Integer(count as i64),
+            Frame::Bulk(Bytes::from("expires")),
+            Frame::Integer(expires as i64),
+        ]));
+    }
+
+    reply.extend([
+        Frame::Bulk(Bytes::from("keys.count")),
+        Frame::Integer(keys as i64),
+        Frame::Bulk(Bytes::from("keys.bytes-per-key")),
+        Frame::Integer(memory.used().checked_div(keys).unwrap_or(0) as i64),
+        Frame::Bulk(Bytes::from("dataset.bytes")),
+        Frame::Integer(memory.used() as i64),
+        Frame::Bulk(Bytes::from("maxmemory")),
+        Frame::Integer(memory.limit() as i64),
+    ]);
+
+    Frame::Array(reply)
+}
+
+/// Advice on the memory used, worded like the report of Redis' MEMORY DOCTOR.
+fn doctor(db: &Database) -> String {
+    let memory = db.memory();
+    let (used, peak, limit) = (memory.used(), memory.peak(), memory.limit());
+
+    if used < DOCTOR_MINIMUM {
+        return "Hi Sam, this instance is empty or is using very little memory, my issues detector can't be used in these conditions. Please, leave for your mission on Earth and fill it with some data. The new Sam and I will be back to our programming as soon as I finished rebooting.".to_string();
+    }
+
+    let mut issues = vec![];
+    if peak > used / 2 * 3 {
+        issues.push(" * Peak memory: In the past this instance used more than 150% the memory that is currently using. The allocator is normally not able to release memory after a peak, so you can expect to see a big fragmentation ratio.");
+    }
+    if limit > 0 && used > limit / 10 * 9 {
+        issues.push(" * Memory limit: The memory used is above 90% of maxmemory, writes will soon evict keys or be refused depending on maxmemory-policy.");
+    }
+
+    match issues.is_empty() {
+        true => "Hi Sam, I can't find any memory issue in your instance. I can only account for what occurs on this base.".to_string(),
+        false => format!("Sam, I detected a few issues in this Redis instance memory implants:\n\n{}\n\nI'm here to keep you safe, Sam. I want to help you.", issues.join("\n\n")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::Database;
+
+    use super::*;
+
+    #[test]
+    fn it_reports_usage_of_keys() {
+        let db = Database::new(1);
+        db.lock().unwrap().insert("short", Bytes::from("a"));
+        db.lock().unwrap().insert("long", Bytes::from(vec![0; 1000]));
+
+        let usage = |key: &str| match Memory::Usage(Bytes::from(key.to_string())).execute(db.clone()) {
+            Frame::Integer(bytes) => bytes,
+            frame => panic!("unexpected reply {:?}", frame),
+        };
+
+        assert_eq!((4 + 1000) - (5 + 1), usage("long") - usage("short"));
+        assert_eq!(Frame::Null, Memory::Usage(Bytes::from("missing")).execute(db.clone()));
+    }
+
+    #[test]
+    fn it_reports_keys_per_database() {
+        let db = Database::new(2);
+        db.select(1).unwrap().lock().unwrap().insert("a", Bytes::from("1"));
+
+        let Frame::Array(stats) = Memory::Stats.execute(db.clone()) else { panic!("expected an array") };
+        let position = stats.iter().position(|frame| frame == &Frame::Bulk(Bytes::from("db.1"))).unwrap();
+
+        assert!(!stats.contains(&Frame::Bulk(Bytes::from("db.0"))));
+        assert_eq!(
+            Frame::Array(vec![Frame::Bulk(Bytes::from("keys")), Frame::Integer(1), Frame::Bulk(Bytes::from("expires")), Frame::Integer(0)]),
+            stats[position + 1]
+        );
+    }
+
+    #[test]
+    fn it_parses_samples_option() {
+        let frames = |arguments: &[&str]| arguments.iter().map(|argument| Frame::Bulk(Bytes::from(argument.to_string()))).collect::<Vec<_>>();
+
+        assert!(Memory::try_from(&mut frames(&["usage", "a", "SAMPLES", "5"]).into_iter()).is_ok());
+        assert!(Memory::try_from(&mut frames(&["usage", "a", "LIMIT", "5"]).into_iter()).is_err());
+        assert!(Memory::try_from(&mut frames(&["stats", "a"]).into_iter()).is_err());
+    }
+}
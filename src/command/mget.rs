@@ -1,24 +1,27 @@
 use std::vec::IntoIter;
 
-use crate::command::next_string;
+use bytes::Bytes;
+
+use crate::command::{next_bytes, wrong_arity};
 use crate::database::Database;
 use crate::frame::Frame;
+use crate::Error;
 
 use super::Command;
 
 pub(crate) struct MGet {
-    keys: Vec<String>,
+    keys: Vec<Bytes>,
 }
 
 impl Command for MGet {
     fn execute(&self, db: Database) -> Frame {
-        let db = db.lock().unwrap();
+        let db = db.lock_keys(&self.keys).unwrap();
         let mut result: Vec<Frame> = vec![];
 
-        for key in self.keys.clone() {
+        for key in &self.keys {
             let frame: Frame;
 
-            if let Some(value) = db.get(&*key) {
+            if let Ok(Some(value)) = db.get(key) {
                 frame = Frame::Bulk(value.clone());
             } else {
                 frame = Frame::Null;
@@ -31,18 +34,21 @@ impl Command for MGet {
     }
 }
 
-impl From<&mut IntoIter<Frame>> for MGet {
-    fn from(frames: &mut IntoIter<Frame>) -> Self {
-        let mut keys: Vec<String> = vec![];
+impl TryFrom<&mut IntoIter<Frame>> for MGet {
+    type Error = Error;
 
-        loop {
-            match next_string(frames) {
-                Ok(key) => keys.push(key),
-                Err(..) => break,
-            };
-        };
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() == 0 {
+            return Err(wrong_arity("mget"));
+        }
+
+        let mut keys: Vec<Bytes> = vec![];
+
+        while frames.len() > 0 {
+            keys.push(next_bytes(frames)?);
+        }
 
-        MGet { keys }
+        Ok(MGet { keys })
     }
 }
 
@@ -62,7 +68,7 @@ mod tests {
             Frame::Simple("what".to_string()),
         ].into_iter();
 
-        let command: MGet = (&mut iter).into();
+        let command: MGet = (&mut iter).try_into().unwrap();
 
         assert_eq!("where".to_string(), command.keys[0]);
         assert_eq!("when".to_string(), command.keys[1]);
@@ -80,9 +86,9 @@ mod tests {
         }
         let command = MGet {
             keys: vec![
-                "when".to_string(),
-                "where".to_string(),
-                "what".to_string(),
+                Bytes::from("when"),
+                Bytes::from("where"),
+                Bytes::from("what"),
             ]
         };
 
@@ -107,9 +113,9 @@ mod tests {
         }
         let command = MGet {
             keys: vec![
-                "first_name".to_string(),
-                "last_name".to_string(),
-                "age".to_string(),
+                Bytes::from("first_name"),
+                Bytes::from("last_name"),
+                Bytes::from("age"),
             ]
         };
 
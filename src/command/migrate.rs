@@ -0,0 +1,195 @@
+use std::time::{Duration, SystemTime};
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+use tokio::net::TcpStream;
+
+use crate::connection::Connection;
+use crate::database::{Database, Value};
+use crate::frame::Frame;
+use crate::rdb;
+use crate::Error;
+
+use super::{error_frame, next_bytes, next_integer, next_string, syntax_error, wrong_arity};
+
+/// Timeout of MIGRATE when given none, as in Redis.
+const DEFAULT_TIMEOUT: Duration = Duration::from_millis(1000);
+
+/// MIGRATE host port key|"" destination-db timeout [COPY] [REPLACE] [AUTH password]
+/// [AUTH2 username password] [KEYS key...], moving keys to another instance with
+/// RESTORE-ASKING. Not a [`Command`](super::Command) as it waits on the network.
+pub(crate) struct Migrate {
+    host: String,
+    port: u16,
+    keys: Vec<Bytes>,
+    database: i64,
+    timeout: Duration,
+    copy: bool,
+    replace: bool,
+    auth: Vec<String>,
+}
+
+impl Migrate {
+    pub(crate) fn keys(&self) -> &[Bytes] {
+        &self.keys
+    }
+
+    /// Restores the keys on the target, then removes them here unless COPY is given.
+    /// Returns the reply and the keys removed, which replicas have to remove as well.
+    pub(crate) async fn execute(&self, db: &Database) -> (Frame, Vec<Bytes>) {
+        let entries: Vec<(Bytes, Value, u64)> = {
+            let keyspace = db.lock_keys(&self.keys).unwrap();
+            self.keys.iter()
+                .filter_map(|key| {
+                    let value = keyspace.get_value(key)?.clone();
+                    let ttl = keyspace.expiration(key).map_or(0, |expires_at| {
+                        expires_at.duration_since(SystemTime::now()).unwrap_or_default().as_millis().max(1) as u64
+                    });
+                    Some((key.clone(), value, ttl))
+                })
+                .collect()
+        };
+        if entries.is_empty() {
+            return (Frame::Simple("NOKEY".to_string()), vec![]);
+        }
+
+        let mut requests = vec![];
+        if !self.auth.is_empty() {
+            requests.push(bulks(["AUTH".to_string()].iter().chain(&self.auth).map(|argument| Bytes::from(argument.clone()))));
+        }
+        requests.push(bulks([Bytes::from("SELECT"), Bytes::from(self.database.to_string())]));
+        for (key, value, ttl) in &entries {
+            let mut restore = vec![Bytes::from("RESTORE-ASKING"), key.clone(), Bytes::from(ttl.to_string()), Bytes::from(rdb::dump(value))];
+            if self.replace {
+                restore.push(Bytes::from("REPLACE"));
+            }
+            requests.push(bulks(restore));
+        }
+
+        let Ok(Ok(socket)) = tokio::time::timeout(self.timeout, TcpStream::connect((self.host.as_str(), self.port))).await else {
+            return (Frame::SimpleError("IOERR error or timeout connecting to the client".to_string()), vec![]);
+        };
+        let mut connection = Connection::new(socket);
+        let count = requests.len();
+        let exchange = async {
+            for request in requests {
+                connection.write_frame(request).await.ok()?;
+            }
+            let mut replies = vec![];
+            for _ in 0..count {
+                replies.push(connection.read_frame().await.ok()??);
+            }
+            Some(replies)
+        };
+        let Ok(Some(replies)) = tokio::time::timeout(self.timeout, exchange).await else {
+            return (Frame::SimpleError("IOERR error or timeout reading to target instance".to_string()), vec![]);
+        };
+
+        // Keys restored before an error are removed all the same.
+        let preamble = replies.len() - entries.len();
+        let mut response = Frame::Simple("OK".to_string());
+        let mut moved = vec![];
+        for (index, reply) in replies.into_iter().enumerate() {
+            match reply {
+                Frame::SimpleError(err) => {
+                    response = error_frame(&format!("Target instance replied with error: {}", err));
+                    if index < preamble {
+                        return (response, vec![]);
+                    }
+                }
+                _ if index >= preamble => moved.push(&entries[index - preamble]),
+                _ => {}
+            }
+        }
+        if self.copy {
+            return (response, vec![]);
+        }
+
+        // Keys written since they were read stay here.
+        let mut keyspace = db.lock_keys(&self.keys).unwrap();
+        let mut removed = vec![];
+        for (key, value, _) in moved {
+            if keyspace.get_value(key) == Some(value) {
+                keyspace.remove(key);
+                removed.push(key.clone());
+            }
+        }
+        (response, removed)
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for Migrate {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() < 5 {
+            return Err(wrong_arity("migrate"));
+        }
+
+        let host = next_string(frames)?;
+        let port = u16::try_from(next_integer(frames)?).map_err(|_| "Invalid port")?;
+        let key = next_bytes(frames)?;
+        let database = next_integer(frames)?;
+        let timeout = match next_integer(frames)? {
+            timeout if timeout <= 0 => DEFAULT_TIMEOUT,
+            timeout => Duration::from_millis(timeout as u64),
+        };
+
+        let mut migrate = Migrate { host, port, keys: vec![key], database, timeout, copy: false, replace: false, auth: vec![] };
+        while frames.len() > 0 {
+            match next_string(frames)?.to_uppercase().as_str() {
+                "COPY" => migrate.copy = true,
+                "REPLACE" => migrate.replace = true,
+                "AUTH" if frames.len() > 0 => migrate.auth = vec![next_string(frames)?],
+                "AUTH2" if frames.len() > 1 => migrate.auth = vec![next_string(frames)?, next_string(frames)?],
+                "KEYS" if migrate.keys[0].is_empty() => {
+                    migrate.keys.clear();
+                    while frames.len() > 0 {
+                        migrate.keys.push(next_bytes(frames)?);
+                    }
+                }
+                "KEYS" => return Err("When using MIGRATE KEYS option, the key argument must be set to the empty string".into()),
+                _ => return Err(syntax_error()),
+            }
+        }
+
+        Ok(migrate)
+    }
+}
+
+fn bulks(arguments: impl IntoIterator<Item = Bytes>) -> Frame {
+    Frame::Array(arguments.into_iter().map(Frame::Bulk).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(arguments: &[&str]) -> Result<Migrate, Error> {
+        let frames: Vec<Frame> = arguments.iter().map(|argument| Frame::Bulk(Bytes::from(argument.to_string()))).collect();
+        Migrate::try_from(&mut frames.into_iter())
+    }
+
+    #[test]
+    fn it_parses_keys_and_options() {
+        let migrate = parse(&["127.0.0.1", "6380", "", "0", "0", "REPLACE", "AUTH2", "user", "secret", "KEYS", "a", "b"]).unwrap();
+        assert_eq!(vec![Bytes::from("a"), Bytes::from("b")], migrate.keys);
+        assert_eq!(vec!["user".to_string(), "secret".to_string()], migrate.auth);
+        assert_eq!(DEFAULT_TIMEOUT, migrate.timeout);
+        assert!(migrate.replace && !migrate.copy);
+
+        assert!(parse(&["127.0.0.1", "6380", "a", "0", "0", "KEYS", "b"]).is_err());
+        assert!(parse(&["127.0.0.1", "6380", "a", "0", "0", "MOVE"]).is_err());
+    }
+
+    #[tokio::test]
+    async fn it_reports_unreachable_targets() {
+        let db = crate::database::new_db();
+        assert_eq!((Frame::Simple("NOKEY".to_string()), vec![]), parse(&["127.0.0.1", "1", "a", "0", "100"]).unwrap().execute(&db).await);
+
+        db.lock().unwrap().insert("a", Bytes::from("1"));
+        let (reply, removed) = parse(&["127.0.0.1", "1", "a", "0", "100"]).unwrap().execute(&db).await;
+        assert!(matches!(reply, Frame::SimpleError(err) if err.starts_with("IOERR")));
+        assert!(removed.is_empty() && db.lock().unwrap().contains_key("a"));
+    }
+}
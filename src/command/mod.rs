@@ -1,22 +1,158 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use std::vec::IntoIter;
 
 use bytes::Bytes;
+use crate::command::append::Append;
+use crate::command::bf_add::BfAdd;
+use crate::command::bf_exists::BfExists;
+use crate::command::bf_reserve::BfReserve;
+use crate::command::bitcount::BitCount;
+use crate::command::bitfield::BitField;
+use crate::command::bitop::BitOp;
+use crate::command::bitpos::BitPos;
+use crate::command::cf_add::CfAdd;
+use crate::command::cf_del::CfDel;
+use crate::command::cf_exists::CfExists;
+use crate::command::config::Config;
+use crate::command::copy::Copy;
+use crate::command::dbsize::DbSize;
+use crate::command::del::Del;
+use crate::command::dump::Dump;
+use crate::command::exists::Exists;
+use crate::command::flushdb::FlushDb;
+use crate::command::geoadd::GeoAdd;
+use crate::command::geodist::GeoDist;
+use crate::command::geohash::GeoHash;
+use crate::command::geopos::GeoPos;
+use crate::command::geosearch::GeoSearch;
 use crate::command::get::Get;
+use crate::command::getbit::GetBit;
+use crate::command::getdel::GetDel;
+use crate::command::getex::GetEx;
+use crate::command::getrange::GetRange;
+use crate::command::incr::IncrBy;
+use crate::command::incrbyfloat::IncrByFloat;
+use crate::command::json_arrappend::JsonArrAppend;
+use crate::command::json_del::JsonDel;
+use crate::command::json_get::JsonGet;
+use crate::command::json_numincrby::JsonNumIncrBy;
+use crate::command::json_objkeys::JsonObjKeys;
+use crate::command::json_set::JsonSet;
+use crate::command::json_type::JsonType;
+use crate::command::key_type::KeyType;
+use crate::command::keys::Keys;
+use crate::command::lcs::Lcs;
+use crate::command::memory::Memory;
 use crate::command::mget::MGet;
+use crate::command::move_key::Move;
+use crate::command::mset::MSet;
+use crate::command::object::Object;
+use crate::command::pfadd::PfAdd;
+use crate::command::pfcount::PfCount;
+use crate::command::pfmerge::PfMerge;
+use crate::command::ping::Ping;
+use crate::command::randomkey::RandomKey;
+use crate::command::rename::Rename;
+use crate::command::restore::Restore;
+use crate::command::scan::Scan;
+use crate::command::select::Select;
 use crate::command::set::Set;
+use crate::command::setbit::SetBit;
+use crate::command::setnx::SetNx;
+use crate::command::setrange::SetRange;
+use crate::command::strlen::StrLen;
+use crate::command::swapdb::SwapDb;
+use crate::command::ts_add::TsAdd;
+use crate::command::ts_create::TsCreate;
+use crate::command::ts_createrule::TsCreateRule;
+use crate::command::ts_deleterule::TsDeleteRule;
+use crate::command::ts_range::TsRange;
 use crate::command::unknown::Unknown;
+use crate::command::zscan::ZScan;
 
-use crate::database::Database;
+use crate::cluster::key_slot;
+use crate::database::{Database, Keyspace, MAX_STRING_LENGTH};
 use crate::frame::Frame;
+use crate::hyperloglog::{DecodeError, HyperLogLog};
+use crate::json::Path;
 use crate::{Error, Result};
 
+pub(crate) mod append;
+pub(crate) mod bf_add;
+pub(crate) mod bf_exists;
+pub(crate) mod bf_reserve;
+pub(crate) mod bitcount;
+pub(crate) mod bitfield;
+pub(crate) mod bitop;
+pub(crate) mod bitpos;
+pub(crate) mod cf_add;
+pub(crate) mod cf_del;
+pub(crate) mod cf_exists;
+pub(crate) mod geoadd;
+pub(crate) mod geodist;
+pub(crate) mod geohash;
+pub(crate) mod geopos;
+pub(crate) mod geosearch;
 pub(crate) mod get;
+pub(crate) mod getbit;
+pub(crate) mod getdel;
+pub(crate) mod getex;
+pub(crate) mod getrange;
+pub(crate) mod incr;
+pub(crate) mod incrbyfloat;
+pub(crate) mod json_arrappend;
+pub(crate) mod json_del;
+pub(crate) mod json_get;
+pub(crate) mod json_numincrby;
+pub(crate) mod json_objkeys;
+pub(crate) mod json_set;
+pub(crate) mod json_type;
+pub(crate) mod lcs;
 pub(crate) mod set;
+pub(crate) mod setbit;
+pub(crate) mod setnx;
+pub(crate) mod setrange;
+pub(crate) mod strlen;
+pub(crate) mod ts_add;
+pub(crate) mod ts_create;
+pub(crate) mod ts_createrule;
+pub(crate) mod ts_deleterule;
+pub(crate) mod ts_range;
 pub(crate) mod unknown;
 pub(crate) mod mget;
+pub(crate) mod mset;
+pub(crate) mod pfadd;
+pub(crate) mod pfcount;
+pub(crate) mod pfmerge;
+pub(crate) mod ping;
+pub(crate) mod copy;
+pub(crate) mod dbsize;
+pub(crate) mod del;
+pub(crate) mod exists;
+pub(crate) mod flushdb;
+pub(crate) mod key_type;
+pub(crate) mod randomkey;
+pub(crate) mod rename;
+pub(crate) mod keys;
+pub(crate) mod scan;
+pub(crate) mod zscan;
+pub(crate) mod move_key;
+pub(crate) mod select;
+pub(crate) mod swapdb;
+pub(crate) mod config;
+pub(crate) mod object;
+pub(crate) mod memory;
+pub(crate) mod dump;
+pub(crate) mod restore;
+pub(crate) mod migrate;
 
-pub trait Command {
+pub trait Command: Send {
     fn execute(&self, db: Database) -> Frame;
+
+    /// Database the connection switches to after the command ran, only SELECT changes it.
+    fn select(&self) -> Option<usize> {
+        None
+    }
 }
 
 impl TryFrom<&mut IntoIter<Frame>> for Box<dyn Command> {
@@ -24,14 +160,91 @@ impl TryFrom<&mut IntoIter<Frame>> for Box<dyn Command> {
 
     fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self> {
         let command_name = match next_string(frames) {
-            Ok(name) => name,
+            Ok(name) => name.to_uppercase(),
             Err(_) => return Err("Lack of command name".into())
         };
 
         let command: Box<dyn Command> = match &command_name[..] {
-            "GET" => Box::new(Get::from(frames)),
-            "MGET" => Box::new(MGet::from(frames)),
-            "SET" => Box::new(Set::from(frames)),
+            "APPEND" => Box::new(Append::try_from(frames)?),
+            "BF.ADD" => Box::new(BfAdd::try_from(frames)?),
+            "BF.EXISTS" => Box::new(BfExists::try_from(frames)?),
+            "BF.MADD" => Box::new(BfAdd::madd(frames)?),
+            "BF.MEXISTS" => Box::new(BfExists::mexists(frames)?),
+            "BF.RESERVE" => Box::new(BfReserve::try_from(frames)?),
+            "BITCOUNT" => Box::new(BitCount::try_from(frames)?),
+            "BITFIELD" => Box::new(BitField::try_from(frames)?),
+            "BITFIELD_RO" => Box::new(BitField::read_only(frames)?),
+            "BITOP" => Box::new(BitOp::try_from(frames)?),
+            "BITPOS" => Box::new(BitPos::try_from(frames)?),
+            "CF.ADD" => Box::new(CfAdd::try_from(frames)?),
+            "CF.DEL" => Box::new(CfDel::try_from(frames)?),
+            "CF.EXISTS" => Box::new(CfExists::try_from(frames)?),
+            "CONFIG" => Box::new(Config::try_from(frames)?),
+            "COPY" => Box::new(Copy::try_from(frames)?),
+            "DBSIZE" => Box::new(DbSize::try_from(frames)?),
+            "DECR" => Box::new(IncrBy::decr(frames)?),
+            "DECRBY" => Box::new(IncrBy::decr_by(frames)?),
+            "DEL" => Box::new(Del::try_from(frames)?),
+            "DUMP" => Box::new(Dump::try_from(frames)?),
+            "EXISTS" => Box::new(Exists::try_from(frames)?),
+            "FLUSHALL" => Box::new(FlushDb::flushall(frames)?),
+            "FLUSHDB" => Box::new(FlushDb::try_from(frames)?),
+            "GEOADD" => Box::new(GeoAdd::try_from(frames)?),
+            "GEODIST" => Box::new(GeoDist::try_from(frames)?),
+            "GEOHASH" => Box::new(GeoHash::try_from(frames)?),
+            "GEOPOS" => Box::new(GeoPos::try_from(frames)?),
+            "GEOSEARCH" => Box::new(GeoSearch::try_from(frames)?),
+            "GEOSEARCHSTORE" => Box::new(GeoSearch::store(frames)?),
+            "GET" => Box::new(Get::try_from(frames)?),
+            "GETBIT" => Box::new(GetBit::try_from(frames)?),
+            "GETDEL" => Box::new(GetDel::try_from(frames)?),
+            "GETEX" => Box::new(GetEx::try_from(frames)?),
+            "GETRANGE" => Box::new(GetRange::try_from(frames)?),
+            "INCR" => Box::new(IncrBy::incr(frames)?),
+            "INCRBY" => Box::new(IncrBy::try_from(frames)?),
+            "INCRBYFLOAT" => Box::new(IncrByFloat::try_from(frames)?),
+            "JSON.ARRAPPEND" => Box::new(JsonArrAppend::try_from(frames)?),
+            "JSON.DEL" => Box::new(JsonDel::try_from(frames)?),
+            "JSON.GET" => Box::new(JsonGet::try_from(frames)?),
+            "JSON.NUMINCRBY" => Box::new(JsonNumIncrBy::try_from(frames)?),
+            "JSON.OBJKEYS" => Box::new(JsonObjKeys::try_from(frames)?),
+            "JSON.SET" => Box::new(JsonSet::try_from(frames)?),
+            "JSON.TYPE" => Box::new(JsonType::try_from(frames)?),
+            "KEYS" => Box::new(Keys::try_from(frames)?),
+            "LCS" => Box::new(Lcs::try_from(frames)?),
+            "MEMORY" => Box::new(Memory::try_from(frames)?),
+            "MGET" => Box::new(MGet::try_from(frames)?),
+            "MOVE" => Box::new(Move::try_from(frames)?),
+            "MSET" => Box::new(MSet::try_from(frames)?),
+            "MSETNX" => Box::new(MSet::msetnx(frames)?),
+            "OBJECT" => Box::new(Object::try_from(frames)?),
+            "PFADD" => Box::new(PfAdd::try_from(frames)?),
+            "PFCOUNT" => Box::new(PfCount::try_from(frames)?),
+            "PFMERGE" => Box::new(PfMerge::try_from(frames)?),
+            "PING" => Box::new(Ping::from(frames)),
+            "PSETEX" => Box::new(Set::psetex(frames)?),
+            "RANDOMKEY" => Box::new(RandomKey::try_from(frames)?),
+            "RENAME" => Box::new(Rename::try_from(frames)?),
+            "RENAMENX" => Box::new(Rename::renamenx(frames)?),
+            "RESTORE" | "RESTORE-ASKING" => Box::new(Restore::try_from(frames)?),
+            "SCAN" => Box::new(Scan::try_from(frames)?),
+            "SELECT" => Box::new(Select::try_from(frames)?),
+            "SET" => Box::new(Set::try_from(frames)?),
+            "SETBIT" => Box::new(SetBit::try_from(frames)?),
+            "SETEX" => Box::new(Set::setex(frames)?),
+            "SETNX" => Box::new(SetNx::try_from(frames)?),
+            "SETRANGE" => Box::new(SetRange::try_from(frames)?),
+            "STRLEN" => Box::new(StrLen::try_from(frames)?),
+            "SWAPDB" => Box::new(SwapDb::try_from(frames)?),
+            "TOUCH" => Box::new(Exists::touch(frames)?),
+            "TS.ADD" => Box::new(TsAdd::try_from(frames)?),
+            "TS.CREATE" => Box::new(TsCreate::try_from(frames)?),
+            "TS.CREATERULE" => Box::new(TsCreateRule::try_from(frames)?),
+            "TS.DELETERULE" => Box::new(TsDeleteRule::try_from(frames)?),
+            "TS.RANGE" => Box::new(TsRange::try_from(frames)?),
+            "TYPE" => Box::new(KeyType::try_from(frames)?),
+            "UNLINK" => Box::new(Del::unlink(frames)?),
+            "ZSCAN" => Box::new(ZScan::try_from(frames)?),
             v => Box::new(Unknown { name: v.to_string() }),
         };
 
@@ -39,6 +252,89 @@ impl TryFrom<&mut IntoIter<Frame>> for Box<dyn Command> {
     }
 }
 
+/// Returns the keys the command in the frames names, `None` when it has no keys. Keys
+/// only found while running, like the compaction destinations of TS.ADD, are left out
+/// as in Redis' key specs.
+pub(crate) fn command_keys(frames: &[Frame]) -> Option<Vec<Bytes>> {
+    let (first, last, step) = key_spec(&command_name(frames)?)?;
+    let last = match last {
+        last if last < 0 => frames.len().checked_sub(last.unsigned_abs())?,
+        last => (last as usize).min(frames.len().saturating_sub(1)),
+    };
+
+    (first..=last).step_by(step)
+        .map(|index| match frames.get(index)? {
+            Frame::Simple(key) => Some(Bytes::from(key.clone())),
+            Frame::Bulk(key) => Some(key.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Hash slot all the keys of the command belong to in cluster mode, `None` when it has
+/// none. Keys in different slots are refused, like Redis' CROSSSLOT error.
+pub(crate) fn command_slot(frames: &[Frame]) -> std::result::Result<Option<u16>, Frame> {
+    let Some(keys) = command_keys(frames) else { return Ok(None) };
+    let mut slots = keys.iter().map(|key| key_slot(key));
+    let Some(slot) = slots.next() else { return Ok(None) };
+
+    match slots.all(|other| other == slot) {
+        true => Ok(Some(slot)),
+        false => Err(Frame::SimpleError("CROSSSLOT Keys in request don't hash to the same slot".to_string())),
+    }
+}
+
+/// Whether the command may grow the memory used, such commands are refused once
+/// the memory limit is reached and nothing can be evicted, like Redis' `denyoom` flag.
+pub(crate) fn denies_oom(frames: &[Frame]) -> bool {
+    matches!(
+        command_name(frames).as_deref().unwrap_or_default(),
+        "APPEND" | "BF.ADD" | "BF.MADD" | "BF.RESERVE" | "BITFIELD" | "BITOP" | "CF.ADD" | "COPY" | "DECR" | "DECRBY"
+            | "GEOADD" | "GEOSEARCHSTORE" | "INCR" | "INCRBY" | "INCRBYFLOAT" | "JSON.ARRAPPEND" | "JSON.NUMINCRBY"
+            | "JSON.SET" | "MSET" | "MSETNX" | "PFADD" | "PFMERGE" | "PSETEX" | "RESTORE" | "RESTORE-ASKING" | "SET"
+            | "SETBIT" | "SETEX" | "SETNX" | "SETRANGE" | "TS.ADD" | "TS.CREATE" | "TS.CREATERULE"
+    )
+}
+
+/// Whether the command may modify the keyspace, such commands are propagated to
+/// replicas and refused on them, like Redis' `write` flag.
+pub(crate) fn is_write(frames: &[Frame]) -> bool {
+    denies_oom(frames) || matches!(
+        command_name(frames).as_deref().unwrap_or_default(),
+        "CF.DEL" | "DEL" | "FLUSHALL" | "FLUSHDB" | "GETDEL" | "GETEX" | "JSON.DEL" | "MOVE" | "RENAME" | "RENAMENX"
+            | "SWAPDB" | "TS.DELETERULE" | "UNLINK"
+    )
+}
+
+/// Upper cased name of the command in the frames.
+pub(crate) fn command_name(frames: &[Frame]) -> Option<String> {
+    match frames.first()? {
+        Frame::Simple(name) => Some(name.to_uppercase()),
+        Frame::Bulk(name) => Some(String::from_utf8_lossy(name).to_uppercase()),
+        _ => None,
+    }
+}
+
+/// Positions of the keys of a command as first, last and step, a negative last
+/// counting from the end, like the key specs reported by Redis' COMMAND INFO.
+fn key_spec(name: &str) -> Option<(usize, isize, usize)> {
+    match name {
+        "APPEND" | "BF.ADD" | "BF.EXISTS" | "BF.MADD" | "BF.MEXISTS" | "BF.RESERVE" | "BITCOUNT" | "BITFIELD"
+        | "BITFIELD_RO" | "BITPOS" | "CF.ADD" | "CF.DEL" | "CF.EXISTS" | "DECR" | "DECRBY" | "DUMP" | "GEOADD"
+        | "GEODIST" | "GEOHASH" | "GEOPOS" | "GEOSEARCH" | "GET" | "GETBIT" | "GETDEL" | "GETEX" | "GETRANGE"
+        | "INCR" | "INCRBY" | "INCRBYFLOAT" | "JSON.ARRAPPEND" | "JSON.DEL" | "JSON.GET" | "JSON.NUMINCRBY"
+        | "JSON.OBJKEYS" | "JSON.SET" | "JSON.TYPE" | "MOVE" | "PFADD" | "PSETEX" | "RESTORE" | "RESTORE-ASKING"
+        | "SET" | "SETBIT" | "SETEX" | "SETNX" | "SETRANGE" | "STRLEN" | "TS.ADD" | "TS.CREATE" | "TS.RANGE"
+        | "TYPE" | "ZSCAN" => Some((1, 1, 1)),
+        "COPY" | "GEOSEARCHSTORE" | "LCS" | "RENAME" | "RENAMENX" | "TS.CREATERULE" | "TS.DELETERULE" => Some((1, 2, 1)),
+        "DEL" | "EXISTS" | "MGET" | "PFCOUNT" | "PFMERGE" | "TOUCH" | "UNLINK" => Some((1, -1, 1)),
+        "BITOP" => Some((2, -1, 1)),
+        "MEMORY" | "OBJECT" => Some((2, 2, 1)),
+        "MSET" | "MSETNX" => Some((1, -1, 2)),
+        _ => None,
+    }
+}
+
 pub(crate) fn next_string(iterator: &mut IntoIter<Frame>) -> Result<String> {
     match iterator.next() {
         Some(frame) => {
@@ -60,22 +356,216 @@ pub(crate) fn next_string(iterator: &mut IntoIter<Frame>) -> Result<String> {
 }
 
 pub(crate) fn next_bytes(iterator: &mut IntoIter<Frame>) -> Result<Bytes> {
-    match iterator.next().unwrap() {
-        Frame::Simple(s) => Ok(Bytes::from(s.into_bytes())),
-        Frame::Bulk(data) => Ok(data),
-        frame => Err(format!(
+    match iterator.next() {
+        Some(Frame::Simple(s)) => Ok(Bytes::from(s.into_bytes())),
+        Some(Frame::Bulk(data)) => Ok(data),
+        Some(frame) => Err(format!(
             "protocol error; expected simple frame or bulk frame, got {:?}",
             frame
         ).into()),
+        None => Err("end".into()),
     }
 }
 
-pub(crate) fn next_integer(iterator: &mut IntoIter<Frame>) -> Result<u64> {
-    match iterator.next().unwrap() {
-        Frame::Integer(u) => Ok(u),
-        frame => Err(format!(
+pub(crate) fn next_integer(iterator: &mut IntoIter<Frame>) -> Result<i64> {
+    match iterator.next() {
+        Some(Frame::Integer(i)) => Ok(i),
+        Some(Frame::Simple(s)) => parse_integer(s.as_bytes()).ok_or_else(not_an_integer),
+        Some(Frame::Bulk(data)) => parse_integer(&data).ok_or_else(not_an_integer),
+        Some(frame) => Err(format!(
             "protocol error; expected integer frame, got {:?}",
             frame
         ).into()),
+        None => Err("end".into()),
+    }
+}
+
+pub(crate) fn next_float(iterator: &mut IntoIter<Frame>) -> Result<f64> {
+    match iterator.next() {
+        Some(Frame::Integer(i)) => Ok(i as f64),
+        Some(Frame::Double(d)) => Ok(d),
+        Some(Frame::Simple(s)) => parse_float(s.as_bytes()).ok_or_else(not_a_float),
+        Some(Frame::Bulk(data)) => parse_float(&data).ok_or_else(not_a_float),
+        Some(frame) => Err(format!(
+            "protocol error; expected double frame, got {:?}",
+            frame
+        ).into()),
+        None => Err("end".into()),
+    }
+}
+
+/// Reads the index of a logical database, whether it exists is checked on execution.
+pub(crate) fn next_db_index(iterator: &mut IntoIter<Frame>) -> Result<usize> {
+    match next_integer(iterator)? {
+        index if index >= 0 => Ok(index as usize),
+        _ => Err("DB index is out of range".into()),
+    }
+}
+
+/// Reads a bit offset, which Redis limits to the bits of the largest possible string.
+pub(crate) fn next_bit_offset(iterator: &mut IntoIter<Frame>) -> Result<usize> {
+    match next_integer(iterator) {
+        Ok(offset) if offset >= 0 && (offset as usize) >> 3 < MAX_STRING_LENGTH => Ok(offset as usize),
+        _ => Err("bit offset is not an integer or out of range".into()),
     }
-}
\ No newline at end of file
+}
+
+/// Reads the argument of an EX, PX, EXAT or PXAT option and turns it into a time to live.
+pub(crate) fn next_ttl(option: &str, iterator: &mut IntoIter<Frame>, command: &str) -> Result<Duration> {
+    if iterator.len() == 0 {
+        return Err(syntax_error());
+    }
+    let value = next_integer(iterator)?;
+    if value <= 0 {
+        return Err(invalid_expire(command));
+    }
+    let millis = match option {
+        "EX" | "EXAT" => value.checked_mul(1000).ok_or_else(|| invalid_expire(command))?,
+        "PX" | "PXAT" => value,
+        _ => return Err(syntax_error()),
+    };
+    let millis = Duration::from_millis(millis as u64);
+
+    match option {
+        "EXAT" | "PXAT" => {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+            Ok(millis.checked_sub(now).unwrap_or_default())
+        }
+        _ => Ok(millis),
+    }
+}
+
+/// Parses an integer with the same strictness as Redis: no whitespace,
+/// no explicit plus sign and no leading zeros.
+pub(crate) fn parse_integer(data: &[u8]) -> Option<i64> {
+    let digits = data.strip_prefix(b"-").unwrap_or(data);
+
+    if digits.is_empty() || (digits[0] == b'0' && data.len() > 1) || !digits.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+
+    std::str::from_utf8(data).ok()?.parse::<i64>().ok()
+}
+
+pub(crate) fn parse_float(data: &[u8]) -> Option<f64> {
+    let string = std::str::from_utf8(data).ok()?;
+
+    if string.is_empty() || string.starts_with(char::is_whitespace) || string.ends_with(char::is_whitespace) {
+        return None;
+    }
+
+    match string.to_lowercase().as_str() {
+        "inf" | "+inf" | "infinity" | "+infinity" => Some(f64::INFINITY),
+        "-inf" | "-infinity" => Some(f64::NEG_INFINITY),
+        _ => string.parse::<f64>().ok().filter(|value| !value.is_nan()),
+    }
+}
+
+pub(crate) fn next_json(iterator: &mut IntoIter<Frame>) -> Result<serde_json::Value> {
+    let bytes = next_bytes(iterator)?;
+    Ok(serde_json::from_slice(&bytes)?)
+}
+
+pub(crate) fn next_json_path(iterator: &mut IntoIter<Frame>) -> Result<Path> {
+    Path::parse(&next_string(iterator)?)
+}
+
+pub(crate) fn wrong_arity(command: &str) -> Error {
+    format!("wrong number of arguments for '{}' command", command).into()
+}
+
+pub(crate) fn syntax_error() -> Error {
+    "syntax error".into()
+}
+
+pub(crate) fn not_an_integer() -> Error {
+    "value is not an integer or out of range".into()
+}
+
+pub(crate) fn not_a_float() -> Error {
+    "value is not a valid float".into()
+}
+
+fn invalid_expire(command: &str) -> Error {
+    format!("invalid expire time in '{}' command", command).into()
+}
+
+pub(crate) fn error_frame(message: &str) -> Frame {
+    Frame::SimpleError(format!("ERR {}", message))
+}
+
+/// Reads the HyperLogLog stored under the key.
+pub(crate) fn read_hyperloglog(db: &Keyspace, key: &[u8]) -> std::result::Result<Option<HyperLogLog>, DecodeError> {
+    match db.get(key) {
+        Ok(Some(value)) => HyperLogLog::decode(value).map(Some),
+        Ok(None) => Ok(None),
+        Err(_) => Err(DecodeError::WrongType),
+    }
+}
+
+pub(crate) fn path_not_found(path: &Path) -> Frame {
+    error_frame(&format!("Path '{}' does not exist", path.as_str()))
+}
+
+pub(crate) fn hyperloglog_error(err: DecodeError) -> Frame {
+    match err {
+        DecodeError::WrongType => Frame::SimpleError("WRONGTYPE Key is not a valid HyperLogLog string value.".to_string()),
+        DecodeError::Corrupted => Frame::SimpleError("INVALIDOBJ Corrupted HLL object detected".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_integers_strictly() {
+        assert_eq!(Some(-15), parse_integer(b"-15"));
+        assert_eq!(Some(0), parse_integer(b"0"));
+        assert_eq!(Some(i64::MIN), parse_integer(b"-9223372036854775808"));
+        assert_eq!(None, parse_integer(b"9223372036854775808"));
+        assert_eq!(None, parse_integer(b"+1"));
+        assert_eq!(None, parse_integer(b"01"));
+        assert_eq!(None, parse_integer(b"-0"));
+        assert_eq!(None, parse_integer(b" 1"));
+        assert_eq!(None, parse_integer(b""));
+    }
+
+    #[test]
+    fn it_reads_integers_from_bulk_frames() {
+        let mut iter: IntoIter<Frame> = vec![Frame::Bulk(Bytes::from("12")), Frame::Bulk(Bytes::from("x"))].into_iter();
+
+        assert_eq!(12, next_integer(&mut iter).unwrap());
+        assert!(next_integer(&mut iter).is_err());
+    }
+
+    #[test]
+    fn it_accepts_binary_keys() {
+        let db = crate::database::new_db();
+        let key = Bytes::from_static(&[0xff, 0x00, 0xfe]);
+        let run = |arguments: Vec<Bytes>| {
+            let mut frames = arguments.into_iter().map(Frame::Bulk).collect::<Vec<_>>().into_iter();
+            let command: Box<dyn Command> = (&mut frames).try_into().unwrap();
+            command.execute(db.clone())
+        };
+
+        run(vec![Bytes::from("SET"), key.clone(), Bytes::from("value")]);
+
+        assert_eq!(Frame::Bulk(Bytes::from("value")), run(vec![Bytes::from("GET"), key.clone()]));
+        assert_eq!(Frame::Array(vec![Frame::Bulk(key)]), run(vec![Bytes::from("KEYS"), Bytes::from("*")]));
+    }
+
+    #[test]
+    fn it_finds_keys_of_commands() {
+        let frames = |arguments: &[&str]| arguments.iter().map(|argument| Frame::Bulk(Bytes::from(argument.to_string()))).collect::<Vec<_>>();
+        let keys = |arguments: &[&str]| command_keys(&frames(arguments));
+
+        assert_eq!(Some(vec![Bytes::from("a")]), keys(&["get", "a"]));
+        assert_eq!(Some(vec![Bytes::from("a"), Bytes::from("b")]), keys(&["MSET", "a", "1", "b", "2"]));
+        assert_eq!(Some(vec![Bytes::from("b"), Bytes::from("c")]), keys(&["BITOP", "AND", "b", "c"]));
+        assert_eq!(Some(vec![Bytes::from("a")]), keys(&["OBJECT", "ENCODING", "a"]));
+        assert_eq!(Some(vec![]), keys(&["MEMORY", "STATS"]));
+        assert_eq!(Some(vec![]), keys(&["DEL"]));
+        assert_eq!(None, keys(&["DBSIZE"]));
+    }
+}
@@ -0,0 +1,92 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::Error;
+
+use super::{Command, error_frame, next_bytes, next_db_index, wrong_arity};
+
+/// MOVE, transfers a key with its expiration to another database unless it already exists there.
+pub(crate) struct Move {
+    key: Bytes,
+    destination: usize,
+}
+
+impl Command for Move {
+    fn execute(&self, db: Database) -> Frame {
+        if db.select(self.destination).is_none() {
+            return error_frame("DB index is out of range");
+        }
+        if db.index() == self.destination {
+            return error_frame("source and destination objects are the same");
+        }
+
+        let (mut source, mut destination) = db.lock_pair(db.index(), self.destination);
+        if !source.contains_key(&self.key) || destination.contains_key(&self.key) {
+            return Frame::Integer(0);
+        }
+
+        let expires_at = source.expiration(&self.key);
+        if let Some(value) = source.remove(&self.key) {
+            destination.insert_with_expiration(self.key.clone(), value, expires_at);
+        }
+
+        Frame::Integer(1)
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for Move {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() != 2 {
+            return Err(wrong_arity("move"));
+        }
+
+        Ok(Move { key: next_bytes(frames)?, destination: next_db_index(frames)? })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use bytes::Bytes;
+
+    use crate::database::new_db;
+
+    use super::*;
+
+    fn move_key(key: &str, destination: usize) -> Move {
+        Move { key: Bytes::copy_from_slice(key.as_bytes()), destination }
+    }
+
+    #[test]
+    fn it_moves_key_with_expiration() {
+        let db = new_db();
+        let future = SystemTime::now() + Duration::from_secs(60);
+        db.lock().unwrap().insert_with_expiration("a".to_string(), Bytes::from("1"), Some(future));
+
+        assert_eq!(Frame::Integer(1), move_key("a", 1).execute(db.clone()));
+
+        assert!(!db.lock().unwrap().contains_key("a"));
+        let other = db.select(1).unwrap();
+        let binding = other.lock().unwrap();
+        assert_eq!(Ok(Some(&Bytes::from("1"))), binding.get("a"));
+        assert_eq!(Some(future), binding.expiration("a"));
+    }
+
+    #[test]
+    fn it_keeps_existing_destination_key() {
+        let db = new_db();
+        db.lock().unwrap().insert("a".to_string(), Bytes::from("1"));
+        db.select(1).unwrap().lock().unwrap().insert("a".to_string(), Bytes::from("2"));
+
+        assert_eq!(Frame::Integer(0), move_key("a", 1).execute(db.clone()));
+        assert_eq!(Frame::Integer(0), move_key("missing", 1).execute(db.clone()));
+        assert_eq!(error_frame("source and destination objects are the same"), move_key("a", 0).execute(db.clone()));
+        assert_eq!(Ok(Some(&Bytes::from("1"))), db.lock().unwrap().get("a"));
+    }
+}
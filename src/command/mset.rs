@@ -0,0 +1,109 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::Error;
+
+use super::{Command, next_bytes, wrong_arity};
+
+/// Covers MSET and MSETNX, the latter only writes when none of the keys exist.
+pub(crate) struct MSet {
+    pairs: Vec<(Bytes, Bytes)>,
+    only_new: bool,
+}
+
+impl Command for MSet {
+    fn execute(&self, db: Database) -> Frame {
+        let mut db = db.lock_keys(self.pairs.iter().map(|(key, _)| key)).unwrap();
+
+        if self.only_new && self.pairs.iter().any(|(key, _)| db.contains_key(key)) {
+            return Frame::Integer(0);
+        }
+
+        for (key, value) in &self.pairs {
+            db.insert(key.clone(), value.clone());
+        }
+
+        match self.only_new {
+            true => Frame::Integer(1),
+            false => Frame::Simple("OK".to_string()),
+        }
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for MSet {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        MSet::parse(frames, false, "mset")
+    }
+}
+
+impl MSet {
+    pub(crate) fn msetnx(frames: &mut IntoIter<Frame>) -> Result<Self, Error> {
+        MSet::parse(frames, true, "msetnx")
+    }
+
+    fn parse(frames: &mut IntoIter<Frame>, only_new: bool, name: &str) -> Result<Self, Error> {
+        if frames.len() == 0 || !frames.len().is_multiple_of(2) {
+            return Err(wrong_arity(name));
+        }
+
+        let mut pairs = vec![];
+        while frames.len() > 0 {
+            pairs.push((next_bytes(frames)?, next_bytes(frames)?));
+        }
+
+        Ok(MSet { pairs, only_new })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::new_db;
+
+    use super::*;
+
+    #[test]
+    fn it_sets_all_pairs() {
+        let db = new_db();
+        let mut iter: IntoIter<Frame> = vec![
+            Frame::Bulk(Bytes::from("a")),
+            Frame::Bulk(Bytes::from("1")),
+            Frame::Bulk(Bytes::from("b")),
+            Frame::Bulk(Bytes::from("2")),
+        ].into_iter();
+        let command = MSet::try_from(&mut iter).unwrap();
+
+        let result = command.execute(db.clone());
+
+        assert_eq!(Frame::Simple("OK".to_string()), result);
+        assert_eq!(Bytes::from("1"), db.lock().unwrap().get("a").unwrap().unwrap());
+        assert_eq!(Bytes::from("2"), db.lock().unwrap().get("b").unwrap().unwrap());
+    }
+
+    #[test]
+    fn it_sets_nothing_when_any_key_exists() {
+        let db = new_db();
+        db.lock().unwrap().insert("b".to_string(), Bytes::from("old"));
+        let command = MSet {
+            pairs: vec![(Bytes::from("a"), Bytes::from("1")), (Bytes::from("b"), Bytes::from("2"))],
+            only_new: true,
+        };
+
+        let result = command.execute(db.clone());
+
+        assert_eq!(Frame::Integer(0), result);
+        assert!(!db.lock().unwrap().contains_key("a"));
+        assert_eq!(Bytes::from("old"), db.lock().unwrap().get("b").unwrap().unwrap());
+    }
+
+    #[test]
+    fn it_requires_even_number_of_arguments() {
+        let mut iter: IntoIter<Frame> = vec![Frame::Bulk(Bytes::from("a"))].into_iter();
+
+        assert!(MSet::try_from(&mut iter).is_err());
+    }
+}
@@ -0,0 +1,114 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::Error;
+
+use super::{Command, error_frame, next_bytes, next_string, wrong_arity};
+
+/// OBJECT subcommands inspecting a key, none of them counts as an access to it.
+pub(crate) enum Object {
+    Encoding(Bytes),
+    Frequency(Bytes),
+    IdleTime(Bytes),
+    RefCount(Bytes),
+}
+
+impl Command for Object {
+    fn execute(&self, db: Database) -> Frame {
+        let key = match self {
+            Object::Encoding(key) | Object::Frequency(key) | Object::IdleTime(key) | Object::RefCount(key) => key,
+        };
+        let keyspace = db.lock_keys([key]).unwrap();
+        let lfu = db.memory().policy().lfu();
+
+        let reply = match self {
+            Object::Encoding(_) => keyspace.encoding(key).map(|encoding| Frame::Bulk(Bytes::from(encoding))),
+            Object::Frequency(_) if !lfu => return error_frame(
+                "An LFU maxmemory policy is not selected, access frequency not tracked. Please note that when switching between policies at runtime LRU and LFU data will take some time to adjust."
+            ),
+            Object::Frequency(_) => keyspace.frequency(key).map(|frequency| Frame::Integer(frequency as i64)),
+            Object::IdleTime(_) if lfu => return error_frame(
+                "An LFU maxmemory policy is selected, idle time not tracked. Please note that when switching between policies at runtime LRU and LFU data will take some time to adjust."
+            ),
+            Object::IdleTime(_) => keyspace.idle_time(key).map(|seconds| Frame::Integer(seconds as i64)),
+            // Values are never shared between keys.
+            Object::RefCount(_) => keyspace.contains_key(key).then_some(Frame::Integer(1)),
+        };
+
+        reply.unwrap_or(Frame::Null)
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for Object {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        let subcommand = next_string(frames).map_err(|_| wrong_arity("object"))?.to_uppercase();
+        let command: fn(Bytes) -> Object = match subcommand.as_str() {
+            "ENCODING" => Object::Encoding,
+            "FREQ" => Object::Frequency,
+            "IDLETIME" => Object::IdleTime,
+            "REFCOUNT" => Object::RefCount,
+            _ => return Err(format!("unknown subcommand '{}'. Try OBJECT HELP.", subcommand).into()),
+        };
+        if frames.len() != 1 {
+            return Err(wrong_arity(&format!("object|{}", subcommand.to_lowercase())));
+        }
+
+        Ok(command(next_bytes(frames)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::new_db;
+    use crate::eviction::EvictionPolicy;
+    use crate::sorted_set::{CompactLimits, SortedSet};
+
+    use super::*;
+
+    #[test]
+    fn it_reports_encodings() {
+        let db = new_db();
+        {
+            let mut keyspace = db.lock().unwrap();
+            keyspace.insert("int", Bytes::from("-42"));
+            keyspace.insert("padded", Bytes::from("042"));
+            keyspace.insert("short", Bytes::from("hello"));
+            keyspace.insert("long", Bytes::from(vec![b'a'; 45]));
+            keyspace.insert("zset", SortedSet::default());
+            let mut large = SortedSet::default();
+            large.insert(Bytes::from(vec![b'm'; 100]), 1.0, CompactLimits::default());
+            keyspace.insert("large", large);
+        }
+
+        for (key, encoding) in [("int", "int"), ("padded", "embstr"), ("short", "embstr"), ("long", "raw"), ("zset", "listpack"), ("large", "skiplist")] {
+            assert_eq!(Frame::Bulk(Bytes::from(encoding)), Object::Encoding(Bytes::from(key)).execute(db.clone()), "{}", key);
+        }
+        assert_eq!(Frame::Null, Object::Encoding(Bytes::from("missing")).execute(db.clone()));
+    }
+
+    #[test]
+    fn it_reports_access_metadata_of_the_selected_policy() {
+        let db = new_db();
+        db.lock().unwrap().insert("a", Bytes::from("1"));
+
+        assert_eq!(Frame::Integer(0), Object::IdleTime(Bytes::from("a")).execute(db.clone()));
+        assert!(matches!(Object::Frequency(Bytes::from("a")).execute(db.clone()), Frame::SimpleError(_)));
+
+        db.memory().set_policy(EvictionPolicy::AllKeysLfu);
+        assert!(matches!(Object::IdleTime(Bytes::from("a")).execute(db.clone()), Frame::SimpleError(_)));
+        assert_eq!(Frame::Integer(5), Object::Frequency(Bytes::from("a")).execute(db.clone()));
+        assert_eq!(Frame::Integer(1), Object::RefCount(Bytes::from("a")).execute(db.clone()));
+    }
+
+    #[test]
+    fn it_rejects_unknown_subcommands() {
+        let frames = vec![Frame::Bulk(Bytes::from("size")), Frame::Bulk(Bytes::from("a"))];
+
+        assert!(Object::try_from(&mut frames.into_iter()).is_err());
+    }
+}
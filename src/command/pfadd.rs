@@ -0,0 +1,95 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::hyperloglog::HyperLogLog;
+use crate::Error;
+
+use super::{Command, hyperloglog_error, next_bytes, read_hyperloglog, wrong_arity};
+
+pub(crate) struct PfAdd {
+    key: Bytes,
+    elements: Vec<Bytes>,
+}
+
+impl Command for PfAdd {
+    fn execute(&self, db: Database) -> Frame {
+        let mut db = db.lock_keys([&self.key]).unwrap();
+        let (mut hll, mut updated) = match read_hyperloglog(&db, &self.key) {
+            Ok(Some(hll)) => (hll, false),
+            Ok(None) => (HyperLogLog::default(), true),
+            Err(err) => return hyperloglog_error(err),
+        };
+
+        for element in &self.elements {
+            updated |= hll.add(element);
+        }
+
+        if updated {
+            db.update(&self.key, hll.encode());
+        }
+
+        Frame::Integer(updated as i64)
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for PfAdd {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() == 0 {
+            return Err(wrong_arity("pfadd"));
+        }
+
+        let key = next_bytes(frames)?;
+        let mut elements = vec![];
+        while frames.len() > 0 {
+            elements.push(next_bytes(frames)?);
+        }
+
+        Ok(PfAdd { key, elements })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::new_db;
+
+    use super::*;
+
+    #[test]
+    fn it_reports_whether_registers_changed() {
+        let db = new_db();
+        let add = |elements: &[&'static str]| PfAdd {
+            key: Bytes::from("hll"),
+            elements: elements.iter().map(|element| Bytes::from(*element)).collect(),
+        }.execute(db.clone());
+
+        assert_eq!(Frame::Integer(1), add(&["a", "b", "c"]));
+        assert_eq!(Frame::Integer(0), add(&["a", "b"]));
+        assert_eq!(Frame::Integer(0), add(&[]));
+        assert!(db.lock().unwrap().get("hll").unwrap().unwrap().starts_with(b"HYLL"));
+    }
+
+    #[test]
+    fn it_creates_empty_hyperloglog_without_elements() {
+        let db = new_db();
+
+        let result = PfAdd { key: Bytes::from("hll"), elements: vec![] }.execute(db.clone());
+
+        assert_eq!(Frame::Integer(1), result);
+        assert_eq!(18, db.lock().unwrap().get("hll").unwrap().unwrap().len());
+    }
+
+    #[test]
+    fn it_rejects_plain_strings() {
+        let db = new_db();
+        db.lock().unwrap().insert("hll".to_string(), Bytes::from("value"));
+
+        let result = PfAdd { key: Bytes::from("hll"), elements: vec![Bytes::from("a")] }.execute(db);
+
+        assert_eq!(Frame::SimpleError("WRONGTYPE Key is not a valid HyperLogLog string value.".to_string()), result);
+    }
+}
@@ -0,0 +1,118 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::hyperloglog::HyperLogLog;
+use crate::Error;
+
+use super::{Command, hyperloglog_error, next_bytes, read_hyperloglog, wrong_arity};
+
+pub(crate) struct PfCount {
+    keys: Vec<Bytes>,
+}
+
+impl Command for PfCount {
+    fn execute(&self, db: Database) -> Frame {
+        let mut db = db.lock_keys(&self.keys).unwrap();
+
+        if let [key] = &self.keys[..] {
+            let mut hll = match read_hyperloglog(&db, key) {
+                Ok(Some(hll)) => hll,
+                Ok(None) => return Frame::Integer(0),
+                Err(err) => return hyperloglog_error(err),
+            };
+
+            let count = hll.count();
+            db.update(key, hll.encode());
+
+            return Frame::Integer(count as i64);
+        }
+
+        let mut union = HyperLogLog::default();
+        for key in &self.keys {
+            match read_hyperloglog(&db, key) {
+                Ok(Some(hll)) => union.merge(&hll),
+                Ok(None) => {}
+                Err(err) => return hyperloglog_error(err),
+            }
+        }
+
+        Frame::Integer(union.count() as i64)
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for PfCount {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() == 0 {
+            return Err(wrong_arity("pfcount"));
+        }
+
+        let mut keys = vec![];
+        while frames.len() > 0 {
+            keys.push(next_bytes(frames)?);
+        }
+
+        Ok(PfCount { keys })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::database::new_db;
+
+    use super::*;
+
+    fn insert(db: &Database, key: &str, elements: std::ops::Range<u32>) {
+        let mut hll = HyperLogLog::default();
+        for element in elements {
+            hll.add(element.to_string().as_bytes());
+        }
+        db.lock().unwrap().insert(key.to_string(), hll.encode());
+    }
+
+    #[test]
+    fn it_counts_and_caches_single_key() {
+        let db = new_db();
+        insert(&db, "hll", 0..7);
+
+        let result = PfCount { keys: vec![Bytes::from("hll")] }.execute(db.clone());
+
+        assert_eq!(Frame::Integer(7), result);
+        let stored = db.lock().unwrap().get("hll").unwrap().cloned().unwrap();
+        assert_eq!(7, u64::from_le_bytes(stored[8..16].try_into().unwrap()));
+    }
+
+    #[test]
+    fn it_counts_union_of_keys() {
+        let db = new_db();
+        insert(&db, "first", 0..50);
+        insert(&db, "second", 25..75);
+
+        let result = PfCount { keys: vec![Bytes::from("first"), Bytes::from("second"), Bytes::from("missing")] }.execute(db);
+
+        assert_eq!(Frame::Integer(75), result);
+    }
+
+    #[test]
+    fn it_returns_zero_for_missing_key() {
+        let result = PfCount { keys: vec![Bytes::from("missing")] }.execute(new_db());
+
+        assert_eq!(Frame::Integer(0), result);
+    }
+
+    #[test]
+    fn it_detects_corrupted_values() {
+        let db = new_db();
+        db.lock().unwrap().insert("hll".to_string(), Bytes::from(&b"HYLL\x01\0\0\0\0\0\0\0\0\0\0\0\x00"[..]));
+
+        let result = PfCount { keys: vec![Bytes::from("hll")] }.execute(db);
+
+        assert_eq!(Frame::SimpleError("INVALIDOBJ Corrupted HLL object detected".to_string()), result);
+    }
+}
@@ -0,0 +1,94 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::hyperloglog::HyperLogLog;
+use crate::Error;
+
+use super::{Command, hyperloglog_error, next_bytes, read_hyperloglog, wrong_arity};
+
+pub(crate) struct PfMerge {
+    destination: Bytes,
+    sources: Vec<Bytes>,
+}
+
+impl Command for PfMerge {
+    fn execute(&self, db: Database) -> Frame {
+        let mut db = db.lock_keys(std::iter::once(&self.destination).chain(&self.sources)).unwrap();
+        let mut result = HyperLogLog::default();
+
+        for key in std::iter::once(&self.destination).chain(&self.sources) {
+            match read_hyperloglog(&db, key) {
+                Ok(Some(hll)) => result.merge(&hll),
+                Ok(None) => {}
+                Err(err) => return hyperloglog_error(err),
+            }
+        }
+
+        db.update(&self.destination, result.encode());
+
+        Frame::Simple("OK".to_string())
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for PfMerge {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() == 0 {
+            return Err(wrong_arity("pfmerge"));
+        }
+
+        let destination = next_bytes(frames)?;
+        let mut sources = vec![];
+        while frames.len() > 0 {
+            sources.push(next_bytes(frames)?);
+        }
+
+        Ok(PfMerge { destination, sources })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::new_db;
+
+    use super::*;
+
+    #[test]
+    fn it_merges_sources_into_destination() {
+        let db = new_db();
+        for (key, range) in [("first", 0..30), ("second", 20..40), ("dest", 35..45)] {
+            let mut hll = HyperLogLog::default();
+            for element in range {
+                hll.add(element.to_string().as_bytes());
+            }
+            db.lock().unwrap().insert(key.to_string(), hll.encode());
+        }
+        let command = PfMerge { destination: Bytes::from("dest"), sources: vec![Bytes::from("first"), Bytes::from("second")] };
+
+        let result = command.execute(db.clone());
+
+        assert_eq!(Frame::Simple("OK".to_string()), result);
+        let mut merged = HyperLogLog::decode(db.lock().unwrap().get("dest").unwrap().unwrap()).unwrap();
+        assert_eq!(45, merged.count());
+    }
+
+    #[test]
+    fn it_keeps_dense_encoding_of_sources() {
+        let db = new_db();
+        let mut dense = HyperLogLog::default();
+        for element in 0..5000 {
+            dense.add(element.to_string().as_bytes());
+        }
+        db.lock().unwrap().insert("dense".to_string(), dense.encode());
+        let command = PfMerge { destination: Bytes::from("dest"), sources: vec![Bytes::from("dense")] };
+
+        command.execute(db.clone());
+
+        let encoding = db.lock().unwrap().get("dest").unwrap().unwrap()[4];
+        assert_eq!(0, encoding);
+    }
+}
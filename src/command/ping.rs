@@ -0,0 +1,54 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::database::Database;
+use crate::frame::Frame;
+
+use super::{Command, next_bytes};
+
+pub(crate) struct Ping {
+    message: Option<Bytes>,
+}
+
+impl Command for Ping {
+    fn execute(&self, _db: Database) -> Frame {
+        match &self.message {
+            None => Frame::Simple("PONG".to_string()),
+            Some(message) => Frame::Bulk(message.clone()),
+        }
+    }
+}
+
+impl From<&mut IntoIter<Frame>> for Ping {
+    fn from(frames: &mut IntoIter<Frame>) -> Self {
+        Ping {
+            message: next_bytes(frames).ok(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::new_db;
+
+    use super::*;
+
+    #[test]
+    fn it_replies_with_pong() {
+        let command = Ping { message: None };
+
+        let result = command.execute(new_db());
+
+        assert_eq!(Frame::Simple("PONG".to_string()), result);
+    }
+
+    #[test]
+    fn it_echoes_the_message() {
+        let mut iter: IntoIter<Frame> = vec![Frame::Bulk(Bytes::from("hello"))].into_iter();
+
+        let command: Ping = (&mut iter).into();
+
+        assert_eq!(Frame::Bulk(Bytes::from("hello")), command.execute(new_db()));
+    }
+}
@@ -0,0 +1,61 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::vec::IntoIter;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::Error;
+
+use super::{Command, wrong_arity};
+
+pub(crate) struct RandomKey;
+
+impl Command for RandomKey {
+    fn execute(&self, db: Database) -> Frame {
+        let db = db.lock().unwrap();
+        let count = db.len();
+        if count == 0 {
+            return Frame::Null;
+        }
+
+        let index = RandomState::new().build_hasher().finish() as usize % count;
+        let key = db.keys().nth(index).cloned();
+        match key {
+            Some(key) => Frame::Bulk(key),
+            None => Frame::Null,
+        }
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for RandomKey {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() > 0 {
+            return Err(wrong_arity("randomkey"));
+        }
+
+        Ok(RandomKey)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::database::new_db;
+
+    use super::*;
+
+    #[test]
+    fn it_returns_one_of_the_keys() {
+        let db = new_db();
+        assert_eq!(Frame::Null, RandomKey.execute(db.clone()));
+
+        db.lock().unwrap().insert("a".to_string(), Bytes::from("1"));
+        db.lock().unwrap().insert("b".to_string(), Bytes::from("2"));
+
+        let result = RandomKey.execute(db.clone());
+        assert!(result == Frame::Bulk(Bytes::from("a")) || result == Frame::Bulk(Bytes::from("b")));
+    }
+}
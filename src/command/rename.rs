@@ -0,0 +1,102 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::Error;
+
+use super::{Command, error_frame, next_bytes, wrong_arity};
+
+/// RENAME and RENAMENX, the latter refusing to overwrite an existing key.
+pub(crate) struct Rename {
+    key: Bytes,
+    new_key: Bytes,
+    only_new: bool,
+}
+
+impl Command for Rename {
+    fn execute(&self, db: Database) -> Frame {
+        let mut db = db.lock_keys([&self.key, &self.new_key]).unwrap();
+        if !db.contains_key(&self.key) {
+            return error_frame("no such key");
+        }
+
+        if self.only_new {
+            if db.contains_key(&self.new_key) {
+                return Frame::Integer(0);
+            }
+            db.rename(&self.key, &self.new_key);
+            return Frame::Integer(1);
+        }
+
+        db.rename(&self.key, &self.new_key);
+        Frame::Simple("OK".to_string())
+    }
+}
+
+impl Rename {
+    pub(crate) fn renamenx(frames: &mut IntoIter<Frame>) -> Result<Self, Error> {
+        Self::parse(frames, "renamenx", true)
+    }
+
+    fn parse(frames: &mut IntoIter<Frame>, name: &str, only_new: bool) -> Result<Self, Error> {
+        if frames.len() != 2 {
+            return Err(wrong_arity(name));
+        }
+
+        Ok(Rename { key: next_bytes(frames)?, new_key: next_bytes(frames)?, only_new })
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for Rename {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        Self::parse(frames, "rename", false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::database::new_db;
+
+    use super::*;
+
+    fn rename(key: &str, new_key: &str, only_new: bool) -> Rename {
+        Rename { key: Bytes::copy_from_slice(key.as_bytes()), new_key: Bytes::copy_from_slice(new_key.as_bytes()), only_new }
+    }
+
+    #[test]
+    fn it_renames_key_overwriting_destination() {
+        let db = new_db();
+        db.lock().unwrap().insert("a".to_string(), Bytes::from("1"));
+        db.lock().unwrap().insert("b".to_string(), Bytes::from("2"));
+
+        assert_eq!(Frame::Simple("OK".to_string()), rename("a", "b", false).execute(db.clone()));
+        assert_eq!(Ok(Some(&Bytes::from("1"))), db.lock().unwrap().get("b"));
+        assert_eq!(Frame::SimpleError("ERR no such key".to_string()), rename("a", "b", false).execute(db.clone()));
+    }
+
+    #[test]
+    fn it_renames_only_to_new_key_with_renamenx() {
+        let db = new_db();
+        db.lock().unwrap().insert("a".to_string(), Bytes::from("1"));
+        db.lock().unwrap().insert("b".to_string(), Bytes::from("2"));
+
+        assert_eq!(Frame::Integer(0), rename("a", "b", true).execute(db.clone()));
+        assert_eq!(Frame::Integer(1), rename("a", "c", true).execute(db.clone()));
+        assert_eq!(Frame::Integer(0), rename("c", "c", true).execute(db.clone()));
+    }
+
+    #[test]
+    fn it_keeps_key_renamed_to_itself() {
+        let db = new_db();
+        db.lock().unwrap().insert("a".to_string(), Bytes::from("1"));
+
+        assert_eq!(Frame::Simple("OK".to_string()), rename("a", "a", false).execute(db.clone()));
+        assert!(db.lock().unwrap().contains_key("a"));
+    }
+}
@@ -0,0 +1,153 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::rdb;
+use crate::Error;
+
+use super::{Command, error_frame, next_bytes, next_integer, next_string, syntax_error, wrong_arity};
+
+/// RESTORE key ttl payload [REPLACE] [ABSTTL] [IDLETIME seconds] [FREQ frequency], and
+/// RESTORE-ASKING which MIGRATE sends to nodes importing the slot of the key.
+pub(crate) struct Restore {
+    key: Bytes,
+    /// Time to live in milliseconds, zero for none.
+    ttl: u64,
+    /// Whether the time to live is a unix time in milliseconds instead.
+    absolute: bool,
+    payload: Bytes,
+    replace: bool,
+    idle_time: Option<u32>,
+    frequency: Option<u8>,
+}
+
+impl Command for Restore {
+    fn execute(&self, db: Database) -> Frame {
+        let Ok(value) = rdb::undump(&self.payload, db.memory().compact_limits()) else {
+            return error_frame("DUMP payload version or checksum are wrong");
+        };
+
+        let policy = db.memory().policy();
+        let mut db = db.lock_keys([&self.key]).unwrap();
+        if !self.replace && db.contains_key(&self.key) {
+            return Frame::SimpleError("BUSYKEY Target key name already exists.".to_string());
+        }
+        let expires_at = match self.ttl {
+            0 => None,
+            ttl if self.absolute => Some(UNIX_EPOCH + Duration::from_millis(ttl)),
+            ttl => Some(SystemTime::now() + Duration::from_millis(ttl)),
+        };
+        // Like Redis, a key restored already expired only replaces the existing one.
+        if expires_at.is_some_and(|expires_at| expires_at <= SystemTime::now()) {
+            db.remove(&self.key);
+            return Frame::Simple("OK".to_string());
+        }
+        db.insert_with_expiration(self.key.clone(), value, expires_at);
+
+        // Only the metadata of the selected policy is tracked.
+        match policy.lfu() {
+            true => db.set_access(&self.key, None, self.frequency),
+            false => db.set_access(&self.key, self.idle_time, None),
+        }
+
+        Frame::Simple("OK".to_string())
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for Restore {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() < 3 {
+            return Err(wrong_arity("restore"));
+        }
+
+        let key = next_bytes(frames)?;
+        let ttl = u64::try_from(next_integer(frames)?).map_err(|_| "Invalid TTL value, must be >= 0")?;
+        let payload = next_bytes(frames)?;
+        let mut restore = Restore { key, ttl, absolute: false, payload, replace: false, idle_time: None, frequency: None };
+        while frames.len() > 0 {
+            match next_string(frames)?.to_uppercase().as_str() {
+                "REPLACE" => restore.replace = true,
+                "ABSTTL" => restore.absolute = true,
+                "IDLETIME" if frames.len() > 0 && restore.frequency.is_none() => {
+                    let seconds = next_integer(frames)?;
+                    restore.idle_time = Some(u32::try_from(seconds).map_err(|_| "Invalid IDLETIME value, must be >= 0")?);
+                }
+                "FREQ" if frames.len() > 0 && restore.idle_time.is_none() => {
+                    let frequency = next_integer(frames)?;
+                    restore.frequency = Some(u8::try_from(frequency).map_err(|_| "Invalid FREQ value, must be >= 0 and <= 255")?);
+                }
+                _ => return Err(syntax_error()),
+            }
+        }
+
+        Ok(restore)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::command::dump::Dump;
+    use crate::database::new_db;
+
+    use super::*;
+
+    #[test]
+    fn it_restores_dumped_values() {
+        let db = new_db();
+        db.lock().unwrap().insert("a", Bytes::from("value"));
+        let payload = match Dump::try_from(&mut vec![Frame::Bulk(Bytes::from("a"))].into_iter()).unwrap().execute(db.clone()) {
+            Frame::Bulk(payload) => payload,
+            frame => panic!("unexpected reply {:?}", frame),
+        };
+
+        let restore = |key: &str, replace| Restore {
+            key: Bytes::from(key.to_string()), ttl: 1000, absolute: false, payload: payload.clone(), replace, idle_time: None, frequency: None,
+        };
+        assert_eq!(Frame::Simple("OK".to_string()), restore("b", false).execute(db.clone()));
+        assert_eq!(Frame::SimpleError("BUSYKEY Target key name already exists.".to_string()), restore("a", false).execute(db.clone()));
+        assert_eq!(Frame::Simple("OK".to_string()), restore("a", true).execute(db.clone()));
+
+        let keyspace = db.lock().unwrap();
+        assert_eq!(Ok(Some(&Bytes::from("value"))), keyspace.get("b"));
+        assert!(keyspace.expiration("b").is_some());
+    }
+
+    #[test]
+    fn it_refuses_altered_payloads() {
+        let restore = Restore {
+            key: Bytes::from("a"), ttl: 0, absolute: false, payload: Bytes::from("\x00\x01a\x0b\x00garbage!"), replace: false, idle_time: None, frequency: None,
+        };
+
+        assert_eq!(error_frame("DUMP payload version or checksum are wrong"), restore.execute(new_db()));
+    }
+
+    #[test]
+    fn it_applies_expiration_and_access_options() {
+        let db = new_db();
+        let payload = Bytes::from(rdb::dump(&crate::database::Value::String(Bytes::from("value"))));
+        let restore = |arguments: &[&str]| {
+            let mut frames = vec![Frame::Bulk(Bytes::from("a")), Frame::Bulk(Bytes::from(arguments[0].to_string())), Frame::Bulk(payload.clone())];
+            frames.extend(arguments[1..].iter().map(|argument| Frame::Bulk(Bytes::from(argument.to_string()))));
+            Restore::try_from(&mut frames.into_iter()).map(|restore| restore.execute(db.clone()))
+        };
+
+        assert_eq!(Frame::Simple("OK".to_string()), restore(&["0", "IDLETIME", "1000"]).unwrap());
+        assert!(db.lock().unwrap().idle_time("a").unwrap() >= 1000);
+
+        // An absolute time to live in the past replaces the key with nothing.
+        assert_eq!(Frame::Simple("OK".to_string()), restore(&["1", "ABSTTL", "REPLACE"]).unwrap());
+        assert!(!db.lock().unwrap().contains_key("a"));
+
+        assert_eq!(Frame::Simple("OK".to_string()), restore(&["4102444800000", "ABSTTL"]).unwrap());
+        assert_eq!(Some(UNIX_EPOCH + Duration::from_millis(4102444800000)), db.lock().unwrap().expiration("a"));
+
+        assert!(restore(&["0", "FREQ", "256"]).is_err());
+        assert!(restore(&["0", "IDLETIME", "-1"]).is_err());
+        assert!(restore(&["0", "IDLETIME", "1", "FREQ", "1"]).is_err());
+    }
+}
@@ -0,0 +1,147 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::glob;
+use crate::Error;
+
+use super::{Command, next_bytes, next_integer, next_string, syntax_error, wrong_arity};
+
+const DEFAULT_COUNT: usize = 10;
+
+pub(crate) struct Scan {
+    cursor: u64,
+    pattern: Option<Bytes>,
+    count: usize,
+    type_name: Option<String>,
+}
+
+impl Command for Scan {
+    fn execute(&self, db: Database) -> Frame {
+        let db = db.lock().unwrap();
+        let (cursor, keys) = db.scan(self.cursor, self.count);
+
+        let keys = keys.into_iter()
+            .filter(|key| self.pattern.as_ref().is_none_or(|pattern| glob::matches(pattern, key)))
+            .filter(|key| match &self.type_name {
+                Some(type_name) => db.get_value(key).is_some_and(|value| value.type_name().eq_ignore_ascii_case(type_name)),
+                None => true,
+            })
+            .map(|key| Frame::Bulk(key.clone()))
+            .collect();
+
+        scan_reply(cursor, keys)
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for Scan {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() == 0 {
+            return Err(wrong_arity("scan"));
+        }
+
+        let mut command = Scan { cursor: next_cursor(frames)?, pattern: None, count: DEFAULT_COUNT, type_name: None };
+        while frames.len() > 0 {
+            match next_string(frames)?.to_uppercase().as_str() {
+                "MATCH" => command.pattern = Some(next_bytes(frames)?),
+                "COUNT" => command.count = next_count(frames)?,
+                "TYPE" => command.type_name = Some(next_string(frames)?),
+                _ => return Err(syntax_error()),
+            }
+        }
+
+        Ok(command)
+    }
+}
+
+pub(crate) fn next_cursor(frames: &mut IntoIter<Frame>) -> Result<u64, Error> {
+    next_string(frames)?.parse().map_err(|_| "invalid cursor".into())
+}
+
+pub(crate) fn next_count(frames: &mut IntoIter<Frame>) -> Result<usize, Error> {
+    match next_integer(frames)? {
+        count if count >= 1 => Ok(count as usize),
+        _ => Err(syntax_error()),
+    }
+}
+
+pub(crate) fn scan_reply(cursor: u64, items: Vec<Frame>) -> Frame {
+    Frame::Array(vec![Frame::Bulk(Bytes::from(cursor.to_string())), Frame::Array(items)])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::database::new_db;
+    use crate::sorted_set::SortedSet;
+
+    use super::*;
+
+    fn scan_all(db: &Database, options: &[&str]) -> HashSet<String> {
+        let mut seen = HashSet::new();
+        let mut cursor = "0".to_string();
+        loop {
+            let mut arguments = vec![cursor.as_str()];
+            arguments.extend_from_slice(options);
+            let frames: Vec<Frame> = arguments.iter().map(|argument| Frame::Bulk(Bytes::from(argument.to_string()))).collect();
+
+            match Scan::try_from(&mut frames.into_iter()).unwrap().execute(db.clone()) {
+                Frame::Array(reply) => match &reply[..] {
+                    [Frame::Bulk(next), Frame::Array(keys)] => {
+                        for key in keys {
+                            if let Frame::Bulk(key) = key {
+                                seen.insert(String::from_utf8(key.to_vec()).unwrap());
+                            }
+                        }
+                        cursor = String::from_utf8(next.to_vec()).unwrap();
+                    }
+                    reply => panic!("unexpected reply {:?}", reply),
+                },
+                frame => panic!("unexpected reply {:?}", frame),
+            }
+            if cursor == "0" {
+                return seen;
+            }
+        }
+    }
+
+    fn with_keys() -> Database {
+        let db = new_db();
+        for i in 0..50 {
+            db.lock().unwrap().insert(format!("user:{}", i), Bytes::from("value"));
+        }
+        db.lock().unwrap().insert("ranking".to_string(), SortedSet::default());
+        db
+    }
+
+    #[test]
+    fn it_iterates_over_all_keys() {
+        let db = with_keys();
+
+        assert_eq!(51, scan_all(&db, &["COUNT", "7"]).len());
+    }
+
+    #[test]
+    fn it_filters_by_pattern_and_type() {
+        let db = with_keys();
+
+        assert_eq!(11, scan_all(&db, &["MATCH", "user:1*"]).len());
+        assert_eq!(HashSet::from(["ranking".to_string()]), scan_all(&db, &["TYPE", "zset"]));
+    }
+
+    #[test]
+    fn it_rejects_invalid_arguments() {
+        let parse = |arguments: &[&str]| {
+            let frames: Vec<Frame> = arguments.iter().map(|argument| Frame::Bulk(Bytes::from(argument.to_string()))).collect();
+            Scan::try_from(&mut frames.into_iter()).err().unwrap().to_string()
+        };
+
+        assert_eq!("invalid cursor", parse(&["abc"]));
+        assert_eq!("syntax error", parse(&["0", "COUNT", "0"]));
+    }
+}
@@ -0,0 +1,55 @@
+use std::vec::IntoIter;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::Error;
+
+use super::{Command, error_frame, next_db_index, wrong_arity};
+
+/// SELECT only validates the index, the server switches the connection to it afterwards.
+pub(crate) struct Select {
+    index: usize,
+}
+
+impl Command for Select {
+    fn execute(&self, db: Database) -> Frame {
+        match db.select(self.index) {
+            Some(_) => Frame::Simple("OK".to_string()),
+            None => error_frame("DB index is out of range"),
+        }
+    }
+
+    fn select(&self) -> Option<usize> {
+        Some(self.index)
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for Select {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() != 1 {
+            return Err(wrong_arity("select"));
+        }
+
+        Ok(Select { index: next_db_index(frames)? })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::database::new_db;
+
+    use super::*;
+
+    #[test]
+    fn it_rejects_indexes_out_of_range() {
+        let db = new_db();
+
+        assert_eq!(Frame::Simple("OK".to_string()), Select { index: 15 }.execute(db.clone()));
+        assert_eq!(error_frame("DB index is out of range"), Select { index: 16 }.execute(db.clone()));
+        assert!(Select::try_from(&mut vec![Frame::Bulk(Bytes::from("-1"))].into_iter()).is_err());
+    }
+}
@@ -1,28 +1,34 @@
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime};
 use std::vec::IntoIter;
 
 use bytes::Bytes;
 
 use crate::database::Database;
 use crate::frame::Frame;
+use crate::Error;
 
-use super::{Command, next_bytes, next_integer, next_string};
+use super::{Command, next_bytes, next_string, next_ttl, syntax_error, wrong_arity};
 
 pub(crate) struct Set {
-    key: String,
+    key: Bytes,
     value: Bytes,
     ttl: Option<Duration>,
+    keep_ttl: bool,
     replacement: Replacement,
     get: bool,
 }
 
 impl Command for Set {
     fn execute(&self, db: Database) -> Frame {
-        let mut db = db.lock().unwrap();
-        let previous = db.get(&*self.key);
+        let mut db = db.lock_keys([&self.key]).unwrap();
+        let previous = match db.get(&self.key) {
+            Ok(previous) => previous.cloned(),
+            Err(err) if self.get => return err.into(),
+            Err(_) => None,
+        };
         let result: Frame = match self.get {
             true => {
-                match previous {
+                match &previous {
                     None => { Frame::Null }
                     Some(value) => { Frame::Bulk(value.clone()) }
                 }
@@ -30,95 +36,112 @@ impl Command for Set {
             false => { Frame::Simple("OK".to_string()) }
         };
 
-        match self.replacement {
-            Replacement::Always => {
-                db.insert(self.key.clone(), self.value.clone());
-            }
-            Replacement::Never => {
-                if None == previous {
-                    db.insert(self.key.clone(), self.value.clone());
-                }
-            }
-            Replacement::OnlyOverride => {
-                if None != previous {
-                    db.insert(self.key.clone(), self.value.clone());
-                }
-            }
+        let should_insert = match self.replacement {
+            Replacement::Always => true,
+            Replacement::Never => !db.contains_key(&self.key),
+            Replacement::OnlyOverride => db.contains_key(&self.key),
+        };
+
+        if should_insert {
+            let expires_at = match (self.ttl, self.keep_ttl) {
+                (Some(ttl), _) => Some(SystemTime::now() + ttl),
+                (None, true) => db.expiration(&self.key),
+                (None, false) => None,
+            };
+            db.insert_with_expiration(self.key.clone(), self.value.clone(), expires_at);
         }
 
         result
     }
 }
 
-impl From<&mut IntoIter<Frame>> for Set {
-    fn from(frames: &mut IntoIter<Frame>) -> Self {
-        let key = next_string(frames).unwrap();
-        let value = next_bytes(frames).unwrap();
+impl Set {
+    /// Builds the command for `SETEX key seconds value`.
+    pub(crate) fn setex(frames: &mut IntoIter<Frame>) -> Result<Self, Error> {
+        Set::with_ttl(frames, "EX", "setex")
+    }
+
+    /// Builds the command for `PSETEX key milliseconds value`.
+    pub(crate) fn psetex(frames: &mut IntoIter<Frame>) -> Result<Self, Error> {
+        Set::with_ttl(frames, "PX", "psetex")
+    }
+
+    fn with_ttl(frames: &mut IntoIter<Frame>, unit: &str, name: &str) -> Result<Self, Error> {
+        if frames.len() != 3 {
+            return Err(wrong_arity(name));
+        }
+
+        let key = next_bytes(frames)?;
+        let ttl = next_ttl(unit, frames, name)?;
+        let value = next_bytes(frames)?;
+
+        Ok(Set {
+            key,
+            value,
+            ttl: Some(ttl),
+            keep_ttl: false,
+            replacement: Replacement::Always,
+            get: false,
+        })
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for Set {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() < 2 {
+            return Err(wrong_arity("set"));
+        }
+
+        let key = next_bytes(frames)?;
+        let value = next_bytes(frames)?;
         let mut ttl: Option<Duration> = None;
+        let mut keep_ttl: bool = false;
         let mut replacement = Replacement::default();
         let mut get: bool = false;
 
-        loop {
-            match next_string(frames) {
-                Ok(key) => match key.as_str() {
-                    "EX" => {
-                        let seconds = next_integer(frames).unwrap();
-                        ttl = Some(Duration::from_secs(seconds));
-                    }
-                    "PX" => {
-                        let millis = next_integer(frames).unwrap();
-                        ttl = Some(Duration::from_millis(millis));
-                    }
-                    "EXAT" => {
-                        let timestamp_seconds = next_integer(frames).unwrap();
-                        let duration = Duration::from_secs(timestamp_seconds);
-                        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
-                        ttl = duration.checked_sub(timestamp);
-                    }
-                    "PXAT" => {
-                        let timestamp_millis = next_integer(frames).unwrap();
-                        let duration = Duration::from_millis(timestamp_millis);
-                        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
-                        ttl = duration.checked_sub(timestamp);
-                    }
-                    "NX" => {
-                        replacement = Replacement::Never;
-                    }
-                    "XX" => {
-                        replacement = Replacement::OnlyOverride;
-                    }
-                    "GET" => {
-                        get = true;
-                    }
-                    _ => {}
-                },
-                Err(..) => break,
-            };
-        };
+        while let Ok(option) = next_string(frames) {
+            let option = option.to_uppercase();
+            match option.as_str() {
+                "EX" | "PX" | "EXAT" | "PXAT" if ttl.is_none() && !keep_ttl => {
+                    ttl = Some(next_ttl(&option, frames, "set")?);
+                }
+                "KEEPTTL" if ttl.is_none() => {
+                    keep_ttl = true;
+                }
+                "NX" if replacement != Replacement::OnlyOverride => {
+                    replacement = Replacement::Never;
+                }
+                "XX" if replacement != Replacement::Never => {
+                    replacement = Replacement::OnlyOverride;
+                }
+                "GET" => {
+                    get = true;
+                }
+                _ => return Err(syntax_error()),
+            }
+        }
 
-        Set {
+        Ok(Set {
             key,
             value,
             ttl,
+            keep_ttl,
             replacement,
             get,
-        }
+        })
     }
 }
 
-#[derive(Eq, PartialEq, Debug)]
+#[derive(Eq, PartialEq, Debug, Default)]
 enum Replacement {
+    #[default]
     Always,
     Never,
     OnlyOverride,
 }
 
-impl Default for Replacement {
-    fn default() -> Self {
-        Replacement::Always
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use bytes::Bytes;
@@ -134,13 +157,13 @@ mod tests {
             Frame::Simple("Jasper".to_string()),
         ].into_iter();
 
-        let command: Set = (&mut iter).into();
+        let command: Set = (&mut iter).try_into().unwrap();
 
         assert_eq!("dog".to_string(), command.key);
         assert_eq!("Jasper".to_string(), String::from_utf8(command.value.to_vec()).unwrap());
         assert_eq!(None, command.ttl);
         assert_eq!(Replacement::Always, command.replacement);
-        assert_eq!(false, command.get);
+        assert!(!command.get);
     }
 
     #[test]
@@ -154,13 +177,13 @@ mod tests {
             Frame::Simple("GET".to_string()),
         ].into_iter();
 
-        let command: Set = (&mut iter).into();
+        let command: Set = (&mut iter).try_into().unwrap();
 
         assert_eq!("airplane".to_string(), command.key);
         assert_eq!("The Beast".to_string(), String::from_utf8(command.value.to_vec()).unwrap());
         assert_eq!(Duration::from_secs(180), command.ttl.unwrap());
         assert_eq!(Replacement::OnlyOverride, command.replacement);
-        assert_eq!(true, command.get);
+        assert!(command.get);
     }
 
     #[test]
@@ -168,9 +191,10 @@ mod tests {
         let db = new_db();
         let name: Bytes = [b'H', b'i', b'g'].to_vec().into();
         let command = Set {
-            key: "name".to_string(),
+            key: Bytes::from("name"),
             value: name.clone(),
             ttl: None,
+            keep_ttl: false,
             replacement: Default::default(),
             get: false
         };
@@ -179,7 +203,7 @@ mod tests {
 
         assert_eq!(Frame::Simple("OK".to_string()), result);
         let binding = db.lock().unwrap();
-        let value = binding.get("name").unwrap();
+        let value = binding.get("name").unwrap().unwrap();
         assert_eq!(name, *value)
     }
 
@@ -193,9 +217,10 @@ mod tests {
         }
         let new_name: Bytes = [b'B', b'a', b'n', b'g', b'l', b'e', b'y'].to_vec().into();
         let command = Set {
-            key: "name".to_string(),
+            key: Bytes::from("name"),
             value: new_name,
             ttl: None,
+            keep_ttl: false,
             replacement: Replacement::Never,
             get: false
         };
@@ -204,7 +229,75 @@ mod tests {
 
         assert_eq!(Frame::Simple("OK".to_string()), result);
         let binding = db.lock().unwrap();
-        let value = binding.get("name").unwrap();
+        let value = binding.get("name").unwrap().unwrap();
         assert_eq!(old_name, *value)
     }
+
+    #[test]
+    fn it_rejects_conflicting_options() {
+        let mut iter: IntoIter<Frame> = vec![
+            Frame::Simple("key".to_string()),
+            Frame::Simple("value".to_string()),
+            Frame::Simple("NX".to_string()),
+            Frame::Simple("XX".to_string()),
+        ].into_iter();
+
+        let result = Set::try_from(&mut iter);
+
+        assert_eq!("syntax error", result.err().unwrap().to_string());
+    }
+
+    #[test]
+    fn it_saves_value_with_expiration() {
+        let db = new_db();
+        let mut iter: IntoIter<Frame> = vec![
+            Frame::Bulk(Bytes::from("session")),
+            Frame::Bulk(Bytes::from("100")),
+            Frame::Bulk(Bytes::from("abc")),
+        ].into_iter();
+        let command = Set::setex(&mut iter).unwrap();
+
+        command.execute(db.clone());
+
+        let binding = db.lock().unwrap();
+        let expires_at = binding.expiration("session").unwrap();
+        let ttl = expires_at.duration_since(SystemTime::now()).unwrap();
+        assert!(ttl > Duration::from_secs(99) && ttl <= Duration::from_secs(100));
+        assert_eq!(Bytes::from("abc"), binding.get("session").unwrap().unwrap());
+    }
+
+    #[test]
+    fn it_keeps_ttl_when_asked_to() {
+        let db = new_db();
+        let expires_at = SystemTime::now() + Duration::from_secs(60);
+        {
+            let db = db.clone();
+            db.lock().unwrap().insert_with_expiration("key".to_string(), Bytes::from("a"), Some(expires_at));
+        }
+        let command = Set {
+            key: Bytes::from("key"),
+            value: Bytes::from("b"),
+            ttl: None,
+            keep_ttl: true,
+            replacement: Default::default(),
+            get: false
+        };
+
+        command.execute(db.clone());
+
+        assert_eq!(Some(expires_at), db.lock().unwrap().expiration("key"));
+    }
+
+    #[test]
+    fn it_rejects_invalid_expire_time() {
+        let mut iter: IntoIter<Frame> = vec![
+            Frame::Bulk(Bytes::from("key")),
+            Frame::Bulk(Bytes::from("0")),
+            Frame::Bulk(Bytes::from("value")),
+        ].into_iter();
+
+        let result = Set::setex(&mut iter);
+
+        assert_eq!("invalid expire time in 'setex' command", result.err().unwrap().to_string());
+    }
 }
\ No newline at end of file
@@ -0,0 +1,98 @@
+use std::vec::IntoIter;
+
+use bytes::BytesMut;
+
+use bytes::Bytes;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::Error;
+
+use super::{Command, next_bit_offset, next_bytes, next_integer, wrong_arity};
+
+pub(crate) struct SetBit {
+    key: Bytes,
+    offset: usize,
+    value: bool,
+}
+
+impl Command for SetBit {
+    fn execute(&self, db: Database) -> Frame {
+        let mut db = db.lock_keys([&self.key]).unwrap();
+        let byte = self.offset >> 3;
+        let mask = 1u8 << (7 - (self.offset & 7));
+
+        let mut value = match db.get(&self.key) {
+            Ok(value) => BytesMut::from(&value.cloned().unwrap_or_default()[..]),
+            Err(err) => return err.into(),
+        };
+        if value.len() <= byte {
+            value.resize(byte + 1, 0);
+        }
+
+        let previous = value[byte] & mask != 0;
+        if self.value {
+            value[byte] |= mask;
+        } else {
+            value[byte] &= !mask;
+        }
+        db.update(&self.key, value.freeze());
+
+        Frame::Integer(previous as i64)
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for SetBit {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() != 3 {
+            return Err(wrong_arity("setbit"));
+        }
+
+        let key = next_bytes(frames)?;
+        let offset = next_bit_offset(frames)?;
+        let value = match next_integer(frames) {
+            Ok(0) => false,
+            Ok(1) => true,
+            _ => return Err("bit is not an integer or out of range".into()),
+        };
+
+        Ok(SetBit { key, offset, value })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::database::new_db;
+
+    use super::*;
+
+    #[test]
+    fn it_sets_bits_from_the_most_significant_one() {
+        let db = new_db();
+
+        let first = SetBit { key: Bytes::from("bits"), offset: 1, value: true }.execute(db.clone());
+        let second = SetBit { key: Bytes::from("bits"), offset: 1, value: false }.execute(db.clone());
+        SetBit { key: Bytes::from("bits"), offset: 15, value: true }.execute(db.clone());
+
+        assert_eq!(Frame::Integer(0), first);
+        assert_eq!(Frame::Integer(1), second);
+        assert_eq!(Bytes::from(&[0x00, 0x01][..]), db.lock().unwrap().get("bits").unwrap().unwrap());
+    }
+
+    #[test]
+    fn it_rejects_invalid_bits() {
+        let mut iter: IntoIter<Frame> = vec![
+            Frame::Bulk(Bytes::from("bits")),
+            Frame::Bulk(Bytes::from("7")),
+            Frame::Bulk(Bytes::from("2")),
+        ].into_iter();
+
+        let result = SetBit::try_from(&mut iter);
+
+        assert_eq!("bit is not an integer or out of range", result.err().unwrap().to_string());
+    }
+}
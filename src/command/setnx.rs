@@ -0,0 +1,60 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::Error;
+
+use super::{Command, next_bytes, wrong_arity};
+
+pub(crate) struct SetNx {
+    key: Bytes,
+    value: Bytes,
+}
+
+impl Command for SetNx {
+    fn execute(&self, db: Database) -> Frame {
+        let mut db = db.lock_keys([&self.key]).unwrap();
+
+        if db.contains_key(&self.key) {
+            return Frame::Integer(0);
+        }
+
+        db.insert(self.key.clone(), self.value.clone());
+        Frame::Integer(1)
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for SetNx {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() != 2 {
+            return Err(wrong_arity("setnx"));
+        }
+
+        Ok(SetNx {
+            key: next_bytes(frames)?,
+            value: next_bytes(frames)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::new_db;
+
+    use super::*;
+
+    #[test]
+    fn it_sets_only_missing_keys() {
+        let db = new_db();
+        let first = SetNx { key: Bytes::from("lock"), value: Bytes::from("a") };
+        let second = SetNx { key: Bytes::from("lock"), value: Bytes::from("b") };
+
+        assert_eq!(Frame::Integer(1), first.execute(db.clone()));
+        assert_eq!(Frame::Integer(0), second.execute(db.clone()));
+        assert_eq!(Bytes::from("a"), db.lock().unwrap().get("lock").unwrap().unwrap());
+    }
+}
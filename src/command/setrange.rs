@@ -0,0 +1,106 @@
+use std::vec::IntoIter;
+
+use bytes::{Bytes, BytesMut};
+
+use crate::database::{Database, MAX_STRING_LENGTH};
+use crate::frame::Frame;
+use crate::Error;
+
+use super::{Command, next_bytes, next_integer, wrong_arity};
+
+pub(crate) struct SetRange {
+    key: Bytes,
+    offset: usize,
+    value: Bytes,
+}
+
+impl Command for SetRange {
+    fn execute(&self, db: Database) -> Frame {
+        let mut db = db.lock_keys([&self.key]).unwrap();
+        let current = match db.get(&self.key) {
+            Ok(current) => current.cloned().unwrap_or_default(),
+            Err(err) => return err.into(),
+        };
+
+        if self.value.is_empty() {
+            return Frame::Integer(current.len() as i64);
+        }
+
+        let end = self.offset + self.value.len();
+        let mut value = BytesMut::from(&current[..]);
+        if value.len() < end {
+            value.resize(end, 0);
+        }
+        value[self.offset..end].copy_from_slice(&self.value);
+        let len = value.len();
+        db.update(&self.key, value.freeze());
+
+        Frame::Integer(len as i64)
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for SetRange {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() != 3 {
+            return Err(wrong_arity("setrange"));
+        }
+
+        let key = next_bytes(frames)?;
+        let offset = next_integer(frames)?;
+        let value = next_bytes(frames)?;
+
+        if offset < 0 {
+            return Err("offset is out of range".into());
+        }
+        if offset as usize + value.len() > MAX_STRING_LENGTH {
+            return Err("string exceeds maximum allowed size (proto-max-bulk-len)".into());
+        }
+
+        Ok(SetRange { key, offset: offset as usize, value })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::new_db;
+
+    use super::*;
+
+    #[test]
+    fn it_overwrites_part_of_the_string() {
+        let db = new_db();
+        db.lock().unwrap().insert("key".to_string(), Bytes::from("Hello World"));
+        let command = SetRange { key: Bytes::from("key"), offset: 6, value: Bytes::from("Redis") };
+
+        let result = command.execute(db.clone());
+
+        assert_eq!(Frame::Integer(11), result);
+        assert_eq!(Bytes::from("Hello Redis"), db.lock().unwrap().get("key").unwrap().unwrap());
+    }
+
+    #[test]
+    fn it_pads_missing_key_with_zero_bytes() {
+        let db = new_db();
+        let command = SetRange { key: Bytes::from("key"), offset: 3, value: Bytes::from("ab") };
+
+        let result = command.execute(db.clone());
+
+        assert_eq!(Frame::Integer(5), result);
+        assert_eq!(Bytes::from(&b"\0\0\0ab"[..]), db.lock().unwrap().get("key").unwrap().unwrap());
+    }
+
+    #[test]
+    fn it_rejects_negative_offset() {
+        let mut iter: IntoIter<Frame> = vec![
+            Frame::Bulk(Bytes::from("key")),
+            Frame::Bulk(Bytes::from("-1")),
+            Frame::Bulk(Bytes::from("value")),
+        ].into_iter();
+
+        let result = SetRange::try_from(&mut iter);
+
+        assert_eq!("offset is out of range", result.err().unwrap().to_string());
+    }
+}
@@ -0,0 +1,55 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::Error;
+
+use super::{Command, next_bytes, wrong_arity};
+
+pub(crate) struct StrLen {
+    key: Bytes,
+}
+
+impl Command for StrLen {
+    fn execute(&self, db: Database) -> Frame {
+        let db = db.lock_keys([&self.key]).unwrap();
+        match db.get(&self.key) {
+            Ok(value) => Frame::Integer(value.map_or(0, |value| value.len()) as i64),
+            Err(err) => err.into(),
+        }
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for StrLen {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() != 1 {
+            return Err(wrong_arity("strlen"));
+        }
+
+        Ok(StrLen {
+            key: next_bytes(frames)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::database::new_db;
+
+    use super::*;
+
+    #[test]
+    fn it_returns_length_of_value() {
+        let db = new_db();
+        db.lock().unwrap().insert("key".to_string(), Bytes::from("Hello world"));
+
+        assert_eq!(Frame::Integer(11), StrLen { key: Bytes::from("key") }.execute(db.clone()));
+        assert_eq!(Frame::Integer(0), StrLen { key: Bytes::from("nope") }.execute(db));
+    }
+}
@@ -0,0 +1,80 @@
+use std::vec::IntoIter;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::Error;
+
+use super::{Command, error_frame, next_integer, wrong_arity};
+
+/// Swaps the contents of two databases, connections keep their selected index
+/// and so immediately see the other data.
+pub(crate) struct SwapDb {
+    first: usize,
+    second: usize,
+}
+
+impl Command for SwapDb {
+    fn execute(&self, db: Database) -> Frame {
+        if db.select(self.first).is_none() || db.select(self.second).is_none() {
+            return error_frame("DB index is out of range");
+        }
+        if self.first != self.second {
+            let (mut first, mut second) = db.lock_pair(self.first, self.second);
+            first.swap(&mut second);
+        }
+
+        Frame::Simple("OK".to_string())
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for SwapDb {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() != 2 {
+            return Err(wrong_arity("swapdb"));
+        }
+
+        let first = next_integer(frames).map_err(|_| "invalid first DB index")?;
+        let second = next_integer(frames).map_err(|_| "invalid second DB index")?;
+        if first < 0 || second < 0 {
+            return Err("DB index is out of range".into());
+        }
+
+        Ok(SwapDb { first: first as usize, second: second as usize })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::database::new_db;
+
+    use super::*;
+
+    #[test]
+    fn it_swaps_databases_for_every_connection() {
+        let db = new_db();
+        let other = db.select(3).unwrap();
+        db.lock().unwrap().insert("a".to_string(), Bytes::from("1"));
+
+        assert_eq!(Frame::Simple("OK".to_string()), SwapDb { first: 0, second: 3 }.execute(db.clone()));
+
+        assert!(db.lock().unwrap().is_empty());
+        assert_eq!(Ok(Some(&Bytes::from("1"))), other.lock().unwrap().get("a"));
+    }
+
+    #[test]
+    fn it_rejects_invalid_indexes() {
+        let db = new_db();
+        let parse = |arguments: [&str; 2]| {
+            let frames: Vec<Frame> = arguments.iter().map(|argument| Frame::Bulk(Bytes::from(argument.to_string()))).collect();
+            SwapDb::try_from(&mut frames.into_iter()).err().unwrap().to_string()
+        };
+
+        assert_eq!("invalid first DB index", parse(["a", "1"]));
+        assert_eq!("invalid second DB index", parse(["1", "b"]));
+        assert_eq!(error_frame("DB index is out of range"), SwapDb { first: 0, second: 16 }.execute(db.clone()));
+    }
+}
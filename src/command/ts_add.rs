@@ -0,0 +1,149 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::time_series::{AddError, DuplicatePolicy, TimeSeries};
+use crate::Error;
+
+use super::{Command, error_frame, next_bytes, next_string, parse_float, parse_integer, syntax_error, wrong_arity};
+use super::ts_create::{next_duplicate_policy, next_retention};
+
+pub(crate) struct TsAdd {
+    key: Bytes,
+    timestamp: Option<i64>,
+    value: f64,
+    retention: Option<u64>,
+    on_duplicate: Option<DuplicatePolicy>,
+}
+
+impl Command for TsAdd {
+    fn execute(&self, db: Database) -> Frame {
+        // Compaction rules name their destinations only once the series is read, so
+        // the whole keyspace is locked rather than just the shard of the key.
+        let mut db = db.lock().unwrap();
+        let timestamp = self.timestamp.unwrap_or_else(|| {
+            SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as i64
+        });
+
+        let series = match db.get_time_series_mut(&self.key) {
+            Ok(Some(series)) => series,
+            Ok(None) => {
+                let series = TimeSeries::new(self.retention.unwrap_or(0), DuplicatePolicy::default());
+                db.insert(self.key.clone(), series);
+                db.get_time_series_mut(&self.key).unwrap().unwrap()
+            }
+            Err(err) => return err.into(),
+        };
+
+        let compacted = match series.add(timestamp, self.value, self.on_duplicate) {
+            Ok(compacted) => compacted,
+            Err(AddError::TooOld) => return error_frame("TSDB: Timestamp is older than retention"),
+            Err(AddError::Blocked) => {
+                return error_frame("TSDB: Error at upsert, update is not supported when DUPLICATE_POLICY is set to BLOCK mode");
+            }
+        };
+        for (destination, timestamp, value) in compacted {
+            if let Ok(Some(destination)) = db.get_time_series_mut(&destination) {
+                destination.add_compacted(timestamp, value);
+            }
+        }
+
+        Frame::Integer(timestamp)
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for TsAdd {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() < 3 {
+            return Err(wrong_arity("ts.add"));
+        }
+
+        let key = next_bytes(frames)?;
+        let timestamp = match next_string(frames)?.as_str() {
+            "*" => None,
+            timestamp => match parse_integer(timestamp.as_bytes()) {
+                Some(timestamp) if timestamp >= 0 => Some(timestamp),
+                _ => return Err("TSDB: invalid timestamp".into()),
+            },
+        };
+        let value = parse_float(next_string(frames)?.as_bytes())
+            .filter(|value| !value.is_nan())
+            .ok_or("TSDB: invalid value")?;
+
+        let mut command = TsAdd { key, timestamp, value, retention: None, on_duplicate: None };
+        while frames.len() > 0 {
+            match next_string(frames)?.to_uppercase().as_str() {
+                "RETENTION" => command.retention = Some(next_retention(frames)?),
+                "ON_DUPLICATE" => command.on_duplicate = Some(next_duplicate_policy(frames)?),
+                _ => return Err(syntax_error()),
+            }
+        }
+
+        Ok(command)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::database::new_db;
+    use crate::time_series::Aggregation;
+
+    use super::*;
+
+    fn add(db: &Database, arguments: &[&str]) -> Frame {
+        let frames: Vec<Frame> = arguments.iter().map(|argument| Frame::Bulk(Bytes::from(argument.to_string()))).collect();
+        TsAdd::try_from(&mut frames.into_iter()).unwrap().execute(db.clone())
+    }
+
+    #[test]
+    fn it_adds_samples_creating_series() {
+        let db = new_db();
+
+        assert_eq!(Frame::Integer(1000), add(&db, &["ts", "1000", "1.5", "RETENTION", "100"]));
+        assert_eq!(Frame::SimpleError("ERR TSDB: Timestamp is older than retention".to_string()), add(&db, &["ts", "10", "1"]));
+        assert_eq!(100, db.lock().unwrap().get_time_series("ts").unwrap().unwrap().retention);
+    }
+
+    #[test]
+    fn it_handles_duplicate_timestamps() {
+        let db = new_db();
+        add(&db, &["ts", "1000", "1"]);
+
+        assert!(matches!(add(&db, &["ts", "1000", "2"]), Frame::SimpleError(_)));
+        assert_eq!(Frame::Integer(1000), add(&db, &["ts", "1000", "2", "ON_DUPLICATE", "SUM"]));
+        assert_eq!(&[(1000, 3.0)], db.lock().unwrap().get_time_series("ts").unwrap().unwrap().range(0, i64::MAX));
+    }
+
+    #[test]
+    fn it_writes_compacted_samples_to_destination() {
+        let db = new_db();
+        let mut source = TimeSeries::default();
+        source.create_rule(Bytes::from("avg"), Aggregation::Avg, 1000);
+        db.lock().unwrap().insert("ts".to_string(), source);
+        db.lock().unwrap().insert("avg".to_string(), TimeSeries::default());
+
+        add(&db, &["ts", "100", "1"]);
+        add(&db, &["ts", "200", "3"]);
+        add(&db, &["ts", "1100", "5"]);
+
+        assert_eq!(&[(0, 2.0)], db.lock().unwrap().get_time_series("avg").unwrap().unwrap().range(0, i64::MAX));
+    }
+
+    #[test]
+    fn it_rejects_invalid_samples() {
+        let parse = |arguments: &[&str]| {
+            let frames: Vec<Frame> = arguments.iter().map(|argument| Frame::Bulk(Bytes::from(argument.to_string()))).collect();
+            TsAdd::try_from(&mut frames.into_iter()).err().unwrap().to_string()
+        };
+
+        assert_eq!("TSDB: invalid timestamp", parse(&["ts", "-1", "1"]));
+        assert_eq!("TSDB: invalid value", parse(&["ts", "*", "abc"]));
+    }
+}
@@ -0,0 +1,94 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::time_series::{DuplicatePolicy, TimeSeries};
+use crate::Error;
+
+use super::{Command, error_frame, next_bytes, next_integer, next_string, syntax_error, wrong_arity};
+
+pub(crate) struct TsCreate {
+    key: Bytes,
+    retention: u64,
+    duplicate_policy: DuplicatePolicy,
+}
+
+impl Command for TsCreate {
+    fn execute(&self, db: Database) -> Frame {
+        let mut db = db.lock_keys([&self.key]).unwrap();
+        if db.contains_key(&self.key) {
+            return error_frame("TSDB: key already exists");
+        }
+
+        db.insert(self.key.clone(), TimeSeries::new(self.retention, self.duplicate_policy));
+
+        Frame::Simple("OK".to_string())
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for TsCreate {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() == 0 {
+            return Err(wrong_arity("ts.create"));
+        }
+
+        let mut command = TsCreate { key: next_bytes(frames)?, retention: 0, duplicate_policy: DuplicatePolicy::default() };
+        while frames.len() > 0 {
+            match next_string(frames)?.to_uppercase().as_str() {
+                "RETENTION" => command.retention = next_retention(frames)?,
+                "DUPLICATE_POLICY" => command.duplicate_policy = next_duplicate_policy(frames)?,
+                _ => return Err(syntax_error()),
+            }
+        }
+
+        Ok(command)
+    }
+}
+
+pub(crate) fn next_retention(frames: &mut IntoIter<Frame>) -> Result<u64, Error> {
+    match next_integer(frames) {
+        Ok(retention) if retention >= 0 => Ok(retention as u64),
+        _ => Err("TSDB: Couldn't parse RETENTION".into()),
+    }
+}
+
+pub(crate) fn next_duplicate_policy(frames: &mut IntoIter<Frame>) -> Result<DuplicatePolicy, Error> {
+    DuplicatePolicy::parse(&next_string(frames)?).ok_or_else(|| "TSDB: Unknown DUPLICATE_POLICY".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::database::new_db;
+
+    use super::*;
+
+    fn create(arguments: &[&str]) -> Result<TsCreate, Error> {
+        let frames: Vec<Frame> = arguments.iter().map(|argument| Frame::Bulk(Bytes::from(argument.to_string()))).collect();
+        TsCreate::try_from(&mut frames.into_iter())
+    }
+
+    #[test]
+    fn it_creates_series_with_options() {
+        let db = new_db();
+        let command = create(&["temperature", "RETENTION", "60000", "DUPLICATE_POLICY", "last"]).unwrap();
+
+        assert_eq!(Frame::Simple("OK".to_string()), command.execute(db.clone()));
+        assert_eq!(Frame::SimpleError("ERR TSDB: key already exists".to_string()), command.execute(db.clone()));
+        let binding = db.lock().unwrap();
+        let series = binding.get_time_series("temperature").unwrap().unwrap();
+        assert_eq!(60000, series.retention);
+        assert_eq!(DuplicatePolicy::Last, series.duplicate_policy);
+    }
+
+    #[test]
+    fn it_rejects_invalid_options() {
+        assert_eq!("TSDB: Couldn't parse RETENTION", create(&["ts", "RETENTION", "-1"]).err().unwrap().to_string());
+        assert_eq!("TSDB: Unknown DUPLICATE_POLICY", create(&["ts", "DUPLICATE_POLICY", "avg"]).err().unwrap().to_string());
+    }
+}
@@ -0,0 +1,115 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::time_series::Aggregation;
+use crate::Error;
+
+use super::{Command, error_frame, next_bytes, next_string, syntax_error, wrong_arity};
+use super::ts_range::next_aggregation;
+
+pub(crate) struct TsCreateRule {
+    source: Bytes,
+    destination: Bytes,
+    aggregation: Aggregation,
+    duration: i64,
+}
+
+impl Command for TsCreateRule {
+    fn execute(&self, db: Database) -> Frame {
+        let mut db = db.lock_keys([&self.source, &self.destination]).unwrap();
+        if self.source == self.destination {
+            return error_frame("TSDB: the source key and destination key should be different");
+        }
+
+        match db.get_time_series(&self.destination) {
+            Ok(Some(destination)) if destination.source.is_some() => {
+                return error_frame("TSDB: the destination key already has a src rule");
+            }
+            Ok(Some(destination)) if !destination.rules.is_empty() => {
+                return error_frame("TSDB: the destination key already has a dst rule");
+            }
+            Ok(Some(_)) => {}
+            Ok(None) => return error_frame("TSDB: the key does not exist"),
+            Err(err) => return err.into(),
+        }
+
+        match db.get_time_series_mut(&self.source) {
+            Ok(Some(source)) if source.source.is_some() => {
+                return error_frame("TSDB: the source key already has a source rule");
+            }
+            Ok(Some(source)) => source.create_rule(self.destination.clone(), self.aggregation, self.duration),
+            Ok(None) => return error_frame("TSDB: the key does not exist"),
+            Err(err) => return err.into(),
+        }
+        db.get_time_series_mut(&self.destination).unwrap().unwrap().source = Some(self.source.clone());
+
+        Frame::Simple("OK".to_string())
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for TsCreateRule {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() != 5 {
+            return Err(wrong_arity("ts.createrule"));
+        }
+
+        let source = next_bytes(frames)?;
+        let destination = next_bytes(frames)?;
+        if next_string(frames)?.to_uppercase() != "AGGREGATION" {
+            return Err(syntax_error());
+        }
+        let (aggregation, duration) = next_aggregation(frames)?;
+
+        Ok(TsCreateRule { source, destination, aggregation, duration })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::database::new_db;
+    use crate::time_series::TimeSeries;
+
+    use super::*;
+
+    fn create_rule(db: &Database, arguments: &[&str]) -> Frame {
+        let frames: Vec<Frame> = arguments.iter().map(|argument| Frame::Bulk(Bytes::from(argument.to_string()))).collect();
+        TsCreateRule::try_from(&mut frames.into_iter()).unwrap().execute(db.clone())
+    }
+
+    #[test]
+    fn it_links_source_and_destination() {
+        let db = new_db();
+        db.lock().unwrap().insert("ts".to_string(), TimeSeries::default());
+        db.lock().unwrap().insert("avg".to_string(), TimeSeries::default());
+
+        assert_eq!(Frame::Simple("OK".to_string()), create_rule(&db, &["ts", "avg", "AGGREGATION", "avg", "60000"]));
+
+        let binding = db.lock().unwrap();
+        assert_eq!("avg", binding.get_time_series("ts").unwrap().unwrap().rules[0].destination);
+        assert_eq!(Some(Bytes::from("ts")), binding.get_time_series("avg").unwrap().unwrap().source);
+    }
+
+    #[test]
+    fn it_rejects_invalid_rules() {
+        let db = new_db();
+        db.lock().unwrap().insert("ts".to_string(), TimeSeries::default());
+        db.lock().unwrap().insert("avg".to_string(), TimeSeries::default());
+        create_rule(&db, &["ts", "avg", "AGGREGATION", "avg", "60000"]);
+
+        let error = |arguments: &[&str]| match create_rule(&db, arguments) {
+            Frame::SimpleError(message) => message,
+            frame => panic!("unexpected reply {:?}", frame),
+        };
+
+        assert_eq!("ERR TSDB: the source key and destination key should be different", error(&["ts", "ts", "AGGREGATION", "avg", "10"]));
+        assert_eq!("ERR TSDB: the destination key already has a src rule", error(&["ts", "avg", "AGGREGATION", "max", "10"]));
+        assert_eq!("ERR TSDB: the key does not exist", error(&["ts", "missing", "AGGREGATION", "max", "10"]));
+    }
+}
@@ -0,0 +1,73 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::database::Database;
+use crate::frame::Frame;
+use crate::Error;
+
+use super::{Command, error_frame, next_bytes, wrong_arity};
+
+pub(crate) struct TsDeleteRule {
+    source: Bytes,
+    destination: Bytes,
+}
+
+impl Command for TsDeleteRule {
+    fn execute(&self, db: Database) -> Frame {
+        let mut db = db.lock_keys([&self.source, &self.destination]).unwrap();
+        let source = match db.get_time_series_mut(&self.source) {
+            Ok(Some(source)) => source,
+            Ok(None) => return error_frame("TSDB: the key does not exist"),
+            Err(err) => return err.into(),
+        };
+
+        let rules = source.rules.len();
+        source.rules.retain(|rule| rule.destination != self.destination);
+        if source.rules.len() == rules {
+            return error_frame("TSDB: compaction rule does not exist");
+        }
+
+        if let Ok(Some(destination)) = db.get_time_series_mut(&self.destination) {
+            destination.source = None;
+        }
+
+        Frame::Simple("OK".to_string())
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for TsDeleteRule {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() != 2 {
+            return Err(wrong_arity("ts.deleterule"));
+        }
+
+        Ok(TsDeleteRule { source: next_bytes(frames)?, destination: next_bytes(frames)? })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::new_db;
+    use crate::time_series::{Aggregation, TimeSeries};
+
+    use super::*;
+
+    #[test]
+    fn it_removes_rule() {
+        let db = new_db();
+        let mut source = TimeSeries::default();
+        source.create_rule(Bytes::from("avg"), Aggregation::Avg, 1000);
+        db.lock().unwrap().insert("ts".to_string(), source);
+        let mut destination = TimeSeries::default();
+        destination.source = Some(Bytes::from("ts"));
+        db.lock().unwrap().insert("avg".to_string(), destination);
+        let command = TsDeleteRule { source: Bytes::from("ts"), destination: Bytes::from("avg") };
+
+        assert_eq!(Frame::Simple("OK".to_string()), command.execute(db.clone()));
+        assert_eq!(Frame::SimpleError("ERR TSDB: compaction rule does not exist".to_string()), command.execute(db.clone()));
+        assert_eq!(None, db.lock().unwrap().get_time_series("avg").unwrap().unwrap().source);
+    }
+}
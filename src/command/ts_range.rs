@@ -0,0 +1,142 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::database::Database;
+use crate::frame::{format_double, Frame};
+use crate::time_series::{self, Aggregation};
+use crate::Error;
+
+use super::{Command, error_frame, next_bytes, next_integer, next_string, parse_integer, syntax_error, wrong_arity};
+
+pub(crate) struct TsRange {
+    key: Bytes,
+    from: i64,
+    to: i64,
+    count: Option<usize>,
+    aggregation: Option<(Aggregation, i64)>,
+}
+
+impl Command for TsRange {
+    fn execute(&self, db: Database) -> Frame {
+        let db = db.lock_keys([&self.key]).unwrap();
+        let series = match db.get_time_series(&self.key) {
+            Ok(Some(series)) => series,
+            Ok(None) => return error_frame("TSDB: the key does not exist"),
+            Err(err) => return err.into(),
+        };
+
+        let samples = series.range(self.from, self.to);
+        let mut samples = match self.aggregation {
+            Some((aggregation, duration)) => time_series::aggregate(samples, aggregation, duration),
+            None => samples.to_vec(),
+        };
+        if let Some(count) = self.count {
+            samples.truncate(count);
+        }
+
+        Frame::Array(samples.into_iter()
+            .map(|(timestamp, value)| Frame::Array(vec![
+                Frame::Integer(timestamp),
+                Frame::Bulk(Bytes::from(format_double(value))),
+            ]))
+            .collect())
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for TsRange {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() < 3 {
+            return Err(wrong_arity("ts.range"));
+        }
+
+        let key = next_bytes(frames)?;
+        let from = next_timestamp(frames, 0)?;
+        let to = next_timestamp(frames, i64::MAX)?;
+        let mut command = TsRange { key, from, to, count: None, aggregation: None };
+
+        while frames.len() > 0 {
+            match next_string(frames)?.to_uppercase().as_str() {
+                "COUNT" => match next_integer(frames) {
+                    Ok(count) if count > 0 => command.count = Some(count as usize),
+                    _ => return Err("TSDB: Invalid COUNT value".into()),
+                },
+                "AGGREGATION" => command.aggregation = Some(next_aggregation(frames)?),
+                _ => return Err(syntax_error()),
+            }
+        }
+
+        Ok(command)
+    }
+}
+
+/// Reads a range boundary, where `-` and `+` stand for the oldest and newest samples.
+fn next_timestamp(frames: &mut IntoIter<Frame>, open: i64) -> Result<i64, Error> {
+    match next_string(frames)?.as_str() {
+        "-" | "+" => Ok(open),
+        timestamp => parse_integer(timestamp.as_bytes())
+            .filter(|timestamp| *timestamp >= 0)
+            .ok_or_else(|| "TSDB: invalid timestamp".into()),
+    }
+}
+
+/// Reads an aggregation type followed by the bucket duration in milliseconds.
+pub(crate) fn next_aggregation(frames: &mut IntoIter<Frame>) -> Result<(Aggregation, i64), Error> {
+    let aggregation = Aggregation::parse(&next_string(frames)?).ok_or("TSDB: Unknown aggregation type")?;
+    match next_integer(frames) {
+        Ok(duration) if duration > 0 => Ok((aggregation, duration)),
+        _ => Err("TSDB: bucketDuration must be greater than zero".into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::new_db;
+    use crate::time_series::TimeSeries;
+
+    use super::*;
+
+    fn range(db: &Database, arguments: &[&str]) -> Frame {
+        let frames: Vec<Frame> = arguments.iter().map(|argument| Frame::Bulk(Bytes::from(argument.to_string()))).collect();
+        TsRange::try_from(&mut frames.into_iter()).unwrap().execute(db.clone())
+    }
+
+    fn sample(timestamp: i64, value: &str) -> Frame {
+        Frame::Array(vec![Frame::Integer(timestamp), Frame::Bulk(Bytes::from(value.to_string()))])
+    }
+
+    fn with_series() -> Database {
+        let db = new_db();
+        let mut series = TimeSeries::default();
+        for (timestamp, value) in [(1000, 1.0), (1500, 2.0), (2000, 4.5), (3000, 6.0)] {
+            series.add(timestamp, value, None).unwrap();
+        }
+        db.lock().unwrap().insert("ts".to_string(), series);
+        db
+    }
+
+    #[test]
+    fn it_returns_samples_within_range() {
+        let db = with_series();
+
+        assert_eq!(Frame::Array(vec![sample(1500, "2"), sample(2000, "4.5")]), range(&db, &["ts", "1200", "2500"]));
+        assert_eq!(Frame::Array(vec![sample(1000, "1")]), range(&db, &["ts", "-", "+", "COUNT", "1"]));
+    }
+
+    #[test]
+    fn it_aggregates_samples() {
+        let db = with_series();
+
+        assert_eq!(Frame::Array(vec![sample(1000, "1.5"), sample(2000, "4.5"), sample(3000, "6")]), range(&db, &["ts", "-", "+", "AGGREGATION", "avg", "1000"]));
+        assert_eq!(Frame::Array(vec![sample(0, "13.5")]), range(&db, &["ts", "-", "+", "AGGREGATION", "SUM", "10000"]));
+    }
+
+    #[test]
+    fn it_fails_for_missing_key() {
+        let db = new_db();
+
+        assert_eq!(Frame::SimpleError("ERR TSDB: the key does not exist".to_string()), range(&db, &["ts", "-", "+"]));
+    }
+}
@@ -0,0 +1,172 @@
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+
+use crate::database::Database;
+use crate::frame::{format_double, Frame};
+use crate::glob;
+use crate::Error;
+
+use super::scan::{next_count, next_cursor, scan_reply};
+use super::{Command, next_bytes, next_string, syntax_error, wrong_arity};
+
+const DEFAULT_COUNT: usize = 10;
+
+/// ZSCAN key cursor [MATCH pattern] [COUNT count] [NOSCORES], HSCAN and SSCAN are
+/// left out until there are hash and set values to scan.
+pub(crate) struct ZScan {
+    key: Bytes,
+    cursor: u64,
+    pattern: Option<Bytes>,
+    count: usize,
+    no_scores: bool,
+}
+
+impl Command for ZScan {
+    fn execute(&self, db: Database) -> Frame {
+        let db = db.lock_keys([&self.key]).unwrap();
+        let set = match db.get_sorted_set(&self.key) {
+            Ok(None) => return scan_reply(0, vec![]),
+            Ok(Some(set)) => set,
+            Err(err) => return err.into(),
+        };
+
+        // Members are visited in the order of their hashes, like keys are by SCAN.
+        let (cursor, members) = set.scan(self.cursor, self.count);
+        let mut items = vec![];
+        for (member, score) in members {
+            if self.pattern.as_ref().is_some_and(|pattern| !glob::matches(pattern, member)) {
+                continue;
+            }
+            items.push(Frame::Bulk(member.clone()));
+            if !self.no_scores {
+                items.push(Frame::Bulk(Bytes::from(format_double(score))));
+            }
+        }
+
+        scan_reply(cursor, items)
+    }
+}
+
+impl TryFrom<&mut IntoIter<Frame>> for ZScan {
+    type Error = Error;
+
+    fn try_from(frames: &mut IntoIter<Frame>) -> Result<Self, Self::Error> {
+        if frames.len() < 2 {
+            return Err(wrong_arity("zscan"));
+        }
+
+        let key = next_bytes(frames)?;
+        let cursor = next_cursor(frames)?;
+        let mut command = ZScan { key, cursor, pattern: None, count: DEFAULT_COUNT, no_scores: false };
+        while frames.len() > 0 {
+            match next_string(frames)?.to_uppercase().as_str() {
+                "MATCH" => command.pattern = Some(next_bytes(frames)?),
+                "COUNT" => command.count = next_count(frames)?,
+                "NOSCORES" => command.no_scores = true,
+                _ => return Err(syntax_error()),
+            }
+        }
+
+        Ok(command)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::new_db;
+    use crate::sorted_set::{CompactLimits, SortedSet};
+
+    use super::*;
+
+    fn zscan(db: &Database, arguments: &[&str]) -> (String, Vec<Frame>) {
+        let frames: Vec<Frame> = arguments.iter().map(|argument| Frame::Bulk(Bytes::from(argument.to_string()))).collect();
+        match ZScan::try_from(&mut frames.into_iter()).unwrap().execute(db.clone()) {
+            Frame::Array(mut reply) => match (reply.pop(), reply.pop()) {
+                (Some(Frame::Array(items)), Some(Frame::Bulk(cursor))) => (String::from_utf8(cursor.to_vec()).unwrap(), items),
+                reply => panic!("unexpected reply {:?}", reply),
+            },
+            frame => panic!("unexpected reply {:?}", frame),
+        }
+    }
+
+    fn with_set() -> Database {
+        let db = new_db();
+        let mut set = SortedSet::default();
+        for i in 0..25 {
+            set.insert(Bytes::from(format!("member:{}", i)), i as f64, CompactLimits::default());
+        }
+        db.lock().unwrap().insert("ranking".to_string(), set);
+        db
+    }
+
+    #[test]
+    fn it_iterates_over_members_with_scores() {
+        let db = with_set();
+        let mut cursor = "0".to_string();
+        let mut items = vec![];
+
+        loop {
+            let (next, page) = zscan(&db, &["ranking", &cursor, "COUNT", "10"]);
+            items.extend(page);
+            cursor = next;
+            if cursor == "0" {
+                break;
+            }
+        }
+
+        assert_eq!(50, items.len());
+        let position = items.iter().position(|item| *item == Frame::Bulk(Bytes::from("member:7"))).unwrap();
+        assert_eq!(Frame::Bulk(Bytes::from("7")), items[position + 1]);
+    }
+
+    #[test]
+    fn it_pages_through_indexed_sets() {
+        let db = new_db();
+        let mut set = SortedSet::default();
+        for i in 0..300 {
+            set.insert(Bytes::from(format!("member:{}", i)), i as f64, CompactLimits::default());
+        }
+        assert!(!set.is_compact());
+        db.lock().unwrap().insert("ranking".to_string(), set);
+
+        let mut cursor = "0".to_string();
+        let mut members = vec![];
+        let mut pages = 0;
+        loop {
+            let (next, page) = zscan(&db, &["ranking", &cursor, "COUNT", "20", "NOSCORES"]);
+            assert!(page.len() < 40);
+            members.extend(page.into_iter().map(|member| match member {
+                Frame::Bulk(member) => member,
+                frame => panic!("unexpected member {:?}", frame),
+            }));
+            pages += 1;
+            cursor = next;
+            if cursor == "0" {
+                break;
+            }
+            // Members removed along the way do not move the cursor of the others.
+            if pages == 3 {
+                db.lock().unwrap().get_sorted_set_mut("ranking").unwrap().unwrap().remove(b"member:0");
+            }
+        }
+
+        assert!(pages >= 15);
+        let count = members.len();
+        members.sort();
+        members.dedup();
+        assert_eq!(count, members.len());
+        assert!((299..=300).contains(&count));
+        assert!(members.contains(&Bytes::from("member:299")));
+    }
+
+    #[test]
+    fn it_filters_members_and_omits_scores() {
+        let db = with_set();
+
+        let (cursor, items) = zscan(&db, &["ranking", "0", "MATCH", "member:2?", "COUNT", "100", "NOSCORES"]);
+
+        assert_eq!("0", cursor);
+        assert_eq!(5, items.len());
+    }
+}
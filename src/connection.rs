@@ -1,4 +1,5 @@
 use std::io::Cursor;
+use std::net::SocketAddr;
 use bytes::{Buf, BytesMut};
 use tokio::io;
 
@@ -37,6 +38,31 @@ impl Connection {
         }
     }
 
+    /// Reads a payload sent like a bulk string but without the trailing CRLF, the way
+    /// masters send their snapshots to replicas.
+    pub(crate) async fn read_payload(&mut self) -> Result<Option<Vec<u8>>> {
+        loop {
+            if let Some(end) = self.buffer.windows(2).position(|window| window == b"\r\n") {
+                if self.buffer[0] != b'$' {
+                    return Err("protocol error; expected a payload".into());
+                }
+                let length = atoi::atoi::<usize>(&self.buffer[1..end]).ok_or("protocol error; invalid payload length")?;
+                if self.buffer.len() >= end + 2 + length {
+                    self.buffer.advance(end + 2);
+                    return Ok(Some(self.buffer.split_to(length).to_vec()));
+                }
+                self.buffer.reserve(end + 2 + length - self.buffer.len());
+            }
+
+            if 0 == self.stream.read_buf(&mut self.buffer).await? {
+                return match self.buffer.is_empty() {
+                    true => Ok(None),
+                    false => Err("connection reset by peer".into()),
+                };
+            }
+        }
+    }
+
     fn parse_frame(&mut self) -> Result<Option<Frame>> {
         use crate::frame::Error;
 
@@ -46,13 +72,23 @@ impl Connection {
             Ok(frame) => {
                 self.buffer.advance(buf.position() as usize);
 
-                return Ok(Some(frame));
+                Ok(Some(frame))
             }
             Err(Error::Incomplete) => Ok(None),
             Err(Error::Other(v)) => Err(v.into()),
         }
     }
 
+    /// Writes bytes which are already encoded, like the replication stream.
+    pub(crate) async fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.stream.write_all(bytes).await?;
+        self.stream.flush().await
+    }
+
+    pub(crate) fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.stream.get_ref().peer_addr()
+    }
+
     pub async fn write_frame(&mut self, frame: Frame) -> io::Result<()> {
         let bytes: Vec<u8> = frame.into();
         let slice = bytes.as_slice();
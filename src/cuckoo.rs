@@ -0,0 +1,184 @@
+use crate::hyperloglog::murmur_hash_64a;
+use crate::rdb::{Corrupt, ModuleWriter, Reader};
+
+pub(crate) const DEFAULT_CAPACITY: u64 = 1024;
+const BUCKET_SIZE: usize = 2;
+const MAX_ITERATIONS: usize = 20;
+const SEED: u64 = 0x5bd1_e995;
+
+/// Cuckoo filter with 8 bit fingerprints. When an item cannot be placed after
+/// `MAX_ITERATIONS` relocations a new filter of the same size is stacked on top.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CuckooFilter {
+    layers: Vec<Layer>,
+    victim: u64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Layer {
+    buckets: Vec<[u8; BUCKET_SIZE]>,
+}
+
+impl Default for CuckooFilter {
+    fn default() -> Self {
+        CuckooFilter::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl CuckooFilter {
+    pub(crate) fn new(capacity: u64) -> Self {
+        let buckets = (capacity.div_ceil(BUCKET_SIZE as u64)).next_power_of_two().max(1);
+        CuckooFilter { layers: vec![Layer::new(buckets as usize)], victim: 0 }
+    }
+
+    /// Approximate number of bytes taken by the filter.
+    pub(crate) fn memory_usage(&self) -> usize {
+        self.layers.iter().map(|layer| layer.buckets.len() * BUCKET_SIZE + std::mem::size_of::<Layer>()).sum()
+    }
+
+    /// Adds the item, duplicates are stored again just like Redis does.
+    pub(crate) fn add(&mut self, item: &[u8]) {
+        let (fingerprint, index) = fingerprint(item);
+        let mut homeless = fingerprint;
+
+        let last = self.layers.last_mut().unwrap();
+        let buckets = last.buckets.len();
+        let index = index & (buckets - 1);
+        let mut index = match last.insert(homeless, index) || last.insert(homeless, alternate(index, homeless, buckets)) {
+            true => return,
+            false => index,
+        };
+
+        // Kick a pseudo randomly chosen fingerprint to its alternate bucket until one finds a free slot.
+        for _ in 0..MAX_ITERATIONS {
+            self.victim = self.victim.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let slot = (self.victim >> 63) as usize;
+            std::mem::swap(&mut homeless, &mut last.buckets[index][slot]);
+            index = alternate(index, homeless, buckets);
+            if last.insert(homeless, index) {
+                return;
+            }
+        }
+
+        let mut layer = Layer::new(buckets);
+        layer.insert(homeless, index);
+        self.layers.push(layer);
+    }
+
+    pub(crate) fn contains(&self, item: &[u8]) -> bool {
+        let (fingerprint, index) = fingerprint(item);
+        self.layers.iter().any(|layer| layer.find(fingerprint, index).is_some())
+    }
+
+    pub(crate) fn save(&self, writer: &mut ModuleWriter) {
+        writer.uint(self.victim);
+        writer.uint(self.layers.len() as u64);
+        for layer in &self.layers {
+            writer.string(layer.buckets.as_flattened());
+        }
+    }
+
+    pub(crate) fn load(reader: &mut Reader) -> Result<Self, Corrupt> {
+        let victim = reader.module_uint()?;
+        let layers = (0..reader.module_count()?)
+            .map(|_| {
+                let slots = reader.module_string()?;
+                let buckets = slots.len() / BUCKET_SIZE;
+                if !slots.len().is_multiple_of(BUCKET_SIZE) || !buckets.is_power_of_two() {
+                    return Err(Corrupt);
+                }
+                Ok(Layer { buckets: slots.chunks(BUCKET_SIZE).map(|bucket| bucket.try_into().unwrap()).collect() })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        match layers.is_empty() {
+            true => Err(Corrupt),
+            false => Ok(CuckooFilter { layers, victim }),
+        }
+    }
+
+    /// Removes a single copy of the item, returning false when it was not found.
+    pub(crate) fn remove(&mut self, item: &[u8]) -> bool {
+        let (fingerprint, index) = fingerprint(item);
+        for layer in self.layers.iter_mut().rev() {
+            if let Some((bucket, slot)) = layer.find(fingerprint, index) {
+                layer.buckets[bucket][slot] = 0;
+                return true;
+            }
+        }
+        false
+    }
+}
+
+impl Layer {
+    fn new(buckets: usize) -> Self {
+        Layer { buckets: vec![[0; BUCKET_SIZE]; buckets] }
+    }
+
+    fn insert(&mut self, fingerprint: u8, index: usize) -> bool {
+        match self.buckets[index].iter_mut().find(|slot| **slot == 0) {
+            Some(slot) => {
+                *slot = fingerprint;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn find(&self, fingerprint: u8, index: usize) -> Option<(usize, usize)> {
+        let index = index & (self.buckets.len() - 1);
+        [index, alternate(index, fingerprint, self.buckets.len())].into_iter()
+            .find_map(|bucket| self.buckets[bucket].iter().position(|slot| *slot == fingerprint).map(|slot| (bucket, slot)))
+    }
+}
+
+/// Returns the item's non zero fingerprint and its primary bucket, reduced by each layer.
+fn fingerprint(item: &[u8]) -> (u8, usize) {
+    let hash = murmur_hash_64a(item, SEED);
+    ((((hash >> 32) % 255) + 1) as u8, hash as u32 as usize)
+}
+
+/// The alternate bucket of a fingerprint, going back and forth between the two candidates.
+fn alternate(index: usize, fingerprint: u8, buckets: usize) -> usize {
+    (index ^ (fingerprint as usize).wrapping_mul(0x5bd1_e995)) & (buckets - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_finds_added_items() {
+        let mut filter = CuckooFilter::default();
+
+        filter.add(b"a");
+
+        assert!(filter.contains(b"a"));
+        assert!(!filter.contains(b"b"));
+    }
+
+    #[test]
+    fn it_removes_single_copy_of_item() {
+        let mut filter = CuckooFilter::default();
+        filter.add(b"a");
+        filter.add(b"a");
+
+        assert!(filter.remove(b"a"));
+        assert!(filter.contains(b"a"));
+        assert!(filter.remove(b"a"));
+        assert!(!filter.contains(b"a"));
+        assert!(!filter.remove(b"a"));
+    }
+
+    #[test]
+    fn it_grows_when_full() {
+        let mut filter = CuckooFilter::new(64);
+
+        for i in 0..500 {
+            filter.add(format!("item:{}", i).as_bytes());
+        }
+
+        assert!(filter.layers.len() > 1);
+        assert!((0..500).all(|i| filter.contains(format!("item:{}", i).as_bytes())));
+    }
+}
@@ -1,9 +1,1049 @@
-use std::sync::{Arc, Mutex};
-use std::collections::HashMap;
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{mpsc, Arc, LockResult, Mutex, MutexGuard, PoisonError};
+use std::thread;
+use std::time::SystemTime;
+
 use bytes::Bytes;
 
-pub type Database = Arc<Mutex<HashMap<String, Bytes>>>;
+use crate::bloom::BloomFilter;
+use crate::cuckoo::CuckooFilter;
+use crate::eviction::{clock, lfu_decay, lfu_increment, random, EvictionPolicy, Memory, LFU_INIT};
+use crate::frame::Frame;
+use crate::hyperloglog::murmur_hash_64a;
+use crate::json;
+use crate::sorted_set::SortedSet;
+use crate::time_series::TimeSeries;
+
+/// Number of logical databases a server starts with, mirroring Redis' `databases` setting.
+pub const DEFAULT_DATABASES: usize = 16;
+
+/// Number of shards each logical database is split into, a power of two so a
+/// shard covers a contiguous range of the SCAN order.
+pub const SHARDS: usize = 16;
+
+const SHARD_BITS: u32 = SHARDS.trailing_zeros();
+
+/// Handle to the logical databases of a server with one of them selected. Every
+/// database is hash-partitioned into [`SHARDS`] independently locked shards so
+/// commands touching different keys do not serialize. Clones share the storage.
+#[derive(Clone)]
+pub struct Database {
+    /// Shards of all databases, those of database `n` start at `n * SHARDS`.
+    shards: Arc<[Mutex<Shard>]>,
+    memory: Arc<Memory>,
+    index: usize,
+}
 
 pub fn new_db() -> Database {
-    Arc::new(Mutex::new(HashMap::<String, Bytes>::new()))
-}
\ No newline at end of file
+    Database::new(DEFAULT_DATABASES)
+}
+
+impl Database {
+    pub fn new(count: usize) -> Self {
+        Database::with_memory(count, Arc::new(Memory::default()))
+    }
+
+    fn with_memory(count: usize, memory: Arc<Memory>) -> Self {
+        let shards = (0..count.max(1) * SHARDS).map(|_| Mutex::new(Shard::new(memory.clone()))).collect();
+        Database { shards, memory, index: 0 }
+    }
+
+    /// Returns empty databases, as many as these, accounting memory and evicting along
+    /// with them. Worker threads hold their shards in such databases of their own.
+    pub(crate) fn sharing_memory(&self) -> Database {
+        Database::with_memory(self.count(), self.memory.clone())
+    }
+
+    /// Returns empty databases, as many as these and encoding collections the same way,
+    /// whose memory is accounted apart. Commands spanning several workers run on copies
+    /// of their keys put in such databases.
+    pub(crate) fn scratch(&self) -> Database {
+        let memory = Memory::default();
+        let limits = self.memory.compact_limits();
+        memory.set_compact_entries(limits.entries);
+        memory.set_compact_value(limits.value);
+        Database::with_memory(self.count(), Arc::new(memory)).select(self.index).unwrap()
+    }
+
+    pub(crate) fn memory(&self) -> &Memory {
+        &self.memory
+    }
+
+    /// Evicts keys of any database until the memory used fits the limit again, recording
+    /// them with the index of their database. Returns false when the policy forbids
+    /// evicting or no key qualifies for eviction.
+    pub fn free_memory(&self, evicted: &mut Vec<(usize, Bytes)>) -> bool {
+        while self.memory.over_limit() {
+            let policy = self.memory.policy();
+            match policy {
+                EvictionPolicy::NoEviction => return false,
+                policy => match self.evict(policy) {
+                    Some(key) => evicted.push(key),
+                    None => return false,
+                },
+            }
+        }
+
+        true
+    }
+
+    /// Evicts the sample qualifying best. Only one shard is locked at a time, so the keys
+    /// are sampled again when the best one was deleted, accessed or replaced before it got evicted.
+    fn evict(&self, policy: EvictionPolicy) -> Option<(usize, Bytes)> {
+        loop {
+            let best = self.eviction_samples(policy, self.memory.samples()).into_iter().max_by_key(|sample| sample.score)?;
+            if let Some(evicted) = self.evict_sample(best) {
+                return Some(evicted);
+            }
+        }
+    }
+
+    /// Samples up to `count` keys qualifying for the policy from the shards in turn,
+    /// starting from a random one.
+    pub(crate) fn eviction_samples(&self, policy: EvictionPolicy, count: usize) -> Vec<Sample> {
+        let start = random() as usize;
+        let mut samples = vec![];
+
+        for offset in 0..self.shards.len() {
+            if samples.len() >= count {
+                break;
+            }
+            let index = start.wrapping_add(offset) % self.shards.len();
+            let shard = self.shards[index].lock().unwrap();
+            samples.extend(shard.eviction_samples(policy, count - samples.len()).into_iter()
+                .map(|(score, key, stamp)| Sample { score, shard: index, key, stamp }));
+        }
+
+        samples
+    }
+
+    /// Removes the sampled key unless it changed since, returning it with the index of its database.
+    pub(crate) fn evict_sample(&self, sample: Sample) -> Option<(usize, Bytes)> {
+        self.shards[sample.shard].lock().unwrap().take_sampled(&sample.key, sample.stamp)?;
+        Some((sample.shard / SHARDS, sample.key))
+    }
+
+    /// Locks every shard of the selected database, for commands working on the whole keyspace.
+    pub fn lock(&self) -> LockResult<Keyspace<'_>> {
+        self.lock_shards(self.index, 0..SHARDS)
+    }
+
+    /// Locks only the shards holding the keys. Shards are always acquired in
+    /// ascending order, which keeps concurrent multi-key commands from deadlocking.
+    pub fn lock_keys<K: AsRef<[u8]>>(&self, keys: impl IntoIterator<Item = K>) -> LockResult<Keyspace<'_>> {
+        let shards: BTreeSet<usize> = keys.into_iter().map(|key| shard_of(key.as_ref())).collect();
+        self.lock_shards(self.index, shards)
+    }
+
+    /// Returns a handle with the database under the index selected, if it exists.
+    pub fn select(&self, index: usize) -> Option<Database> {
+        (index < self.count()).then(|| Database { shards: self.shards.clone(), memory: self.memory.clone(), index })
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Number of logical databases.
+    pub fn count(&self) -> usize {
+        self.shards.len() / SHARDS
+    }
+
+    /// Locks two distinct databases, always acquiring the lower index first so
+    /// concurrent callers cannot deadlock.
+    pub fn lock_pair(&self, first: usize, second: usize) -> (Keyspace<'_>, Keyspace<'_>) {
+        assert_ne!(first, second, "a keyspace cannot be locked twice");
+        if first < second {
+            let first = self.lock_shards(first, 0..SHARDS).unwrap();
+            (first, self.lock_shards(second, 0..SHARDS).unwrap())
+        } else {
+            let second = self.lock_shards(second, 0..SHARDS).unwrap();
+            (self.lock_shards(first, 0..SHARDS).unwrap(), second)
+        }
+    }
+
+    /// Locks every database in index order.
+    pub fn lock_all(&self) -> Vec<Keyspace<'_>> {
+        (0..self.count()).map(|index| self.lock_shards(index, 0..SHARDS).unwrap()).collect()
+    }
+
+    fn lock_shards(&self, database: usize, shards: impl IntoIterator<Item = usize>) -> LockResult<Keyspace<'_>> {
+        let mut poisoned = false;
+        let shards = shards.into_iter()
+            .map(|shard| {
+                let guard = self.shards[database * SHARDS + shard].lock().unwrap_or_else(|err| {
+                    poisoned = true;
+                    err.into_inner()
+                });
+                (shard, guard)
+            })
+            .collect();
+
+        let keyspace = Keyspace { shards, modified: vec![] };
+        match poisoned {
+            true => Err(PoisonError::new(keyspace)),
+            false => Ok(keyspace),
+        }
+    }
+}
+
+/// Shard of a database holding the key, taken from the top bits of its SCAN hash.
+pub(crate) fn shard_of(key: &[u8]) -> usize {
+    (scan_hash(key) >> (u64::BITS - SHARD_BITS)) as usize
+}
+
+/// Maximum length of a string value, mirroring Redis' `proto-max-bulk-len`.
+pub const MAX_STRING_LENGTH: usize = 512 * 1024 * 1024;
+
+const SCAN_SEED: u64 = 0x5ca7_0000;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    String(Bytes),
+    SortedSet(SortedSet),
+    Json(serde_json::Value),
+    Bloom(BloomFilter),
+    Cuckoo(CuckooFilter),
+    TimeSeries(TimeSeries),
+}
+
+impl From<Bytes> for Value {
+    fn from(value: Bytes) -> Self {
+        Value::String(value)
+    }
+}
+
+impl From<SortedSet> for Value {
+    fn from(value: SortedSet) -> Self {
+        Value::SortedSet(value)
+    }
+}
+
+impl From<serde_json::Value> for Value {
+    fn from(value: serde_json::Value) -> Self {
+        Value::Json(value)
+    }
+}
+
+impl From<BloomFilter> for Value {
+    fn from(value: BloomFilter) -> Self {
+        Value::Bloom(value)
+    }
+}
+
+impl From<CuckooFilter> for Value {
+    fn from(value: CuckooFilter) -> Self {
+        Value::Cuckoo(value)
+    }
+}
+
+impl From<TimeSeries> for Value {
+    fn from(value: TimeSeries) -> Self {
+        Value::TimeSeries(value)
+    }
+}
+
+impl Value {
+    /// Approximate number of bytes taken by the value.
+    pub fn memory_usage(&self) -> usize {
+        std::mem::size_of::<Value>() + match self {
+            Value::String(value) => value.len(),
+            Value::SortedSet(value) => value.memory_usage(),
+            Value::Json(value) => json::memory_usage(value),
+            Value::Bloom(value) => value.memory_usage(),
+            Value::Cuckoo(value) => value.memory_usage(),
+            Value::TimeSeries(value) => value.memory_usage(),
+        }
+    }
+
+    /// Name of the type as reported by TYPE.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::String(_) => "string",
+            Value::SortedSet(_) => "zset",
+            Value::Json(_) => "ReJSON-RL",
+            Value::Bloom(_) => "MBbloom--",
+            Value::Cuckoo(_) => "MBbloomCF",
+            Value::TimeSeries(_) => "TSDB-TYPE",
+        }
+    }
+
+    /// Internal representation as reported by OBJECT ENCODING, module types report `raw` like in Redis.
+    pub fn encoding(&self) -> &'static str {
+        match self {
+            Value::String(value) if is_integer(value) => "int",
+            Value::String(value) if value.len() <= EMBEDDED_STRING_LENGTH => "embstr",
+            Value::SortedSet(value) if value.is_compact() => "listpack",
+            Value::SortedSet(_) => "skiplist",
+            _ => "raw",
+        }
+    }
+}
+
+/// Longest string Redis allocates together with its object header.
+const EMBEDDED_STRING_LENGTH: usize = 44;
+
+/// Whether the string is an integer in canonical form, which Redis stores as a number.
+fn is_integer(value: &[u8]) -> bool {
+    let integer = std::str::from_utf8(value).ok().and_then(|value| value.parse::<i64>().ok());
+    integer.is_some_and(|integer| integer.to_string().as_bytes() == value)
+}
+
+thread_local! {
+    /// Thread freeing the values dropped in the background by this thread, for threads
+    /// outside the runtime like the workers.
+    static FREER: RefCell<Option<mpsc::Sender<Box<dyn Send>>>> = const { RefCell::new(None) };
+}
+
+/// Drops the values on a blocking task so large collections are not freed while the keyspace
+/// is locked, or on the freeing thread of threads outside the runtime, see [`spawn_freer`].
+pub fn drop_in_background<T: Send + 'static>(values: T) {
+    if let Ok(handle) = tokio::runtime::Handle::try_current() {
+        handle.spawn_blocking(move || drop(values));
+        return;
+    }
+    FREER.with_borrow(|freer| match freer {
+        // Values the freeing thread cannot take anymore are dropped here.
+        Some(freer) => drop(freer.send(Box::new(values))),
+        None => drop(values),
+    });
+}
+
+/// Starts a thread freeing the values the calling thread drops in the background.
+pub(crate) fn spawn_freer(name: String) {
+    let (sender, receiver) = mpsc::channel::<Box<dyn Send>>();
+    thread::Builder::new()
+        .name(name)
+        .spawn(move || receiver.into_iter().for_each(drop))
+        .expect("failed to spawn a freeing thread");
+    FREER.set(Some(sender));
+}
+
+/// Position of the key, or of a collection member, in the SCAN order.
+pub fn scan_hash(key: &[u8]) -> u64 {
+    murmur_hash_64a(key, SCAN_SEED)
+}
+
+/// Returned when a command is run against a key holding a different type of value.
+#[derive(Debug, PartialEq)]
+pub struct WrongType;
+
+impl From<WrongType> for Frame {
+    fn from(_: WrongType) -> Self {
+        Frame::SimpleError("WRONGTYPE Operation against a key holding the wrong kind of value".to_string())
+    }
+}
+
+/// Bytes a key takes besides its name and value: the map slot, the SCAN order
+/// node and the access metadata.
+const ENTRY_OVERHEAD: usize = 80;
+
+/// One hash partition of a database.
+struct Shard {
+    entries: HashMap<Bytes, Entry>,
+    /// Keys ordered by a fixed hash, SCAN cursors are positions in this order so
+    /// they stay valid however the map grows or shrinks between calls.
+    scan_order: BTreeSet<(u64, Bytes)>,
+    memory: Arc<Memory>,
+}
+
+struct Entry {
+    value: Value,
+    expires_at: Option<SystemTime>,
+    /// Approximate bytes taken by the key and the value, as accounted in [`Memory`].
+    size: usize,
+    /// Second of the last access, for LRU eviction.
+    accessed: Cell<u32>,
+    /// Logarithmic access frequency, for LFU eviction.
+    frequency: Cell<u8>,
+}
+
+/// Access metadata and expiration of an entry, telling whether it changed since it was sampled.
+type Stamp = (u32, u8, Option<SystemTime>);
+
+/// Key sampled for eviction, see [`Database::eviction_samples`].
+pub(crate) struct Sample {
+    /// How strongly the key qualifies for eviction, higher goes first.
+    pub(crate) score: u64,
+    /// Position of its shard among the shards of all databases.
+    shard: usize,
+    key: Bytes,
+    stamp: Stamp,
+}
+
+impl Entry {
+    fn new(key: &[u8], value: Value, expires_at: Option<SystemTime>) -> Self {
+        let size = key.len() + value.memory_usage() + ENTRY_OVERHEAD;
+        Entry { value, expires_at, size, accessed: Cell::new(clock()), frequency: Cell::new(LFU_INIT) }
+    }
+
+    fn is_expired(&self) -> bool {
+        matches!(self.expires_at, Some(at) if at <= SystemTime::now())
+    }
+
+    fn idle_seconds(&self) -> u32 {
+        clock().saturating_sub(self.accessed.get())
+    }
+
+    fn stamp(&self) -> Stamp {
+        (self.accessed.get(), self.frequency.get(), self.expires_at)
+    }
+
+    fn touch(&self) {
+        let frequency = lfu_decay(self.frequency.get(), self.idle_seconds());
+        self.frequency.set(lfu_increment(frequency));
+        self.accessed.set(clock());
+    }
+
+    /// How strongly the entry qualifies for eviction under the policy, higher goes first.
+    fn eviction_score(&self, policy: EvictionPolicy) -> u64 {
+        if self.is_expired() {
+            return u64::MAX;
+        }
+        match policy {
+            EvictionPolicy::AllKeysLru | EvictionPolicy::VolatileLru => self.idle_seconds() as u64,
+            EvictionPolicy::AllKeysLfu | EvictionPolicy::VolatileLfu => {
+                (u8::MAX - lfu_decay(self.frequency.get(), self.idle_seconds())) as u64
+            }
+            EvictionPolicy::VolatileTtl => {
+                let expires_at = self.expires_at.and_then(|at| at.duration_since(SystemTime::UNIX_EPOCH).ok());
+                u64::MAX - 1 - expires_at.map_or(0, |at| at.as_millis() as u64)
+            }
+            EvictionPolicy::AllKeysRandom | EvictionPolicy::VolatileRandom | EvictionPolicy::NoEviction => 0,
+        }
+    }
+}
+
+impl Shard {
+    fn new(memory: Arc<Memory>) -> Self {
+        Shard { entries: HashMap::new(), scan_order: BTreeSet::new(), memory }
+    }
+
+    /// Looks the key up without counting it as an access.
+    fn peek(&self, key: &[u8]) -> Option<&Entry> {
+        self.entries.get(key).filter(|entry| !entry.is_expired())
+    }
+
+    fn get(&self, key: &[u8]) -> Option<&Entry> {
+        let entry = self.peek(key)?;
+        entry.touch();
+        Some(entry)
+    }
+
+    fn get_mut(&mut self, key: &[u8]) -> Option<&mut Entry> {
+        self.remove_if_expired(key);
+        let entry = self.entries.get_mut(key)?;
+        entry.touch();
+        Some(entry)
+    }
+
+    /// Accounts for the new size of a value modified in place.
+    fn resize(&mut self, key: &[u8]) {
+        if let Some(entry) = self.entries.get_mut(key) {
+            let size = key.len() + entry.value.memory_usage() + ENTRY_OVERHEAD;
+            self.memory.shrink(entry.size);
+            self.memory.grow(size);
+            entry.size = size;
+        }
+    }
+
+    /// Samples up to `count` keys qualifying for the policy, starting at a random
+    /// position, together with their eviction scores and stamps.
+    fn eviction_samples(&self, policy: EvictionPolicy, count: usize) -> Vec<(u64, Bytes, Stamp)> {
+        let Some((first, _)) = self.scan_order.first() else {
+            return vec![];
+        };
+        let start = (first & !(u64::MAX >> SHARD_BITS)) | (random() >> SHARD_BITS);
+
+        self.scan_order.range((start, Bytes::new())..).chain(self.scan_order.iter())
+            .take(self.scan_order.len())
+            .filter_map(|(_, key)| Some((key, self.entries.get(key)?)))
+            .filter(|(_, entry)| !policy.volatile_only() || entry.expires_at.is_some())
+            .take(count)
+            .map(|(key, entry)| (entry.eviction_score(policy), key.clone(), entry.stamp()))
+            .collect()
+    }
+
+    /// Removes the entry of the key unless it changed since it was sampled with the stamp.
+    fn take_sampled(&mut self, key: &[u8], stamp: Stamp) -> Option<Entry> {
+        match self.entries.get(key)?.stamp() == stamp {
+            true => self.take(key),
+            false => None,
+        }
+    }
+
+    fn remove_if_expired(&mut self, key: &[u8]) {
+        if self.entries.get(key).is_some_and(Entry::is_expired) {
+            self.take(key);
+        }
+    }
+
+    fn put(&mut self, key: Bytes, entry: Entry) -> Option<Entry> {
+        self.memory.grow(entry.size);
+        let order = (scan_hash(&key), key);
+        let previous = self.entries.insert(order.1.clone(), entry);
+        match &previous {
+            Some(previous) => self.memory.shrink(previous.size),
+            None => {
+                self.scan_order.insert(order);
+            }
+        }
+        previous
+    }
+
+    fn take(&mut self, key: &[u8]) -> Option<Entry> {
+        let (key, entry) = self.entries.remove_entry(key)?;
+        self.scan_order.remove(&(scan_hash(&key), key));
+        self.memory.shrink(entry.size);
+        Some(entry)
+    }
+
+    fn clear(&mut self) -> impl Iterator<Item = Value> {
+        self.scan_order.clear();
+        self.memory.shrink(self.entries.values().map(|entry| entry.size).sum());
+        std::mem::take(&mut self.entries).into_values().map(|entry| entry.value)
+    }
+}
+
+/// Locked view over some or all shards of a database, see [`Database::lock`] and
+/// [`Database::lock_keys`]. Accessing a key whose shard is not locked is a bug and panics.
+pub struct Keyspace<'a> {
+    /// Locked shards with their position in the database, in ascending order.
+    shards: Vec<(usize, MutexGuard<'a, Shard>)>,
+    /// Keys handed out mutably, their size is accounted again once the view is dropped.
+    modified: Vec<Bytes>,
+}
+
+impl Keyspace<'_> {
+    /// Returns the string stored under the key.
+    pub fn get(&self, key: impl AsRef<[u8]>) -> Result<Option<&Bytes>, WrongType> {
+        match self.get_value(key) {
+            None => Ok(None),
+            Some(Value::String(value)) => Ok(Some(value)),
+            Some(_) => Err(WrongType),
+        }
+    }
+
+    /// Returns a mutable reference to a live string, preserving its expiration.
+    pub fn get_mut(&mut self, key: impl AsRef<[u8]>) -> Result<Option<&mut Bytes>, WrongType> {
+        match self.get_value_mut(key) {
+            None => Ok(None),
+            Some(Value::String(value)) => Ok(Some(value)),
+            Some(_) => Err(WrongType),
+        }
+    }
+
+    pub fn get_sorted_set(&self, key: impl AsRef<[u8]>) -> Result<Option<&SortedSet>, WrongType> {
+        match self.get_value(key) {
+            None => Ok(None),
+            Some(Value::SortedSet(value)) => Ok(Some(value)),
+            Some(_) => Err(WrongType),
+        }
+    }
+
+    pub fn get_sorted_set_mut(&mut self, key: impl AsRef<[u8]>) -> Result<Option<&mut SortedSet>, WrongType> {
+        match self.get_value_mut(key) {
+            None => Ok(None),
+            Some(Value::SortedSet(value)) => Ok(Some(value)),
+            Some(_) => Err(WrongType),
+        }
+    }
+
+    pub fn get_json(&self, key: impl AsRef<[u8]>) -> Result<Option<&serde_json::Value>, WrongType> {
+        match self.get_value(key) {
+            None => Ok(None),
+            Some(Value::Json(value)) => Ok(Some(value)),
+            Some(_) => Err(WrongType),
+        }
+    }
+
+    pub fn get_json_mut(&mut self, key: impl AsRef<[u8]>) -> Result<Option<&mut serde_json::Value>, WrongType> {
+        match self.get_value_mut(key) {
+            None => Ok(None),
+            Some(Value::Json(value)) => Ok(Some(value)),
+            Some(_) => Err(WrongType),
+        }
+    }
+
+    pub fn get_bloom(&self, key: impl AsRef<[u8]>) -> Result<Option<&BloomFilter>, WrongType> {
+        match self.get_value(key) {
+            None => Ok(None),
+            Some(Value::Bloom(value)) => Ok(Some(value)),
+            Some(_) => Err(WrongType),
+        }
+    }
+
+    pub fn get_bloom_mut(&mut self, key: impl AsRef<[u8]>) -> Result<Option<&mut BloomFilter>, WrongType> {
+        match self.get_value_mut(key) {
+            None => Ok(None),
+            Some(Value::Bloom(value)) => Ok(Some(value)),
+            Some(_) => Err(WrongType),
+        }
+    }
+
+    pub fn get_cuckoo(&self, key: impl AsRef<[u8]>) -> Result<Option<&CuckooFilter>, WrongType> {
+        match self.get_value(key) {
+            None => Ok(None),
+            Some(Value::Cuckoo(value)) => Ok(Some(value)),
+            Some(_) => Err(WrongType),
+        }
+    }
+
+    pub fn get_cuckoo_mut(&mut self, key: impl AsRef<[u8]>) -> Result<Option<&mut CuckooFilter>, WrongType> {
+        match self.get_value_mut(key) {
+            None => Ok(None),
+            Some(Value::Cuckoo(value)) => Ok(Some(value)),
+            Some(_) => Err(WrongType),
+        }
+    }
+
+    pub fn get_time_series(&self, key: impl AsRef<[u8]>) -> Result<Option<&TimeSeries>, WrongType> {
+        match self.get_value(key) {
+            None => Ok(None),
+            Some(Value::TimeSeries(value)) => Ok(Some(value)),
+            Some(_) => Err(WrongType),
+        }
+    }
+
+    pub fn get_time_series_mut(&mut self, key: impl AsRef<[u8]>) -> Result<Option<&mut TimeSeries>, WrongType> {
+        match self.get_value_mut(key) {
+            None => Ok(None),
+            Some(Value::TimeSeries(value)) => Ok(Some(value)),
+            Some(_) => Err(WrongType),
+        }
+    }
+
+    pub fn get_value(&self, key: impl AsRef<[u8]>) -> Option<&Value> {
+        let key = key.as_ref();
+        self.shard(key).get(key).map(|entry| &entry.value)
+    }
+
+    pub fn get_value_mut(&mut self, key: impl AsRef<[u8]>) -> Option<&mut Value> {
+        let key = key.as_ref();
+        self.modified.push(Bytes::copy_from_slice(key));
+        self.shard_mut(key).get_mut(key).map(|entry| &mut entry.value)
+    }
+
+    pub fn contains_key(&self, key: impl AsRef<[u8]>) -> bool {
+        self.get_value(key).is_some()
+    }
+
+    /// Stores the value discarding any expiration previously set on the key.
+    pub fn insert(&mut self, key: impl Into<Bytes>, value: impl Into<Value>) -> Option<Value> {
+        self.insert_with_expiration(key, value, None)
+    }
+
+    pub fn insert_with_expiration(&mut self, key: impl Into<Bytes>, value: impl Into<Value>, expires_at: Option<SystemTime>) -> Option<Value> {
+        let key = key.into();
+        let shard = self.shard_mut(&key);
+        shard.remove_if_expired(&key);
+        let entry = Entry::new(&key, value.into(), expires_at);
+        shard.put(key, entry).map(|entry| entry.value)
+    }
+
+    /// Stores the value preserving the expiration of an existing key.
+    pub fn update(&mut self, key: impl AsRef<[u8]>, value: impl Into<Value>) {
+        let key = key.as_ref();
+        match self.get_value_mut(key) {
+            Some(stored) => *stored = value.into(),
+            None => {
+                self.insert(Bytes::copy_from_slice(key), value);
+            }
+        }
+    }
+
+    pub fn remove(&mut self, key: impl AsRef<[u8]>) -> Option<Value> {
+        let key = key.as_ref();
+        let shard = self.shard_mut(key);
+        shard.remove_if_expired(key);
+        shard.take(key).map(|entry| entry.value)
+    }
+
+    /// Moves the value with its expiration to another key, returns false when the source does not exist.
+    pub fn rename(&mut self, from: impl AsRef<[u8]>, to: impl AsRef<[u8]>) -> bool {
+        let from = from.as_ref();
+        let shard = self.shard_mut(from);
+        shard.remove_if_expired(from);
+        match shard.take(from) {
+            Some(entry) => {
+                let to = Bytes::copy_from_slice(to.as_ref());
+                self.shard_mut(&to).put(to, entry);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Number of keys which have not expired yet.
+    pub fn len(&self) -> usize {
+        self.entries().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &Bytes> {
+        self.entries().map(|(key, _)| key)
+    }
+
+    /// Removes all keys, handing back their values so the caller decides where to drop them.
+    pub fn clear(&mut self) -> Vec<Value> {
+        self.shards.iter_mut().flat_map(|(_, shard)| shard.clear()).collect()
+    }
+
+    /// Exchanges the contents of two fully locked databases.
+    pub fn swap(&mut self, other: &mut Keyspace) {
+        for ((_, shard), (_, other)) in self.shards.iter_mut().zip(other.shards.iter_mut()) {
+            std::mem::swap(&mut **shard, &mut **other);
+        }
+    }
+
+    /// Visits up to `count` keys starting at the cursor, returning the live ones and the
+    /// cursor to continue from, zero once the iteration is complete. Keys sharing a hash
+    /// are always returned together so a cursor never splits them. Shards cover
+    /// consecutive ranges of hashes, so walking them in order visits the whole database.
+    pub fn scan(&self, cursor: u64, count: usize) -> (u64, Vec<&Bytes>) {
+        let mut keys = vec![];
+        let mut visited = 0;
+        let mut last = None;
+
+        for (_, shard) in &self.shards {
+            for (hash, key) in shard.scan_order.range((cursor, Bytes::new())..) {
+                if visited >= count && last != Some(*hash) {
+                    return (*hash, keys);
+                }
+                visited += 1;
+                last = Some(*hash);
+                if shard.peek(key).is_some() {
+                    keys.push(key);
+                }
+            }
+        }
+
+        (0, keys)
+    }
+
+    /// Iterates the keys which have not expired yet with their values and expirations,
+    /// without counting it as an access.
+    pub fn iter(&self) -> impl Iterator<Item = (&Bytes, &Value, Option<SystemTime>)> {
+        self.entries().map(|(key, entry)| (key, &entry.value, entry.expires_at))
+    }
+
+    /// Number of keys with an expiration which have not expired yet.
+    pub fn expires_len(&self) -> usize {
+        self.entries().filter(|(_, entry)| entry.expires_at.is_some()).count()
+    }
+
+    /// Encoding of the value, looking does not count as an access.
+    pub fn encoding(&self, key: impl AsRef<[u8]>) -> Option<&'static str> {
+        let key = key.as_ref();
+        self.shard(key).peek(key).map(|entry| entry.value.encoding())
+    }
+
+    /// Seconds since the key was last accessed, looking does not count as an access.
+    pub fn idle_time(&self, key: impl AsRef<[u8]>) -> Option<u32> {
+        let key = key.as_ref();
+        self.shard(key).peek(key).map(|entry| entry.idle_seconds())
+    }
+
+    /// Logarithmic access counter of the key, decayed by the time it has been idle,
+    /// looking does not count as an access.
+    pub fn frequency(&self, key: impl AsRef<[u8]>) -> Option<u8> {
+        let key = key.as_ref();
+        self.shard(key).peek(key).map(|entry| lfu_decay(entry.frequency.get(), entry.idle_seconds()))
+    }
+
+    /// Overrides the access metadata of the key, as RESTORE's IDLETIME and FREQ do.
+    pub fn set_access(&self, key: impl AsRef<[u8]>, idle_seconds: Option<u32>, frequency: Option<u8>) {
+        let key = key.as_ref();
+        if let Some(entry) = self.shard(key).peek(key) {
+            if let Some(idle_seconds) = idle_seconds {
+                entry.accessed.set(clock().saturating_sub(idle_seconds));
+            }
+            if let Some(frequency) = frequency {
+                entry.frequency.set(frequency);
+            }
+        }
+    }
+
+    /// Approximate bytes taken by the key and its value.
+    pub fn memory_usage(&self, key: impl AsRef<[u8]>) -> Option<usize> {
+        let key = key.as_ref();
+        self.shard(key).peek(key).map(|entry| entry.size)
+    }
+
+    pub fn expiration(&self, key: impl AsRef<[u8]>) -> Option<SystemTime> {
+        let key = key.as_ref();
+        self.shard(key).peek(key).and_then(|entry| entry.expires_at)
+    }
+
+    /// Changes the expiration of an existing key, returns false when the key does not exist.
+    pub fn set_expiration(&mut self, key: impl AsRef<[u8]>, expires_at: Option<SystemTime>) -> bool {
+        let key = key.as_ref();
+        match self.shard_mut(key).get_mut(key) {
+            Some(entry) => {
+                entry.expires_at = expires_at;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn entries(&self) -> impl Iterator<Item = (&Bytes, &Entry)> {
+        self.shards.iter()
+            .flat_map(|(_, shard)| shard.entries.iter())
+            .filter(|(_, entry)| !entry.is_expired())
+    }
+
+    fn shard(&self, key: &[u8]) -> &Shard {
+        let index = shard_of(key);
+        self.shards.iter()
+            .find(|(shard, _)| *shard == index)
+            .map(|(_, shard)| &**shard)
+            .expect("the shard holding the key is not locked")
+    }
+
+    fn shard_mut(&mut self, key: &[u8]) -> &mut Shard {
+        let index = shard_of(key);
+        self.shards.iter_mut()
+            .find(|(shard, _)| *shard == index)
+            .map(|(_, shard)| &mut **shard)
+            .expect("the shard holding the key is not locked")
+    }
+}
+
+impl Drop for Keyspace<'_> {
+    fn drop(&mut self) {
+        for key in std::mem::take(&mut self.modified) {
+            self.shard_mut(&key).resize(&key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn it_hides_expired_keys() {
+        let db = Database::new(1);
+        let mut keyspace = db.lock().unwrap();
+        let past = SystemTime::now() - Duration::from_secs(1);
+        keyspace.insert_with_expiration("old".to_string(), Bytes::from("value"), Some(past));
+
+        assert_eq!(Ok(None), keyspace.get("old"));
+        assert_eq!(None, keyspace.remove("old"));
+        assert!(keyspace.shards.iter().all(|(_, shard)| shard.entries.is_empty()));
+    }
+
+    #[test]
+    fn it_keeps_expiration_when_value_is_modified_in_place() {
+        let db = Database::new(1);
+        let mut keyspace = db.lock().unwrap();
+        let future = SystemTime::now() + Duration::from_secs(60);
+        keyspace.insert_with_expiration("key".to_string(), Bytes::from("a"), Some(future));
+
+        *keyspace.get_mut("key").unwrap().unwrap() = Bytes::from("b");
+
+        assert_eq!(Ok(Some(&Bytes::from("b"))), keyspace.get("key"));
+        assert_eq!(Some(future), keyspace.expiration("key"));
+    }
+
+    #[test]
+    fn it_discards_expiration_on_insert() {
+        let db = Database::new(1);
+        let mut keyspace = db.lock().unwrap();
+        let future = SystemTime::now() + Duration::from_secs(60);
+        keyspace.insert_with_expiration("key".to_string(), Bytes::from("a"), Some(future));
+
+        keyspace.insert("key".to_string(), Bytes::from("b"));
+
+        assert_eq!(None, keyspace.expiration("key"));
+    }
+
+    #[test]
+    fn it_renames_keys_with_expiration() {
+        let db = Database::new(1);
+        let mut keyspace = db.lock().unwrap();
+        let future = SystemTime::now() + Duration::from_secs(60);
+        keyspace.insert_with_expiration("a".to_string(), Bytes::from("value"), Some(future));
+
+        assert!(keyspace.rename("a", "b"));
+        assert!(!keyspace.rename("a", "c"));
+
+        assert!(!keyspace.contains_key("a"));
+        assert_eq!(Some(future), keyspace.expiration("b"));
+        assert_eq!(1, keyspace.len());
+    }
+
+    #[test]
+    fn it_scans_every_key_while_keyspace_grows() {
+        let db = Database::new(1);
+        let mut keyspace = db.lock().unwrap();
+        for i in 0..100 {
+            keyspace.insert(format!("key:{}", i), Bytes::from("value"));
+        }
+
+        let mut seen = std::collections::HashSet::new();
+        let mut cursor = 0;
+        let mut added = 0;
+        loop {
+            let (next, keys) = keyspace.scan(cursor, 10);
+            seen.extend(keys.into_iter().cloned());
+            for _ in 0..10 {
+                keyspace.insert(format!("new:{}", added), Bytes::from("value"));
+                added += 1;
+            }
+            cursor = next;
+            if cursor == 0 {
+                break;
+            }
+        }
+
+        assert!((0..100).all(|i| seen.contains(format!("key:{}", i).as_bytes())));
+    }
+
+    #[test]
+    fn it_skips_removed_and_expired_keys_while_scanning() {
+        let db = Database::new(1);
+        let mut keyspace = db.lock().unwrap();
+        keyspace.insert("a".to_string(), Bytes::from("1"));
+        keyspace.insert("b".to_string(), Bytes::from("2"));
+        keyspace.insert_with_expiration("c".to_string(), Bytes::from("3"), Some(SystemTime::now() - Duration::from_secs(1)));
+        keyspace.remove("b");
+
+        let (cursor, keys) = keyspace.scan(0, 10);
+
+        assert_eq!(0, cursor);
+        assert_eq!(vec![&"a".to_string()], keys);
+        assert_eq!(2, keyspace.shards.iter().map(|(_, shard)| shard.scan_order.len()).sum::<usize>());
+    }
+
+    #[test]
+    fn it_reports_wrong_type() {
+        let db = Database::new(1);
+        let mut keyspace = db.lock().unwrap();
+        keyspace.insert("zset".to_string(), SortedSet::default());
+        keyspace.insert("string".to_string(), Bytes::from("a"));
+
+        assert_eq!(Err(WrongType), keyspace.get("zset"));
+        assert_eq!(Err(WrongType), keyspace.get_sorted_set("string"));
+        assert!(keyspace.get_sorted_set("zset").unwrap().is_some());
+    }
+
+    #[test]
+    fn it_locks_only_the_shards_of_the_keys() {
+        let db = Database::new(1);
+        let first = Bytes::from("a");
+        let second = (0..).map(|i| Bytes::from(format!("key:{}", i))).find(|key| shard_of(key) != shard_of(&first)).unwrap();
+
+        let mut keyspace = db.lock_keys([&first]).unwrap();
+        keyspace.insert(first.clone(), Bytes::from("1"));
+        db.lock_keys([&second]).unwrap().insert(second.clone(), Bytes::from("2"));
+        drop(keyspace);
+
+        let keyspace = db.lock_keys([&second, &first]).unwrap();
+        assert_eq!(vec![shard_of(&first).min(shard_of(&second)), shard_of(&first).max(shard_of(&second))],
+                   keyspace.shards.iter().map(|(shard, _)| *shard).collect::<Vec<_>>());
+        assert_eq!(2, keyspace.len());
+    }
+
+    #[test]
+    #[should_panic(expected = "the shard holding the key is not locked")]
+    fn it_refuses_keys_outside_of_the_locked_shards() {
+        let db = Database::new(1);
+        let first = Bytes::from("a");
+        let second = (0..).map(|i| Bytes::from(format!("key:{}", i))).find(|key| shard_of(key) != shard_of(&first)).unwrap();
+
+        db.lock_keys([&first]).unwrap().get(&second).unwrap();
+    }
+
+    #[test]
+    fn it_accounts_memory_of_keys() {
+        let db = Database::new(2);
+
+        let mut keyspace = db.lock_keys(["a"]).unwrap();
+        keyspace.insert("a", Bytes::from("1"));
+        let inserted = db.memory().used();
+        *keyspace.get_value_mut("a").unwrap() = Value::String(Bytes::from(vec![0; 100]));
+        drop(keyspace);
+        assert_eq!(inserted + 99, db.memory().used());
+
+        db.select(1).unwrap().lock().unwrap().insert("b", Bytes::from("2"));
+        db.lock().unwrap().remove("a");
+        db.select(1).unwrap().lock().unwrap().clear();
+
+        assert!(inserted > 0);
+        assert_eq!(0, db.memory().used());
+    }
+
+    #[test]
+    fn it_evicts_keys_by_policy_until_under_the_limit() {
+        let db = Database::new(1);
+        db.memory().set_samples(64);
+        let soon = SystemTime::now() + Duration::from_secs(10);
+        {
+            let mut keyspace = db.lock().unwrap();
+            for i in 0..10 {
+                keyspace.insert_with_expiration(format!("key:{}", i), Bytes::from("value"), Some(soon + Duration::from_secs(i)));
+            }
+            keyspace.insert("persistent", Bytes::from("value"));
+            keyspace.insert_with_expiration("idle", Bytes::from("value"), Some(soon + Duration::from_secs(60)));
+            keyspace.shard(b"idle").entries[&b"idle"[..]].accessed.set(clock() - 100);
+            keyspace.shard(b"key:3").entries[&b"key:3"[..]].frequency.set(0);
+        }
+        let evict_one = |policy| {
+            db.memory().set_policy(policy);
+            db.memory().set_limit(db.memory().used() - 1);
+            let mut evicted = vec![];
+            assert!(db.free_memory(&mut evicted));
+            assert_eq!(1, evicted.len());
+        };
+
+        evict_one(EvictionPolicy::VolatileLru);
+        assert!(!db.lock().unwrap().contains_key("idle"));
+        evict_one(EvictionPolicy::AllKeysLfu);
+        assert!(!db.lock().unwrap().contains_key("key:3"));
+        evict_one(EvictionPolicy::VolatileTtl);
+        assert!(!db.lock().unwrap().contains_key("key:0"));
+
+        db.memory().set_limit(1);
+        db.memory().set_policy(EvictionPolicy::VolatileRandom);
+        assert!(!db.free_memory(&mut vec![]));
+        assert_eq!(vec![&Bytes::from("persistent")], db.lock().unwrap().keys().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn it_evicts_a_sample_only_while_it_is_unchanged() {
+        let mut shard = Shard::new(Arc::default());
+        shard.put(Bytes::from("a"), Entry::new(b"a", Value::String(Bytes::from("1")), None));
+        let (_, key, stamp) = shard.eviction_samples(EvictionPolicy::AllKeysLru, 1).remove(0);
+
+        shard.take(&key);
+        assert!(shard.take_sampled(&key, stamp).is_none());
+
+        let mut replaced = Entry::new(b"a", Value::String(Bytes::from("2")), None);
+        replaced.frequency = Cell::new(0);
+        shard.put(key.clone(), replaced);
+        assert!(shard.take_sampled(&key, stamp).is_none());
+        assert!(shard.peek(&key).is_some());
+
+        let (_, key, stamp) = shard.eviction_samples(EvictionPolicy::AllKeysLru, 1).remove(0);
+        assert!(shard.take_sampled(&key, stamp).is_some());
+        assert_eq!(0, shard.memory.used());
+    }
+
+    #[test]
+    fn it_refuses_to_free_memory_without_eviction() {
+        let db = Database::new(1);
+        db.lock().unwrap().insert("a", Bytes::from("1"));
+        db.memory().set_limit(1);
+
+        assert!(!db.free_memory(&mut vec![]));
+        assert!(db.lock().unwrap().contains_key("a"));
+    }
+}
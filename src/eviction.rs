@@ -0,0 +1,192 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::sorted_set::CompactLimits;
+
+/// Keys sampled to pick one to evict, mirroring Redis' `maxmemory-samples`.
+pub(crate) const DEFAULT_SAMPLES: usize = 5;
+/// Frequency counter of a newly created key, so it is not evicted right away.
+pub(crate) const LFU_INIT: u8 = 5;
+const LFU_LOG_FACTOR: f64 = 10.0;
+/// Minutes of inactivity after which the frequency counter is decremented.
+const LFU_DECAY_MINUTES: u32 = 1;
+
+/// How keys are picked for eviction once the memory limit is reached, as `maxmemory-policy`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) enum EvictionPolicy {
+    #[default]
+    NoEviction,
+    AllKeysLru,
+    VolatileLru,
+    AllKeysLfu,
+    VolatileLfu,
+    AllKeysRandom,
+    VolatileRandom,
+    VolatileTtl,
+}
+
+const POLICIES: [(EvictionPolicy, &str); 8] = [
+    (EvictionPolicy::NoEviction, "noeviction"),
+    (EvictionPolicy::AllKeysLru, "allkeys-lru"),
+    (EvictionPolicy::VolatileLru, "volatile-lru"),
+    (EvictionPolicy::AllKeysLfu, "allkeys-lfu"),
+    (EvictionPolicy::VolatileLfu, "volatile-lfu"),
+    (EvictionPolicy::AllKeysRandom, "allkeys-random"),
+    (EvictionPolicy::VolatileRandom, "volatile-random"),
+    (EvictionPolicy::VolatileTtl, "volatile-ttl"),
+];
+
+impl EvictionPolicy {
+    pub(crate) fn parse(name: &str) -> Option<Self> {
+        POLICIES.iter().find(|(_, known)| known.eq_ignore_ascii_case(name)).map(|(policy, _)| *policy)
+    }
+
+    pub(crate) fn name(&self) -> &'static str {
+        POLICIES.iter().find(|(policy, _)| policy == self).map(|(_, name)| *name).unwrap()
+    }
+
+    /// Whether keys are evicted by access frequency rather than by idle time.
+    pub(crate) fn lfu(&self) -> bool {
+        matches!(self, EvictionPolicy::AllKeysLfu | EvictionPolicy::VolatileLfu)
+    }
+
+    /// Whether only keys with an expiration may be evicted.
+    pub(crate) fn volatile_only(&self) -> bool {
+        matches!(self, EvictionPolicy::VolatileLru | EvictionPolicy::VolatileLfu | EvictionPolicy::VolatileRandom | EvictionPolicy::VolatileTtl)
+    }
+}
+
+/// Approximate memory accounting shared by all databases of a server, together
+/// with the `maxmemory` settings deciding when and how keys get evicted and the
+/// thresholds of the compact encodings trading speed for memory.
+#[derive(Debug, Default)]
+pub(crate) struct Memory {
+    used: AtomicUsize,
+    /// Highest memory used since the server started.
+    peak: AtomicUsize,
+    /// Limit in bytes, zero for none.
+    limit: AtomicUsize,
+    policy: Mutex<EvictionPolicy>,
+    samples: AtomicUsize,
+    compact: Mutex<CompactLimits>,
+}
+
+impl Memory {
+    pub(crate) fn used(&self) -> usize {
+        self.used.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn grow(&self, bytes: usize) {
+        let used = self.used.fetch_add(bytes, Ordering::Relaxed) + bytes;
+        self.peak.fetch_max(used, Ordering::Relaxed);
+    }
+
+    pub(crate) fn peak(&self) -> usize {
+        self.peak.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn shrink(&self, bytes: usize) {
+        self.used.fetch_sub(bytes, Ordering::Relaxed);
+    }
+
+    pub(crate) fn limit(&self) -> usize {
+        self.limit.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn set_limit(&self, bytes: usize) {
+        self.limit.store(bytes, Ordering::Relaxed);
+    }
+
+    pub(crate) fn over_limit(&self) -> bool {
+        let limit = self.limit();
+        limit > 0 && self.used() > limit
+    }
+
+    pub(crate) fn policy(&self) -> EvictionPolicy {
+        *self.policy.lock().unwrap()
+    }
+
+    pub(crate) fn set_policy(&self, policy: EvictionPolicy) {
+        *self.policy.lock().unwrap() = policy;
+    }
+
+    pub(crate) fn samples(&self) -> usize {
+        match self.samples.load(Ordering::Relaxed) {
+            0 => DEFAULT_SAMPLES,
+            samples => samples,
+        }
+    }
+
+    pub(crate) fn set_samples(&self, samples: usize) {
+        self.samples.store(samples, Ordering::Relaxed);
+    }
+
+    pub(crate) fn compact_limits(&self) -> CompactLimits {
+        *self.compact.lock().unwrap()
+    }
+
+    pub(crate) fn set_compact_entries(&self, entries: usize) {
+        self.compact.lock().unwrap().entries = entries;
+    }
+
+    pub(crate) fn set_compact_value(&self, length: usize) {
+        self.compact.lock().unwrap().value = length;
+    }
+}
+
+/// Seconds since the epoch, the clock access times of keys are kept in.
+pub(crate) fn clock() -> u32 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as u32
+}
+
+/// Logarithmic increment of a frequency counter, the more accesses it counts the
+/// less likely another one bumps it, so 255 stands for about a million hits.
+pub(crate) fn lfu_increment(counter: u8) -> u8 {
+    if counter == u8::MAX {
+        return counter;
+    }
+    let base = counter.saturating_sub(LFU_INIT) as f64;
+    let probability = 1.0 / (base * LFU_LOG_FACTOR + 1.0);
+
+    match (random() as f64 / u64::MAX as f64) < probability {
+        true => counter + 1,
+        false => counter,
+    }
+}
+
+/// Decrements the counter once for every decay period the key was left idle.
+pub(crate) fn lfu_decay(counter: u8, idle_seconds: u32) -> u8 {
+    let periods = idle_seconds / 60 / LFU_DECAY_MINUTES;
+    counter.saturating_sub(periods.min(u8::MAX as u32) as u8)
+}
+
+pub(crate) fn random() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_policies() {
+        assert_eq!(Some(EvictionPolicy::VolatileTtl), EvictionPolicy::parse("VOLATILE-TTL"));
+        assert_eq!(None, EvictionPolicy::parse("lru"));
+        assert_eq!("allkeys-lfu", EvictionPolicy::AllKeysLfu.name());
+    }
+
+    #[test]
+    fn it_counts_frequency_logarithmically() {
+        let mut counter = LFU_INIT;
+        for _ in 0..1000 {
+            counter = lfu_increment(counter);
+        }
+
+        assert!(counter > LFU_INIT && counter < 60, "counter {}", counter);
+        assert_eq!(3, lfu_decay(5, 120));
+        assert_eq!(0, lfu_decay(5, 60 * 60));
+    }
+}
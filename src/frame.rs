@@ -2,16 +2,20 @@ use std::io::Cursor;
 
 use bytes::{Buf, Bytes};
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Frame {
     Simple(String),
     SimpleError(String),
-    Integer(u64),
+    Integer(i64),
+    Double(f64),
     Bulk(Bytes),
     Null,
     Array(Vec<Frame>),
 }
 
+/// Longest inline request accepted, as in Redis.
+const MAX_INLINE_LENGTH: usize = 64 * 1024;
+
 #[derive(Debug)]
 pub enum Error {
     Incomplete,
@@ -22,6 +26,13 @@ impl TryFrom<&mut Cursor<&[u8]>> for Frame {
     type Error = Error;
 
     fn try_from(payload: &mut Cursor<&[u8]>) -> Result<Self, Self::Error> {
+        Frame::parse(payload, true)
+    }
+}
+
+impl Frame {
+    /// Parses the next frame, a request may come inline only when it is not nested in an array.
+    fn parse(payload: &mut Cursor<&[u8]>, top_level: bool) -> Result<Self, Error> {
         if !payload.has_remaining() {
             return Err(Error::Incomplete);
         }
@@ -40,9 +51,14 @@ impl TryFrom<&mut Cursor<&[u8]>> for Frame {
                 Ok(Frame::SimpleError(string))
             }
             b':' => {
-                let len = get_uint(payload)?;
+                let value = get_int(payload)?;
 
-                Ok(Frame::Integer(len))
+                Ok(Frame::Integer(value))
+            }
+            b',' => {
+                let value = get_double(payload)?;
+
+                Ok(Frame::Double(value))
             }
             b'$' => {
                 if b'-' == peek_u8(payload)? {
@@ -69,16 +85,45 @@ impl TryFrom<&mut Cursor<&[u8]>> for Frame {
                 }
             }
             b'*' => {
+                if b'-' == peek_u8(payload)? {
+                    let line = get_line(payload)?;
+
+                    if line != b"-1" {
+                        return Err(Error::Other("protocol error; invalid frame format".to_string()));
+                    }
+
+                    return Ok(Frame::Null);
+                }
+
                 let len = get_uint(payload)?.try_into().unwrap();
                 let mut out = Vec::with_capacity(len);
 
                 for _ in 0..len {
-                    out.push(Frame::try_from(&mut *payload)?);
+                    out.push(Frame::parse(&mut *payload, false)?);
                 }
 
                 Ok(Frame::Array(out))
             }
-            actual => Err(Error::Other(format!("unknown frame leading byte {}", actual))),
+            byte if !top_level => Err(Error::Other(format!("Protocol error: expected '$', got '{}'", byte as char))),
+            _ => {
+                payload.set_position(payload.position() - 1);
+                let start = payload.position() as usize;
+
+                // Blank lines are skipped, counting towards the size of the request following them.
+                loop {
+                    let line = get_inline_line(payload, start)?;
+                    let args = split_inline_args(line)?;
+                    if !args.is_empty() {
+                        return Ok(Frame::Array(args.into_iter().map(Frame::Bulk).collect()));
+                    }
+
+                    match payload.chunk().first() {
+                        None => return Err(Error::Incomplete),
+                        Some(b'+' | b'-' | b':' | b',' | b'$' | b'*') => return Frame::parse(payload, true),
+                        Some(_) => {}
+                    }
+                }
+            }
         }
     }
 }
@@ -100,6 +145,10 @@ impl From<Frame> for Vec<u8> {
                 bytes.push(b':');
                 bytes.extend(val.to_string().as_bytes());
             }
+            Frame::Double(val) => {
+                bytes.push(b',');
+                bytes.extend(format_double(val).as_bytes());
+            }
             Frame::Bulk(val) => {
                 let len = val.len() as u64;
                 bytes.push(b'$');
@@ -148,7 +197,153 @@ fn get_uint(payload: &mut Cursor<&[u8]>) -> Result<u64, Error> {
 
     let line = get_line(payload)?;
 
-    atoi::<u64>(line).ok_or_else(|| Err(Error::Other("unable to parse integer".to_string())).unwrap())
+    atoi::<u64>(line).ok_or_else(|| Error::Other("unable to parse integer".to_string()))
+}
+
+fn get_int(payload: &mut Cursor<&[u8]>) -> Result<i64, Error> {
+    use atoi::atoi;
+
+    let line = get_line(payload)?;
+
+    atoi::<i64>(line).ok_or_else(|| Error::Other("unable to parse integer".to_string()))
+}
+
+fn get_double(payload: &mut Cursor<&[u8]>) -> Result<f64, Error> {
+    let line = get_line(payload)?;
+    let invalid = || Error::Other("unable to parse double".to_string());
+    let string = std::str::from_utf8(line).map_err(|_| invalid())?;
+
+    match string {
+        "inf" => Ok(f64::INFINITY),
+        "-inf" => Ok(f64::NEG_INFINITY),
+        "nan" => Ok(f64::NAN),
+        _ => string.parse::<f64>().map_err(|_| invalid()),
+    }
+}
+
+/// Formats a double the way RESP3 expects it, using the shortest
+/// representation that parses back to the very same value.
+pub(crate) fn format_double(value: f64) -> String {
+    if value.is_nan() {
+        "nan".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "inf" } else { "-inf" }.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+/// Reads the next line of an inline request which started at `request`, refusing
+/// requests longer than [`MAX_INLINE_LENGTH`] whether their end arrived or not.
+fn get_inline_line<'a>(payload: &mut Cursor<&'a [u8]>, request: usize) -> Result<&'a [u8], Error> {
+    let start = payload.position() as usize;
+    let buffer: &'a [u8] = payload.get_ref();
+    let limit = buffer.len().min(request + MAX_INLINE_LENGTH);
+
+    match buffer[start..limit].iter().position(|byte| *byte == b'\n') {
+        None if limit < buffer.len() => Err(Error::Other("Protocol error: too big inline request".to_string())),
+        Some(offset) => {
+            let end = start + offset;
+            payload.set_position((end + 1) as u64);
+
+            match buffer[start..end].last() {
+                Some(b'\r') => Ok(&buffer[start..end - 1]),
+                _ => Ok(&buffer[start..end]),
+            }
+        }
+        None => Err(Error::Incomplete),
+    }
+}
+
+/// Splits an inline command line into arguments following the quoting rules
+/// of the original Redis implementation: double quoted arguments support
+/// escape sequences, single quoted arguments are taken literally except for `\'`.
+fn split_inline_args(line: &[u8]) -> Result<Vec<Bytes>, Error> {
+    let unbalanced = || Error::Other("Protocol error: unbalanced quotes in request".to_string());
+    let mut args: Vec<Bytes> = vec![];
+    let mut i = 0;
+
+    loop {
+        while i < line.len() && line[i].is_ascii_whitespace() {
+            i += 1;
+        }
+
+        if i == line.len() {
+            return Ok(args);
+        }
+
+        let mut current: Vec<u8> = vec![];
+
+        match line[i] {
+            b'"' => {
+                i += 1;
+                loop {
+                    match line.get(i) {
+                        None => return Err(unbalanced()),
+                        Some(b'\\') if i + 3 < line.len()
+                            && line[i + 1] == b'x'
+                            && line[i + 2].is_ascii_hexdigit()
+                            && line[i + 3].is_ascii_hexdigit() => {
+                            let hex = std::str::from_utf8(&line[i + 2..i + 4]).unwrap();
+                            current.push(u8::from_str_radix(hex, 16).unwrap());
+                            i += 4;
+                        }
+                        Some(b'\\') if i + 1 < line.len() => {
+                            current.push(match line[i + 1] {
+                                b'n' => b'\n',
+                                b'r' => b'\r',
+                                b't' => b'\t',
+                                b'b' => 0x08,
+                                b'a' => 0x07,
+                                other => other,
+                            });
+                            i += 2;
+                        }
+                        Some(b'"') => {
+                            i += 1;
+                            break;
+                        }
+                        Some(byte) => {
+                            current.push(*byte);
+                            i += 1;
+                        }
+                    }
+                }
+            }
+            b'\'' => {
+                i += 1;
+                loop {
+                    match line.get(i) {
+                        None => return Err(unbalanced()),
+                        Some(b'\\') if line.get(i + 1) == Some(&b'\'') => {
+                            current.push(b'\'');
+                            i += 2;
+                        }
+                        Some(b'\'') => {
+                            i += 1;
+                            break;
+                        }
+                        Some(byte) => {
+                            current.push(*byte);
+                            i += 1;
+                        }
+                    }
+                }
+            }
+            _ => {
+                while i < line.len() && !line[i].is_ascii_whitespace() {
+                    current.push(line[i]);
+                    i += 1;
+                }
+            }
+        }
+
+        if i < line.len() && !line[i].is_ascii_whitespace() {
+            return Err(unbalanced());
+        }
+
+        args.push(Bytes::from(current));
+    }
 }
 
 fn peek_u8(payload: &mut Cursor<&[u8]>) -> Result<u8, Error> {
@@ -199,4 +394,145 @@ mod tests {
         assert_eq!(115, result);
         assert_eq!(5, cursor.position());
     }
+
+    #[test]
+    fn it_gets_negative_int() {
+        let buffer = b"-42\r\n";
+        let mut cursor: Cursor<&[u8]> = Cursor::new(buffer);
+
+        let result = get_int(&mut cursor).unwrap();
+
+        assert_eq!(-42, result);
+        assert_eq!(5, cursor.position());
+    }
+
+    #[test]
+    fn it_round_trips_integers() {
+        for value in [0, 1, -1, -2, i64::MAX, i64::MIN] {
+            let bytes: Vec<u8> = Frame::Integer(value).into();
+            let mut cursor: Cursor<&[u8]> = Cursor::new(&bytes);
+
+            let result = Frame::try_from(&mut cursor).unwrap();
+
+            assert_eq!(Frame::Integer(value), result);
+        }
+    }
+
+    #[test]
+    fn it_round_trips_doubles() {
+        for value in [0.0, -1.5, 0.1 + 0.2, 1e300, -2.5e-300, f64::INFINITY, f64::NEG_INFINITY] {
+            let bytes: Vec<u8> = Frame::Double(value).into();
+            let mut cursor: Cursor<&[u8]> = Cursor::new(&bytes);
+
+            let result = Frame::try_from(&mut cursor).unwrap();
+
+            assert_eq!(Frame::Double(value), result);
+        }
+    }
+
+    #[test]
+    fn it_round_trips_nan() {
+        let bytes: Vec<u8> = Frame::Double(f64::NAN).into();
+        assert_eq!(b",nan\r\n".to_vec(), bytes);
+        let mut cursor: Cursor<&[u8]> = Cursor::new(&bytes);
+
+        let result = Frame::try_from(&mut cursor).unwrap();
+
+        assert!(matches!(result, Frame::Double(value) if value.is_nan()));
+    }
+
+    #[test]
+    fn it_parses_null_array() {
+        let buffer = b"*-1\r\n";
+        let mut cursor: Cursor<&[u8]> = Cursor::new(buffer);
+
+        let result = Frame::try_from(&mut cursor).unwrap();
+
+        assert_eq!(Frame::Null, result);
+    }
+
+    #[test]
+    fn it_parses_inline_command() {
+        let buffer = b"SET key \"hello world\"\r\n";
+        let mut cursor: Cursor<&[u8]> = Cursor::new(buffer);
+
+        let result = Frame::try_from(&mut cursor).unwrap();
+
+        assert_eq!(Frame::Array(vec![
+            Frame::Bulk(Bytes::from("SET")),
+            Frame::Bulk(Bytes::from("key")),
+            Frame::Bulk(Bytes::from("hello world")),
+        ]), result);
+        assert_eq!(buffer.len() as u64, cursor.position());
+    }
+
+    #[test]
+    fn it_parses_inline_command_terminated_with_newline_only() {
+        let buffer = b"\r\nPING\n";
+        let mut cursor: Cursor<&[u8]> = Cursor::new(buffer);
+
+        let result = Frame::try_from(&mut cursor).unwrap();
+
+        assert_eq!(Frame::Array(vec![Frame::Bulk(Bytes::from("PING"))]), result);
+    }
+
+    #[test]
+    fn it_skips_any_number_of_blank_lines() {
+        let mut buffer = vec![b'\n'; MAX_INLINE_LENGTH - 100];
+        buffer.extend(b"*1\r\n$4\r\nPING\r\n");
+        let mut cursor: Cursor<&[u8]> = Cursor::new(&buffer);
+
+        assert_eq!(Frame::Array(vec![Frame::Bulk(Bytes::from("PING"))]), Frame::try_from(&mut cursor).unwrap());
+
+        let buffer = vec![b'\n'; 2_000_000];
+        let result = Frame::try_from(&mut Cursor::new(&buffer[..]));
+        assert!(matches!(result, Err(Error::Other(err)) if err == "Protocol error: too big inline request"));
+    }
+
+    #[test]
+    fn it_refuses_too_big_inline_commands() {
+        let buffer = [b"SET key ".as_slice(), &vec![b'a'; MAX_INLINE_LENGTH]].concat();
+        let result = Frame::try_from(&mut Cursor::new(&buffer[..]));
+        assert!(matches!(result, Err(Error::Other(err)) if err == "Protocol error: too big inline request"));
+
+        let buffer = [b"GET ".as_slice(), &vec![b'a'; 1000], b"\r\n"].concat();
+        assert!(Frame::try_from(&mut Cursor::new(&buffer[..])).is_ok());
+    }
+
+    #[test]
+    fn it_refuses_inline_arguments_inside_arrays() {
+        let mut cursor: Cursor<&[u8]> = Cursor::new(b"*1\r\nPING\r\n");
+
+        let result = Frame::try_from(&mut cursor);
+
+        assert!(matches!(result, Err(Error::Other(err)) if err == "Protocol error: expected '$', got 'P'"));
+    }
+
+    #[test]
+    fn it_waits_for_complete_inline_command() {
+        let buffer = b"GET ke";
+        let mut cursor: Cursor<&[u8]> = Cursor::new(buffer);
+
+        let result = Frame::try_from(&mut cursor);
+
+        assert!(matches!(result, Err(Error::Incomplete)));
+    }
+
+    #[test]
+    fn it_splits_inline_args_with_escapes_and_quotes() {
+        let result = split_inline_args(br#"SET "a\x41\n" 'it\'s' "\"q\"""#).unwrap();
+
+        assert_eq!(vec![
+            Bytes::from("SET"),
+            Bytes::from("aA\n"),
+            Bytes::from("it's"),
+            Bytes::from("\"q\""),
+        ], result);
+    }
+
+    #[test]
+    fn it_rejects_unbalanced_quotes() {
+        assert!(split_inline_args(b"SET \"key value").is_err());
+        assert!(split_inline_args(b"SET 'key'value").is_err());
+    }
 }
@@ -0,0 +1,414 @@
+//! Geohash helpers ported from Redis' `geohash.c` and `geohash_helper.c`.
+//! Positions are stored as 52 bit interleaved geohashes used as sorted set scores.
+
+pub(crate) const LONGITUDE_MIN: f64 = -180.0;
+pub(crate) const LONGITUDE_MAX: f64 = 180.0;
+pub(crate) const LATITUDE_MIN: f64 = -85.051_128_78;
+pub(crate) const LATITUDE_MAX: f64 = 85.051_128_78;
+pub(crate) const STEP_MAX: u8 = 26;
+
+const EARTH_RADIUS_IN_METERS: f64 = 6_372_797.560_856;
+const MERCATOR_MAX: f64 = 20_037_726.37;
+const ALPHABET: &[u8; 32] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Hash {
+    pub bits: u64,
+    pub step: u8,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Area {
+    pub longitude: (f64, f64),
+    pub latitude: (f64, f64),
+}
+
+/// Shape searched around a center, dimensions are kept in meters.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Shape {
+    Radius(f64),
+    Box { width: f64, height: f64 },
+}
+
+pub(crate) fn is_valid(longitude: f64, latitude: f64) -> bool {
+    (LONGITUDE_MIN..=LONGITUDE_MAX).contains(&longitude) && (LATITUDE_MIN..=LATITUDE_MAX).contains(&latitude)
+}
+
+pub(crate) fn encode(longitude: f64, latitude: f64, step: u8) -> Hash {
+    encode_in(longitude, latitude, step, (LATITUDE_MIN, LATITUDE_MAX))
+}
+
+fn encode_in(longitude: f64, latitude: f64, step: u8, latitude_range: (f64, f64)) -> Hash {
+    let scale = (1u64 << step) as f64;
+    let latitude_offset = (latitude - latitude_range.0) / (latitude_range.1 - latitude_range.0) * scale;
+    let longitude_offset = (longitude - LONGITUDE_MIN) / (LONGITUDE_MAX - LONGITUDE_MIN) * scale;
+
+    Hash {
+        bits: interleave(latitude_offset as u32, longitude_offset as u32),
+        step,
+    }
+}
+
+pub(crate) fn decode(hash: Hash) -> Area {
+    let (latitude, longitude) = deinterleave(hash.bits);
+    let scale = (1u64 << hash.step) as f64;
+    let latitude_scale = LATITUDE_MAX - LATITUDE_MIN;
+    let longitude_scale = LONGITUDE_MAX - LONGITUDE_MIN;
+
+    Area {
+        latitude: (
+            LATITUDE_MIN + (latitude as f64 / scale) * latitude_scale,
+            LATITUDE_MIN + ((latitude as f64 + 1.0) / scale) * latitude_scale,
+        ),
+        longitude: (
+            LONGITUDE_MIN + (longitude as f64 / scale) * longitude_scale,
+            LONGITUDE_MIN + ((longitude as f64 + 1.0) / scale) * longitude_scale,
+        ),
+    }
+}
+
+/// Turns a sorted set score back into the longitude and latitude at the center of its area.
+pub(crate) fn position(score: f64) -> (f64, f64) {
+    let area = decode(Hash { bits: score as u64, step: STEP_MAX });
+    let longitude = ((area.longitude.0 + area.longitude.1) / 2.0).clamp(LONGITUDE_MIN, LONGITUDE_MAX);
+    let latitude = ((area.latitude.0 + area.latitude.1) / 2.0).clamp(LATITUDE_MIN, LATITUDE_MAX);
+
+    (longitude, latitude)
+}
+
+pub(crate) fn score(longitude: f64, latitude: f64) -> f64 {
+    encode(longitude, latitude, STEP_MAX).bits as f64
+}
+
+/// Returns the standard 11 characters geohash, which uses the whole -90..90 latitude range.
+pub(crate) fn geohash_string(score: f64) -> String {
+    let (longitude, latitude) = position(score);
+    let hash = encode_in(longitude, latitude, STEP_MAX, (-90.0, 90.0));
+
+    (0..11)
+        .map(|i| {
+            let index = if i == 10 { 0 } else { (hash.bits >> (52 - (i + 1) * 5)) & 0x1F };
+            ALPHABET[index as usize] as char
+        })
+        .collect()
+}
+
+/// Returns how many meters make a single unit accepted by the GEO commands.
+pub(crate) fn unit_to_meters(unit: &str) -> Option<f64> {
+    match unit.to_lowercase().as_str() {
+        "m" => Some(1.0),
+        "km" => Some(1000.0),
+        "ft" => Some(0.3048),
+        "mi" => Some(1609.34),
+        _ => None,
+    }
+}
+
+/// Formats a coordinate with 17 decimal digits stripped of trailing zeros, like Redis replies do.
+pub(crate) fn format_coordinate(value: f64) -> String {
+    let formatted = format!("{:.17}", value);
+    formatted.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// Haversine distance in meters.
+pub(crate) fn distance(longitude1: f64, latitude1: f64, longitude2: f64, latitude2: f64) -> f64 {
+    let v = ((longitude2.to_radians() - longitude1.to_radians()) / 2.0).sin();
+    if v == 0.0 {
+        return latitude_distance(latitude1, latitude2);
+    }
+
+    let (latitude1, latitude2) = (latitude1.to_radians(), latitude2.to_radians());
+    let u = ((latitude2 - latitude1) / 2.0).sin();
+    let a = u * u + latitude1.cos() * latitude2.cos() * v * v;
+
+    2.0 * EARTH_RADIUS_IN_METERS * a.sqrt().asin()
+}
+
+fn latitude_distance(latitude1: f64, latitude2: f64) -> f64 {
+    EARTH_RADIUS_IN_METERS * (latitude2.to_radians() - latitude1.to_radians()).abs()
+}
+
+/// Returns the distance from the center when the point lies within the shape.
+pub(crate) fn distance_within(shape: Shape, center: (f64, f64), point: (f64, f64)) -> Option<f64> {
+    match shape {
+        Shape::Radius(radius) => {
+            let distance = distance(center.0, center.1, point.0, point.1);
+            (distance <= radius).then_some(distance)
+        }
+        Shape::Box { width, height } => {
+            if latitude_distance(point.1, center.1) > height / 2.0 {
+                return None;
+            }
+            if distance(point.0, point.1, center.0, point.1) > width / 2.0 {
+                return None;
+            }
+            Some(distance(center.0, center.1, point.0, point.1))
+        }
+    }
+}
+
+/// Computes the geohash boxes, the center one and its neighbours, which together
+/// cover the whole shape. Boxes which cannot contain any matching point are skipped.
+pub(crate) fn search_areas(shape: Shape, center: (f64, f64)) -> Vec<Hash> {
+    let (longitude, latitude) = center;
+    let (half_width, half_height, radius) = match shape {
+        Shape::Radius(radius) => (radius, radius, radius),
+        Shape::Box { width, height } => (width / 2.0, height / 2.0, (width / 2.0).hypot(height / 2.0)),
+    };
+
+    let latitude_delta = (half_height / EARTH_RADIUS_IN_METERS).to_degrees();
+    let longitude_delta_top = (half_width / EARTH_RADIUS_IN_METERS / (latitude + latitude_delta).to_radians().cos()).to_degrees();
+    let longitude_delta_bottom = (half_width / EARTH_RADIUS_IN_METERS / (latitude - latitude_delta).to_radians().cos()).to_degrees();
+    let longitude_delta = if latitude < 0.0 { longitude_delta_bottom } else { longitude_delta_top };
+    let (min_longitude, max_longitude) = (longitude - longitude_delta, longitude + longitude_delta);
+    let (min_latitude, max_latitude) = (latitude - latitude_delta, latitude + latitude_delta);
+
+    let mut step = estimate_step(radius, latitude);
+    let mut hash = encode(longitude, latitude, step);
+    let mut neighbours = Neighbours::of(hash);
+
+    let decrease_step = decode(neighbours.north).latitude.1 < max_latitude
+        || decode(neighbours.south).latitude.0 > min_latitude
+        || decode(neighbours.east).longitude.1 < max_longitude
+        || decode(neighbours.west).longitude.0 > min_longitude;
+
+    if step > 1 && decrease_step {
+        step -= 1;
+        hash = encode(longitude, latitude, step);
+        neighbours = Neighbours::of(hash);
+    }
+
+    let area = decode(hash);
+    let mut boxes = vec![
+        Some(hash),
+        Some(neighbours.north),
+        Some(neighbours.south),
+        Some(neighbours.east),
+        Some(neighbours.west),
+        Some(neighbours.north_east),
+        Some(neighbours.north_west),
+        Some(neighbours.south_east),
+        Some(neighbours.south_west),
+    ];
+
+    if step >= 2 {
+        let (north, south, east, west) = (1, 2, 3, 4);
+        let (north_east, north_west, south_east, south_west) = (5, 6, 7, 8);
+
+        if area.latitude.0 < min_latitude {
+            for i in [south, south_west, south_east] {
+                boxes[i] = None;
+            }
+        }
+        if area.latitude.1 > max_latitude {
+            for i in [north, north_east, north_west] {
+                boxes[i] = None;
+            }
+        }
+        if area.longitude.0 < min_longitude {
+            for i in [west, south_west, north_west] {
+                boxes[i] = None;
+            }
+        }
+        if area.longitude.1 > max_longitude {
+            for i in [east, south_east, north_east] {
+                boxes[i] = None;
+            }
+        }
+    }
+
+    let mut result: Vec<Hash> = vec![];
+    for hash in boxes.into_iter().flatten() {
+        if !result.contains(&hash) {
+            result.push(hash);
+        }
+    }
+    result
+}
+
+/// Returns the half open range of sorted set scores covered by the box.
+pub(crate) fn score_range(hash: Hash) -> (f64, f64) {
+    let shift = 2 * (STEP_MAX - hash.step) as u32;
+
+    ((hash.bits << shift) as f64, ((hash.bits + 1) << shift) as f64)
+}
+
+fn estimate_step(mut range: f64, latitude: f64) -> u8 {
+    if range == 0.0 {
+        return STEP_MAX;
+    }
+
+    let mut step: i32 = 1;
+    while range < MERCATOR_MAX {
+        range *= 2.0;
+        step += 1;
+    }
+    step -= 2;
+
+    if !(-66.0..=66.0).contains(&latitude) {
+        step -= 1;
+        if !(-80.0..=80.0).contains(&latitude) {
+            step -= 1;
+        }
+    }
+
+    step.clamp(1, STEP_MAX as i32) as u8
+}
+
+struct Neighbours {
+    north: Hash,
+    south: Hash,
+    east: Hash,
+    west: Hash,
+    north_east: Hash,
+    north_west: Hash,
+    south_east: Hash,
+    south_west: Hash,
+}
+
+impl Neighbours {
+    fn of(hash: Hash) -> Self {
+        let moved = |x: i8, y: i8| move_y(move_x(hash, x), y);
+
+        Neighbours {
+            north: moved(0, 1),
+            south: moved(0, -1),
+            east: moved(1, 0),
+            west: moved(-1, 0),
+            north_east: moved(1, 1),
+            north_west: moved(-1, 1),
+            south_east: moved(1, -1),
+            south_west: moved(-1, -1),
+        }
+    }
+}
+
+fn move_x(hash: Hash, direction: i8) -> Hash {
+    if direction == 0 {
+        return hash;
+    }
+
+    let shift = 64 - hash.step as u32 * 2;
+    let mut x = hash.bits & 0xAAAA_AAAA_AAAA_AAAA;
+    let y = hash.bits & 0x5555_5555_5555_5555;
+    let zz = 0x5555_5555_5555_5555u64 >> shift;
+
+    if direction > 0 {
+        x = x.wrapping_add(zz + 1);
+    } else {
+        x = (x | zz).wrapping_sub(zz + 1);
+    }
+    x &= 0xAAAA_AAAA_AAAA_AAAAu64 >> shift;
+
+    Hash { bits: x | y, step: hash.step }
+}
+
+fn move_y(hash: Hash, direction: i8) -> Hash {
+    if direction == 0 {
+        return hash;
+    }
+
+    let shift = 64 - hash.step as u32 * 2;
+    let x = hash.bits & 0xAAAA_AAAA_AAAA_AAAA;
+    let mut y = hash.bits & 0x5555_5555_5555_5555;
+    let zz = 0xAAAA_AAAA_AAAA_AAAAu64 >> shift;
+
+    if direction > 0 {
+        y = y.wrapping_add(zz + 1);
+    } else {
+        y = (y | zz).wrapping_sub(zz + 1);
+    }
+    y &= 0x5555_5555_5555_5555u64 >> shift;
+
+    Hash { bits: x | y, step: hash.step }
+}
+
+/// Interleaves the bits of x into even positions and the bits of y into odd positions.
+fn interleave(x: u32, y: u32) -> u64 {
+    spread(x) | (spread(y) << 1)
+}
+
+fn deinterleave(bits: u64) -> (u32, u32) {
+    (squash(bits), squash(bits >> 1))
+}
+
+fn spread(value: u32) -> u64 {
+    let mut value = value as u64;
+    value = (value | (value << 16)) & 0x0000_FFFF_0000_FFFF;
+    value = (value | (value << 8)) & 0x00FF_00FF_00FF_00FF;
+    value = (value | (value << 4)) & 0x0F0F_0F0F_0F0F_0F0F;
+    value = (value | (value << 2)) & 0x3333_3333_3333_3333;
+    (value | (value << 1)) & 0x5555_5555_5555_5555
+}
+
+fn squash(value: u64) -> u32 {
+    let mut value = value & 0x5555_5555_5555_5555;
+    value = (value | (value >> 1)) & 0x3333_3333_3333_3333;
+    value = (value | (value >> 2)) & 0x0F0F_0F0F_0F0F_0F0F;
+    value = (value | (value >> 4)) & 0x00FF_00FF_00FF_00FF;
+    value = (value | (value >> 8)) & 0x0000_FFFF_0000_FFFF;
+    ((value | (value >> 16)) & 0x0000_0000_FFFF_FFFF) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PALERMO: (f64, f64) = (13.361389, 38.115556);
+    const CATANIA: (f64, f64) = (15.087269, 37.502669);
+
+    #[test]
+    fn it_encodes_scores_like_redis() {
+        assert_eq!(3479099956230698.0, score(PALERMO.0, PALERMO.1));
+        assert_eq!(3479447370796909.0, score(CATANIA.0, CATANIA.1));
+    }
+
+    #[test]
+    fn it_decodes_positions_close_to_the_original() {
+        let (longitude, latitude) = position(score(PALERMO.0, PALERMO.1));
+
+        assert!((longitude - PALERMO.0).abs() < 1e-5);
+        assert!((latitude - PALERMO.1).abs() < 1e-5);
+    }
+
+    #[test]
+    fn it_computes_haversine_distance() {
+        let distance = distance(PALERMO.0, PALERMO.1, CATANIA.0, CATANIA.1);
+
+        assert!((distance - 166_274.15).abs() < 1.0, "{}", distance);
+    }
+
+    #[test]
+    fn it_returns_standard_geohash_strings() {
+        assert_eq!("sqc8b49rny0", geohash_string(score(PALERMO.0, PALERMO.1)));
+        assert_eq!("sqdtr74hyu0", geohash_string(score(CATANIA.0, CATANIA.1)));
+    }
+
+    #[test]
+    fn it_formats_coordinates_like_redis() {
+        let (longitude, latitude) = position(score(PALERMO.0, PALERMO.1));
+
+        assert_eq!("13.36138933897018433", format_coordinate(longitude));
+        assert_eq!("38.11555639549629859", format_coordinate(latitude));
+        assert_eq!("2", format_coordinate(2.0));
+    }
+
+    #[test]
+    fn it_interleaves_bits() {
+        assert_eq!(0b10, interleave(0, 1));
+        assert_eq!(0b01, interleave(1, 0));
+        assert_eq!((0xDEAD_BEEF, 0x1234_5678), deinterleave(interleave(0xDEAD_BEEF, 0x1234_5678)));
+    }
+
+    #[test]
+    fn it_covers_the_search_shape_with_areas() {
+        let shape = Shape::Radius(200_000.0);
+        let areas = search_areas(shape, (15.0, 37.0));
+        let palermo = score(PALERMO.0, PALERMO.1);
+
+        assert!(areas.iter().any(|hash| {
+            let (min, max) = score_range(*hash);
+            (min..max).contains(&palermo)
+        }));
+    }
+}
@@ -0,0 +1,128 @@
+/// Glob-style matching with the semantics of Redis' `stringmatchlen`: `*`, `?`,
+/// `[...]` classes with `^` negation and ranges, and `\` escapes.
+pub(crate) fn matches(pattern: &[u8], string: &[u8]) -> bool {
+    let (mut p, mut s) = (0, 0);
+    // Where the pattern continues after the last `*` and the byte that continuation was last
+    // tried from. On a mismatch it is tried again one byte further, earlier stars never need
+    // to be retried, so matching takes at most the product of both lengths and no recursion.
+    let mut star: Option<(usize, usize)> = None;
+
+    while s < string.len() {
+        if pattern.get(p) == Some(&b'*') {
+            p += 1;
+            star = Some((p, s));
+            continue;
+        }
+        if p < pattern.len() {
+            let (matched, next) = element(pattern, p, string[s]);
+            if matched {
+                p = next;
+                s += 1;
+                continue;
+            }
+        }
+        match &mut star {
+            Some((after, from)) => {
+                *from += 1;
+                p = *after;
+                s = *from;
+            }
+            None => return false,
+        }
+    }
+
+    while pattern.get(p) == Some(&b'*') {
+        p += 1;
+    }
+    p == pattern.len()
+}
+
+/// Matches the element of the pattern starting at `p`, anything but `*`, against a byte.
+/// Returns whether it matched and where the next element starts.
+fn element(pattern: &[u8], mut p: usize, byte: u8) -> (bool, usize) {
+    match pattern[p] {
+        b'?' => (true, p + 1),
+        b'[' => {
+            p += 1;
+            let negate = pattern.get(p) == Some(&b'^');
+            if negate {
+                p += 1;
+            }
+
+            let mut matched = false;
+            // A class missing its `]` takes the rest of the pattern.
+            while let Some(&current) = pattern.get(p) {
+                match current {
+                    b'\\' if p + 1 < pattern.len() => {
+                        p += 1;
+                        matched |= pattern[p] == byte;
+                    }
+                    b']' => {
+                        p += 1;
+                        break;
+                    }
+                    start if pattern.get(p + 1) == Some(&b'-') && p + 2 < pattern.len() => {
+                        let end = pattern[p + 2];
+                        let (low, high) = if start > end { (end, start) } else { (start, end) };
+                        matched |= (low..=high).contains(&byte);
+                        p += 2;
+                    }
+                    current => matched |= current == byte,
+                }
+                p += 1;
+            }
+
+            (matched != negate, p)
+        }
+        b'\\' if p + 1 < pattern.len() => (pattern[p + 1] == byte, p + 2),
+        current => (current == byte, p + 1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_matches_wildcards() {
+        assert!(matches(b"*", b"anything"));
+        assert!(matches(b"*", b""));
+        assert!(matches(b"h?llo", b"hello"));
+        assert!(matches(b"h*llo", b"heeeello"));
+        assert!(matches(b"user:*:name", b"user:42:name"));
+        assert!(!matches(b"h?llo", b"hllo"));
+        assert!(!matches(b"user:*", b"session:1"));
+    }
+
+    #[test]
+    fn it_matches_classes() {
+        assert!(matches(b"h[ae]llo", b"hello"));
+        assert!(!matches(b"h[ae]llo", b"hillo"));
+        assert!(matches(b"h[^e]llo", b"hallo"));
+        assert!(!matches(b"h[^e]llo", b"hello"));
+        assert!(matches(b"h[a-b]llo", b"hbllo"));
+        assert!(matches(b"h[b-a]llo", b"hallo"));
+        assert!(!matches(b"h[a-b]llo", b"hcllo"));
+    }
+
+    #[test]
+    fn it_matches_escaped_characters() {
+        assert!(matches(b"h\\*llo", b"h*llo"));
+        assert!(!matches(b"h\\*llo", b"hello"));
+        assert!(matches(b"[\\]]", b"]"));
+    }
+
+    #[test]
+    fn it_backtracks_only_to_the_last_star() {
+        assert!(matches(b"*a*b", b"xaxxaxb"));
+        assert!(matches(b"a*b*c", b"abcbc"));
+        assert!(!matches(b"a*b*c", b"abcb"));
+        assert!(matches(b"*[0-9]", b"key:12"));
+        assert!(matches(b"h[a", b"ha"));
+
+        // Exponential when every star retries every suffix, as in CVE-2022-36021.
+        let string = vec![b'a'; 10_000];
+        assert!(!matches(b"*a*a*a*a*a*a*a*a*a*a*a*a*a*a*a*a*b", &string));
+        assert!(matches(&[b"*a".repeat(5000), b"*".to_vec()].concat(), &string));
+    }
+}
@@ -0,0 +1,423 @@
+use bytes::Bytes;
+
+/// Number of registers, Redis uses 14 bits of the hash to select one.
+pub(crate) const REGISTERS: usize = 1 << P;
+const P: u32 = 14;
+const Q: u32 = 64 - P;
+const BITS: usize = 6;
+const REGISTER_MAX: u8 = (1 << BITS) - 1;
+const HEADER_SIZE: usize = 16;
+const DENSE_SIZE: usize = HEADER_SIZE + (REGISTERS * BITS).div_ceil(8);
+const MAGIC: &[u8; 4] = b"HYLL";
+const DENSE: u8 = 0;
+const SPARSE: u8 = 1;
+const SPARSE_MAX_BYTES: usize = 3000;
+const SPARSE_VAL_MAX_VALUE: u8 = 32;
+const SPARSE_VAL_MAX_LEN: usize = 4;
+const SPARSE_ZERO_MAX_LEN: usize = 64;
+const SPARSE_XZERO_MAX_LEN: usize = 16384;
+const ALPHA_INF: f64 = 0.721_347_520_444_481_7;
+const SEED: u64 = 0xadc8_3b19;
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum DecodeError {
+    /// The value is not a HyperLogLog at all.
+    WrongType,
+    /// The value claims to be a HyperLogLog but its registers cannot be read.
+    Corrupted,
+}
+
+/// HyperLogLog kept as plain registers in memory and serialized with the exact
+/// layout Redis uses for its `HYLL` strings, either sparse or dense.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct HyperLogLog {
+    registers: Vec<u8>,
+    dense: bool,
+    cache: [u8; 8],
+}
+
+impl Default for HyperLogLog {
+    fn default() -> Self {
+        HyperLogLog {
+            registers: vec![0; REGISTERS],
+            dense: false,
+            cache: [0; 8],
+        }
+    }
+}
+
+impl HyperLogLog {
+    pub(crate) fn decode(data: &[u8]) -> Result<Self, DecodeError> {
+        if data.len() < HEADER_SIZE || &data[..4] != MAGIC || data[4] > SPARSE {
+            return Err(DecodeError::WrongType);
+        }
+        if data[4] == DENSE && data.len() != DENSE_SIZE {
+            return Err(DecodeError::WrongType);
+        }
+
+        let mut cache = [0; 8];
+        cache.copy_from_slice(&data[8..HEADER_SIZE]);
+        let payload = &data[HEADER_SIZE..];
+
+        let registers = match data[4] {
+            DENSE => (0..REGISTERS).map(|index| dense_get(payload, index)).collect(),
+            _ => sparse_decode(payload)?,
+        };
+
+        Ok(HyperLogLog { registers, dense: data[4] == DENSE, cache })
+    }
+
+    /// Serializes to the sparse representation when it is small enough, promoting to dense otherwise.
+    /// Like in Redis, once dense the representation never goes back to sparse.
+    pub(crate) fn encode(&mut self) -> Bytes {
+        let mut data = Vec::with_capacity(DENSE_SIZE);
+        data.extend_from_slice(MAGIC);
+        data.extend_from_slice(&[DENSE, 0, 0, 0]);
+        data.extend_from_slice(&self.cache);
+
+        if !self.dense {
+            if let Some(payload) = sparse_encode(&self.registers) {
+                if HEADER_SIZE + payload.len() <= SPARSE_MAX_BYTES {
+                    data[4] = SPARSE;
+                    data.extend_from_slice(&payload);
+                    return Bytes::from(data);
+                }
+            }
+            self.dense = true;
+        }
+
+        data.resize(DENSE_SIZE, 0);
+        for (index, value) in self.registers.iter().enumerate() {
+            dense_set(&mut data[HEADER_SIZE..], index, *value);
+        }
+
+        Bytes::from(data)
+    }
+
+    /// Adds the element, returns true when one of the registers changed.
+    pub(crate) fn add(&mut self, element: &[u8]) -> bool {
+        let (index, count) = position(element);
+
+        if self.registers[index] >= count {
+            return false;
+        }
+
+        self.registers[index] = count;
+        self.invalidate_cache();
+        true
+    }
+
+    /// Merges registers of the other HyperLogLog taking the maximum of each pair.
+    pub(crate) fn merge(&mut self, other: &HyperLogLog) {
+        for (register, value) in self.registers.iter_mut().zip(&other.registers) {
+            *register = (*register).max(*value);
+        }
+        self.dense |= other.dense;
+        self.invalidate_cache();
+    }
+
+    /// Returns the estimated cardinality, reusing the value cached in the header when still valid.
+    pub(crate) fn count(&mut self) -> u64 {
+        if self.cache[7] & 0x80 == 0 {
+            return u64::from_le_bytes(self.cache);
+        }
+
+        let count = estimate(&self.registers);
+        self.cache = count.to_le_bytes();
+        count
+    }
+
+    fn invalidate_cache(&mut self) {
+        self.cache[7] |= 0x80;
+    }
+}
+
+/// Estimates the cardinality with the improved estimator by Otmar Ertl used by Redis.
+pub(crate) fn estimate(registers: &[u8]) -> u64 {
+    let m = REGISTERS as f64;
+    let mut histogram = [0u32; 64];
+    for value in registers {
+        histogram[*value as usize] += 1;
+    }
+
+    let mut z = m * tau((m - histogram[Q as usize + 1] as f64) / m);
+    for j in (1..=Q as usize).rev() {
+        z += histogram[j] as f64;
+        z *= 0.5;
+    }
+    z += m * sigma(histogram[0] as f64 / m);
+
+    (ALPHA_INF * m * m / z).round() as u64
+}
+
+fn tau(mut x: f64) -> f64 {
+    if x == 0.0 || x == 1.0 {
+        return 0.0;
+    }
+
+    let mut y = 1.0;
+    let mut z = 1.0 - x;
+    loop {
+        x = x.sqrt();
+        let previous = z;
+        y *= 0.5;
+        z -= (1.0 - x).powi(2) * y;
+        if previous == z {
+            return z / 3.0;
+        }
+    }
+}
+
+fn sigma(mut x: f64) -> f64 {
+    if x == 1.0 {
+        return f64::INFINITY;
+    }
+
+    let mut y = 1.0;
+    let mut z = x;
+    loop {
+        x *= x;
+        let previous = z;
+        z += x * y;
+        y += y;
+        if previous == z {
+            return z;
+        }
+    }
+}
+
+/// Returns the register selected by the element and the length of the run of zeros it observed.
+fn position(element: &[u8]) -> (usize, u8) {
+    let hash = murmur_hash_64a(element, SEED);
+    let index = (hash & (REGISTERS as u64 - 1)) as usize;
+    let hash = (hash >> P) | (1 << Q);
+
+    (index, hash.trailing_zeros() as u8 + 1)
+}
+
+pub(crate) fn murmur_hash_64a(data: &[u8], seed: u64) -> u64 {
+    const M: u64 = 0xc6a4_a793_5bd1_e995;
+    const R: u32 = 47;
+
+    let mut h = seed ^ (data.len() as u64).wrapping_mul(M);
+    let mut chunks = data.chunks_exact(8);
+
+    for chunk in &mut chunks {
+        let mut k = u64::from_le_bytes(chunk.try_into().unwrap());
+        k = k.wrapping_mul(M);
+        k ^= k >> R;
+        k = k.wrapping_mul(M);
+        h ^= k;
+        h = h.wrapping_mul(M);
+    }
+
+    let tail = chunks.remainder();
+    if !tail.is_empty() {
+        for (i, byte) in tail.iter().enumerate() {
+            h ^= (*byte as u64) << (8 * i);
+        }
+        h = h.wrapping_mul(M);
+    }
+
+    h ^= h >> R;
+    h = h.wrapping_mul(M);
+    h ^= h >> R;
+    h
+}
+
+fn dense_get(payload: &[u8], index: usize) -> u8 {
+    let byte = index * BITS / 8;
+    let first_bit = (index * BITS) & 7;
+    let b0 = payload[byte] as u16;
+    let b1 = payload.get(byte + 1).copied().unwrap_or(0) as u16;
+
+    (((b0 >> first_bit) | (b1 << (8 - first_bit))) as u8) & REGISTER_MAX
+}
+
+fn dense_set(payload: &mut [u8], index: usize, value: u8) {
+    let byte = index * BITS / 8;
+    let first_bit = (index * BITS) & 7;
+    let value = value as u16;
+    let max = REGISTER_MAX as u16;
+
+    payload[byte] &= !((max << first_bit) as u8);
+    payload[byte] |= (value << first_bit) as u8;
+    if byte + 1 < payload.len() {
+        payload[byte + 1] &= !((max >> (8 - first_bit)) as u8);
+        payload[byte + 1] |= (value >> (8 - first_bit)) as u8;
+    }
+}
+
+/// Decodes the ZERO (`00xxxxxx`), XZERO (`01xxxxxx yyyyyyyy`) and VAL (`1vvvvvxx`) opcodes.
+fn sparse_decode(payload: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    let mut registers = Vec::with_capacity(REGISTERS);
+    let mut i = 0;
+
+    while i < payload.len() {
+        let opcode = payload[i];
+        let (value, len) = if opcode & 0xC0 == 0x00 {
+            i += 1;
+            (0, (opcode & 0x3F) as usize + 1)
+        } else if opcode & 0xC0 == 0x40 {
+            let next = *payload.get(i + 1).ok_or(DecodeError::Corrupted)?;
+            i += 2;
+            (0, ((((opcode & 0x3F) as usize) << 8) | next as usize) + 1)
+        } else {
+            i += 1;
+            (((opcode >> 2) & 0x1F) + 1, (opcode & 0x03) as usize + 1)
+        };
+
+        if registers.len() + len > REGISTERS {
+            return Err(DecodeError::Corrupted);
+        }
+        registers.resize(registers.len() + len, value);
+    }
+
+    if registers.len() != REGISTERS {
+        return Err(DecodeError::Corrupted);
+    }
+
+    Ok(registers)
+}
+
+/// Encodes registers with sparse opcodes, returns None when a value is too big for VAL.
+fn sparse_encode(registers: &[u8]) -> Option<Vec<u8>> {
+    let mut payload = vec![];
+    let mut i = 0;
+
+    while i < registers.len() {
+        let value = registers[i];
+        let run = registers[i..].iter().take_while(|register| **register == value).count();
+        i += run;
+
+        if value > SPARSE_VAL_MAX_VALUE {
+            return None;
+        }
+
+        let mut remaining = run;
+        while remaining > 0 {
+            if value == 0 && remaining > SPARSE_ZERO_MAX_LEN {
+                let len = remaining.min(SPARSE_XZERO_MAX_LEN);
+                payload.push(0x40 | ((len - 1) >> 8) as u8);
+                payload.push(((len - 1) & 0xFF) as u8);
+                remaining -= len;
+            } else if value == 0 {
+                payload.push((remaining - 1) as u8);
+                remaining = 0;
+            } else {
+                let len = remaining.min(SPARSE_VAL_MAX_LEN);
+                payload.push(0x80 | ((value - 1) << 2) | (len - 1) as u8);
+                remaining -= len;
+            }
+        }
+    }
+
+    Some(payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with(elements: impl IntoIterator<Item = String>) -> HyperLogLog {
+        let mut hll = HyperLogLog::default();
+        for element in elements {
+            hll.add(element.as_bytes());
+        }
+        hll
+    }
+
+    #[test]
+    fn it_encodes_empty_hyperloglog_as_single_xzero_opcode() {
+        let mut hll = HyperLogLog::default();
+
+        let encoded = hll.encode();
+
+        assert_eq!(&b"HYLL\x01\0\0\0\0\0\0\0\0\0\0\0\x7f\xff"[..], &encoded[..]);
+    }
+
+    #[test]
+    fn it_computes_murmur_hash_like_redis() {
+        assert_eq!(0, murmur_hash_64a(b"", 0));
+        assert_ne!(murmur_hash_64a(b"abcdefgh", SEED), murmur_hash_64a(b"abcdefgi", SEED));
+    }
+
+    #[test]
+    fn it_round_trips_sparse_encoding() {
+        let mut hll = with((0..100).map(|i| format!("element:{}", i)));
+
+        let encoded = hll.encode();
+        let decoded = HyperLogLog::decode(&encoded).unwrap();
+
+        assert!(!hll.dense);
+        assert_eq!(hll, decoded);
+    }
+
+    #[test]
+    fn it_promotes_to_dense_encoding() {
+        let mut hll = with((0..5000).map(|i| format!("element:{}", i)));
+
+        let encoded = hll.encode();
+        let decoded = HyperLogLog::decode(&encoded).unwrap();
+
+        assert!(hll.dense);
+        assert_eq!(DENSE_SIZE, encoded.len());
+        assert_eq!(DENSE, encoded[4]);
+        assert_eq!(hll, decoded);
+    }
+
+    #[test]
+    fn it_packs_dense_registers_like_redis() {
+        let mut payload = vec![0u8; DENSE_SIZE - HEADER_SIZE];
+
+        dense_set(&mut payload, 0, 0b111111);
+        dense_set(&mut payload, 1, 0b000011);
+        dense_set(&mut payload, REGISTERS - 1, 51);
+
+        assert_eq!(0b1111_1111, payload[0]);
+        assert_eq!(0b0000_0000, payload[1]);
+        assert_eq!(51, dense_get(&payload, REGISTERS - 1));
+        assert_eq!(3, dense_get(&payload, 1));
+    }
+
+    #[test]
+    fn it_stays_within_error_bounds() {
+        for cardinality in [10, 100, 1_000, 10_000, 100_000] {
+            let mut hll = with((0..cardinality).map(|i| format!("user:{}", i)));
+
+            let count = hll.count() as f64;
+            let error = (count - cardinality as f64).abs() / cardinality as f64;
+
+            assert!(error < 0.03, "cardinality {} estimated as {}", cardinality, count);
+        }
+    }
+
+    #[test]
+    fn it_caches_cardinality_in_header() {
+        let mut hll = with((0..10).map(|i| i.to_string()));
+        assert_eq!(0x80, hll.cache[7] & 0x80);
+
+        let count = hll.count();
+        let decoded = HyperLogLog::decode(&hll.encode()).unwrap();
+
+        assert_eq!(count.to_le_bytes(), decoded.cache);
+    }
+
+    #[test]
+    fn it_merges_registers() {
+        let mut first = with((0..1000).map(|i| i.to_string()));
+        let second = with((500..1500).map(|i| i.to_string()));
+
+        first.merge(&second);
+
+        let error = (first.count() as f64 - 1500.0).abs() / 1500.0;
+        assert!(error < 0.03);
+    }
+
+    #[test]
+    fn it_rejects_values_that_are_not_hyperloglogs() {
+        assert_eq!(Err(DecodeError::WrongType), HyperLogLog::decode(b"plain string value"));
+        assert_eq!(Err(DecodeError::WrongType), HyperLogLog::decode(b"HYLL\0\0\0\0\0\0\0\0\0\0\0\0\x01"));
+        assert_eq!(Err(DecodeError::Corrupted), HyperLogLog::decode(b"HYLL\x01\0\0\0\0\0\0\0\0\0\0\0\x7f\xff\x00"));
+    }
+}
@@ -0,0 +1,392 @@
+use serde_json::Value;
+
+use crate::Error;
+
+/// A single step from a JSON value to one of its children.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Step {
+    Key(String),
+    Index(usize),
+}
+
+/// Concrete position of a value within a document, the root being an empty location.
+pub(crate) type Location = Vec<Step>;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Selector {
+    Name(String),
+    Index(i64),
+    Slice(Option<i64>, Option<i64>),
+    Wildcard,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Segment {
+    descendant: bool,
+    selectors: Vec<Selector>,
+}
+
+/// A parsed JSONPath. Paths not starting with `$` follow the legacy RedisJSON syntax,
+/// which selects a single value and replies with it directly instead of an array of matches.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Path {
+    source: String,
+    legacy: bool,
+    segments: Vec<Segment>,
+}
+
+impl Path {
+    pub(crate) fn root() -> Self {
+        Path { source: ".".to_string(), legacy: true, segments: vec![] }
+    }
+
+    pub(crate) fn parse(source: &str) -> Result<Self, Error> {
+        let legacy = !source.starts_with('$');
+        let normalized = match source {
+            _ if !legacy => source.to_string(),
+            "." => "$".to_string(),
+            _ if source.starts_with('.') || source.starts_with('[') => format!("${}", source),
+            _ => format!("$.{}", source),
+        };
+
+        let segments = Parser { input: normalized.as_bytes(), position: 1 }.segments()
+            .ok_or_else(|| format!("invalid JSONPath '{}'", source))?;
+
+        Ok(Path { source: source.to_string(), legacy, segments })
+    }
+
+    pub(crate) fn is_legacy(&self) -> bool {
+        self.legacy
+    }
+
+    pub(crate) fn is_root(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    pub(crate) fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// Returns the locations of all values matched by the path, in document order.
+    pub(crate) fn locate(&self, root: &Value) -> Vec<Location> {
+        locate(&self.segments, root)
+    }
+
+    /// When the path ends with a plain member name, returns the locations of the objects
+    /// that member would be added to, together with the name.
+    pub(crate) fn parents(&self, root: &Value) -> Option<(Vec<Location>, String)> {
+        let (last, parents) = self.segments.split_last()?;
+        match &last.selectors[..] {
+            [Selector::Name(name)] if !last.descendant => {
+                let locations = locate(parents, root).into_iter()
+                    .filter(|location| matches!(get(root, location), Some(Value::Object(_))))
+                    .collect();
+                Some((locations, name.clone()))
+            }
+            _ => None,
+        }
+    }
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn segments(&mut self) -> Option<Vec<Segment>> {
+        let mut segments = vec![];
+
+        while let Some(&byte) = self.input.get(self.position) {
+            let descendant = self.input[self.position..].starts_with(b"..");
+            let selectors = match byte {
+                b'.' => {
+                    self.position += if descendant { 2 } else { 1 };
+                    match self.input.get(self.position) {
+                        Some(b'[') if descendant => self.bracket()?,
+                        Some(b'*') => {
+                            self.position += 1;
+                            vec![Selector::Wildcard]
+                        }
+                        _ => vec![Selector::Name(self.name()?)],
+                    }
+                }
+                b'[' => self.bracket()?,
+                _ => return None,
+            };
+            segments.push(Segment { descendant, selectors });
+        }
+
+        Some(segments)
+    }
+
+    fn name(&mut self) -> Option<String> {
+        let start = self.position;
+        while let Some(byte) = self.input.get(self.position) {
+            if matches!(byte, b'.' | b'[' | b']' | b' ') {
+                break;
+            }
+            self.position += 1;
+        }
+
+        (self.position > start).then(|| String::from_utf8_lossy(&self.input[start..self.position]).into_owned())
+    }
+
+    fn bracket(&mut self) -> Option<Vec<Selector>> {
+        self.position += 1;
+        let mut selectors = vec![];
+
+        loop {
+            self.skip_spaces();
+            selectors.push(self.selector()?);
+            self.skip_spaces();
+            match self.input.get(self.position)? {
+                b',' => self.position += 1,
+                b']' => {
+                    self.position += 1;
+                    return Some(selectors);
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    fn selector(&mut self) -> Option<Selector> {
+        match self.input.get(self.position)? {
+            b'*' => {
+                self.position += 1;
+                Some(Selector::Wildcard)
+            }
+            quote @ (b'\'' | b'"') => {
+                let quote = *quote;
+                let start = self.position + 1;
+                let length = self.input[start..].iter().position(|&byte| byte == quote)?;
+                self.position = start + length + 1;
+                Some(Selector::Name(String::from_utf8_lossy(&self.input[start..start + length]).into_owned()))
+            }
+            _ => {
+                let start = self.integer();
+                self.skip_spaces();
+                if self.input.get(self.position) != Some(&b':') {
+                    return start.map(Selector::Index);
+                }
+                self.position += 1;
+                self.skip_spaces();
+                Some(Selector::Slice(start, self.integer()))
+            }
+        }
+    }
+
+    fn integer(&mut self) -> Option<i64> {
+        let start = self.position;
+        if self.input.get(self.position) == Some(&b'-') {
+            self.position += 1;
+        }
+        while self.input.get(self.position).is_some_and(u8::is_ascii_digit) {
+            self.position += 1;
+        }
+
+        std::str::from_utf8(&self.input[start..self.position]).ok()?.parse().ok()
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.input.get(self.position) == Some(&b' ') {
+            self.position += 1;
+        }
+    }
+}
+
+fn locate(segments: &[Segment], root: &Value) -> Vec<Location> {
+    let mut current = vec![(vec![], root)];
+
+    for segment in segments {
+        let mut next = vec![];
+        for (location, value) in current {
+            let mut candidates = vec![(location, value)];
+            if segment.descendant {
+                descendants(&mut candidates, 0);
+            }
+            for (location, value) in candidates {
+                for selector in &segment.selectors {
+                    select(selector, &location, value, &mut next);
+                }
+            }
+        }
+        current = next;
+    }
+
+    current.into_iter().map(|(location, _)| location).collect()
+}
+
+/// Appends every value nested within the candidate at the index, depth first.
+fn descendants(candidates: &mut Vec<(Location, &Value)>, index: usize) {
+    let (location, value) = candidates[index].clone();
+    for (step, child) in children(value) {
+        let mut child_location = location.clone();
+        child_location.push(step);
+        candidates.push((child_location, child));
+        descendants(candidates, candidates.len() - 1);
+    }
+}
+
+fn children(value: &Value) -> Vec<(Step, &Value)> {
+    match value {
+        Value::Object(map) => map.iter().map(|(key, child)| (Step::Key(key.clone()), child)).collect(),
+        Value::Array(items) => items.iter().enumerate().map(|(index, child)| (Step::Index(index), child)).collect(),
+        _ => vec![],
+    }
+}
+
+fn select<'a>(selector: &Selector, location: &Location, value: &'a Value, matches: &mut Vec<(Location, &'a Value)>) {
+    let mut push = |step: Step, child: &'a Value| {
+        let mut child_location = location.clone();
+        child_location.push(step);
+        matches.push((child_location, child));
+    };
+
+    match (selector, value) {
+        (Selector::Wildcard, _) => children(value).into_iter().for_each(|(step, child)| push(step, child)),
+        (Selector::Name(name), Value::Object(map)) => {
+            if let Some(child) = map.get(name) {
+                push(Step::Key(name.clone()), child);
+            }
+        }
+        (Selector::Index(index), Value::Array(items)) => {
+            let index = if *index < 0 { items.len() as i64 + index } else { *index };
+            if let Some(child) = usize::try_from(index).ok().and_then(|index| items.get(index)) {
+                push(Step::Index(index as usize), child);
+            }
+        }
+        (Selector::Slice(start, end), Value::Array(items)) => {
+            let length = items.len() as i64;
+            let clamp = |bound: i64| (if bound < 0 { length + bound } else { bound }).clamp(0, length) as usize;
+            let start = clamp(start.unwrap_or(0));
+            let end = clamp(end.unwrap_or(length));
+            for (index, child) in items.iter().enumerate().take(end).skip(start) {
+                push(Step::Index(index), child);
+            }
+        }
+        _ => {}
+    }
+}
+
+pub(crate) fn get<'a>(root: &'a Value, location: &[Step]) -> Option<&'a Value> {
+    location.iter().try_fold(root, |value, step| match (step, value) {
+        (Step::Key(key), Value::Object(map)) => map.get(key),
+        (Step::Index(index), Value::Array(items)) => items.get(*index),
+        _ => None,
+    })
+}
+
+pub(crate) fn get_mut<'a>(root: &'a mut Value, location: &[Step]) -> Option<&'a mut Value> {
+    location.iter().try_fold(root, |value, step| match (step, value) {
+        (Step::Key(key), Value::Object(map)) => map.get_mut(key),
+        (Step::Index(index), Value::Array(items)) => items.get_mut(*index),
+        _ => None,
+    })
+}
+
+/// Removes the value at the location, which must not be the root.
+pub(crate) fn remove(root: &mut Value, location: &[Step]) -> bool {
+    let Some((last, parent)) = location.split_last() else {
+        return false;
+    };
+
+    match (last, get_mut(root, parent)) {
+        (Step::Key(key), Some(Value::Object(map))) => {
+            let length = map.len();
+            map.retain(|name, _| name != key);
+            map.len() < length
+        }
+        (Step::Index(index), Some(Value::Array(items))) if *index < items.len() => {
+            items.remove(*index);
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Returns the name of the value's type as reported by JSON.TYPE.
+pub(crate) fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(number) if number.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Approximate number of bytes taken by the document.
+pub(crate) fn memory_usage(value: &Value) -> usize {
+    std::mem::size_of::<Value>() + match value {
+        Value::String(string) => string.len(),
+        Value::Array(values) => values.iter().map(memory_usage).sum(),
+        Value::Object(map) => map.iter().map(|(key, value)| key.len() + std::mem::size_of::<String>() + memory_usage(value)).sum(),
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn matches(path: &str, document: &Value) -> Vec<Value> {
+        let path = Path::parse(path).unwrap();
+        path.locate(document).iter().map(|location| get(document, location).unwrap().clone()).collect()
+    }
+
+    #[test]
+    fn it_selects_members_and_indexes() {
+        let document = json!({"a": {"b": [1, 2, 3]}, "c": "d"});
+
+        assert_eq!(vec![json!([1, 2, 3])], matches("$.a.b", &document));
+        assert_eq!(vec![json!(3)], matches("$.a.b[-1]", &document));
+        assert_eq!(vec![json!(2), json!(3)], matches("$.a.b[1:]", &document));
+        assert_eq!(vec![json!(1), json!(3)], matches("$.a.b[0,2]", &document));
+        assert_eq!(vec![json!("d")], matches("$['c']", &document));
+        assert_eq!(vec![document.clone()], matches("$", &document));
+        assert!(matches("$.missing", &document).is_empty());
+    }
+
+    #[test]
+    fn it_selects_wildcards_and_descendants() {
+        let document = json!({"a": {"x": 1}, "b": {"x": 2, "c": {"x": 3}}});
+
+        assert_eq!(vec![json!(1), json!(2), json!(3)], matches("$..x", &document));
+        assert_eq!(vec![json!({"x": 1}), json!({"x": 2, "c": {"x": 3}})], matches("$.*", &document));
+    }
+
+    #[test]
+    fn it_parses_legacy_paths() {
+        let document = json!({"a": {"b": [1, 2]}});
+
+        assert!(Path::parse(".").unwrap().is_root());
+        assert!(Path::parse(".a").unwrap().is_legacy());
+        assert!(!Path::parse("$.a").unwrap().is_legacy());
+        assert_eq!(vec![json!(2)], matches("a.b[1]", &document));
+        assert_eq!(vec![json!([1, 2])], matches(".a.b", &document));
+    }
+
+    #[test]
+    fn it_rejects_invalid_paths() {
+        assert!(Path::parse("$.a[").is_err());
+        assert!(Path::parse("$a").is_err());
+        assert!(Path::parse("$.a[?(@.b)]").is_err());
+    }
+
+    #[test]
+    fn it_removes_values_preserving_member_order() {
+        let mut document = json!({"a": 1, "b": 2, "c": [1, 2, 3]});
+
+        assert!(remove(&mut document, &[Step::Key("a".to_string())]));
+        assert!(remove(&mut document, &[Step::Key("c".to_string()), Step::Index(0)]));
+        assert!(!remove(&mut document, &[Step::Key("a".to_string())]));
+
+        assert_eq!(r#"{"b":2,"c":[2,3]}"#, document.to_string());
+    }
+}
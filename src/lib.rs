@@ -1,7 +1,22 @@
+pub(crate) mod auth;
+pub(crate) mod bloom;
+pub(crate) mod cluster;
 pub(crate) mod frame;
+pub(crate) mod geo;
+pub(crate) mod glob;
 pub(crate) mod command;
 pub(crate) mod connection;
+pub(crate) mod cuckoo;
 pub(crate) mod database;
+pub(crate) mod eviction;
+pub(crate) mod hyperloglog;
+pub(crate) mod json;
+pub(crate) mod rdb;
+pub(crate) mod replication;
+pub mod sentinel;
+pub(crate) mod sorted_set;
+pub(crate) mod time_series;
+pub(crate) mod worker;
 pub mod server;
 
 pub type Error = Box<dyn std::error::Error + Send + Sync>;
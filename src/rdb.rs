@@ -0,0 +1,656 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use bytes::Bytes;
+
+use crate::bloom::BloomFilter;
+use crate::cuckoo::CuckooFilter;
+use crate::database::{Database, Value, MAX_STRING_LENGTH};
+use crate::sorted_set::{CompactLimits, SortedSet};
+use crate::time_series::TimeSeries;
+
+/// Snapshots are written in the layout of Redis' RDB files, the version being the one of Redis 7.2.
+const MAGIC: &[u8] = b"REDIS0011";
+/// RDB version written after DUMP payloads.
+const VERSION: u16 = 11;
+/// Latest RDB version of DUMP payloads read, the one of Redis 7.4 which only adds types this server lacks.
+const MAX_VERSION: u16 = 12;
+/// Reflected polynomial of the CRC-64/Jones checksum Redis uses.
+const CRC64_POLYNOMIAL: u64 = 0x95ac_9329_ac4b_c9b5;
+
+/// Most bytes LZF expands one compressed byte to, a three byte back reference copying 264.
+const LZF_MAX_EXPANSION: usize = 88;
+
+const TYPE_STRING: u8 = 0;
+const TYPE_ZSET_2: u8 = 5;
+const TYPE_MODULE_2: u8 = 7;
+const TYPE_ZSET_LISTPACK: u8 = 17;
+
+const OPCODE_EXPIRETIME_MS: u8 = 0xfc;
+const OPCODE_SELECTDB: u8 = 0xfe;
+const OPCODE_EOF: u8 = 0xff;
+
+const MODULE_OPCODE_EOF: u64 = 0;
+const MODULE_OPCODE_UINT: u64 = 2;
+const MODULE_OPCODE_DOUBLE: u64 = 4;
+const MODULE_OPCODE_STRING: u64 = 5;
+
+/// Characters module type names are made of, their positions encode the names in module ids.
+const MODULE_CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// The payload is truncated or does not follow the format.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Corrupt;
+
+impl std::fmt::Display for Corrupt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Bad data format")
+    }
+}
+
+impl std::error::Error for Corrupt {}
+
+/// Key read from a snapshot, with the index of its database and its expiration.
+pub(crate) type Loaded = (usize, Bytes, Value, Option<SystemTime>);
+
+/// Writes a snapshot of every database.
+pub(crate) fn save(db: &Database) -> Vec<u8> {
+    assemble(vec![save_keys(db)])
+}
+
+/// Serializes the keys of every database, one buffer per database. Snapshots of keyspaces
+/// held in parts are put together from the keys of each part with [`assemble`].
+pub(crate) fn save_keys(db: &Database) -> Vec<Vec<u8>> {
+    db.lock_all().iter()
+        .map(|keyspace| {
+            let mut out = vec![];
+            for (key, value, expires_at) in keyspace.iter() {
+                if let Some(expires_at) = expires_at {
+                    out.push(OPCODE_EXPIRETIME_MS);
+                    let milliseconds = expires_at.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64;
+                    out.extend(milliseconds.to_le_bytes());
+                }
+                out.push(value_type(value));
+                write_string(&mut out, key);
+                write_value(&mut out, value);
+            }
+            out
+        })
+        .collect()
+}
+
+/// Writes a snapshot of the keys serialized by [`save_keys`] for each part of the keyspace.
+pub(crate) fn assemble(parts: Vec<Vec<Vec<u8>>>) -> Vec<u8> {
+    let mut out = MAGIC.to_vec();
+
+    let count = parts.iter().map(Vec::len).max().unwrap_or(0);
+    for index in 0..count {
+        let keys: Vec<&Vec<u8>> = parts.iter().filter_map(|part| part.get(index)).filter(|keys| !keys.is_empty()).collect();
+        if keys.is_empty() {
+            continue;
+        }
+        out.push(OPCODE_SELECTDB);
+        write_length(&mut out, index as u64);
+        keys.into_iter().for_each(|keys| out.extend(keys));
+    }
+
+    out.push(OPCODE_EOF);
+    // A zero checksum tells readers checksums are disabled.
+    out.extend(0u64.to_le_bytes());
+    out
+}
+
+/// Replaces the contents of every database with the snapshot, leaving them untouched when it is corrupt.
+pub(crate) fn load(db: &Database, snapshot: &[u8]) -> Result<(), Corrupt> {
+    replace(db, read(snapshot, db.count(), db.memory().compact_limits())?);
+    Ok(())
+}
+
+/// Replaces the contents of every database with the keys read from a snapshot.
+pub(crate) fn replace(db: &Database, entries: Vec<Loaded>) {
+    let mut keyspaces = db.lock_all();
+    for keyspace in keyspaces.iter_mut() {
+        keyspace.clear();
+    }
+    for (index, key, value, expires_at) in entries {
+        keyspaces[index].insert_with_expiration(key, value, expires_at);
+    }
+}
+
+/// Reads the keys of a snapshot of at most as many databases.
+pub(crate) fn read(snapshot: &[u8], databases: usize, limits: CompactLimits) -> Result<Vec<Loaded>, Corrupt> {
+    let mut reader = Reader::new(snapshot);
+    if reader.take(MAGIC.len())? != MAGIC {
+        return Err(Corrupt);
+    }
+
+    let mut entries = vec![];
+    let mut index = 0;
+    let mut expires_at = None;
+    loop {
+        match reader.byte()? {
+            OPCODE_EOF => {
+                reader.take(8)?;
+                break;
+            }
+            OPCODE_SELECTDB => index = reader.length()? as usize,
+            OPCODE_EXPIRETIME_MS => {
+                let milliseconds = u64::from_le_bytes(reader.take(8)?.try_into().unwrap());
+                expires_at = Some(UNIX_EPOCH + Duration::from_millis(milliseconds));
+            }
+            kind => {
+                let key = reader.string()?;
+                let value = read_value(&mut reader, kind, limits)?;
+                if index >= databases {
+                    return Err(Corrupt);
+                }
+                entries.push((index, key, value, expires_at.take()));
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Serializes a value as DUMP does: its type and encoding, the RDB version and a checksum.
+pub(crate) fn dump(value: &Value) -> Vec<u8> {
+    let mut out = vec![value_type(value)];
+    write_value(&mut out, value);
+    out.extend(VERSION.to_le_bytes());
+    out.extend(crc64(&out).to_le_bytes());
+    out
+}
+
+/// Reads a value serialized by [`dump`], refusing payloads of later versions or altered ones.
+pub(crate) fn undump(payload: &[u8], limits: CompactLimits) -> Result<Value, Corrupt> {
+    let Some((body, checksum)) = payload.split_last_chunk::<8>() else { return Err(Corrupt) };
+    let Some((value, version)) = body.split_last_chunk::<2>() else { return Err(Corrupt) };
+    if u16::from_le_bytes(*version) > MAX_VERSION || u64::from_le_bytes(*checksum) != crc64(body) {
+        return Err(Corrupt);
+    }
+
+    let mut reader = Reader::new(value);
+    let kind = reader.byte()?;
+    let value = read_value(&mut reader, kind, limits)?;
+    match reader.input.is_empty() {
+        true => Ok(value),
+        false => Err(Corrupt),
+    }
+}
+
+fn crc64(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0, |crc, byte| {
+        (0..8).fold(crc ^ *byte as u64, |crc, _| match crc & 1 {
+            0 => crc >> 1,
+            _ => (crc >> 1) ^ CRC64_POLYNOMIAL,
+        })
+    })
+}
+
+fn value_type(value: &Value) -> u8 {
+    match value {
+        Value::String(_) => TYPE_STRING,
+        Value::SortedSet(_) => TYPE_ZSET_2,
+        Value::Json(_) | Value::Bloom(_) | Value::Cuckoo(_) | Value::TimeSeries(_) => TYPE_MODULE_2,
+    }
+}
+
+/// Writes the value without its type, module types start with the id of their type name.
+pub(crate) fn write_value(out: &mut Vec<u8>, value: &Value) {
+    match value {
+        Value::String(string) => write_string(out, string),
+        Value::SortedSet(set) => {
+            write_length(out, set.len() as u64);
+            for (member, score) in set.iter() {
+                write_string(out, member);
+                out.extend(score.to_le_bytes());
+            }
+        }
+        module => {
+            write_length(out, module_id(module.type_name()));
+            let mut writer = ModuleWriter { out };
+            match module {
+                Value::Json(json) => writer.string(json.to_string().as_bytes()),
+                Value::Bloom(filter) => filter.save(&mut writer),
+                Value::Cuckoo(filter) => filter.save(&mut writer),
+                Value::TimeSeries(series) => series.save(&mut writer),
+                Value::String(_) | Value::SortedSet(_) => unreachable!(),
+            }
+            write_length(writer.out, MODULE_OPCODE_EOF);
+        }
+    }
+}
+
+/// Reads a value of the type written by [`write_value`].
+pub(crate) fn read_value(reader: &mut Reader, kind: u8, limits: CompactLimits) -> Result<Value, Corrupt> {
+    match kind {
+        TYPE_STRING => Ok(Value::String(reader.string()?)),
+        TYPE_ZSET_2 => {
+            let mut set = SortedSet::default();
+            for _ in 0..reader.length()? {
+                let member = reader.string()?;
+                let score = f64::from_le_bytes(reader.take(8)?.try_into().unwrap());
+                if score.is_nan() {
+                    return Err(Corrupt);
+                }
+                set.insert(member, score, limits);
+            }
+            Ok(Value::SortedSet(set))
+        }
+        TYPE_ZSET_LISTPACK => {
+            let mut set = SortedSet::default();
+            let mut entries = listpack(&reader.string()?)?.into_iter();
+            while let (Some(member), Some(score)) = (entries.next(), entries.next()) {
+                let score = std::str::from_utf8(&score).ok()
+                    .and_then(|score| score.parse::<f64>().ok())
+                    .filter(|score| !score.is_nan())
+                    .ok_or(Corrupt)?;
+                set.insert(member, score, limits);
+            }
+            Ok(Value::SortedSet(set))
+        }
+        TYPE_MODULE_2 => {
+            let id = reader.length()?;
+            let name = ["ReJSON-RL", "MBbloom--", "MBbloomCF", "TSDB-TYPE"].into_iter()
+                .find(|name| module_id(name) == id)
+                .ok_or(Corrupt)?;
+            let value = match name {
+                "ReJSON-RL" => Value::Json(serde_json::from_slice(&reader.module_string()?).map_err(|_| Corrupt)?),
+                "MBbloom--" => Value::Bloom(BloomFilter::load(reader)?),
+                "MBbloomCF" => Value::Cuckoo(CuckooFilter::load(reader)?),
+                _ => Value::TimeSeries(TimeSeries::load(reader)?),
+            };
+            match reader.length()? {
+                MODULE_OPCODE_EOF => Ok(value),
+                _ => Err(Corrupt),
+            }
+        }
+        _ => Err(Corrupt),
+    }
+}
+
+/// Entries of a listpack, the compact encoding Redis gives small collections, integers
+/// being turned to strings. Entries are followed by their length to be walked backwards.
+fn listpack(blob: &[u8]) -> Result<Vec<Bytes>, Corrupt> {
+    let mut reader = Reader::new(blob);
+    let total = u32::from_le_bytes(reader.take(4)?.try_into().unwrap());
+    if total as usize != blob.len() {
+        return Err(Corrupt);
+    }
+    reader.take(2)?;
+
+    let mut entries = vec![];
+    loop {
+        let before = reader.input.len();
+        let first = reader.byte()?;
+        let integer = |bytes: &[u8]| {
+            let mut padded = [if bytes[bytes.len() - 1] & 0x80 == 0 { 0 } else { 0xff }; 8];
+            padded[..bytes.len()].copy_from_slice(bytes);
+            i64::from_le_bytes(padded)
+        };
+        let entry = match first {
+            0x00..=0x7f => Bytes::from(first.to_string()),
+            0x80..=0xbf => Bytes::copy_from_slice(reader.take((first & 0x3f) as usize)?),
+            0xc0..=0xdf => {
+                let unsigned = (((first & 0x1f) as i64) << 8) | reader.byte()? as i64;
+                Bytes::from(((unsigned << 51) >> 51).to_string())
+            }
+            0xe0..=0xef => {
+                let length = (((first & 0x0f) as usize) << 8) | reader.byte()? as usize;
+                Bytes::copy_from_slice(reader.take(length)?)
+            }
+            0xf0 => {
+                let length = u32::from_le_bytes(reader.take(4)?.try_into().unwrap());
+                Bytes::copy_from_slice(reader.take(length as usize)?)
+            }
+            0xf1 => Bytes::from(integer(reader.take(2)?).to_string()),
+            0xf2 => Bytes::from(integer(reader.take(3)?).to_string()),
+            0xf3 => Bytes::from(integer(reader.take(4)?).to_string()),
+            0xf4 => Bytes::from(integer(reader.take(8)?).to_string()),
+            0xff if reader.input.is_empty() => return Ok(entries),
+            _ => return Err(Corrupt),
+        };
+        let length = before - reader.input.len();
+        reader.take(match length {
+            0..=127 => 1,
+            128..=16382 => 2,
+            16383..=2097150 => 3,
+            2097151..=268435454 => 4,
+            _ => 5,
+        })?;
+        entries.push(entry);
+    }
+}
+
+/// Expands a string compressed with LZF, made of literal runs and back references to the output.
+fn lzf_decompress(mut input: &[u8], length: usize) -> Result<Vec<u8>, Corrupt> {
+    let mut out = Vec::with_capacity(length);
+    while let Some((&control, rest)) = input.split_first() {
+        input = rest;
+        let control = control as usize;
+        if control < 32 {
+            let literal = input.get(..control + 1).ok_or(Corrupt)?;
+            if out.len() + literal.len() > length {
+                return Err(Corrupt);
+            }
+            out.extend_from_slice(literal);
+            input = &input[control + 1..];
+            continue;
+        }
+
+        let mut run = control >> 5;
+        if run == 7 {
+            run += *input.first().ok_or(Corrupt)? as usize;
+            input = &input[1..];
+        }
+        let offset = ((control & 0x1f) << 8) + *input.first().ok_or(Corrupt)? as usize + 1;
+        input = &input[1..];
+        let start = out.len().checked_sub(offset).ok_or(Corrupt)?;
+        if out.len() + run + 2 > length {
+            return Err(Corrupt);
+        }
+        // The reference may overlap the bytes it produces, so they are copied one by one.
+        for index in start..start + run + 2 {
+            out.push(out[index]);
+        }
+    }
+    match out.len() == length {
+        true => Ok(out),
+        false => Err(Corrupt),
+    }
+}
+
+/// Id of a module type, its nine character name packed in six bits per character
+/// followed by ten bits of encoding version, as Redis computes it.
+fn module_id(name: &str) -> u64 {
+    let id = name.bytes().fold(0, |id, char| {
+        (id << 6) | MODULE_CHARSET.iter().position(|known| *known == char).unwrap() as u64
+    });
+    id << 10
+}
+
+/// Lengths take one, two, five or nine bytes depending on their size.
+fn write_length(out: &mut Vec<u8>, length: u64) {
+    match length {
+        0..=0x3f => out.push(length as u8),
+        0x40..=0x3fff => out.extend([0x40 | (length >> 8) as u8, length as u8]),
+        0x4000..=0xffff_ffff => {
+            out.push(0x80);
+            out.extend((length as u32).to_be_bytes());
+        }
+        _ => {
+            out.push(0x81);
+            out.extend(length.to_be_bytes());
+        }
+    }
+}
+
+fn write_string(out: &mut Vec<u8>, string: &[u8]) {
+    write_length(out, string.len() as u64);
+    out.extend(string);
+}
+
+/// Writes the fields of module values, each one tagged with its kind like Redis' module API does.
+pub(crate) struct ModuleWriter<'a> {
+    out: &'a mut Vec<u8>,
+}
+
+impl ModuleWriter<'_> {
+    pub(crate) fn uint(&mut self, value: u64) {
+        write_length(self.out, MODULE_OPCODE_UINT);
+        write_length(self.out, value);
+    }
+
+    pub(crate) fn double(&mut self, value: f64) {
+        write_length(self.out, MODULE_OPCODE_DOUBLE);
+        self.out.extend(value.to_le_bytes());
+    }
+
+    pub(crate) fn string(&mut self, value: &[u8]) {
+        write_length(self.out, MODULE_OPCODE_STRING);
+        write_string(self.out, value);
+    }
+}
+
+pub(crate) struct Reader<'a> {
+    input: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(input: &'a [u8]) -> Self {
+        Reader { input }
+    }
+
+    fn take(&mut self, count: usize) -> Result<&'a [u8], Corrupt> {
+        if self.input.len() < count {
+            return Err(Corrupt);
+        }
+        let (taken, rest) = self.input.split_at(count);
+        self.input = rest;
+        Ok(taken)
+    }
+
+    pub(crate) fn byte(&mut self) -> Result<u8, Corrupt> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub(crate) fn length(&mut self) -> Result<u64, Corrupt> {
+        let first = self.byte()?;
+        match first >> 6 {
+            0 => Ok((first & 0x3f) as u64),
+            1 => Ok((((first & 0x3f) as u64) << 8) | self.byte()? as u64),
+            2 if first == 0x80 => Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()) as u64),
+            2 if first == 0x81 => Ok(u64::from_be_bytes(self.take(8)?.try_into().unwrap())),
+            _ => Err(Corrupt),
+        }
+    }
+
+    /// Reads a string, including the integer and LZF encodings Redis uses for numeric and long strings.
+    pub(crate) fn string(&mut self) -> Result<Bytes, Corrupt> {
+        if self.input.first() == Some(&0xc3) {
+            self.take(1)?;
+            let compressed = usize::try_from(self.length()?).map_err(|_| Corrupt)?;
+            let length = usize::try_from(self.length()?).map_err(|_| Corrupt)?;
+            // The length is only checked against what the payload can possibly expand to before allocating.
+            if length > MAX_STRING_LENGTH || length > compressed.saturating_mul(LZF_MAX_EXPANSION) {
+                return Err(Corrupt);
+            }
+            return Ok(Bytes::from(lzf_decompress(self.take(compressed)?, length)?));
+        }
+
+        let integer = match self.input.first() {
+            Some(0xc0) => Some(self.take(2)?[1] as i8 as i64),
+            Some(0xc1) => Some(i16::from_le_bytes(self.take(3)?[1..].try_into().unwrap()) as i64),
+            Some(0xc2) => Some(i32::from_le_bytes(self.take(5)?[1..].try_into().unwrap()) as i64),
+            _ => None,
+        };
+        if let Some(integer) = integer {
+            return Ok(Bytes::from(integer.to_string()));
+        }
+
+        let length = self.length()?;
+        let length = usize::try_from(length).map_err(|_| Corrupt)?;
+        Ok(Bytes::copy_from_slice(self.take(length)?))
+    }
+
+    pub(crate) fn module_uint(&mut self) -> Result<u64, Corrupt> {
+        match self.length()? {
+            MODULE_OPCODE_UINT => self.length(),
+            _ => Err(Corrupt),
+        }
+    }
+
+    pub(crate) fn module_double(&mut self) -> Result<f64, Corrupt> {
+        match self.length()? {
+            MODULE_OPCODE_DOUBLE => Ok(f64::from_le_bytes(self.take(8)?.try_into().unwrap())),
+            _ => Err(Corrupt),
+        }
+    }
+
+    pub(crate) fn module_string(&mut self) -> Result<Bytes, Corrupt> {
+        match self.length()? {
+            MODULE_OPCODE_STRING => self.string(),
+            _ => Err(Corrupt),
+        }
+    }
+
+    /// Reads a count of items, refusing counts the remaining input cannot possibly hold.
+    pub(crate) fn module_count(&mut self) -> Result<usize, Corrupt> {
+        match self.module_uint()? {
+            count if count as usize <= self.input.len() => Ok(count as usize),
+            _ => Err(Corrupt),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn it_restores_every_database_from_a_snapshot() {
+        let source = Database::new(2);
+        let expires_at = UNIX_EPOCH + Duration::from_millis(4_102_444_800_000);
+        {
+            let mut keyspace = source.lock().unwrap();
+            keyspace.insert("string", Bytes::from(vec![7; 20_000]));
+            keyspace.insert_with_expiration("volatile", Bytes::from("1"), Some(expires_at));
+            let mut set = SortedSet::default();
+            set.insert(Bytes::from("member"), -2.5, CompactLimits::default());
+            keyspace.insert("zset", set);
+            keyspace.insert("json", json!({"a": [1, "two", null]}));
+            let mut bloom = BloomFilter::default();
+            bloom.add(b"item").unwrap();
+            keyspace.insert("bloom", bloom);
+            let mut cuckoo = CuckooFilter::default();
+            cuckoo.add(b"item");
+            keyspace.insert("cuckoo", cuckoo);
+            let mut series = TimeSeries::default();
+            series.create_rule(Bytes::from("compacted"), crate::time_series::Aggregation::Sum, 10);
+            series.add(5, 1.5, None).unwrap();
+            keyspace.insert("series", series);
+        }
+        source.select(1).unwrap().lock().unwrap().insert("other", Bytes::from("db"));
+
+        let target = Database::new(2);
+        target.lock().unwrap().insert("stale", Bytes::from("gone"));
+        load(&target, &save(&source)).unwrap();
+
+        let (source, target) = (source.lock_all(), target.lock_all());
+        for (source, target) in source.iter().zip(target.iter()) {
+            assert_eq!(source.len(), target.len());
+            for (key, value, expires_at) in source.iter() {
+                assert_eq!(Some(value), target.get_value(key), "{:?}", key);
+                assert_eq!(expires_at, target.expiration(key));
+            }
+        }
+    }
+
+    #[test]
+    fn it_refuses_corrupt_snapshots() {
+        let db = Database::new(1);
+        db.lock().unwrap().insert("a", Bytes::from("1"));
+        let snapshot = save(&db);
+
+        assert_eq!(Err(Corrupt), load(&db, &snapshot[..snapshot.len() - 12]));
+        assert_eq!(Err(Corrupt), load(&db, b"REDIS0011\xfe\x05\x00\x01a\x01b\xff\0\0\0\0\0\0\0\0"));
+        assert!(db.lock().unwrap().contains_key("a"));
+    }
+
+    #[test]
+    fn it_dumps_values_with_a_checksum() {
+        assert_eq!(0xe9c6_d914_c4b8_d9ca, crc64(b"123456789"));
+
+        let mut set = SortedSet::default();
+        set.insert(Bytes::from("member"), 1.5, CompactLimits::default());
+        for value in [Value::String(Bytes::from("hello")), Value::SortedSet(set)] {
+            assert_eq!(Ok(value.clone()), undump(&dump(&value), CompactLimits::default()));
+        }
+
+        let mut payload = dump(&Value::String(Bytes::from("hello")));
+        assert_eq!(b"\x00\x05hello\x0b\x00", &payload[..9]);
+        payload[1] = 4;
+        assert_eq!(Err(Corrupt), undump(&payload, CompactLimits::default()));
+        assert_eq!(Err(Corrupt), undump(b"\x00\x05hello", CompactLimits::default()));
+    }
+
+    #[test]
+    fn it_undumps_payloads_of_redis() {
+        let signed = |body: &[u8]| {
+            let mut payload = body.to_vec();
+            payload.extend(12u16.to_le_bytes());
+            payload.extend(crc64(&payload).to_le_bytes());
+            payload
+        };
+
+        // 24 times "a", compressed to a literal and a back reference.
+        let string = signed(b"\x00\xc3\x05\x18\x00a\xe0\x0e\x00");
+        assert_eq!(Ok(Value::String(Bytes::from("a".repeat(24)))), undump(&string, CompactLimits::default()));
+
+        let listpack = b"\x1b\x00\x00\x00\x06\x00\x81a\x02\x01\x01\x81b\x02\x832.5\x04\xdf\xfb\x02\xf1\xe8\x03\x03\xff";
+        let mut zset = vec![TYPE_ZSET_LISTPACK, listpack.len() as u8];
+        zset.extend(listpack);
+        let mut set = SortedSet::default();
+        set.insert(Bytes::from("a"), 1.0, CompactLimits::default());
+        set.insert(Bytes::from("b"), 2.5, CompactLimits::default());
+        set.insert(Bytes::from("-5"), 1000.0, CompactLimits::default());
+        assert_eq!(Ok(Value::SortedSet(set)), undump(&signed(&zset), CompactLimits::default()));
+
+        zset[2] = 0x1c;
+        assert_eq!(Err(Corrupt), undump(&signed(&zset), CompactLimits::default()));
+    }
+
+    #[test]
+    fn it_refuses_nan_scores() {
+        let signed = |body: &[u8]| {
+            let mut payload = body.to_vec();
+            payload.extend(12u16.to_le_bytes());
+            payload.extend(crc64(&payload).to_le_bytes());
+            payload
+        };
+
+        let mut zset = vec![TYPE_ZSET_2, 1, 1, b'a'];
+        zset.extend(f64::NAN.to_le_bytes());
+        assert_eq!(Err(Corrupt), undump(&signed(&zset), CompactLimits::default()));
+
+        let listpack = b"\x0f\x00\x00\x00\x02\x00\x81a\x02\x83nan\x04\xff";
+        let mut zset = vec![TYPE_ZSET_LISTPACK, listpack.len() as u8];
+        zset.extend(listpack);
+        assert_eq!(Err(Corrupt), undump(&signed(&zset), CompactLimits::default()));
+        let last = zset.len() - 5;
+        zset[last..last + 3].copy_from_slice(b"1.5");
+        assert!(undump(&signed(&zset), CompactLimits::default()).is_ok());
+    }
+
+    #[test]
+    fn it_refuses_lzf_strings_claiming_huge_lengths() {
+        let signed = |body: &[u8]| {
+            let mut payload = body.to_vec();
+            payload.extend(12u16.to_le_bytes());
+            payload.extend(crc64(&payload).to_le_bytes());
+            payload
+        };
+
+        // Five compressed bytes claiming 2^60 bytes, then claiming 441, more than they can expand to.
+        let mut forged = b"\x00\xc3\x05\x81".to_vec();
+        forged.extend((1u64 << 60).to_be_bytes());
+        forged.extend(b"\x00a\xe0\x0e\x00");
+        assert_eq!(Err(Corrupt), undump(&signed(&forged), CompactLimits::default()));
+        assert_eq!(Err(Corrupt), undump(&signed(b"\x00\xc3\x05\x41\xb9\x00a\xe0\x0e\x00"), CompactLimits::default()));
+        // A length within bounds the data does not fill is refused as well.
+        assert_eq!(Err(Corrupt), undump(&signed(b"\x00\xc3\x05\x20\x00a\xe0\x0e\x00"), CompactLimits::default()));
+    }
+
+    #[test]
+    fn it_encodes_lengths_and_module_ids() {
+        for length in [0, 63, 64, 16383, 16384, u32::MAX as u64, u64::MAX] {
+            let mut out = vec![];
+            write_length(&mut out, length);
+            assert_eq!(Ok(length), Reader::new(&out).length());
+        }
+        assert_eq!(Ok(Bytes::from("-2")), Reader::new(&[0xc0, 0xfe]).string());
+        assert_eq!(0, module_id("ReJSON-RL") & 0x3ff);
+        assert_ne!(module_id("MBbloom--"), module_id("MBbloomCF"));
+    }
+}
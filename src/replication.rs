@@ -0,0 +1,935 @@
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU16, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+use tokio::net::TcpStream;
+use tokio::sync::{mpsc, Mutex, MutexGuard, Notify, RwLock};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+use crate::command::{command_name, error_frame, next_integer, next_string, syntax_error, wrong_arity};
+use crate::connection::Connection;
+use crate::database::{shard_of, SHARDS};
+use crate::eviction::random;
+use crate::frame::Frame;
+use crate::server::Server;
+use crate::{Error, Result};
+
+/// Bytes of the replication stream kept for replicas resuming after a short disconnection.
+pub(crate) const BACKLOG_SIZE: usize = 1024 * 1024;
+/// Bytes of the stream a replica may lag behind before it is disconnected, the hard limit
+/// of Redis' default `client-output-buffer-limit replica`.
+const REPLICA_BUFFER_LIMIT: usize = 256 * 1024 * 1024;
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+/// How often replicas report the offset they processed.
+const ACK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Replication role and stream of a server. Writes hold the state lock shared until the
+/// first replica attaches. From then on they are ordered per shard instead, see
+/// [`Replication::order`], and only take the state lock to feed the stream.
+#[derive(Default)]
+pub(crate) struct Replication {
+    pub(crate) state: RwLock<State>,
+    /// One lock per shard of the keyspace, whatever the database.
+    order: [Mutex<()>; SHARDS],
+    /// Port the server listens on, announced to masters.
+    port: AtomicU16,
+    /// Woken whenever a replica acknowledges an offset.
+    acknowledged: Notify,
+}
+
+pub(crate) struct State {
+    replid: String,
+    /// Former id of the stream with its last offset, so replicas of the previous master
+    /// can continue from a promoted replica.
+    replid2: Option<(String, u64)>,
+    /// Bytes of the stream produced since its start, the offset of the last one.
+    offset: u64,
+    /// Created once the first replica attaches, its presence means writes are propagated.
+    backlog: Option<VecDeque<u8>>,
+    /// Database the last propagated command ran against.
+    selected: Option<usize>,
+    /// Database the stream has selected at its current offset, where a replica continuing
+    /// the stream after a partial resynchronization applies the commands that follow.
+    stream_database: usize,
+    replicas: Vec<Replica>,
+    next_replica: u64,
+    master: Option<Master>,
+    /// Bytes queued for a replica past which it is dropped.
+    buffer_limit: usize,
+}
+
+struct Replica {
+    id: u64,
+    address: SocketAddr,
+    /// Port the replica listens on as announced with REPLCONF, its peer port until then.
+    port: u16,
+    stream: mpsc::UnboundedSender<Bytes>,
+    /// Bytes queued on the stream but not written to the connection yet.
+    pending: Arc<AtomicUsize>,
+    /// Offset the replica processed the stream up to, as reported by REPLCONF ACK.
+    acknowledged: u64,
+}
+
+struct Master {
+    host: String,
+    port: u16,
+    status: &'static str,
+    link: JoinHandle<()>,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        State {
+            replid: new_replid(),
+            replid2: None,
+            offset: 0,
+            backlog: None,
+            selected: None,
+            stream_database: 0,
+            replicas: vec![],
+            next_replica: 0,
+            master: None,
+            buffer_limit: REPLICA_BUFFER_LIMIT,
+        }
+    }
+}
+
+impl State {
+    pub(crate) fn is_replica(&self) -> bool {
+        self.master.is_some()
+    }
+
+    pub(crate) fn propagates(&self) -> bool {
+        self.backlog.is_some()
+    }
+
+    /// Offset of the last byte of the stream.
+    pub(crate) fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Appends the command to the stream, preceded by a SELECT when it runs against
+    /// another database than the previous one.
+    pub(crate) fn propagate(&mut self, database: usize, frames: Vec<Frame>) {
+        if !self.propagates() {
+            return;
+        }
+        if self.selected != Some(database) {
+            self.selected = Some(database);
+            self.stream_database = database;
+            self.feed(&command(&["SELECT", &database.to_string()]));
+        }
+        self.feed(&Vec::from(Frame::Array(frames)));
+    }
+
+    fn feed(&mut self, bytes: &[u8]) {
+        self.offset += bytes.len() as u64;
+        if let Some(backlog) = &mut self.backlog {
+            backlog.extend(bytes);
+            let excess = backlog.len().saturating_sub(BACKLOG_SIZE);
+            backlog.drain(..excess);
+        }
+        let bytes = Bytes::copy_from_slice(bytes);
+        let limit = self.buffer_limit;
+        self.replicas.retain(|replica| replica.send(bytes.clone(), limit));
+    }
+
+    /// Part of the stream a replica misses when it asks to continue the stream of the
+    /// id from the offset, `None` when it is unknown or not in the backlog anymore.
+    fn continuation(&self, replid: &str, offset: u64) -> Option<Vec<u8>> {
+        let known = replid == self.replid || self.replid2.as_ref().is_some_and(|(id, last)| id == replid && offset <= last + 1);
+        let backlog = self.backlog.as_ref()?;
+        let first = self.offset + 1 - backlog.len() as u64;
+        if !known || offset < first || offset > self.offset + 1 {
+            return None;
+        }
+
+        Some(backlog.iter().skip((offset - first) as usize).copied().collect())
+    }
+
+    /// Starts a new stream history, keeping the current one as the previous.
+    fn shift_replid(&mut self) {
+        let previous = std::mem::replace(&mut self.replid, new_replid());
+        self.replid2 = Some((previous, self.offset));
+    }
+}
+
+impl Replica {
+    /// Queues the bytes for the replica, false once it is gone or lags so far behind that
+    /// its queue would outgrow the limit. Dropping it then closes its connection, it
+    /// resynchronizes instead of the master buffering without bounds.
+    fn send(&self, bytes: Bytes, limit: usize) -> bool {
+        let pending = self.pending.fetch_add(bytes.len(), Ordering::Relaxed) + bytes.len();
+        pending <= limit && self.stream.send(bytes).is_ok()
+    }
+}
+
+impl Replication {
+    /// Locks the shards of the keys in ascending order, all of them for writes without keys.
+    /// A write holds them from applying the command until it is fed to the stream, so writes
+    /// to the same keys reach replicas in the order they were applied while writes to other
+    /// shards go on. Holding all of them there is no write in flight, as snapshots need.
+    pub(crate) async fn order(&self, keys: Option<&[Bytes]>) -> Vec<MutexGuard<'_, ()>> {
+        let mut shards: Vec<usize> = match keys {
+            Some(keys) if !keys.is_empty() => keys.iter().map(|key| shard_of(key)).collect(),
+            _ => (0..SHARDS).collect(),
+        };
+        shards.sort_unstable();
+        shards.dedup();
+
+        let mut guards = Vec::with_capacity(shards.len());
+        for shard in shards {
+            guards.push(self.order[shard].lock().await);
+        }
+        guards
+    }
+
+    pub(crate) fn set_port(&self, port: u16) {
+        self.port.store(port, Ordering::Relaxed);
+    }
+
+    /// Reply of ROLE.
+    pub(crate) async fn role(&self) -> Frame {
+        let state = self.state.read().await;
+        match &state.master {
+            None => Frame::Array(vec![
+                Frame::Bulk(Bytes::from("master")),
+                Frame::Integer(state.offset as i64),
+                Frame::Array(state.replicas.iter()
+                    .map(|replica| Frame::Array(vec![
+                        Frame::Bulk(Bytes::from(replica.address.ip().to_string())),
+                        Frame::Bulk(Bytes::from(replica.port.to_string())),
+                        Frame::Bulk(Bytes::from(replica.acknowledged.to_string())),
+                    ]))
+                    .collect()),
+            ]),
+            Some(master) => Frame::Array(vec![
+                Frame::Bulk(Bytes::from("slave")),
+                Frame::Bulk(Bytes::from(master.host.clone())),
+                Frame::Integer(master.port as i64),
+                Frame::Bulk(Bytes::from(master.status)),
+                Frame::Integer(if state.propagates() { state.offset as i64 } else { -1 }),
+            ]),
+        }
+    }
+
+    /// Blocks until enough replicas acknowledged the stream up to the offset, the last
+    /// write of the client, or the timeout in milliseconds elapses, zero waiting forever.
+    /// Returns how many replicas acknowledged it.
+    pub(crate) async fn wait(&self, offset: u64, replicas: usize, timeout: u64) -> usize {
+        let deadline = (timeout > 0).then(|| Instant::now() + Duration::from_millis(timeout));
+        let mut asked = false;
+
+        loop {
+            let notified = self.acknowledged.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            let acknowledged = {
+                let state = self.state.read().await;
+                state.replicas.iter()
+                    .filter(|replica| replica.acknowledged >= offset)
+                    .count()
+            };
+            if acknowledged >= replicas {
+                return acknowledged;
+            }
+            if !asked {
+                asked = true;
+                let mut state = self.state.write().await;
+                if state.propagates() {
+                    state.feed(&command(&["REPLCONF", "GETACK", "*"]));
+                }
+            }
+
+            match deadline {
+                Some(deadline) => {
+                    if tokio::time::timeout_at(deadline, notified).await.is_err() {
+                        return acknowledged;
+                    }
+                }
+                None => notified.await,
+            }
+        }
+    }
+
+    /// Records the offset a replica reported with REPLCONF ACK, the FACK offset of an
+    /// append only file being ignored as there is none.
+    async fn acknowledge(&self, id: u64, frames: Vec<Frame>) {
+        let mut frames = frames.into_iter().skip(2).collect::<Vec<_>>().into_iter();
+        let Ok(acknowledged) = next_integer(&mut frames) else { return };
+
+        let mut state = self.state.write().await;
+        if let Some(replica) = state.replicas.iter_mut().find(|replica| replica.id == id) {
+            replica.acknowledged = replica.acknowledged.max(acknowledged.max(0) as u64);
+        }
+        self.acknowledged.notify_waiters();
+    }
+}
+
+/// Rewrites the relative times to live of a write into absolute ones before it is propagated,
+/// so keys expire on the replicas when they expire on the master however late the replicas
+/// apply the stream: SET and GETEX EX or PX become PXAT, SETEX and PSETEX a SET with PXAT and
+/// RESTORE gets ABSTTL.
+pub(crate) fn absolute_expirations(mut frames: Vec<Frame>, now: SystemTime) -> Vec<Frame> {
+    let now = now.duration_since(UNIX_EPOCH).unwrap().as_millis() as i64;
+    let at = |ttl: &Frame, multiplier: i64| {
+        let ttl = integer(ttl)?.checked_mul(multiplier)?;
+        Some(Frame::Bulk(Bytes::from(now.checked_add(ttl)?.to_string())))
+    };
+
+    match command_name(&frames).as_deref() {
+        // Options of SET follow its key and value, the ones of GETEX its key.
+        Some(name @ ("SET" | "GETEX")) => {
+            let first = if name == "SET" { 3 } else { 2 };
+            for index in first..frames.len().saturating_sub(1) {
+                let multiplier = match text(&frames[index]).map(|option| option.to_uppercase()).as_deref() {
+                    Some("EX") => 1000,
+                    Some("PX") => 1,
+                    _ => continue,
+                };
+                if let Some(at) = at(&frames[index + 1], multiplier) {
+                    frames[index] = Frame::Bulk(Bytes::from("PXAT"));
+                    frames[index + 1] = at;
+                }
+            }
+            frames
+        }
+        Some(name @ ("SETEX" | "PSETEX")) if frames.len() == 4 => {
+            let multiplier = if name == "SETEX" { 1000 } else { 1 };
+            match at(&frames[2], multiplier) {
+                Some(at) => vec![Frame::Bulk(Bytes::from("SET")), frames[1].clone(), frames[3].clone(), Frame::Bulk(Bytes::from("PXAT")), at],
+                None => frames,
+            }
+        }
+        Some("RESTORE" | "RESTORE-ASKING") if frames.len() >= 4 => {
+            let absolute = frames[4..].iter().any(|option| text(option).is_some_and(|option| option.eq_ignore_ascii_case("ABSTTL")));
+            if !absolute && integer(&frames[2]).is_some_and(|ttl| ttl > 0) {
+                if let Some(at) = at(&frames[2], 1) {
+                    frames[2] = at;
+                    frames.push(Frame::Bulk(Bytes::from("ABSTTL")));
+                }
+            }
+            frames
+        }
+        _ => frames,
+    }
+}
+
+fn text(frame: &Frame) -> Option<&str> {
+    match frame {
+        Frame::Bulk(bytes) => std::str::from_utf8(bytes).ok(),
+        Frame::Simple(text) => Some(text),
+        _ => None,
+    }
+}
+
+fn integer(frame: &Frame) -> Option<i64> {
+    text(frame)?.parse().ok()
+}
+
+/// Parses the numbers of WAIT and WAITAOF, WAIT numreplicas timeout and WAITAOF numlocal
+/// numreplicas timeout, into the number of local syncs, replicas and the timeout.
+pub(crate) fn wait_arguments(frames: Vec<Frame>) -> Result<(i64, usize, u64)> {
+    let mut frames = frames.into_iter();
+    let name = next_string(&mut frames)?.to_lowercase();
+    let local = match (name.as_str(), frames.len()) {
+        ("wait", 2) => 0,
+        ("waitaof", 3) => next_integer(&mut frames)?,
+        _ => return Err(wrong_arity(&name)),
+    };
+    let replicas = next_integer(&mut frames)?.max(0) as usize;
+    let timeout = u64::try_from(next_integer(&mut frames)?).map_err(|_| "timeout is negative")?;
+
+    Ok((local, replicas, timeout))
+}
+
+/// Parses REPLCONF, returning the listening port a replica announces, the only option of interest.
+pub(crate) fn replconf(frames: Vec<Frame>) -> Result<Option<u16>> {
+    let mut frames = frames.into_iter();
+    frames.next();
+    if !frames.len().is_multiple_of(2) {
+        return Err(syntax_error());
+    }
+
+    let mut port = None;
+    while frames.len() > 0 {
+        let option = next_string(&mut frames)?;
+        match option.eq_ignore_ascii_case("listening-port") {
+            true => port = Some(next_port(&mut frames)?),
+            false => {
+                next_string(&mut frames)?;
+            }
+        }
+    }
+
+    Ok(port)
+}
+
+/// REPLICAOF host port starts following the master, REPLICAOF NO ONE turns the server
+/// into a master again.
+pub(crate) async fn replicaof(server: &Server, frames: Vec<Frame>) -> Result<Frame> {
+    let mut frames = frames.into_iter();
+    frames.next();
+    if frames.len() != 2 {
+        return Err(wrong_arity("replicaof"));
+    }
+    let host = next_string(&mut frames)?;
+    let target = match host.eq_ignore_ascii_case("NO") {
+        true if next_string(&mut frames)?.eq_ignore_ascii_case("ONE") => None,
+        true => return Err(syntax_error()),
+        false => Some((host, next_port(&mut frames)?)),
+    };
+
+    let _order = server.replication.order(None).await;
+    let mut state = server.replication.state.write().await;
+    let Some((host, port)) = target else {
+        if let Some(master) = state.master.take() {
+            master.link.abort();
+            state.shift_replid();
+        }
+        return Ok(Frame::Simple("OK".to_string()));
+    };
+
+    if state.master.as_ref().is_some_and(|master| master.host == host && master.port == port) {
+        return Ok(Frame::Simple("OK Already connected to specified master".to_string()));
+    }
+    if let Some(master) = state.master.take() {
+        master.link.abort();
+    }
+    let link = tokio::spawn(follow(server.clone(), host.clone(), port));
+    state.master = Some(Master { host, port, status: "connect", link });
+
+    Ok(Frame::Simple("OK".to_string()))
+}
+
+/// Takes over the connection of a replica which sent PSYNC or SYNC: the replica gets
+/// either the part of the stream it misses or a snapshot, then the stream as it grows.
+pub(crate) async fn serve_replica(server: &Server, mut connection: Connection, frames: Vec<Frame>, port: Option<u16>) {
+    let (replid, offset) = match psync_arguments(frames) {
+        Ok(arguments) => arguments,
+        Err(err) => {
+            let _ = connection.write_frame(error_frame(&err.to_string())).await;
+            return;
+        }
+    };
+    let Ok(address) = connection.peer_addr() else { return };
+
+    let (sender, mut stream) = mpsc::unbounded_channel();
+    let pending = Arc::new(AtomicUsize::new(0));
+    let (id, start, limit) = {
+        // With every shard locked no write is in flight, the snapshot matches the offset.
+        let order = server.replication.order(None).await;
+        let mut state = server.replication.state.write().await;
+        if state.is_replica() && !state.propagates() {
+            drop((state, order));
+            let _ = connection.write_frame(error_frame("Can't SYNC while not connected with my master")).await;
+            return;
+        }
+        state.backlog.get_or_insert_with(VecDeque::new);
+
+        let continuation = replid.as_deref().and_then(|replid| state.continuation(replid, offset));
+        let start = match (continuation, &replid) {
+            (Some(missing), _) => [format!("+CONTINUE {}\r\n", state.replid).into_bytes(), missing].concat(),
+            (None, replid) => {
+                // The replica starts from the snapshot on database zero, the next command selects its own.
+                state.selected = None;
+                let snapshot = server.save().await;
+                let header = match replid {
+                    Some(_) => format!("+FULLRESYNC {} {}\r\n", state.replid, state.offset),
+                    None => String::new(),
+                };
+                [header.into_bytes(), format!("${}\r\n", snapshot.len()).into_bytes(), snapshot].concat()
+            }
+        };
+
+        let id = state.next_replica;
+        state.next_replica += 1;
+        let replica = Replica { id, address, port: port.unwrap_or(address.port()), stream: sender, pending: pending.clone(), acknowledged: 0 };
+        state.replicas.push(replica);
+        (id, start, state.buffer_limit)
+    };
+
+    if connection.write_bytes(&start).await.is_ok() {
+        loop {
+            tokio::select! {
+                bytes = stream.recv() => match bytes {
+                    // Once over the limit the rest of the queue is dropped with the connection.
+                    Some(bytes) if pending.load(Ordering::Relaxed) <= limit && connection.write_bytes(&bytes).await.is_ok() => {
+                        pending.fetch_sub(bytes.len(), Ordering::Relaxed);
+                    }
+                    _ => break,
+                },
+                frame = connection.read_frame() => match frame {
+                    Ok(Some(Frame::Array(frames))) if is_ack(&frames) => server.replication.acknowledge(id, frames).await,
+                    Ok(Some(_)) => {}
+                    _ => break,
+                },
+            }
+        }
+    }
+
+    server.replication.state.write().await.replicas.retain(|replica| replica.id != id);
+}
+
+/// Id and offset a replica asks to continue from, no id for SYNC or a replica without history.
+fn psync_arguments(frames: Vec<Frame>) -> Result<(Option<String>, u64)> {
+    let mut frames = frames.into_iter();
+    let name = next_string(&mut frames)?.to_uppercase();
+    match (name.as_str(), frames.len()) {
+        ("SYNC", 0) => Ok((None, 0)),
+        ("PSYNC", 2) => {
+            let replid = next_string(&mut frames)?;
+            let offset = next_integer(&mut frames)?;
+            Ok((Some(replid), offset.max(0) as u64))
+        }
+        _ => Err(wrong_arity(&name.to_lowercase())),
+    }
+}
+
+/// Keeps the server synchronized with the master, reconnecting after failures.
+async fn follow(server: Server, host: String, port: u16) {
+    loop {
+        let _ = synchronize(&server, &host, port).await;
+        set_status(&server, "connect").await;
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+/// Connects to the master, resynchronizes and applies its stream until the link breaks.
+async fn synchronize(server: &Server, host: &str, port: u16) -> Result<()> {
+    let mut connection = Connection::new(TcpStream::connect((host, port)).await?);
+    set_status(server, "connecting").await;
+
+    if let Some(password) = &server.masterauth {
+        request(&mut connection, &["AUTH", password]).await?;
+    }
+    request(&mut connection, &["PING"]).await?;
+    let listening = server.replication.port.load(Ordering::Relaxed).to_string();
+    request(&mut connection, &["REPLCONF", "listening-port", &listening]).await?;
+    request(&mut connection, &["REPLCONF", "capa", "psync2"]).await?;
+
+    let (replid, offset) = {
+        let state = server.replication.state.read().await;
+        match state.propagates() {
+            true => (state.replid.clone(), (state.offset + 1).to_string()),
+            false => ("?".to_string(), "-1".to_string()),
+        }
+    };
+    connection.write_bytes(&command(&["PSYNC", &replid, &offset])).await?;
+
+    let reply = match connection.read_frame().await? {
+        Some(Frame::Simple(reply)) => reply,
+        reply => return Err(format!("unexpected reply to PSYNC: {:?}", reply).into()),
+    };
+    let mut words = reply.split(' ');
+    match (words.next(), words.next(), words.next()) {
+        (Some("FULLRESYNC"), Some(replid), Some(offset)) => {
+            set_status(server, "sync").await;
+            let offset = offset.parse().map_err(|_| "invalid offset in FULLRESYNC")?;
+            let snapshot = connection.read_payload().await?.ok_or("connection closed during the transfer")?;
+            let mut state = server.replication.state.write().await;
+            server.load(&snapshot).await?;
+            state.replid = replid.to_string();
+            state.replid2 = None;
+            state.offset = offset;
+            state.backlog = Some(VecDeque::new());
+            state.stream_database = 0;
+            // Replicas of this server hold a history which does not exist anymore.
+            state.replicas.clear();
+        }
+        (Some("CONTINUE"), replid, None) => {
+            let mut state = server.replication.state.write().await;
+            if let Some(replid) = replid.filter(|replid| *replid != state.replid) {
+                state.replid2 = Some((std::mem::replace(&mut state.replid, replid.to_string()), state.offset));
+            }
+        }
+        _ => return Err(format!("unexpected reply to PSYNC: {}", reply).into()),
+    }
+    set_status(server, "connected").await;
+
+    let database = server.replication.state.read().await.stream_database;
+    let mut db = server.db.select(database).unwrap_or_else(|| server.db.clone());
+    let mut ack = tokio::time::interval(ACK_INTERVAL);
+    loop {
+        let frame = tokio::select! {
+            frame = connection.read_frame() => frame?,
+            _ = ack.tick() => {
+                let offset = server.replication.state.read().await.offset;
+                connection.write_bytes(&command(&["REPLCONF", "ACK", &offset.to_string()])).await?;
+                continue;
+            }
+        };
+        let Some(Frame::Array(frames)) = frame else {
+            return match frame {
+                None => Ok(()),
+                Some(_) => Err("protocol error; expected array from master".into()),
+            };
+        };
+
+        let bytes = Vec::from(Frame::Array(frames.clone()));
+        let getack = command_name(&frames).as_deref() == Some("REPLCONF");
+        let mut state = server.replication.state.write().await;
+        if !getack {
+            let _ = server.apply(frames, &mut db).await;
+            state.stream_database = db.index();
+        }
+        // The stream goes on to the replicas of this server as it was received.
+        state.selected = None;
+        state.feed(&bytes);
+        if getack {
+            let offset = state.offset.to_string();
+            drop(state);
+            connection.write_bytes(&command(&["REPLCONF", "ACK", &offset])).await?;
+        }
+    }
+}
+
+fn is_ack(frames: &[Frame]) -> bool {
+    let argument = match frames.get(1) {
+        Some(Frame::Bulk(argument)) => argument.as_ref(),
+        Some(Frame::Simple(argument)) => argument.as_bytes(),
+        _ => return false,
+    };
+    command_name(frames).as_deref() == Some("REPLCONF") && argument.eq_ignore_ascii_case(b"ACK")
+}
+
+async fn set_status(server: &Server, status: &'static str) {
+    if let Some(master) = &mut server.replication.state.write().await.master {
+        master.status = status;
+    }
+}
+
+/// Sends a command of the handshake and fails unless the master accepts it.
+async fn request(connection: &mut Connection, arguments: &[&str]) -> Result<()> {
+    connection.write_bytes(&command(arguments)).await?;
+    match connection.read_frame().await? {
+        Some(Frame::SimpleError(err)) => Err(format!("master refused {}: {}", arguments[0], err).into()),
+        Some(_) => Ok(()),
+        None => Err(Error::from("connection closed by master")),
+    }
+}
+
+fn command(arguments: &[&str]) -> Vec<u8> {
+    Frame::Array(arguments.iter().map(|argument| Frame::Bulk(Bytes::from(argument.to_string()))).collect()).into()
+}
+
+fn next_port(frames: &mut IntoIter<Frame>) -> Result<u16> {
+    u16::try_from(next_integer(frames)?).map_err(|_| "Invalid master port".into())
+}
+
+/// Random 40 characters hexadecimal id of a stream history.
+fn new_replid() -> String {
+    format!("{:016x}{:016x}{:08x}", random(), random(), random() as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    fn request(arguments: &[&str]) -> Frame {
+        Frame::Array(arguments.iter().map(|argument| Frame::Bulk(Bytes::from(argument.to_string()))).collect())
+    }
+
+    async fn start() -> (Server, u16) {
+        start_server(Server::new(2)).await
+    }
+
+    async fn start_server(server: Server) -> (Server, u16) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(server.clone().serve(listener));
+        (server, port)
+    }
+
+    async fn client(port: u16) -> Connection {
+        Connection::new(TcpStream::connect(("127.0.0.1", port)).await.unwrap())
+    }
+
+    async fn call(connection: &mut Connection, arguments: &[&str]) -> Frame {
+        connection.write_frame(request(arguments)).await.unwrap();
+        connection.read_frame().await.unwrap().unwrap()
+    }
+
+    /// Polls until the command replies as expected, replicas apply the stream asynchronously.
+    async fn eventually(connection: &mut Connection, arguments: &[&str], expected: Frame) {
+        for _ in 0..200 {
+            if call(connection, arguments).await == expected {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("{:?} never replied {:?}", arguments, expected);
+    }
+
+    #[tokio::test]
+    async fn it_authenticates_to_a_master_requiring_a_password() {
+        let (_, master_port) = start_server(Server::new(2).requirepass("secret")).await;
+        let (_, replica_port) = start_server(Server::new(2).masterauth("secret")).await;
+        let mut master = client(master_port).await;
+        let mut replica = client(replica_port).await;
+
+        call(&mut master, &["AUTH", "secret"]).await;
+        call(&mut master, &["SET", "a", "1"]).await;
+        call(&mut replica, &["REPLICAOF", "127.0.0.1", &master_port.to_string()]).await;
+        eventually(&mut replica, &["GET", "a"], Frame::Bulk(Bytes::from("1"))).await;
+        call(&mut master, &["SET", "a", "2"]).await;
+        eventually(&mut replica, &["GET", "a"], Frame::Bulk(Bytes::from("2"))).await;
+
+        // Without the password the replica never gets past the handshake.
+        let (_, unauthenticated_port) = start().await;
+        let mut unauthenticated = client(unauthenticated_port).await;
+        call(&mut unauthenticated, &["REPLICAOF", "127.0.0.1", &master_port.to_string()]).await;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(Frame::Null, call(&mut unauthenticated, &["GET", "a"]).await);
+    }
+
+    #[tokio::test]
+    async fn it_replicates_snapshot_and_stream() {
+        let (_, master_port) = start().await;
+        let (_, replica_port) = start().await;
+        let mut master = client(master_port).await;
+        let mut replica = client(replica_port).await;
+
+        call(&mut master, &["SET", "before", "1"]).await;
+        let reply = call(&mut replica, &["REPLICAOF", "127.0.0.1", &master_port.to_string()]).await;
+        assert_eq!(Frame::Simple("OK".to_string()), reply);
+        eventually(&mut replica, &["GET", "before"], Frame::Bulk(Bytes::from("1"))).await;
+
+        call(&mut master, &["SELECT", "1"]).await;
+        call(&mut master, &["SET", "after", "2"]).await;
+        call(&mut replica, &["SELECT", "1"]).await;
+        eventually(&mut replica, &["GET", "after"], Frame::Bulk(Bytes::from("2"))).await;
+
+        assert!(matches!(call(&mut replica, &["SET", "a", "1"]).await, Frame::SimpleError(err) if err.starts_with("READONLY")));
+        assert_eq!(Frame::Integer(1), call(&mut master, &["WAIT", "1", "0"]).await);
+        let Frame::Array(role) = call(&mut master, &["ROLE"]).await else { panic!("expected an array") };
+        let Frame::Array(replicas) = &role[2] else { panic!("expected replicas") };
+        let Frame::Array(fields) = &replicas[0] else { panic!("expected a replica") };
+        assert_eq!(Frame::Bulk(Bytes::from("127.0.0.1")), fields[0]);
+        assert_eq!(Frame::Bulk(Bytes::from(replica_port.to_string())), fields[1]);
+        assert!(matches!(&fields[2], Frame::Bulk(offset) if offset.as_ref() != b"0"));
+
+        call(&mut replica, &["REPLICAOF", "NO", "ONE"]).await;
+        assert_eq!(Frame::Simple("OK".to_string()), call(&mut replica, &["SET", "a", "1"]).await);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn it_replicates_concurrent_writes_while_a_replica_attaches() {
+        let (_, master_port) = start().await;
+        let (_, replica_port) = start().await;
+        let mut replica = client(replica_port).await;
+
+        let writers: Vec<_> = (0..8)
+            .map(|writer| tokio::spawn(async move {
+                let mut connection = client(master_port).await;
+                for i in 0..100 {
+                    call(&mut connection, &["INCR", "counter"]).await;
+                    call(&mut connection, &["SET", &format!("{}:{}", writer, i), "1"]).await;
+                }
+            }))
+            .collect();
+        call(&mut replica, &["REPLICAOF", "127.0.0.1", &master_port.to_string()]).await;
+        for writer in writers {
+            writer.await.unwrap();
+        }
+
+        // Every increment is in either the snapshot or the stream, none in both.
+        eventually(&mut replica, &["GET", "counter"], Frame::Bulk(Bytes::from("800"))).await;
+        eventually(&mut replica, &["DBSIZE"], Frame::Integer(801)).await;
+    }
+
+    #[tokio::test]
+    async fn it_snapshots_once_writes_in_flight_are_fed() {
+        let (master, port) = start().await;
+        let in_flight = master.replication.order(Some(&[Bytes::from("a")])).await;
+
+        let mut replica = client(port).await;
+        replica.write_frame(request(&["PSYNC", "?", "-1"])).await.unwrap();
+        assert!(tokio::time::timeout(Duration::from_millis(50), replica.read_frame()).await.is_err());
+
+        drop(in_flight);
+        assert!(matches!(replica.read_frame().await.unwrap(), Some(Frame::Simple(reply)) if reply.starts_with("FULLRESYNC")));
+    }
+
+    #[tokio::test]
+    async fn it_synchronizes_replicas_of_thread_per_core_servers() {
+        let (_, master_port) = start_server(Server::thread_per_core(2, 3)).await;
+        let (_, replica_port) = start_server(Server::thread_per_core(2, 2)).await;
+        let mut writer = client(master_port).await;
+        let mut replica = client(replica_port).await;
+
+        call(&mut writer, &["MSET", "a", "1", "b", "2", "c", "3"]).await;
+        call(&mut replica, &["REPLICAOF", "127.0.0.1", &master_port.to_string()]).await;
+        eventually(&mut replica, &["DBSIZE"], Frame::Integer(3)).await;
+
+        call(&mut writer, &["RENAME", "a", "d"]).await;
+        eventually(&mut replica, &["MGET", "a", "b", "c", "d"], Frame::Array(vec![
+            Frame::Null,
+            Frame::Bulk(Bytes::from("2")),
+            Frame::Bulk(Bytes::from("3")),
+            Frame::Bulk(Bytes::from("1")),
+        ])).await;
+    }
+
+    #[tokio::test]
+    async fn it_keeps_the_selected_database_across_partial_resynchronizations() {
+        let (master, master_port) = start().await;
+        let (_, replica_port) = start().await;
+        let mut writer = client(master_port).await;
+        let mut replica = client(replica_port).await;
+
+        call(&mut replica, &["REPLICAOF", "127.0.0.1", &master_port.to_string()]).await;
+        call(&mut replica, &["SELECT", "1"]).await;
+        call(&mut writer, &["SELECT", "1"]).await;
+        call(&mut writer, &["SET", "a", "1"]).await;
+        eventually(&mut replica, &["GET", "a"], Frame::Bulk(Bytes::from("1"))).await;
+
+        // Dropping the replica closes its link, the write after it waits in the backlog.
+        master.replication.state.write().await.replicas.clear();
+        call(&mut writer, &["SET", "b", "2"]).await;
+        eventually(&mut replica, &["GET", "b"], Frame::Bulk(Bytes::from("2"))).await;
+
+        // A full resynchronization would have made the master select the database again.
+        assert_eq!(Some(1), master.replication.state.read().await.selected);
+        call(&mut replica, &["SELECT", "0"]).await;
+        assert_eq!(Frame::Null, call(&mut replica, &["GET", "b"]).await);
+    }
+
+    #[tokio::test]
+    async fn it_continues_the_stream_from_the_backlog() {
+        let (master, port) = start().await;
+        let mut first = client(port).await;
+        first.write_frame(request(&["PSYNC", "?", "-1"])).await.unwrap();
+        let Some(Frame::Simple(reply)) = first.read_frame().await.unwrap() else { panic!("expected FULLRESYNC") };
+        let replid = reply.split(' ').nth(1).unwrap().to_string();
+        first.read_payload().await.unwrap().unwrap();
+
+        let mut writer = client(port).await;
+        call(&mut writer, &["SET", "a", "1"]).await;
+        let propagated = command(&["SELECT", "0"]).len() + command(&["SET", "a", "1"]).len();
+        assert_eq!(propagated as u64, master.replication.state.read().await.offset);
+
+        // A replica which only got the SELECT asks for the rest of the stream.
+        let mut second = client(port).await;
+        let offset = (command(&["SELECT", "0"]).len() + 1).to_string();
+        second.write_frame(request(&["PSYNC", &replid, &offset])).await.unwrap();
+        assert_eq!(Some(Frame::Simple(format!("CONTINUE {}", replid))), second.read_frame().await.unwrap());
+        assert_eq!(Some(request(&["SET", "a", "1"])), second.read_frame().await.unwrap());
+
+        let mut unknown = client(port).await;
+        unknown.write_frame(request(&["PSYNC", "0123", "1"])).await.unwrap();
+        assert!(matches!(unknown.read_frame().await.unwrap(), Some(Frame::Simple(reply)) if reply.starts_with("FULLRESYNC")));
+    }
+
+    #[tokio::test]
+    async fn it_waits_for_replicas_to_acknowledge_writes() {
+        let (master, port) = start().await;
+        let mut writer = client(port).await;
+        assert_eq!(Frame::Integer(0), call(&mut writer, &["WAIT", "0", "0"]).await);
+        assert_eq!(Frame::Integer(0), call(&mut writer, &["WAIT", "1", "50"]).await);
+
+        let mut replica = client(port).await;
+        replica.write_frame(request(&["PSYNC", "?", "-1"])).await.unwrap();
+        replica.read_frame().await.unwrap();
+        replica.read_payload().await.unwrap().unwrap();
+        call(&mut writer, &["SET", "a", "1"]).await;
+
+        // The replica is asked for its offset once WAIT finds it behind, and answers it.
+        writer.write_frame(request(&["WAIT", "1", "0"])).await.unwrap();
+        assert_eq!(Some(request(&["SELECT", "0"])), replica.read_frame().await.unwrap());
+        assert_eq!(Some(request(&["SET", "a", "1"])), replica.read_frame().await.unwrap());
+        assert_eq!(Some(request(&["REPLCONF", "GETACK", "*"])), replica.read_frame().await.unwrap());
+        let offset = master.replication.state.read().await.offset.to_string();
+        replica.write_frame(request(&["REPLCONF", "ACK", &offset])).await.unwrap();
+        assert_eq!(Some(Frame::Integer(1)), writer.read_frame().await.unwrap());
+
+        // There is no append only file for replicas to sync either.
+        assert!(matches!(call(&mut writer, &["WAITAOF", "0", "1", "0"]).await, Frame::SimpleError(err) if err.contains("numreplicas")));
+        assert_eq!(Frame::Array(vec![Frame::Integer(0), Frame::Integer(0)]), call(&mut writer, &["WAITAOF", "0", "0", "0"]).await);
+        assert!(matches!(call(&mut writer, &["WAITAOF", "1", "0", "0"]).await, Frame::SimpleError(err) if err.contains("appendonly")));
+        assert!(matches!(call(&mut writer, &["WAIT", "1", "-1"]).await, Frame::SimpleError(err) if err.contains("negative")));
+    }
+
+    #[tokio::test]
+    async fn it_disconnects_replicas_lagging_past_the_buffer_limit() {
+        let (master, port) = start().await;
+        master.replication.state.write().await.buffer_limit = 1024 * 1024;
+        let mut replica = client(port).await;
+        replica.write_frame(request(&["PSYNC", "?", "-1"])).await.unwrap();
+        replica.read_frame().await.unwrap();
+        replica.read_payload().await.unwrap().unwrap();
+
+        // The replica stops reading, so the stream piles up on the master.
+        let mut writer = client(port).await;
+        let value = "v".repeat(512 * 1024);
+        for key in 0..16 {
+            call(&mut writer, &["SET", &key.to_string(), &value]).await;
+        }
+
+        assert!(master.replication.state.read().await.replicas.is_empty());
+        while let Ok(Some(_)) = replica.read_frame().await {}
+    }
+
+    #[test]
+    fn it_propagates_absolute_expirations() {
+        let now = UNIX_EPOCH + Duration::from_secs(1000);
+        let rewrite = |arguments: &[&str]| {
+            let Frame::Array(frames) = request(arguments) else { unreachable!() };
+            Frame::Array(absolute_expirations(frames, now))
+        };
+
+        assert_eq!(request(&["SET", "EX", "v", "PXAT", "1010000", "GET"]), rewrite(&["SET", "EX", "v", "ex", "10", "GET"]));
+        assert_eq!(request(&["SET", "k", "v", "NX", "PXAT", "1000500"]), rewrite(&["SET", "k", "v", "NX", "PX", "500"]));
+        assert_eq!(request(&["SET", "k", "v", "PXAT", "5"]), rewrite(&["SET", "k", "v", "PXAT", "5"]));
+        assert_eq!(request(&["SET", "k", "v", "PXAT", "1010000"]), rewrite(&["SETEX", "k", "10", "v"]));
+        assert_eq!(request(&["SET", "k", "v", "PXAT", "1000010"]), rewrite(&["PSETEX", "k", "10", "v"]));
+        assert_eq!(request(&["GETEX", "k", "PXAT", "1001000"]), rewrite(&["GETEX", "k", "EX", "1"]));
+        assert_eq!(request(&["GETEX", "k", "PERSIST"]), rewrite(&["GETEX", "k", "PERSIST"]));
+        assert_eq!(request(&["RESTORE", "k", "1001000", "payload", "REPLACE", "ABSTTL"]), rewrite(&["RESTORE", "k", "1000", "payload", "REPLACE"]));
+        assert_eq!(request(&["RESTORE", "k", "0", "payload"]), rewrite(&["RESTORE", "k", "0", "payload"]));
+        assert_eq!(request(&["RESTORE", "k", "5", "payload", "ABSTTL"]), rewrite(&["RESTORE", "k", "5", "payload", "ABSTTL"]));
+    }
+
+    #[tokio::test]
+    async fn it_streams_absolute_expirations_to_replicas() {
+        let (_, port) = start().await;
+        let mut replica = client(port).await;
+        replica.write_frame(request(&["PSYNC", "?", "-1"])).await.unwrap();
+        replica.read_frame().await.unwrap();
+        replica.read_payload().await.unwrap().unwrap();
+
+        let mut writer = client(port).await;
+        let before = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis();
+        call(&mut writer, &["SETEX", "a", "100", "1"]).await;
+
+        assert_eq!(Some(request(&["SELECT", "0"])), replica.read_frame().await.unwrap());
+        let Some(Frame::Array(frames)) = replica.read_frame().await.unwrap() else { panic!("expected a command") };
+        assert_eq!(request(&["SET", "a", "1", "PXAT"]), Frame::Array(frames[..4].to_vec()));
+        let at: u128 = text(&frames[4]).unwrap().parse().unwrap();
+        assert!((before + 100_000..before + 101_000).contains(&at));
+    }
+
+    #[test]
+    fn it_keeps_the_end_of_the_stream_in_the_backlog() {
+        let mut state = State { backlog: Some(VecDeque::new()), ..State::default() };
+        let replid = state.replid.clone();
+        state.feed(&vec![b'a'; BACKLOG_SIZE]);
+        state.feed(b"bc");
+
+        assert_eq!(Some(b"bc".to_vec()), state.continuation(&replid, BACKLOG_SIZE as u64 + 1));
+        assert_eq!(Some(vec![]), state.continuation(&replid, BACKLOG_SIZE as u64 + 3));
+        assert_eq!(None, state.continuation(&replid, 2));
+        assert_eq!(None, state.continuation("other", BACKLOG_SIZE as u64 + 1));
+
+        state.shift_replid();
+        assert_eq!(Some(b"c".to_vec()), state.continuation(&replid, BACKLOG_SIZE as u64 + 2));
+    }
+}
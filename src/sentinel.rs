@@ -0,0 +1,593 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use std::vec::IntoIter;
+
+use bytes::Bytes;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time::Instant;
+
+use crate::command::{command_name, error_frame, next_integer, next_string, wrong_arity};
+use crate::connection::Connection;
+use crate::eviction::random;
+use crate::frame::Frame;
+use crate::Result;
+
+/// How often the master is checked and the other sentinels are asked about it.
+const CHECK_INTERVAL: Duration = Duration::from_millis(100);
+/// Longest random delay before a failover starts, so sentinels seeing the master down at
+/// the same time do not all ask for votes in the same epoch and split them.
+const FAILOVER_DESYNC: Duration = Duration::from_millis(500);
+
+/// Monitors a master and its replicas, promoting a replica once enough sentinels agree
+/// the master is down. Sentinels know each other from their configuration, they share
+/// the master they follow and vote for the one leading a failover with
+/// SENTINEL IS-MASTER-DOWN-BY-ADDR, like Redis Sentinel does.
+pub struct Sentinel {
+    name: String,
+    master: (String, u16),
+    quorum: usize,
+    down_after: Duration,
+    failover_timeout: Duration,
+    /// Password of the master and its replicas, as `requirepass` sets it on them.
+    auth_pass: Option<String>,
+    peers: Vec<(String, u16)>,
+}
+
+/// Configuration and view of the monitored master shared by the monitoring task and connections.
+struct Shared {
+    id: String,
+    name: String,
+    quorum: usize,
+    down_after: Duration,
+    failover_timeout: Duration,
+    auth_pass: Option<String>,
+    peers: Vec<(String, u16)>,
+    state: Mutex<State>,
+}
+
+struct State {
+    master: (String, u16),
+    replicas: Vec<(String, u16)>,
+    /// When the master last replied, it is subjectively down once this is older than `down_after`.
+    replied: Instant,
+    /// Set when the quorum of sentinels agreed the master is down.
+    objectively_down: bool,
+    /// Highest epoch seen, failovers start a new one.
+    current_epoch: u64,
+    /// Epoch of the failover which made the master the current one.
+    config_epoch: u64,
+    /// Epoch and id of the sentinel voted for as the failover leader.
+    vote: Option<(u64, String)>,
+    /// Failovers are not attempted again before `failover_timeout` passed since this one.
+    failover_started: Option<Instant>,
+}
+
+impl Sentinel {
+    /// Monitors the master known under `name`, failing it over once `quorum` sentinels agree it is down.
+    pub fn monitor(name: &str, host: &str, port: u16, quorum: usize) -> Self {
+        Sentinel {
+            name: name.to_string(),
+            master: (host.to_string(), port),
+            quorum: quorum.max(1),
+            down_after: Duration::from_secs(30),
+            failover_timeout: Duration::from_secs(180),
+            auth_pass: None,
+            peers: vec![],
+        }
+    }
+
+    /// Applies an option of the monitored master as SENTINEL SET does, like `down-after-milliseconds`.
+    pub fn configure(&mut self, name: &str, value: &str) -> std::result::Result<(), String> {
+        if name.eq_ignore_ascii_case("auth-pass") {
+            self.auth_pass = Some(value.to_string()).filter(|password| !password.is_empty());
+            return Ok(());
+        }
+        let milliseconds = value.parse::<u64>().ok().filter(|milliseconds| *milliseconds > 0);
+        let milliseconds = milliseconds.ok_or_else(|| format!("Invalid argument '{}' for SENTINEL SET '{}'", value, name))?;
+        match name.to_lowercase().as_str() {
+            "down-after-milliseconds" => self.down_after = Duration::from_millis(milliseconds),
+            "failover-timeout" => self.failover_timeout = Duration::from_millis(milliseconds),
+            _ => return Err(format!("Invalid argument '{}' for SENTINEL SET", name)),
+        }
+        Ok(())
+    }
+
+    /// Adds another sentinel monitoring the same master.
+    pub fn add_peer(&mut self, host: &str, port: u16) {
+        self.peers.push((host.to_string(), port));
+    }
+
+    /// Monitors the master and answers clients until the listener fails.
+    pub async fn serve(self, listener: TcpListener) {
+        let shared = Arc::new(Shared {
+            id: format!("{:016x}{:016x}{:08x}", random(), random(), random() as u32),
+            name: self.name,
+            quorum: self.quorum,
+            down_after: self.down_after,
+            failover_timeout: self.failover_timeout,
+            auth_pass: self.auth_pass,
+            peers: self.peers,
+            state: Mutex::new(State {
+                master: self.master,
+                replicas: vec![],
+                replied: Instant::now(),
+                objectively_down: false,
+                current_epoch: 0,
+                config_epoch: 0,
+                vote: None,
+                failover_started: None,
+            }),
+        });
+
+        let monitor = tokio::spawn(monitor(shared.clone()));
+        while let Ok((socket, _)) = listener.accept().await {
+            let shared = shared.clone();
+            tokio::spawn(async move {
+                shared.process(socket).await;
+            });
+        }
+        monitor.abort();
+    }
+}
+
+async fn monitor(shared: Arc<Shared>) {
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+        // Peers are asked first, a failover they already made is adopted instead of started again.
+        shared.gossip().await;
+        shared.check_master().await;
+        if shared.subjectively_down() && shared.agreed_down().await {
+            shared.failover().await;
+        }
+        shared.reconfigure().await;
+    }
+}
+
+impl Shared {
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap()
+    }
+
+    /// Sends a command to the master or a replica, authenticating first when a password is configured.
+    async fn ask(&self, address: &(String, u16), arguments: &[&str]) -> Option<Frame> {
+        authenticated_query(address, self.auth_pass.as_deref(), arguments, self.down_after).await
+    }
+
+    fn subjectively_down(&self) -> bool {
+        self.state().replied.elapsed() > self.down_after
+    }
+
+    /// Asks the master for its role, learning about its replicas from the reply.
+    async fn check_master(&self) {
+        let master = self.state().master.clone();
+        let Some(Frame::Array(role)) = self.ask(&master, &["ROLE"]).await else { return };
+
+        let mut state = self.state();
+        if state.master != master {
+            return;
+        }
+        state.replied = Instant::now();
+        state.objectively_down = false;
+        if let (Some("master"), Some(Frame::Array(replicas))) = (role.first().and_then(text).as_deref(), role.get(2)) {
+            for replica in replicas {
+                let Frame::Array(fields) = replica else { continue };
+                let address = fields.first().and_then(text).zip(fields.get(1).and_then(text).and_then(|port| port.parse().ok()));
+                if let Some(address) = address.filter(|address| *address != master && !state.replicas.contains(address)) {
+                    state.replicas.push(address);
+                }
+            }
+        }
+    }
+
+    /// Adopts the master of a peer which took part in a more recent failover.
+    async fn gossip(&self) {
+        for peer in &self.peers {
+            let Some(Frame::Array(fields)) = query(peer, &["SENTINEL", "MASTER", &self.name], self.down_after).await else { continue };
+            let field = |name: &str| fields.chunks(2).find(|pair| text(&pair[0]).as_deref() == Some(name)).and_then(|pair| text(&pair[1]));
+            let (Some(host), Some(port), Some(epoch)) = (
+                field("ip"),
+                field("port").and_then(|port| port.parse().ok()),
+                field("config-epoch").and_then(|epoch| epoch.parse::<u64>().ok()),
+            ) else { continue };
+
+            let mut state = self.state();
+            if epoch > state.config_epoch {
+                let previous = std::mem::replace(&mut state.master, (host, port));
+                let master = state.master.clone();
+                state.replicas.retain(|replica| *replica != master);
+                state.replicas.push(previous);
+                state.config_epoch = epoch;
+                state.current_epoch = state.current_epoch.max(epoch);
+                state.replied = Instant::now();
+                state.objectively_down = false;
+            }
+        }
+    }
+
+    /// Asks the other sentinels whether they see the master down too, true once the quorum agrees.
+    async fn agreed_down(&self) -> bool {
+        let ((host, port), epoch) = {
+            let state = self.state();
+            (state.master.clone(), state.current_epoch)
+        };
+        let mut agreeing = 1;
+        for peer in &self.peers {
+            let reply = query(peer, &["SENTINEL", "IS-MASTER-DOWN-BY-ADDR", &host, &port.to_string(), &epoch.to_string(), "*"], self.down_after).await;
+            if matches!(reply, Some(Frame::Array(reply)) if reply.first() == Some(&Frame::Integer(1))) {
+                agreeing += 1;
+            }
+        }
+
+        let down = agreeing >= self.quorum;
+        self.state().objectively_down = down;
+        down
+    }
+
+    /// Starts a new epoch asking the other sentinels to elect this one as its leader, then
+    /// promotes the replica with the most of the stream and points the others to it.
+    async fn failover(&self) {
+        if self.state().failover_started.is_some_and(|started| started.elapsed() < self.failover_timeout) {
+            return;
+        }
+        tokio::time::sleep(FAILOVER_DESYNC.mul_f64((random() % 1000) as f64 / 1000.0)).await;
+
+        let (master, epoch) = {
+            let mut state = self.state();
+            if state.failover_started.is_some_and(|started| started.elapsed() < self.failover_timeout) {
+                return;
+            }
+            state.current_epoch += 1;
+            state.vote = Some((state.current_epoch, self.id.clone()));
+            state.failover_started = Some(Instant::now());
+            (state.master.clone(), state.current_epoch)
+        };
+
+        let mut votes = 1;
+        for peer in &self.peers {
+            let arguments = ["SENTINEL", "IS-MASTER-DOWN-BY-ADDR", &master.0, &master.1.to_string(), &epoch.to_string(), &self.id];
+            let Some(Frame::Array(reply)) = query(peer, &arguments, self.down_after).await else { continue };
+            if reply.get(1).and_then(text).as_deref() == Some(self.id.as_str()) && reply.get(2) == Some(&Frame::Integer(epoch as i64)) {
+                votes += 1;
+            }
+        }
+        // The leader needs the majority of all sentinels, and at least the quorum.
+        let sentinels = self.peers.len() + 1;
+        if votes < self.quorum.max(sentinels / 2 + 1) {
+            return;
+        }
+
+        let replicas = self.state().replicas.clone();
+        let mut promoted = None;
+        for replica in &replicas {
+            let Some(Frame::Array(role)) = self.ask(replica, &["ROLE"]).await else { continue };
+            let offset = match (role.first().and_then(text).as_deref(), role.get(4)) {
+                (Some("slave"), Some(Frame::Integer(offset))) => *offset,
+                _ => continue,
+            };
+            if promoted.as_ref().is_none_or(|(_, best)| offset > *best) {
+                promoted = Some((replica.clone(), offset));
+            }
+        }
+        let Some((promoted, _)) = promoted else { return };
+        if !matches!(self.ask(&promoted, &["REPLICAOF", "NO", "ONE"]).await, Some(Frame::Simple(_))) {
+            return;
+        }
+
+        {
+            let mut state = self.state();
+            if state.config_epoch >= epoch || state.master != master {
+                return;
+            }
+            state.replicas.retain(|replica| *replica != promoted);
+            state.replicas.push(master);
+            state.master = promoted;
+            state.config_epoch = epoch;
+            state.replied = Instant::now();
+            state.objectively_down = false;
+        }
+        self.reconfigure().await;
+    }
+
+    /// Points instances following another master, or acting as one, like a former master
+    /// coming back, to the current master. Only done after a failover, before it the
+    /// configuration of this sentinel may be older than what the instances follow.
+    async fn reconfigure(&self) {
+        let (master, replicas) = {
+            let state = self.state();
+            if state.config_epoch == 0 {
+                return;
+            }
+            (state.master.clone(), state.replicas.clone())
+        };
+
+        for replica in &replicas {
+            let Some(Frame::Array(role)) = self.ask(replica, &["ROLE"]).await else { continue };
+            let following = (role.get(1).and_then(text), role.get(2).and_then(text).and_then(|port| port.parse::<u16>().ok()));
+            let follows = match role.first().and_then(text).as_deref() {
+                Some("slave") => matches!(&following, (Some(host), Some(port)) if *host == master.0 && *port == master.1),
+                _ => false,
+            };
+            if !follows {
+                self.ask(replica, &["REPLICAOF", &master.0, &master.1.to_string()]).await;
+            }
+        }
+    }
+
+    async fn process(&self, socket: TcpStream) {
+        let mut connection = Connection::new(socket);
+        while let Ok(Some(frame)) = connection.read_frame().await {
+            let response = match frame {
+                Frame::Array(frames) => self.execute(frames).unwrap_or_else(|err| error_frame(&err.to_string())),
+                frame => error_frame(&format!("protocol error; expected array, got {:?}", frame)),
+            };
+            if connection.write_frame(response).await.is_err() {
+                return;
+            }
+        }
+    }
+
+    fn execute(&self, frames: Vec<Frame>) -> Result<Frame> {
+        let name = command_name(&frames).unwrap_or_default();
+        let mut frames = frames.into_iter();
+        frames.next();
+
+        match name.as_str() {
+            "PING" => Ok(Frame::Simple("PONG".to_string())),
+            "ROLE" => Ok(Frame::Array(vec![
+                Frame::Bulk(Bytes::from("sentinel")),
+                Frame::Array(vec![Frame::Bulk(Bytes::from(self.name.clone()))]),
+            ])),
+            "SENTINEL" => self.sentinel(frames),
+            _ => Err(format!("unknown command '{}', sentinels only answer PING, ROLE and SENTINEL", name).into()),
+        }
+    }
+
+    fn sentinel(&self, mut frames: IntoIter<Frame>) -> Result<Frame> {
+        let subcommand = next_string(&mut frames).map_err(|_| wrong_arity("sentinel"))?.to_uppercase();
+        let arity = match subcommand.as_str() {
+            "MASTERS" | "MYID" => 0,
+            "GET-MASTER-ADDR-BY-NAME" | "MASTER" | "REPLICAS" | "SLAVES" => 1,
+            "IS-MASTER-DOWN-BY-ADDR" => 4,
+            _ => return Err(format!("unknown subcommand '{}'. Try SENTINEL HELP.", subcommand).into()),
+        };
+        if frames.len() != arity {
+            return Err(wrong_arity(&format!("sentinel|{}", subcommand.to_lowercase())));
+        }
+        if arity == 1 && next_string(&mut frames)? != self.name {
+            return match subcommand.as_str() {
+                "GET-MASTER-ADDR-BY-NAME" => Ok(Frame::Null),
+                _ => Err("No such master with that name".into()),
+            };
+        }
+
+        let state = self.state();
+        Ok(match subcommand.as_str() {
+            "GET-MASTER-ADDR-BY-NAME" => Frame::Array(vec![
+                Frame::Bulk(Bytes::from(state.master.0.clone())),
+                Frame::Bulk(Bytes::from(state.master.1.to_string())),
+            ]),
+            "MASTER" => self.describe(&state),
+            "MASTERS" => Frame::Array(vec![self.describe(&state)]),
+            "MYID" => Frame::Bulk(Bytes::from(self.id.clone())),
+            "REPLICAS" | "SLAVES" => Frame::Array(state.replicas.iter().map(|(host, port)| pairs(&[
+                ("name", format!("{}:{}", host, port)),
+                ("ip", host.clone()),
+                ("port", port.to_string()),
+                ("flags", "slave".to_string()),
+            ])).collect()),
+            _ => {
+                drop(state);
+                return self.is_master_down(frames);
+            }
+        })
+    }
+
+    /// Whether the master at the address is down for this sentinel, voting for the sentinel
+    /// asking to lead the failover of the epoch unless it already voted in that epoch.
+    fn is_master_down(&self, mut frames: IntoIter<Frame>) -> Result<Frame> {
+        let host = next_string(&mut frames)?;
+        let port = next_integer(&mut frames)?;
+        let epoch = u64::try_from(next_integer(&mut frames)?).map_err(|_| "epoch is negative")?;
+        let candidate = next_string(&mut frames)?;
+
+        let down = self.subjectively_down();
+        let mut state = self.state();
+        let monitored = state.master.0 == host && i64::from(state.master.1) == port;
+        if monitored && candidate != "*" && state.vote.as_ref().is_none_or(|(voted, _)| *voted < epoch) {
+            state.vote = Some((epoch, candidate.clone()));
+            state.current_epoch = state.current_epoch.max(epoch);
+            // Leaves the elected sentinel the time to complete the failover before trying one.
+            if candidate != self.id {
+                state.failover_started = Some(Instant::now());
+            }
+        }
+        let (leader, leader_epoch) = match &state.vote {
+            Some((voted, leader)) if candidate != "*" => (leader.clone(), *voted),
+            _ => ("*".to_string(), 0),
+        };
+
+        Ok(Frame::Array(vec![
+            Frame::Integer((monitored && down) as i64),
+            Frame::Bulk(Bytes::from(leader)),
+            Frame::Integer(leader_epoch as i64),
+        ]))
+    }
+
+    fn describe(&self, state: &State) -> Frame {
+        let mut flags = "master".to_string();
+        if state.replied.elapsed() > self.down_after {
+            flags.push_str(",s_down");
+        }
+        if state.objectively_down {
+            flags.push_str(",o_down");
+        }
+
+        pairs(&[
+            ("name", self.name.clone()),
+            ("ip", state.master.0.clone()),
+            ("port", state.master.1.to_string()),
+            ("flags", flags),
+            ("num-slaves", state.replicas.len().to_string()),
+            ("num-other-sentinels", self.peers.len().to_string()),
+            ("quorum", self.quorum.to_string()),
+            ("config-epoch", state.config_epoch.to_string()),
+            ("down-after-milliseconds", self.down_after.as_millis().to_string()),
+            ("failover-timeout", self.failover_timeout.as_millis().to_string()),
+        ])
+    }
+}
+
+/// Flat array of field names and values, as SENTINEL MASTER replies.
+fn pairs(fields: &[(&str, String)]) -> Frame {
+    Frame::Array(fields.iter()
+        .flat_map(|(name, value)| [Frame::Bulk(Bytes::from(name.to_string())), Frame::Bulk(Bytes::from(value.clone()))])
+        .collect())
+}
+
+fn text(frame: &Frame) -> Option<String> {
+    match frame {
+        Frame::Simple(text) => Some(text.clone()),
+        Frame::Bulk(bytes) => String::from_utf8(bytes.to_vec()).ok(),
+        Frame::Integer(integer) => Some(integer.to_string()),
+        _ => None,
+    }
+}
+
+/// Sends a command on a new connection, no reply when the instance cannot be reached in time.
+async fn query(address: &(String, u16), arguments: &[&str], timeout: Duration) -> Option<Frame> {
+    authenticated_query(address, None, arguments, timeout).await
+}
+
+/// Sends a command on a new connection after AUTH with the password, no reply when the
+/// instance cannot be reached in time or refuses the password.
+async fn authenticated_query(address: &(String, u16), password: Option<&str>, arguments: &[&str], timeout: Duration) -> Option<Frame> {
+    let bulks = |arguments: &[&str]| Frame::Array(arguments.iter().map(|argument| Frame::Bulk(Bytes::from(argument.to_string()))).collect());
+    let request = async {
+        let mut connection = Connection::new(TcpStream::connect((address.0.as_str(), address.1)).await.ok()?);
+        if let Some(password) = password {
+            connection.write_frame(bulks(&["AUTH", password])).await.ok()?;
+            let Some(Frame::Simple(_)) = connection.read_frame().await.ok()? else { return None };
+        }
+        connection.write_frame(bulks(arguments)).await.ok()?;
+        connection.read_frame().await.ok()?
+    };
+    tokio::time::timeout(timeout, request).await.ok()?
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::server::Server;
+
+    use super::*;
+
+    async fn listen() -> (TcpListener, u16) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        (listener, port)
+    }
+
+    async fn master_address(port: u16) -> Option<Frame> {
+        query(&("127.0.0.1".to_string(), port), &["SENTINEL", "GET-MASTER-ADDR-BY-NAME", "main"], Duration::from_secs(1)).await
+    }
+
+    fn address(port: u16) -> Frame {
+        Frame::Array(vec![Frame::Bulk(Bytes::from("127.0.0.1")), Frame::Bulk(Bytes::from(port.to_string()))])
+    }
+
+    #[tokio::test]
+    async fn it_promotes_a_replica_once_the_quorum_agrees() {
+        let (listener, master_port) = listen().await;
+        let master = tokio::spawn(Server::new(1).serve(listener));
+        let (listener, replica_port) = listen().await;
+        tokio::spawn(Server::new(1).serve(listener));
+        let replica = ("127.0.0.1".to_string(), replica_port);
+        query(&replica, &["REPLICAOF", "127.0.0.1", &master_port.to_string()], Duration::from_secs(1)).await;
+
+        let mut listeners = vec![];
+        for _ in 0..3 {
+            listeners.push(listen().await);
+        }
+        let ports: Vec<u16> = listeners.iter().map(|(_, port)| *port).collect();
+        for (listener, port) in listeners {
+            let mut sentinel = Sentinel::monitor("main", "127.0.0.1", master_port, 2);
+            sentinel.configure("down-after-milliseconds", "300").unwrap();
+            sentinel.configure("failover-timeout", "1000").unwrap();
+            for peer in ports.iter().filter(|peer| **peer != port) {
+                sentinel.add_peer("127.0.0.1", *peer);
+            }
+            tokio::spawn(sentinel.serve(listener));
+        }
+
+        // Sentinels learn about the replica from the master before it goes away.
+        for _ in 0..100 {
+            let replicas = query(&("127.0.0.1".to_string(), ports[0]), &["SENTINEL", "REPLICAS", "main"], Duration::from_secs(1)).await;
+            if matches!(replicas, Some(Frame::Array(replicas)) if !replicas.is_empty()) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(Some(address(master_port)), master_address(ports[1]).await);
+        master.abort();
+
+        for port in ports {
+            for _ in 0..200 {
+                if master_address(port).await == Some(address(replica_port)) {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+            assert_eq!(Some(address(replica_port)), master_address(port).await);
+        }
+        let Some(Frame::Array(role)) = query(&replica, &["ROLE"], Duration::from_secs(1)).await else { panic!("expected a role") };
+        assert_eq!(Frame::Bulk(Bytes::from("master")), role[0]);
+    }
+
+    #[tokio::test]
+    async fn it_authenticates_to_instances_requiring_a_password() {
+        let (listener, master_port) = listen().await;
+        tokio::spawn(Server::new(1).requirepass("secret").serve(listener));
+        let (listener, replica_port) = listen().await;
+        tokio::spawn(Server::new(1).requirepass("secret").masterauth("secret").serve(listener));
+        let replica = ("127.0.0.1".to_string(), replica_port);
+        authenticated_query(&replica, Some("secret"), &["REPLICAOF", "127.0.0.1", &master_port.to_string()], Duration::from_secs(1)).await;
+
+        let (listener, port) = listen().await;
+        let mut sentinel = Sentinel::monitor("main", "127.0.0.1", master_port, 1);
+        sentinel.configure("auth-pass", "secret").unwrap();
+        tokio::spawn(sentinel.serve(listener));
+
+        // The replicas are only learnt from the reply of the master to ROLE.
+        let mut replicas = None;
+        for _ in 0..100 {
+            replicas = query(&("127.0.0.1".to_string(), port), &["SENTINEL", "REPLICAS", "main"], Duration::from_secs(1)).await;
+            if matches!(&replicas, Some(Frame::Array(replicas)) if !replicas.is_empty()) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let Some(Frame::Array(replicas)) = replicas else { panic!("expected replicas") };
+        let Frame::Array(fields) = &replicas[0] else { panic!("expected a replica") };
+        assert!(fields.contains(&Frame::Bulk(Bytes::from(replica_port.to_string()))));
+    }
+
+    #[tokio::test]
+    async fn it_votes_once_per_epoch() {
+        let (listener, port) = listen().await;
+        tokio::spawn(Sentinel::monitor("main", "127.0.0.1", 1, 1).serve(listener));
+        let sentinel = ("127.0.0.1".to_string(), port);
+        let vote = |candidate: &'static str, epoch: &'static str| {
+            let sentinel = sentinel.clone();
+            async move {
+                query(&sentinel, &["SENTINEL", "IS-MASTER-DOWN-BY-ADDR", "127.0.0.1", "1", epoch, candidate], Duration::from_secs(1)).await
+            }
+        };
+        let reply = |leader: &str, epoch| Some(Frame::Array(vec![Frame::Integer(0), Frame::Bulk(Bytes::from(leader.to_string())), Frame::Integer(epoch)]));
+
+        assert_eq!(reply("a", 1), vote("a", "1").await);
+        assert_eq!(reply("a", 1), vote("b", "1").await);
+        assert_eq!(reply("b", 2), vote("b", "2").await);
+        assert_eq!(reply("*", 0), vote("*", "2").await);
+        assert_eq!(Some(address(1)), master_address(port).await);
+    }
+}
@@ -1,47 +1,396 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::SystemTime;
 use std::vec::IntoIter;
 
-use tokio::net::TcpStream;
+use bytes::Bytes;
+use tokio::net::{TcpListener, TcpStream};
 
-use crate::command::Command;
+use crate::auth::{self, Hello};
+use crate::cluster::{self, Cluster};
+use crate::command::migrate::Migrate;
+use crate::command::{command_keys, command_name, config, denies_oom, is_write, Command};
 use crate::connection::Connection;
 use crate::database::{new_db, Database};
+use crate::rdb::{self, Corrupt};
 use crate::frame::Frame;
+use crate::replication::{self, Replication};
+use crate::worker::Workers;
 use crate::Error;
 
+pub use crate::database::DEFAULT_DATABASES;
+
+/// Id of the next connection, as HELLO reports it.
+static NEXT_CLIENT_ID: AtomicU64 = AtomicU64::new(1);
+
 #[derive(Clone)]
 pub struct Server {
+    /// Databases of the server. In thread-per-core mode the workers hold the keys, these
+    /// stay empty and only carry the memory settings and the number of databases.
     pub db: Database,
+    /// Threads owning the shards in thread-per-core mode, without them commands run on
+    /// the tokio task serving the connection.
+    workers: Option<Workers>,
+    pub(crate) replication: Arc<Replication>,
+    /// Nodes and slots of the cluster in cluster mode.
+    pub(crate) cluster: Option<Arc<Cluster>>,
+    /// Password clients authenticate with before running commands, when required.
+    password: Option<Arc<str>>,
+    /// Password replicas authenticate with to their master, like Redis' `masterauth`.
+    pub(crate) masterauth: Option<Arc<str>>,
+}
+
+/// State of a connection kept between its commands.
+struct Client {
+    id: u64,
+    /// Every connection starts on database zero and switches with SELECT.
+    db: Database,
+    /// Port a replica announced with REPLCONF before asking for the stream.
+    listening_port: Option<u16>,
+    /// Offset of the replication stream after the last write of the client, WAIT
+    /// blocks until replicas acknowledged it.
+    write_offset: u64,
+    /// Set by ASKING, lets the next command use a slot this node is importing.
+    asking: bool,
+    /// Whether AUTH or HELLO gave the password, always set when none is required.
+    authenticated: bool,
 }
 
 impl Server {
+    /// Creates a server with the given number of logical databases.
+    pub fn new(databases: usize) -> Self {
+        Server { db: Database::new(databases), workers: None, replication: Arc::default(), cluster: None, password: None, masterauth: None }
+    }
+
+    /// Creates a server in thread-per-core mode, where the worker threads hold the shards
+    /// of the keyspace and commands are forwarded to the workers owning their keys.
+    pub fn thread_per_core(databases: usize, workers: usize) -> Self {
+        let db = Database::new(databases);
+        let workers = Some(Workers::spawn(&db, workers));
+        Server { db, workers, replication: Arc::default(), cluster: None, password: None, masterauth: None }
+    }
+
+    /// Turns on cluster mode: the node serves the keys of the hash slots assigned to it and
+    /// redirects clients to the node serving the others, found over the cluster bus.
+    pub fn cluster_mode(mut self) -> Self {
+        self.cluster = Some(Arc::default());
+        self
+    }
+
+    /// Requires clients to authenticate with the password before running commands,
+    /// like Redis' `requirepass`.
+    pub fn requirepass(mut self, password: &str) -> Self {
+        self.password = Some(password.into());
+        self
+    }
+
+    /// Authenticates with the password to the master when following one.
+    pub fn masterauth(mut self, password: &str) -> Self {
+        self.masterauth = Some(password.into());
+        self
+    }
+
+    /// Applies a configuration parameter as CONFIG SET does, like `maxmemory`.
+    pub fn configure(&self, name: &str, value: &str) -> Result<(), String> {
+        config::set(self.db.memory(), name, value)
+    }
+
+    /// Accepts connections until the listener fails.
+    pub async fn serve(self, listener: TcpListener) {
+        if let Ok(address) = listener.local_addr() {
+            self.replication.set_port(address.port());
+            if let Some(cluster) = &self.cluster {
+                cluster.start(address).await.expect("failed to listen on the cluster bus");
+            }
+        }
+
+        while let Ok((socket, _)) = listener.accept().await {
+            let server = self.clone();
+            tokio::spawn(async move {
+                server.process(socket).await;
+            });
+        }
+    }
+
     pub async fn process(&self, socket: TcpStream) {
         let mut connection = Connection::new(socket);
+        let mut client = Client {
+            id: NEXT_CLIENT_ID.fetch_add(1, Ordering::Relaxed),
+            db: self.db.clone(),
+            listening_port: None,
+            write_offset: 0,
+            asking: false,
+            authenticated: self.password.is_none(),
+        };
+
+        loop {
+            let frame = match connection.read_frame().await {
+                Ok(Some(frame)) => frame,
+                Ok(None) => return,
+                Err(err) => {
+                    let _ = connection.write_frame(Frame::SimpleError(format!("ERR {}", err))).await;
+                    return;
+                }
+            };
 
-        while let Some(frame) = connection.read_frame().await.unwrap() {
-            let response: Frame = self.execute(frame).unwrap();
+            let name = match &frame {
+                Frame::Array(frames) => command_name(frames),
+                _ => None,
+            };
+            let response = match (name.as_deref(), frame) {
+                (name, _) if !client.authenticated && !matches!(name, Some("AUTH" | "HELLO")) => Ok(auth::required()),
+                (Some("PSYNC" | "SYNC"), Frame::Array(frames)) => {
+                    return replication::serve_replica(self, connection, frames, client.listening_port).await;
+                }
+                (Some("REPLCONF"), Frame::Array(frames)) => replication::replconf(frames).map(|port| {
+                    client.listening_port = port.or(client.listening_port);
+                    Frame::Simple("OK".to_string())
+                }),
+                (_, frame) => self.execute(frame, &mut client).await,
+            };
 
-            connection.write_frame(response).await.unwrap();
+            let response = response.unwrap_or_else(|err| Frame::SimpleError(format!("ERR {}", err)));
+            if connection.write_frame(response).await.is_err() {
+                return;
+            }
         }
     }
 
-    fn execute(&self, frame: Frame) -> Result<Frame, Error> {
-        let mut iterator: IntoIter<Frame>;
+    async fn execute(&self, frame: Frame, client: &mut Client) -> Result<Frame, Error> {
+        let frames = match frame {
+            Frame::Array(frames) => frames,
+            _ => return Err(format!("protocol error; expected array, got {:?}", frame).into()),
+        };
+
+        let name = command_name(&frames);
+        let asking = std::mem::take(&mut client.asking);
+        match (&self.cluster, name.as_deref()) {
+            (None, Some("ASKING" | "CLUSTER")) => return Ok(cluster::disabled()),
+            (Some(_), Some("ASKING")) => {
+                client.asking = true;
+                return Ok(Frame::Simple("OK".to_string()));
+            }
+            (Some(cluster), Some("CLUSTER")) => return cluster.command(frames, self, &client.db).await,
+            (Some(cluster), _) => {
+                cluster::check_database(&frames)?;
+                if let Some(redirect) = cluster.redirect(&frames, self, &client.db, asking).await {
+                    return Ok(redirect);
+                }
+            }
+            (None, _) => {}
+        }
 
-        match frame {
-            Frame::Array(val) => {
-                iterator = Vec::into_iter(val);
+        match name.as_deref() {
+            Some("AUTH") => {
+                let authenticated = auth::auth(self.password.as_deref(), frames)?;
+                client.authenticated |= authenticated;
+                return Ok(match authenticated {
+                    true => Frame::Simple("OK".to_string()),
+                    false => auth::wrong_password(),
+                });
             }
-            _ => return Err(format!("protocol error; expected array, got {:?}", frame).into()),
+            Some("HELLO") => return self.hello(frames, client).await,
+            Some("REPLICAOF" | "SLAVEOF") => return replication::replicaof(self, frames).await,
+            Some("ROLE") => return Ok(self.replication.role().await),
+            Some("WAIT" | "WAITAOF") => return self.wait(frames, client).await,
+            Some("MIGRATE") => return self.migrate(frames, client).await,
+            _ if !is_write(&frames) => return self.apply(frames, &mut client.db).await,
+            _ => {}
+        }
+
+        let state = self.replication.state.read().await;
+        if state.is_replica() {
+            return Ok(Frame::SimpleError("READONLY You can't write against a read only replica.".to_string()));
+        }
+        if !state.propagates() {
+            return self.write(frames, client, false).await;
+        }
+        drop(state);
+
+        self.write(frames, client, true).await
+    }
+
+    /// Authenticates the client when HELLO comes with credentials, and describes the server.
+    async fn hello(&self, frames: Vec<Frame>, client: &mut Client) -> Result<Frame, Error> {
+        let hello = match Hello::parse(frames) {
+            Ok(hello) => hello,
+            Err(response) => return Ok(response),
+        };
+        if let Some((username, password)) = &hello.credentials {
+            if !auth::check(self.password.as_deref(), username, password) {
+                return Ok(auth::wrong_password());
+            }
+            client.authenticated = true;
+        }
+        if !client.authenticated {
+            return Ok(Frame::SimpleError("NOAUTH HELLO must be called with the client already authenticated, otherwise the HELLO <proto> AUTH <user> <pass> option can be used to authenticate the client and select the RESP protocol version at the same time".to_string()));
         }
 
+        let replica = self.replication.state.read().await.is_replica();
+        Ok(auth::hello_reply(client.id, self.cluster.is_some(), replica))
+    }
+
+    /// Moves keys to another instance, replicas remove the keys moved away as well.
+    async fn migrate(&self, frames: Vec<Frame>, client: &mut Client) -> Result<Frame, Error> {
+        let keys = command_keys(&frames);
+        let mut frames = frames.into_iter();
+        frames.next();
+        let migrate = Migrate::try_from(&mut frames)?;
+        let _order = self.replication.order(keys.as_deref()).await;
+        if self.replication.state.read().await.is_replica() {
+            return Ok(Frame::SimpleError("READONLY You can't write against a read only replica.".to_string()));
+        }
+
+        let (response, removed) = match &self.workers {
+            Some(workers) => {
+                let view = workers.gather(&client.db, migrate.keys()).await;
+                let migrated = migrate.execute(&view.db).await;
+                workers.scatter(view).await;
+                migrated
+            }
+            None => migrate.execute(&client.db).await,
+        };
+        let mut state = self.replication.state.write().await;
+        if state.propagates() && !removed.is_empty() {
+            let del = std::iter::once(Bytes::from("DEL")).chain(removed).map(Frame::Bulk).collect();
+            state.propagate(client.db.index(), del);
+            client.write_offset = state.offset();
+        }
+
+        Ok(response)
+    }
+
+    /// Blocks the client as WAIT and WAITAOF do until replicas acknowledged its writes.
+    async fn wait(&self, frames: Vec<Frame>, client: &Client) -> Result<Frame, Error> {
+        let aof = command_name(&frames).as_deref() == Some("WAITAOF");
+        let (local, replicas, timeout) = replication::wait_arguments(frames)?;
+
+        if self.replication.state.read().await.is_replica() {
+            let name = if aof { "WAITAOF" } else { "WAIT" };
+            return Ok(Frame::SimpleError(format!("ERR {} cannot be used with replica instances. Please also note that writes to replicas are just local and are not propagated.", name)));
+        }
+        // There is no append only file to sync, here or on replicas.
+        if local > 0 {
+            return Err("WAITAOF cannot be used when numlocal is set but appendonly is disabled.".into());
+        }
+        if aof && replicas > 0 {
+            return Err("WAITAOF cannot be used when numreplicas is set as replicas have no append only file to sync.".into());
+        }
+        if aof {
+            return Ok(Frame::Array(vec![Frame::Integer(0), Frame::Integer(0)]));
+        }
+
+        let acknowledged = self.replication.wait(client.write_offset, replicas, timeout).await as i64;
+        Ok(Frame::Integer(acknowledged))
+    }
+
+    /// Runs a write command, propagating it and the keys evicted to make room for it
+    /// when replicas are attached.
+    async fn write(&self, frames: Vec<Frame>, client: &mut Client, propagate: bool) -> Result<Frame, Error> {
+        // Keys are evicted before the command runs, the first write past the limit still succeeds.
+        let evicts = denies_oom(&frames) && client.db.memory().over_limit();
+        // Evicted keys may live in any shard, so such a write is ordered against all others.
+        // Workers write back the keys of commands spanning several of them after the command
+        // ran on copies, which other writes to the keys wait for.
+        let _order = match propagate || self.workers.is_some() {
+            true => Some(self.replication.order(command_keys(&frames).filter(|_| !evicts).as_deref()).await),
+            false => None,
+        };
+        if propagate && self.replication.state.read().await.is_replica() {
+            return Ok(Frame::SimpleError("READONLY You can't write against a read only replica.".to_string()));
+        }
+
+        let mut evicted = vec![];
+        let fits = !evicts || self.free_memory(&client.db, &mut evicted).await;
+        let mut propagated: Vec<(usize, Vec<Frame>)> = evicted.into_iter()
+            .map(|(database, key)| (database, vec![Frame::Bulk(Bytes::from("DEL")), Frame::Bulk(key)]))
+            .collect();
+        let response = match fits {
+            true => {
+                let database = client.db.index();
+                // Times to live are made absolute with the clock of the master as the command starts.
+                let command = propagate.then(|| replication::absolute_expirations(frames.clone(), SystemTime::now()));
+                let response = self.apply(frames, &mut client.db).await;
+                if let (Ok(response), Some(command)) = (&response, command) {
+                    if !matches!(response, Frame::SimpleError(_)) {
+                        propagated.push((database, command));
+                    }
+                }
+                response
+            }
+            false => Ok(Frame::SimpleError("OOM command not allowed when used memory > 'maxmemory'.".to_string())),
+        };
+
+        if propagate {
+            let mut state = self.replication.state.write().await;
+            for (database, frames) in propagated {
+                state.propagate(database, frames);
+            }
+            client.write_offset = state.offset();
+        }
+
+        response
+    }
+
+    /// Runs the command on the databases, or on the workers owning its keys in thread-per-core mode.
+    pub(crate) async fn apply(&self, mut frames: Vec<Frame>, db: &mut Database) -> Result<Frame, Error> {
+        // Workers split some commands by their frames.
+        let mut iterator: IntoIter<Frame> = match &self.workers {
+            Some(_) => frames.clone().into_iter(),
+            None => std::mem::take(&mut frames).into_iter(),
+        };
         let command: Box<dyn Command> = (&mut iterator).try_into()?;
+        let select = command.select();
+
+        let response = match &self.workers {
+            Some(workers) => workers.apply(frames, command, db).await,
+            None => command.execute(db.clone()),
+        };
+        if let Some(selected) = select.and_then(|index| db.select(index)) {
+            *db = selected;
+        }
+
+        Ok(response)
+    }
+
+    /// Evicts keys until the memory used fits the limit again, see [`Database::free_memory`].
+    async fn free_memory(&self, db: &Database, evicted: &mut Vec<(usize, Bytes)>) -> bool {
+        match &self.workers {
+            Some(workers) => workers.free_memory(db, evicted).await,
+            None => db.free_memory(evicted),
+        }
+    }
+
+    /// Writes a snapshot of every database.
+    pub(crate) async fn save(&self) -> Vec<u8> {
+        match &self.workers {
+            Some(workers) => workers.save().await,
+            None => rdb::save(&self.db),
+        }
+    }
+
+    /// Replaces the contents of every database with the snapshot, leaving them untouched when it is corrupt.
+    pub(crate) async fn load(&self, snapshot: &[u8]) -> Result<(), Corrupt> {
+        match &self.workers {
+            Some(workers) => workers.load(&self.db, snapshot).await,
+            None => rdb::load(&self.db, snapshot),
+        }
+    }
 
-        Ok(command.execute(self.db.clone()))
+    /// Up to `count` keys of the database hashing to the slot.
+    pub(crate) async fn keys_in_slot(&self, db: &Database, slot: usize, count: usize) -> Vec<Bytes> {
+        let Some(workers) = &self.workers else {
+            return cluster::keys_in_slot(db, slot, count);
+        };
+        let index = db.index();
+        let mut keys = workers.each(move |db| cluster::keys_in_slot(&db.select(index).unwrap(), slot, count)).await.concat();
+        keys.truncate(count);
+        keys
     }
 }
 
 impl Default for Server {
     fn default() -> Self {
-        Server { db: new_db() }
+        Server { db: new_db(), workers: None, replication: Arc::default(), cluster: None, password: None, masterauth: None }
     }
 }
@@ -0,0 +1,323 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap};
+use std::ops::Bound;
+
+use bytes::Bytes;
+
+use crate::database::scan_hash;
+
+/// Bytes a member takes besides its contents: a hash map slot and two tree nodes.
+const MEMBER_OVERHEAD: usize = 144;
+/// Bytes a member of a compact set takes besides its contents: the score and length headers.
+const COMPACT_MEMBER_OVERHEAD: usize = 24;
+
+/// Thresholds a set keeps the compact encoding within, set per server with
+/// `zset-max-listpack-entries` and `zset-max-listpack-value`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CompactLimits {
+    /// Most members a compact set holds.
+    pub entries: usize,
+    /// Longest member a compact set holds.
+    pub value: usize,
+}
+
+impl Default for CompactLimits {
+    fn default() -> Self {
+        CompactLimits { entries: 128, value: 64 }
+    }
+}
+
+/// Members ordered by score, ties are broken by comparing the members
+/// lexicographically, the same way Redis orders its sorted sets.
+///
+/// Small sets keep their members in one ordered vector, like Redis' listpack,
+/// and switch to a map with a tree index once they outgrow the thresholds.
+/// They never switch back, as in Redis.
+#[derive(Clone, Debug)]
+pub struct SortedSet {
+    encoding: Encoding,
+}
+
+#[derive(Clone, Debug)]
+enum Encoding {
+    Compact(Vec<(Score, Bytes)>),
+    Indexed {
+        scores: HashMap<Bytes, f64>,
+        ordered: BTreeSet<(Score, Bytes)>,
+        /// Members ordered by their SCAN hash, ZSCAN cursors are positions in this order.
+        scan_order: BTreeSet<(u64, Bytes)>,
+    },
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Score(f64);
+
+impl PartialEq for Score {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Score {}
+
+impl PartialOrd for Score {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Score {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl Default for SortedSet {
+    fn default() -> Self {
+        SortedSet { encoding: Encoding::Compact(vec![]) }
+    }
+}
+
+/// Sets are equal when they hold the same members and scores, whatever their encodings.
+impl PartialEq for SortedSet {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl SortedSet {
+    /// Adds the member or updates its score, returns true when the member is new.
+    /// The set leaves the compact encoding once it would outgrow the limits.
+    pub fn insert(&mut self, member: Bytes, score: f64, limits: CompactLimits) -> bool {
+        if let Encoding::Compact(members) = &self.encoding {
+            let too_many = members.len() >= limits.entries;
+            if member.len() > limits.value || (too_many && self.score(&member).is_none()) {
+                self.convert();
+            }
+        }
+
+        match &mut self.encoding {
+            Encoding::Compact(members) => {
+                let previous = members.iter().position(|(_, existing)| *existing == member);
+                if let Some(index) = previous {
+                    members.remove(index);
+                }
+                let entry = (Score(score), member);
+                let index = members.partition_point(|existing| *existing < entry);
+                members.insert(index, entry);
+                previous.is_none()
+            }
+            Encoding::Indexed { scores, ordered, scan_order } => match scores.insert(member.clone(), score) {
+                Some(previous) => {
+                    ordered.remove(&(Score(previous), member.clone()));
+                    ordered.insert((Score(score), member));
+                    false
+                }
+                None => {
+                    scan_order.insert((scan_hash(&member), member.clone()));
+                    ordered.insert((Score(score), member));
+                    true
+                }
+            },
+        }
+    }
+
+    pub fn remove(&mut self, member: &[u8]) -> bool {
+        match &mut self.encoding {
+            Encoding::Compact(members) => match members.iter().position(|(_, existing)| existing == member) {
+                Some(index) => {
+                    members.remove(index);
+                    true
+                }
+                None => false,
+            },
+            Encoding::Indexed { scores, ordered, scan_order } => match scores.remove_entry(member) {
+                Some((member, score)) => {
+                    scan_order.remove(&(scan_hash(&member), member.clone()));
+                    ordered.remove(&(Score(score), member));
+                    true
+                }
+                None => false,
+            },
+        }
+    }
+
+    pub fn score(&self, member: &[u8]) -> Option<f64> {
+        match &self.encoding {
+            Encoding::Compact(members) => members.iter().find(|(_, existing)| existing == member).map(|(score, _)| score.0),
+            Encoding::Indexed { scores, .. } => scores.get(member).copied(),
+        }
+    }
+
+    /// Approximate number of bytes taken by the members and, past the compact encoding, their indexes.
+    pub fn memory_usage(&self) -> usize {
+        match &self.encoding {
+            Encoding::Compact(members) => members.iter().map(|(_, member)| member.len() + COMPACT_MEMBER_OVERHEAD).sum(),
+            Encoding::Indexed { scores, .. } => scores.keys().map(|member| member.len() + MEMBER_OVERHEAD).sum(),
+        }
+    }
+
+    /// Whether the members are kept in the compact encoding.
+    pub fn is_compact(&self) -> bool {
+        matches!(self.encoding, Encoding::Compact(_))
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.encoding {
+            Encoding::Compact(members) => members.len(),
+            Encoding::Indexed { scores, .. } => scores.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Iterates members from the lowest to the highest score.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (&Bytes, f64)> {
+        let (compact, indexed) = match &self.encoding {
+            Encoding::Compact(members) => (Some(members.iter()), None),
+            Encoding::Indexed { ordered, .. } => (None, Some(ordered.iter())),
+        };
+
+        compact.into_iter().flatten().chain(indexed.into_iter().flatten())
+            .map(|(score, member)| (member, score.0))
+    }
+
+    /// Iterates members with scores in the half open range `[min, max)`.
+    pub fn range_by_score(&self, min: f64, max: f64) -> impl Iterator<Item = (&Bytes, f64)> {
+        let (compact, indexed) = match &self.encoding {
+            Encoding::Compact(members) => {
+                let start = members.partition_point(|(score, _)| *score < Score(min));
+                let end = members.partition_point(|(score, _)| *score < Score(max));
+                (Some(members[start..end.max(start)].iter()), None)
+            }
+            Encoding::Indexed { ordered, .. } => {
+                let range = (Bound::Included((Score(min), Bytes::new())), Bound::Excluded((Score(max), Bytes::new())));
+                (None, Some(ordered.range(range)))
+            }
+        };
+
+        compact.into_iter().flatten().chain(indexed.into_iter().flatten())
+            .map(|(score, member)| (member, score.0))
+    }
+
+    /// Visits up to `count` members in the SCAN order starting at the cursor, returning them
+    /// with their scores and the cursor to continue from, zero once the iteration is complete.
+    /// Members sharing a hash are returned together, and compact sets all at once as in Redis.
+    pub fn scan(&self, cursor: u64, count: usize) -> (u64, Vec<(&Bytes, f64)>) {
+        let (scores, scan_order) = match &self.encoding {
+            Encoding::Compact(members) => return (0, members.iter().map(|(score, member)| (member, score.0)).collect()),
+            Encoding::Indexed { scores, scan_order, .. } => (scores, scan_order),
+        };
+
+        let mut members = vec![];
+        let mut last = None;
+        for (hash, member) in scan_order.range((cursor, Bytes::new())..) {
+            if members.len() >= count && last != Some(*hash) {
+                return (*hash, members);
+            }
+            last = Some(*hash);
+            members.push((member, scores[member]));
+        }
+
+        (0, members)
+    }
+
+    fn convert(&mut self) {
+        if let Encoding::Compact(members) = &mut self.encoding {
+            let members = std::mem::take(members);
+            let scores = members.iter().map(|(score, member)| (member.clone(), score.0)).collect();
+            let scan_order = members.iter().map(|(_, member)| (scan_hash(member), member.clone())).collect();
+            self.encoding = Encoding::Indexed { scores, ordered: members.into_iter().collect(), scan_order };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_orders_members_by_score_then_member() {
+        let mut set = SortedSet::default();
+        set.insert(Bytes::from("c"), 1.0, CompactLimits::default());
+        set.insert(Bytes::from("a"), 2.0, CompactLimits::default());
+        set.insert(Bytes::from("b"), 1.0, CompactLimits::default());
+
+        let members: Vec<&Bytes> = set.iter().map(|(member, _)| member).collect();
+
+        assert_eq!(vec!["b", "c", "a"], members);
+    }
+
+    #[test]
+    fn it_updates_score_of_existing_member() {
+        let mut set = SortedSet::default();
+
+        assert!(set.insert(Bytes::from("a"), 1.0, CompactLimits::default()));
+        assert!(!set.insert(Bytes::from("a"), 5.0, CompactLimits::default()));
+
+        assert_eq!(1, set.len());
+        assert_eq!(Some(5.0), set.score(b"a"));
+        assert_eq!(vec![(&Bytes::from("a"), 5.0)], set.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn it_returns_members_in_half_open_score_range() {
+        let mut set = SortedSet::default();
+        for (member, score) in [("a", 1.0), ("b", 2.0), ("c", 3.0)] {
+            set.insert(Bytes::from(member), score, CompactLimits::default());
+        }
+
+        let members: Vec<&Bytes> = set.range_by_score(2.0, 3.0).map(|(member, _)| member).collect();
+
+        assert_eq!(vec!["b"], members);
+    }
+
+    #[test]
+    fn it_removes_members() {
+        let mut set = SortedSet::default();
+        set.insert(Bytes::from("a"), 1.0, CompactLimits::default());
+
+        assert!(set.remove(b"a"));
+        assert!(!set.remove(b"a"));
+        assert!(set.is_empty());
+        assert_eq!(0, set.iter().count());
+    }
+
+    #[test]
+    fn it_converts_to_the_indexed_encoding_past_thresholds() {
+        let mut set = SortedSet::default();
+        let limits = CompactLimits::default();
+        for i in 0..limits.entries {
+            set.insert(Bytes::from(format!("member:{}", i)), i as f64, limits);
+        }
+        set.insert(Bytes::from("member:0"), -1.0, limits);
+        assert!(set.is_compact());
+
+        let compact = set.clone();
+        set.insert(Bytes::from("one more"), 0.5, limits);
+        assert!(!set.is_compact());
+        set.remove(b"one more");
+        assert!(!set.is_compact());
+        assert_eq!(compact, set);
+        assert!(compact.memory_usage() < set.memory_usage());
+
+        let mut set = SortedSet::default();
+        set.insert(Bytes::from(vec![b'a'; limits.value + 1]), 1.0, limits);
+        assert!(!set.is_compact());
+    }
+
+    #[test]
+    fn it_returns_score_ranges_of_compact_sets() {
+        let mut set = SortedSet::default();
+        for (member, score) in [("a", 1.0), ("b", 2.0), ("c", 2.0), ("d", 3.0)] {
+            set.insert(Bytes::from(member), score, CompactLimits::default());
+        }
+
+        assert!(set.is_compact());
+        assert_eq!(vec![(&Bytes::from("b"), 2.0), (&Bytes::from("c"), 2.0)], set.range_by_score(2.0, 3.0).collect::<Vec<_>>());
+        assert_eq!(Some((&Bytes::from("d"), 3.0)), set.iter().next_back());
+        assert_eq!(0, set.range_by_score(3.0, 1.0).count());
+    }
+}
@@ -0,0 +1,362 @@
+use bytes::Bytes;
+
+use crate::rdb::{Corrupt, ModuleWriter, Reader};
+
+const DUPLICATE_POLICIES: [DuplicatePolicy; 6] = [
+    DuplicatePolicy::Block,
+    DuplicatePolicy::First,
+    DuplicatePolicy::Last,
+    DuplicatePolicy::Min,
+    DuplicatePolicy::Max,
+    DuplicatePolicy::Sum,
+];
+
+const AGGREGATIONS: [Aggregation; 8] = [
+    Aggregation::Avg,
+    Aggregation::Sum,
+    Aggregation::Min,
+    Aggregation::Max,
+    Aggregation::Count,
+    Aggregation::First,
+    Aggregation::Last,
+    Aggregation::Range,
+];
+
+/// What to do when a sample is added with a timestamp the series already holds.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) enum DuplicatePolicy {
+    #[default]
+    Block,
+    First,
+    Last,
+    Min,
+    Max,
+    Sum,
+}
+
+impl DuplicatePolicy {
+    pub(crate) fn parse(name: &str) -> Option<Self> {
+        match name.to_uppercase().as_str() {
+            "BLOCK" => Some(DuplicatePolicy::Block),
+            "FIRST" => Some(DuplicatePolicy::First),
+            "LAST" => Some(DuplicatePolicy::Last),
+            "MIN" => Some(DuplicatePolicy::Min),
+            "MAX" => Some(DuplicatePolicy::Max),
+            "SUM" => Some(DuplicatePolicy::Sum),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Aggregation {
+    Avg,
+    Sum,
+    Min,
+    Max,
+    Count,
+    First,
+    Last,
+    Range,
+}
+
+impl Aggregation {
+    pub(crate) fn parse(name: &str) -> Option<Self> {
+        match name.to_uppercase().as_str() {
+            "AVG" => Some(Aggregation::Avg),
+            "SUM" => Some(Aggregation::Sum),
+            "MIN" => Some(Aggregation::Min),
+            "MAX" => Some(Aggregation::Max),
+            "COUNT" => Some(Aggregation::Count),
+            "FIRST" => Some(Aggregation::First),
+            "LAST" => Some(Aggregation::Last),
+            "RANGE" => Some(Aggregation::Range),
+            _ => None,
+        }
+    }
+}
+
+/// Running state of a single aggregation bucket.
+#[derive(Clone, Debug, PartialEq)]
+struct Bucket {
+    start: i64,
+    count: u64,
+    sum: f64,
+    min: f64,
+    max: f64,
+    first: f64,
+    last: f64,
+}
+
+impl Bucket {
+    fn new(start: i64, value: f64) -> Self {
+        Bucket { start, count: 1, sum: value, min: value, max: value, first: value, last: value }
+    }
+
+    fn add(&mut self, value: f64) {
+        self.count += 1;
+        self.sum += value;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.last = value;
+    }
+
+    fn value(&self, aggregation: Aggregation) -> f64 {
+        match aggregation {
+            Aggregation::Avg => self.sum / self.count as f64,
+            Aggregation::Sum => self.sum,
+            Aggregation::Min => self.min,
+            Aggregation::Max => self.max,
+            Aggregation::Count => self.count as f64,
+            Aggregation::First => self.first,
+            Aggregation::Last => self.last,
+            Aggregation::Range => self.max - self.min,
+        }
+    }
+}
+
+/// Compaction rule downsampling the series into the destination key.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Rule {
+    pub destination: Bytes,
+    pub aggregation: Aggregation,
+    pub duration: i64,
+    open: Option<Bucket>,
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum AddError {
+    TooOld,
+    Blocked,
+}
+
+/// Series of samples ordered by their millisecond timestamps.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TimeSeries {
+    samples: Vec<(i64, f64)>,
+    /// How many milliseconds behind the newest sample are kept, zero keeps everything.
+    pub(crate) retention: u64,
+    pub(crate) duplicate_policy: DuplicatePolicy,
+    pub(crate) rules: Vec<Rule>,
+    /// Key of the series compacted into this one.
+    pub(crate) source: Option<Bytes>,
+}
+
+impl TimeSeries {
+    pub(crate) fn new(retention: u64, duplicate_policy: DuplicatePolicy) -> Self {
+        TimeSeries { retention, duplicate_policy, ..TimeSeries::default() }
+    }
+
+    /// Adds the sample, returning the finished buckets each compaction rule writes to its destination.
+    pub(crate) fn add(&mut self, timestamp: i64, value: f64, policy: Option<DuplicatePolicy>) -> Result<Vec<(Bytes, i64, f64)>, AddError> {
+        if let Some(&(newest, _)) = self.samples.last() {
+            if self.retention > 0 && timestamp < newest.saturating_sub(self.retention as i64) {
+                return Err(AddError::TooOld);
+            }
+        }
+
+        match self.samples.binary_search_by_key(&timestamp, |&(timestamp, _)| timestamp) {
+            Ok(index) => {
+                let current = self.samples[index].1;
+                self.samples[index].1 = match policy.unwrap_or(self.duplicate_policy) {
+                    DuplicatePolicy::Block => return Err(AddError::Blocked),
+                    DuplicatePolicy::First => current,
+                    DuplicatePolicy::Last => value,
+                    DuplicatePolicy::Min => current.min(value),
+                    DuplicatePolicy::Max => current.max(value),
+                    DuplicatePolicy::Sum => current + value,
+                };
+            }
+            Err(index) => self.samples.insert(index, (timestamp, value)),
+        }
+        self.trim();
+
+        Ok(self.rules.iter_mut().filter_map(|rule| rule.add(timestamp, value)).collect())
+    }
+
+    /// Adds a downsampled sample coming from a compaction rule, replacing any previous value.
+    pub(crate) fn add_compacted(&mut self, timestamp: i64, value: f64) {
+        let _ = self.add(timestamp, value, Some(DuplicatePolicy::Last));
+    }
+
+    pub(crate) fn create_rule(&mut self, destination: Bytes, aggregation: Aggregation, duration: i64) {
+        self.rules.push(Rule { destination, aggregation, duration, open: None });
+    }
+
+    /// Approximate number of bytes taken by the samples and the compaction rules.
+    pub(crate) fn memory_usage(&self) -> usize {
+        self.samples.capacity() * std::mem::size_of::<(i64, f64)>()
+            + self.rules.iter().map(|rule| rule.destination.len() + std::mem::size_of::<Rule>()).sum::<usize>()
+    }
+
+    pub(crate) fn save(&self, writer: &mut ModuleWriter) {
+        writer.uint(self.retention);
+        writer.uint(self.duplicate_policy as u64);
+        writer.string(self.source.as_deref().unwrap_or_default());
+        writer.uint(self.samples.len() as u64);
+        for &(timestamp, value) in &self.samples {
+            writer.uint(timestamp as u64);
+            writer.double(value);
+        }
+        writer.uint(self.rules.len() as u64);
+        for rule in &self.rules {
+            writer.string(&rule.destination);
+            writer.uint(rule.aggregation as u64);
+            writer.uint(rule.duration as u64);
+            writer.uint(rule.open.is_some() as u64);
+            if let Some(open) = &rule.open {
+                writer.uint(open.start as u64);
+                writer.uint(open.count);
+                for value in [open.sum, open.min, open.max, open.first, open.last] {
+                    writer.double(value);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn load(reader: &mut Reader) -> Result<Self, Corrupt> {
+        let retention = reader.module_uint()?;
+        let duplicate_policy = *DUPLICATE_POLICIES.get(reader.module_uint()? as usize).ok_or(Corrupt)?;
+        let source = Some(reader.module_string()?).filter(|source| !source.is_empty());
+        let samples = (0..reader.module_count()?)
+            .map(|_| Ok((reader.module_uint()? as i64, reader.module_double()?)))
+            .collect::<Result<_, _>>()?;
+        let rules = (0..reader.module_count()?)
+            .map(|_| {
+                let destination = reader.module_string()?;
+                let aggregation = *AGGREGATIONS.get(reader.module_uint()? as usize).ok_or(Corrupt)?;
+                let duration = reader.module_uint()? as i64;
+                if duration <= 0 {
+                    return Err(Corrupt);
+                }
+                let open = match reader.module_uint()? {
+                    0 => None,
+                    _ => Some(Bucket {
+                        start: reader.module_uint()? as i64,
+                        count: reader.module_uint()?,
+                        sum: reader.module_double()?,
+                        min: reader.module_double()?,
+                        max: reader.module_double()?,
+                        first: reader.module_double()?,
+                        last: reader.module_double()?,
+                    }),
+                };
+                Ok(Rule { destination, aggregation, duration, open })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(TimeSeries { samples, retention, duplicate_policy, rules, source })
+    }
+
+    pub(crate) fn range(&self, from: i64, to: i64) -> &[(i64, f64)] {
+        let start = self.samples.partition_point(|&(timestamp, _)| timestamp < from);
+        let end = self.samples.partition_point(|&(timestamp, _)| timestamp <= to);
+
+        &self.samples[start..end.max(start)]
+    }
+
+    fn trim(&mut self) {
+        if let (Some(&(newest, _)), true) = (self.samples.last(), self.retention > 0) {
+            let oldest = newest.saturating_sub(self.retention as i64);
+            let expired = self.samples.partition_point(|&(timestamp, _)| timestamp < oldest);
+            self.samples.drain(..expired);
+        }
+    }
+}
+
+impl Rule {
+    /// Feeds the sample into the open bucket, returning the previous bucket once a later one starts.
+    fn add(&mut self, timestamp: i64, value: f64) -> Option<(Bytes, i64, f64)> {
+        let start = bucket_start(timestamp, self.duration);
+        match &mut self.open {
+            Some(open) if open.start == start => {
+                open.add(value);
+                None
+            }
+            Some(open) if open.start > start => None,
+            open => {
+                let finished = open.replace(Bucket::new(start, value))?;
+                Some((self.destination.clone(), finished.start, finished.value(self.aggregation)))
+            }
+        }
+    }
+}
+
+/// Aggregates the samples into buckets of the duration, aligned to the epoch.
+pub(crate) fn aggregate(samples: &[(i64, f64)], aggregation: Aggregation, duration: i64) -> Vec<(i64, f64)> {
+    let mut buckets: Vec<Bucket> = vec![];
+
+    for &(timestamp, value) in samples {
+        let start = bucket_start(timestamp, duration);
+        match buckets.last_mut() {
+            Some(bucket) if bucket.start == start => bucket.add(value),
+            _ => buckets.push(Bucket::new(start, value)),
+        }
+    }
+
+    buckets.iter().map(|bucket| (bucket.start, bucket.value(aggregation))).collect()
+}
+
+fn bucket_start(timestamp: i64, duration: i64) -> i64 {
+    timestamp - timestamp.rem_euclid(duration)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_keeps_samples_ordered() {
+        let mut series = TimeSeries::default();
+
+        series.add(20, 2.0, None).unwrap();
+        series.add(10, 1.0, None).unwrap();
+        series.add(30, 3.0, None).unwrap();
+
+        assert_eq!(&[(10, 1.0), (20, 2.0)], series.range(0, 25));
+    }
+
+    #[test]
+    fn it_applies_duplicate_policies() {
+        let mut series = TimeSeries::default();
+        series.add(10, 1.0, None).unwrap();
+
+        assert_eq!(Err(AddError::Blocked), series.add(10, 5.0, None));
+        series.add(10, 5.0, Some(DuplicatePolicy::Sum)).unwrap();
+        series.duplicate_policy = DuplicatePolicy::Min;
+        series.add(10, 2.0, None).unwrap();
+
+        assert_eq!(&[(10, 2.0)], series.range(0, 100));
+    }
+
+    #[test]
+    fn it_drops_samples_outside_of_retention() {
+        let mut series = TimeSeries { retention: 100, ..TimeSeries::default() };
+        series.add(10, 1.0, None).unwrap();
+        series.add(100, 2.0, None).unwrap();
+        series.add(150, 3.0, None).unwrap();
+
+        assert_eq!(Err(AddError::TooOld), series.add(40, 4.0, None));
+        assert_eq!(&[(100, 2.0), (150, 3.0)], series.range(0, 1000));
+    }
+
+    #[test]
+    fn it_aggregates_into_aligned_buckets() {
+        let samples = [(5, 1.0), (9, 3.0), (10, 10.0), (25, 4.0), (29, 6.0)];
+
+        assert_eq!(vec![(0, 2.0), (10, 10.0), (20, 5.0)], aggregate(&samples, Aggregation::Avg, 10));
+        assert_eq!(vec![(0, 2.0), (10, 1.0), (20, 2.0)], aggregate(&samples, Aggregation::Count, 10));
+        assert_eq!(vec![(0, 2.0), (10, 0.0), (20, 2.0)], aggregate(&samples, Aggregation::Range, 10));
+    }
+
+    #[test]
+    fn it_emits_finished_buckets_of_compaction_rules() {
+        let mut series = TimeSeries::default();
+        series.create_rule(Bytes::from("dest"), Aggregation::Sum, 10);
+
+        assert!(series.add(1, 1.0, None).unwrap().is_empty());
+        assert!(series.add(5, 2.0, None).unwrap().is_empty());
+
+        assert_eq!(vec![(Bytes::from("dest"), 0, 3.0)], series.add(12, 4.0, None).unwrap());
+    }
+}
@@ -0,0 +1,629 @@
+use std::collections::HashSet;
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+use std::time::SystemTime;
+
+use bytes::Bytes;
+use tokio::sync::oneshot;
+
+use crate::command::memory;
+use crate::command::scan::scan_reply;
+use crate::command::{command_keys, command_name, is_write, Command};
+use crate::database::{scan_hash, shard_of, spawn_freer, Database, Value, SHARDS};
+use crate::eviction::{random, EvictionPolicy};
+use crate::frame::Frame;
+use crate::rdb::{self, Corrupt, Loaded};
+
+/// Worker threads of the thread-per-core mode. Every shard of the keyspace is owned by
+/// exactly one worker: the worker thread holds it in databases of its own, which no
+/// other thread reaches, so the shard locks are never contended. Commands confined to
+/// the shards of one worker are sent to it. The others become messages to the workers
+/// owning their keys, or to all of them for the whole keyspace, whose replies are merged.
+#[derive(Clone)]
+pub(crate) struct Workers {
+    senders: Arc<[mpsc::Sender<Job>]>,
+}
+
+/// Work a worker runs on the databases it holds.
+type Job = Box<dyn FnOnce(&Database) + Send>;
+
+/// Key copied out of its worker: the index of its database, the key, and its value with
+/// its expiration when it exists.
+type Copied = (usize, Bytes, Option<(Value, Option<SystemTime>)>);
+
+/// Copies of keys taken from the workers owning them, in every database, to run a command
+/// spanning several workers on, see [`Workers::gather`].
+pub(crate) struct View {
+    /// Databases holding the copies, with the database of the command selected.
+    pub(crate) db: Database,
+    /// Keys as they were copied, the missing ones included.
+    copied: Vec<Copied>,
+}
+
+impl Workers {
+    /// Starts the workers, never more than there are shards to assign, each holding empty
+    /// databases as many as these and sharing their memory accounting.
+    pub(crate) fn spawn(db: &Database, count: usize) -> Self {
+        let senders = (0..count.clamp(1, SHARDS))
+            .map(|index| {
+                let (sender, receiver) = mpsc::channel::<Job>();
+                let db = db.sharing_memory();
+                thread::Builder::new()
+                    .name(format!("worker-{}", index))
+                    .spawn(move || {
+                        // Values UNLINK and FLUSHALL ASYNC remove are freed next to the worker.
+                        spawn_freer(format!("worker-{}-free", index));
+                        for job in receiver {
+                            job(&db);
+                        }
+                    })
+                    .expect("failed to spawn a worker thread");
+                sender
+            })
+            .collect();
+
+        Workers { senders }
+    }
+
+    /// Worker the shards of all the keys are assigned to, if a single one is.
+    pub(crate) fn owner(&self, keys: &[Bytes]) -> Option<usize> {
+        let mut owners = keys.iter().map(|key| self.owner_of(key));
+        let owner = owners.next()?;
+        owners.all(|other| other == owner).then_some(owner)
+    }
+
+    fn owner_of(&self, key: &[u8]) -> usize {
+        shard_of(key) % self.senders.len()
+    }
+
+    /// Runs the command where its keys are: on the worker owning them, split over the
+    /// workers owning some of them, or on every worker for commands on the whole keyspace.
+    /// Commands without keys which do not look at the keyspace run on the caller's databases.
+    pub(crate) async fn apply(&self, frames: Vec<Frame>, command: Box<dyn Command>, db: &Database) -> Frame {
+        let index = db.index();
+        let name = command_name(&frames).unwrap_or_default();
+        let mut keys = command_keys(&frames).unwrap_or_default();
+
+        if keys.is_empty() {
+            return match name.as_str() {
+                "DBSIZE" => sum(self.on_all(frames, index).await),
+                "FLUSHALL" | "FLUSHDB" | "SWAPDB" => first_error(self.on_all(frames, index).await),
+                "KEYS" => concat(self.on_all(frames, index).await),
+                "RANDOMKEY" => any(self.on_all(frames, index).await),
+                "SCAN" => merge_scans(self.on_all(frames, index).await),
+                "MEMORY" if is_stats(&frames) => {
+                    let counts = self.each(memory::key_counts).await.into_iter()
+                        .reduce(|total, counts| total.into_iter().zip(counts).map(|((keys, expires), (more, more_expires))| (keys + more, expires + more_expires)).collect())
+                        .unwrap_or_default();
+                    memory::stats(db.memory(), counts)
+                }
+                _ => command.execute(db.clone()),
+            };
+        }
+
+        // Compaction rules name the series TS.ADD writes besides its own only once it is read.
+        if name == "TS.ADD" {
+            let key = keys[0].clone();
+            keys.extend(self.run(self.owner_of(&key), move |db| compaction_destinations(&select(db, index), &key)).await);
+        }
+        if let Some(owner) = self.owner(&keys) {
+            return self.run(owner, move |db| command.execute(select(db, index))).await;
+        }
+
+        match name.as_str() {
+            "MSET" => first_error(self.split(&frames, &keys, 2, index).await.into_iter().map(|(_, reply)| reply).collect()),
+            "MGET" => {
+                let mut values = vec![Frame::Null; keys.len()];
+                for (positions, reply) in self.split(&frames, &keys, 1, index).await {
+                    let Frame::Array(found) = reply else { return reply };
+                    for (position, value) in positions.into_iter().zip(found) {
+                        values[position] = value;
+                    }
+                }
+                Frame::Array(values)
+            }
+            "DEL" | "EXISTS" | "TOUCH" | "UNLINK" => sum(self.split(&frames, &keys, 1, index).await.into_iter().map(|(_, reply)| reply).collect()),
+            _ => {
+                let view = self.gather(db, &keys).await;
+                let response = command.execute(view.db.clone());
+                if is_write(&frames) {
+                    self.scatter(view).await;
+                }
+                response
+            }
+        }
+    }
+
+    /// Runs the work on the worker and waits for its result.
+    pub(crate) async fn run<R: Send + 'static>(&self, worker: usize, work: impl FnOnce(&Database) -> R + Send + 'static) -> R {
+        self.send(worker, work).await.expect("worker thread has stopped")
+    }
+
+    /// Runs the work on every worker at once, returning their results in order.
+    pub(crate) async fn each<R: Send + 'static>(&self, work: impl Fn(&Database) -> R + Send + Sync + 'static) -> Vec<R> {
+        let work = Arc::new(work);
+        let responses: Vec<_> = (0..self.senders.len())
+            .map(|worker| {
+                let work = work.clone();
+                self.send(worker, move |db| work(db))
+            })
+            .collect();
+
+        let mut results = Vec::with_capacity(responses.len());
+        for response in responses {
+            results.push(response.await.expect("worker thread has stopped"));
+        }
+        results
+    }
+
+    /// Hands the work to the worker, the receiver gets its result.
+    fn send<R: Send + 'static>(&self, worker: usize, work: impl FnOnce(&Database) -> R + Send + 'static) -> oneshot::Receiver<R> {
+        let (reply, response) = oneshot::channel();
+        let job: Job = Box::new(move |db| {
+            let _ = reply.send(work(db));
+        });
+        self.senders[worker].send(job).expect("worker thread has stopped");
+        response
+    }
+
+    /// Runs the command on every worker, each one on its own shards.
+    async fn on_all(&self, frames: Vec<Frame>, index: usize) -> Vec<Frame> {
+        self.each(move |db| parse(frames.clone()).execute(select(db, index))).await
+    }
+
+    /// Sends every worker owning some of the keys the command with only those, each key
+    /// with the `step - 1` arguments following it. Returns the replies with the positions
+    /// among the keys of those each one answers for.
+    async fn split(&self, frames: &[Frame], keys: &[Bytes], step: usize, index: usize) -> Vec<(Vec<usize>, Frame)> {
+        let arguments: Vec<&[Frame]> = frames[1..].chunks(step).collect();
+        let mut positions = vec![vec![]; self.senders.len()];
+        for (position, key) in keys.iter().enumerate() {
+            positions[self.owner_of(key)].push(position);
+        }
+
+        let responses: Vec<_> = positions.into_iter().enumerate()
+            .filter(|(_, positions)| !positions.is_empty())
+            .map(|(worker, positions)| {
+                let mut part = vec![frames[0].clone()];
+                for position in &positions {
+                    part.extend_from_slice(arguments[*position]);
+                }
+                (positions, self.send(worker, move |db| parse(part).execute(select(db, index))))
+            })
+            .collect();
+
+        let mut replies = Vec::with_capacity(responses.len());
+        for (positions, response) in responses {
+            replies.push((positions, response.await.expect("worker thread has stopped")));
+        }
+        replies
+    }
+
+    /// Copies the keys out of the workers owning them, in every database, into databases
+    /// of their own. A command writing to keys of several workers runs on the copies, then
+    /// [`Workers::scatter`] writes back what it changed, while the caller keeps other
+    /// writes to the keys waiting.
+    pub(crate) async fn gather(&self, db: &Database, keys: &[Bytes]) -> View {
+        let mut owned = vec![vec![]; self.senders.len()];
+        let mut seen = HashSet::new();
+        for key in keys.iter().filter(|key| seen.insert(*key)) {
+            owned[self.owner_of(key)].push(key.clone());
+        }
+
+        let responses: Vec<_> = owned.into_iter().enumerate()
+            .filter(|(_, keys)| !keys.is_empty())
+            .map(|(worker, keys)| self.send(worker, move |db| copy(db, &keys)))
+            .collect();
+        let mut view = View { db: db.scratch(), copied: vec![] };
+        for response in responses {
+            view.copied.extend(response.await.expect("worker thread has stopped"));
+        }
+
+        for (index, key, entry) in &view.copied {
+            if let Some((value, expires_at)) = entry {
+                let db = select(&view.db, *index);
+                db.lock_keys([key]).unwrap().insert_with_expiration(key.clone(), value.clone(), *expires_at);
+            }
+        }
+        view
+    }
+
+    /// Writes the keys of the view the command changed back to the workers owning them.
+    pub(crate) async fn scatter(&self, view: View) {
+        let mut changed = vec![vec![]; self.senders.len()];
+        for (index, key, entry) in view.copied {
+            let current = {
+                let db = select(&view.db, index);
+                let mut keyspace = db.lock_keys([&key]).unwrap();
+                let expires_at = keyspace.expiration(&key);
+                keyspace.remove(&key).map(|value| (value, expires_at))
+            };
+            if current != entry {
+                changed[self.owner_of(&key)].push((index, key, current));
+            }
+        }
+
+        let responses: Vec<_> = changed.into_iter().enumerate()
+            .filter(|(_, changed)| !changed.is_empty())
+            .map(|(worker, changed)| self.send(worker, move |db| write(db, changed)))
+            .collect();
+        for response in responses {
+            response.await.expect("worker thread has stopped");
+        }
+    }
+
+    /// Evicts keys of the workers until the memory used fits the limit again, recording
+    /// them with the index of their database, see [`Database::free_memory`]. Every worker
+    /// samples its share of the keys and the best sample of all is evicted.
+    pub(crate) async fn free_memory(&self, db: &Database, evicted: &mut Vec<(usize, Bytes)>) -> bool {
+        let memory = db.memory();
+        while memory.over_limit() {
+            let policy = memory.policy();
+            if policy == EvictionPolicy::NoEviction {
+                return false;
+            }
+
+            let count = memory.samples().div_ceil(self.senders.len());
+            let samples = self.each(move |db| db.eviction_samples(policy, count)).await;
+            let best = samples.into_iter().enumerate()
+                .flat_map(|(worker, samples)| samples.into_iter().map(move |sample| (worker, sample)))
+                .max_by_key(|(_, sample)| sample.score);
+            let Some((worker, sample)) = best else { return false };
+            // A sample changed since it was taken stays, the keys are sampled again.
+            if let Some(key) = self.run(worker, move |db| db.evict_sample(sample)).await {
+                evicted.push(key);
+            }
+        }
+
+        true
+    }
+
+    /// Writes a snapshot of every database out of the keys of every worker.
+    pub(crate) async fn save(&self) -> Vec<u8> {
+        rdb::assemble(self.each(rdb::save_keys).await)
+    }
+
+    /// Replaces the keys of every worker with those of the snapshot, leaving them untouched when it is corrupt.
+    pub(crate) async fn load(&self, db: &Database, snapshot: &[u8]) -> Result<(), Corrupt> {
+        let mut owned: Vec<Vec<Loaded>> = (0..self.senders.len()).map(|_| vec![]).collect();
+        for entry in rdb::read(snapshot, db.count(), db.memory().compact_limits())? {
+            owned[self.owner_of(&entry.1)].push(entry);
+        }
+
+        let responses: Vec<_> = owned.into_iter().enumerate()
+            .map(|(worker, entries)| self.send(worker, move |db| rdb::replace(db, entries)))
+            .collect();
+        for response in responses {
+            response.await.expect("worker thread has stopped");
+        }
+        Ok(())
+    }
+}
+
+/// Parses a command whose frames parsed already once.
+fn parse(frames: Vec<Frame>) -> Box<dyn Command> {
+    (&mut frames.into_iter()).try_into().expect("the command parsed before")
+}
+
+fn select(db: &Database, index: usize) -> Database {
+    db.select(index).expect("the selected database exists on every worker")
+}
+
+/// Copies of the keys in every database.
+fn copy(db: &Database, keys: &[Bytes]) -> Vec<Copied> {
+    let mut copied = vec![];
+    for index in 0..db.count() {
+        let db = select(db, index);
+        let keyspace = db.lock_keys(keys).unwrap();
+        copied.extend(keys.iter().map(|key| {
+            let entry = keyspace.get_value(key).map(|value| (value.clone(), keyspace.expiration(key)));
+            (index, key.clone(), entry)
+        }));
+    }
+    copied
+}
+
+/// Stores the changed keys, removing those which do not exist anymore.
+fn write(db: &Database, changed: Vec<Copied>) {
+    for (index, key, entry) in changed {
+        let db = select(db, index);
+        let mut keyspace = db.lock_keys([&key]).unwrap();
+        match entry {
+            Some((value, expires_at)) => {
+                keyspace.insert_with_expiration(key, value, expires_at);
+            }
+            None => {
+                keyspace.remove(&key);
+            }
+        }
+    }
+}
+
+/// Keys of the series TS.ADD on the key adds compacted samples to.
+fn compaction_destinations(db: &Database, key: &Bytes) -> Vec<Bytes> {
+    match db.lock_keys([key]).unwrap().get_time_series(key) {
+        Ok(Some(series)) => series.rules.iter().map(|rule| rule.destination.clone()).collect(),
+        _ => vec![],
+    }
+}
+
+fn is_stats(frames: &[Frame]) -> bool {
+    matches!(frames.get(1), Some(Frame::Bulk(subcommand)) if subcommand.eq_ignore_ascii_case(b"STATS"))
+        || matches!(frames.get(1), Some(Frame::Simple(subcommand)) if subcommand.eq_ignore_ascii_case("STATS"))
+}
+
+/// Adds up the counts replied by the workers.
+fn sum(replies: Vec<Frame>) -> Frame {
+    let mut total = 0;
+    for reply in replies {
+        match reply {
+            Frame::Integer(count) => total += count,
+            reply => return reply,
+        }
+    }
+    Frame::Integer(total)
+}
+
+/// Reply of a command every worker answers alike unless it failed.
+fn first_error(replies: Vec<Frame>) -> Frame {
+    let mut replies = replies.into_iter();
+    let first = replies.next().unwrap_or(Frame::Simple("OK".to_string()));
+    replies.find(|reply| matches!(reply, Frame::SimpleError(_))).unwrap_or(first)
+}
+
+/// Puts together the arrays replied by the workers.
+fn concat(replies: Vec<Frame>) -> Frame {
+    let mut items = vec![];
+    for reply in replies {
+        match reply {
+            Frame::Array(more) => items.extend(more),
+            reply => return reply,
+        }
+    }
+    Frame::Array(items)
+}
+
+/// One of the keys the workers picked at random, from a worker picked at random.
+fn any(replies: Vec<Frame>) -> Frame {
+    let mut keys: Vec<Frame> = replies.into_iter().filter(|reply| *reply != Frame::Null).collect();
+    match keys.len() {
+        0 => Frame::Null,
+        count => keys.swap_remove(random() as usize % count),
+    }
+}
+
+/// Merges the SCAN replies of the workers, each one having visited its own shards from
+/// the cursor. The scan goes on from the closest of their cursors, so keys found beyond
+/// it are left for the next call to return.
+fn merge_scans(replies: Vec<Frame>) -> Frame {
+    let mut next = 0;
+    let mut keys = vec![];
+    for reply in replies {
+        let Frame::Array(reply) = reply else { return reply };
+        let Ok([Frame::Bulk(cursor), Frame::Array(found)]) = <[Frame; 2]>::try_from(reply) else {
+            return Frame::SimpleError("ERR unexpected SCAN reply of a worker".to_string());
+        };
+        let cursor = std::str::from_utf8(&cursor).ok().and_then(|cursor| cursor.parse().ok()).unwrap_or(0);
+        if cursor != 0 && (next == 0 || cursor < next) {
+            next = cursor;
+        }
+        keys.extend(found);
+    }
+
+    keys.retain(|key| next == 0 || matches!(key, Frame::Bulk(key) if scan_hash(key) < next));
+    scan_reply(next, keys)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::new_db;
+
+    use super::*;
+
+    fn request(arguments: &[&str]) -> Vec<Frame> {
+        arguments.iter().map(|argument| Frame::Bulk(Bytes::from(argument.to_string()))).collect()
+    }
+
+    async fn call(workers: &Workers, db: &Database, arguments: &[&str]) -> Frame {
+        let frames = request(arguments);
+        workers.apply(frames.clone(), parse(frames), db).await
+    }
+
+    /// Keys owned by each of the workers, `count` of them per worker.
+    fn keys_per_worker(workers: usize, count: usize) -> Vec<Vec<String>> {
+        let mut keys = vec![vec![]; workers];
+        for key in (0..).map(|i| format!("key:{}", i)) {
+            let owned = &mut keys[shard_of(key.as_bytes()) % workers];
+            if owned.len() < count {
+                owned.push(key);
+            }
+            if keys.iter().all(|keys| keys.len() == count) {
+                return keys;
+            }
+        }
+        unreachable!()
+    }
+
+    #[test]
+    fn it_assigns_keys_of_one_shard_to_one_worker() {
+        let workers = Workers::spawn(&new_db(), 4);
+        let key = Bytes::from("a");
+        let other = (0..).map(|i| Bytes::from(format!("key:{}", i))).find(|other| shard_of(other) % 4 != shard_of(&key) % 4).unwrap();
+
+        assert_eq!(Some(shard_of(&key) % 4), workers.owner(&[key.clone(), key.clone()]));
+        assert_eq!(None, workers.owner(&[key, other]));
+        assert_eq!(None, workers.owner(&[]));
+    }
+
+    #[tokio::test]
+    async fn it_keeps_the_keys_on_the_worker_owning_them() {
+        let db = new_db();
+        let workers = Workers::spawn(&db, 2);
+        let keys = keys_per_worker(2, 1);
+
+        assert_eq!(Frame::Simple("OK".to_string()), call(&workers, &db, &["SET", &keys[1][0], "1"]).await);
+        assert_eq!(Frame::Bulk(Bytes::from("1")), call(&workers, &db, &["GET", &keys[1][0]]).await);
+        // Neither the caller's databases nor the other worker hold it.
+        assert!(db.lock().unwrap().is_empty());
+        assert_eq!(vec![0, 1], workers.each(|db| db.lock().unwrap().len()).await);
+    }
+
+    #[tokio::test]
+    async fn it_splits_commands_over_the_workers_owning_their_keys() {
+        let db = new_db();
+        let workers = Workers::spawn(&db, 3);
+        let keys = keys_per_worker(3, 2);
+        let (a, b, c) = (&keys[0][0], &keys[1][0], &keys[2][0]);
+
+        assert_eq!(Frame::Simple("OK".to_string()), call(&workers, &db, &["MSET", a, "1", b, "2", c, "3"]).await);
+        assert_eq!(vec![1, 1, 1], workers.each(|db| db.lock().unwrap().len()).await);
+        assert_eq!(
+            Frame::Array(vec![Frame::Bulk(Bytes::from("3")), Frame::Null, Frame::Bulk(Bytes::from("1")), Frame::Bulk(Bytes::from("2"))]),
+            call(&workers, &db, &["MGET", c, &keys[2][1], a, b]).await
+        );
+        assert_eq!(Frame::Integer(4), call(&workers, &db, &["EXISTS", a, a, b, c, &keys[0][1]]).await);
+        assert_eq!(Frame::Integer(3), call(&workers, &db, &["DBSIZE"]).await);
+
+        let mut found = match call(&workers, &db, &["KEYS", "*"]).await {
+            Frame::Array(found) => found,
+            reply => panic!("unexpected reply {:?}", reply),
+        };
+        found.sort_by_key(|key| format!("{:?}", key));
+        let mut expected: Vec<Frame> = [a, b, c].iter().map(|key| Frame::Bulk(Bytes::from(key.to_string()))).collect();
+        expected.sort_by_key(|key| format!("{:?}", key));
+        assert_eq!(expected, found);
+
+        assert_eq!(Frame::Integer(2), call(&workers, &db, &["UNLINK", a, c]).await);
+        assert_eq!(Frame::Simple("OK".to_string()), call(&workers, &db, &["FLUSHALL", "ASYNC"]).await);
+        assert_eq!(Frame::Integer(0), call(&workers, &db, &["DBSIZE"]).await);
+    }
+
+    #[tokio::test]
+    async fn it_scans_the_shards_of_every_worker() {
+        let db = new_db();
+        let workers = Workers::spawn(&db, 3);
+        for i in 0..200 {
+            call(&workers, &db, &["SET", &format!("key:{}", i), "1"]).await;
+        }
+
+        let mut seen = vec![];
+        let mut cursor = "0".to_string();
+        loop {
+            let Frame::Array(reply) = call(&workers, &db, &["SCAN", &cursor, "COUNT", "7"]).await else { panic!() };
+            let [Frame::Bulk(next), Frame::Array(keys)] = &reply[..] else { panic!() };
+            seen.extend(keys.iter().cloned());
+            cursor = String::from_utf8(next.to_vec()).unwrap();
+            if cursor == "0" {
+                break;
+            }
+        }
+
+        assert_eq!(200, seen.len());
+        seen.sort_by_key(|key| format!("{:?}", key));
+        seen.dedup();
+        assert_eq!(200, seen.len());
+    }
+
+    #[tokio::test]
+    async fn it_runs_commands_spanning_workers_on_copies_of_their_keys() {
+        let db = new_db();
+        let workers = Workers::spawn(&db, 2);
+        let keys = keys_per_worker(2, 2);
+        let (a, b) = (&keys[0][0], &keys[1][0]);
+
+        call(&workers, &db, &["SET", a, "1", "EX", "100"]).await;
+        assert_eq!(Frame::Simple("OK".to_string()), call(&workers, &db, &["RENAME", a, b]).await);
+        assert_eq!(Frame::Null, call(&workers, &db, &["GET", a]).await);
+        assert_eq!(Frame::Bulk(Bytes::from("1")), call(&workers, &db, &["GET", b]).await);
+        let expiration = workers.run(1, {
+            let b = b.clone();
+            move |db| db.lock_keys([&b]).unwrap().expiration(&b)
+        }).await;
+        assert!(expiration.is_some());
+        assert_eq!(vec![0, 1], workers.each(|db| db.lock().unwrap().len()).await);
+
+        assert!(matches!(call(&workers, &db, &["RENAME", a, b]).await, Frame::SimpleError(_)));
+        assert_eq!(Frame::Integer(1), call(&workers, &db, &["COPY", b, &keys[0][1], "DB", "3"]).await);
+        let copied = workers.run(0, {
+            let key = keys[0][1].clone();
+            move |db| db.select(3).unwrap().lock_keys([&key]).unwrap().get(&key).unwrap().cloned()
+        }).await;
+        assert_eq!(Some(Bytes::from("1")), copied);
+    }
+
+    #[tokio::test]
+    async fn it_compacts_into_series_of_other_workers() {
+        let db = new_db();
+        let workers = Workers::spawn(&db, 2);
+        let keys = keys_per_worker(2, 1);
+        let (source, destination) = (&keys[0][0], &keys[1][0]);
+
+        call(&workers, &db, &["TS.CREATE", source]).await;
+        call(&workers, &db, &["TS.CREATE", destination]).await;
+        assert_eq!(Frame::Simple("OK".to_string()), call(&workers, &db, &["TS.CREATERULE", source, destination, "AGGREGATION", "sum", "10"]).await);
+        for (timestamp, value) in [("1", "1"), ("2", "2"), ("12", "5")] {
+            call(&workers, &db, &["TS.ADD", source, timestamp, value]).await;
+        }
+
+        assert_eq!(
+            Frame::Array(vec![Frame::Array(vec![Frame::Integer(0), Frame::Bulk(Bytes::from("3"))])]),
+            call(&workers, &db, &["TS.RANGE", destination, "-", "+"]).await
+        );
+    }
+
+    #[tokio::test]
+    async fn it_evicts_keys_of_every_worker() {
+        let db = new_db();
+        let workers = Workers::spawn(&db, 2);
+        for i in 0..100 {
+            call(&workers, &db, &["SET", &format!("key:{}", i), "value"]).await;
+        }
+        db.memory().set_policy(EvictionPolicy::AllKeysRandom);
+        db.memory().set_limit(db.memory().used() / 2);
+
+        let mut evicted = vec![];
+        assert!(workers.free_memory(&db, &mut evicted).await);
+        assert!(!db.memory().over_limit());
+        let Frame::Integer(left) = call(&workers, &db, &["DBSIZE"]).await else { panic!() };
+        assert_eq!(100, left as usize + evicted.len());
+    }
+
+    #[tokio::test]
+    async fn it_saves_and_loads_the_keys_of_every_worker() {
+        let db = new_db();
+        let workers = Workers::spawn(&db, 3);
+        for i in 0..50 {
+            call(&workers, &db, &["SET", &format!("key:{}", i), &i.to_string()]).await;
+        }
+        call(&workers, &db.select(2).unwrap(), &["SET", "other", "1"]).await;
+        let snapshot = workers.save().await;
+
+        let copy = new_db();
+        rdb::load(&copy, &snapshot).unwrap();
+        assert_eq!(50, copy.lock().unwrap().len());
+        assert_eq!(Ok(Some(&Bytes::from("1"))), copy.select(2).unwrap().lock().unwrap().get("other"));
+
+        call(&workers, &db, &["FLUSHALL"]).await;
+        workers.load(&db, &snapshot).await.unwrap();
+        assert_eq!(Frame::Integer(50), call(&workers, &db, &["DBSIZE"]).await);
+        assert_eq!(Frame::Bulk(Bytes::from("1")), call(&workers, &db.select(2).unwrap(), &["GET", "other"]).await);
+    }
+
+    #[test]
+    fn it_frees_values_dropped_in_the_background_on_a_thread_of_the_worker() {
+        struct Dropped(mpsc::Sender<String>);
+        impl Drop for Dropped {
+            fn drop(&mut self) {
+                let _ = self.0.send(thread::current().name().unwrap_or_default().to_string());
+            }
+        }
+
+        let workers = Workers::spawn(&new_db(), 1);
+        let (sender, receiver) = mpsc::channel();
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        runtime.block_on(workers.run(0, move |_| crate::database::drop_in_background(Dropped(sender))));
+
+        assert_eq!("worker-0-free", receiver.recv().unwrap());
+    }
+}